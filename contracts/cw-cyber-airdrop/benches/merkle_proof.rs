@@ -0,0 +1,55 @@
+use std::convert::TryInto;
+
+use cosmwasm_std::Binary;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sha2::{Digest, Sha256};
+
+use cw_cyber_airdrop::merkle_proof_root;
+use cw_cyber_airdrop::msg::ProofNode;
+use cw_cyber_airdrop::state::HashAlgo;
+
+const PROOF_LEVELS: usize = 20;
+
+/// Verifies the proof the way the contract did before, decoding nodes from hex
+/// and hashing a concatenated buffer per level.
+fn hex_proof_root(leaf: &[u8], proof: &[String]) -> [u8; 32] {
+    let hash: [u8; 32] = Sha256::digest(leaf).into();
+    proof.iter().fold(hash, |hash, node| {
+        let node: [u8; 32] = hex::decode(node).unwrap().try_into().unwrap();
+        let mut hashes = [hash, node];
+        hashes.sort_unstable();
+        Sha256::digest(&hashes.concat()).into()
+    })
+}
+
+fn proof_nodes() -> Vec<[u8; 32]> {
+    (0..PROOF_LEVELS)
+        .map(|level| Sha256::digest(format!("node{}", level).as_bytes()).into())
+        .collect()
+}
+
+fn merkle_proof(c: &mut Criterion) {
+    let leaf = b"bostrom1claimer1000";
+    let nodes = proof_nodes();
+    let hex_proof: Vec<String> = nodes.iter().map(hex::encode).collect();
+    let proof: Vec<ProofNode> = nodes
+        .iter()
+        .map(|node| ProofNode(Binary::from(node.as_ref())))
+        .collect();
+    assert_eq!(
+        merkle_proof_root(HashAlgo::Sha256, leaf, &proof).unwrap(),
+        hex_proof_root(leaf, &hex_proof)
+    );
+
+    let mut group = c.benchmark_group("merkle_proof_20_levels");
+    group.bench_function("hex", |b| {
+        b.iter(|| hex_proof_root(black_box(leaf), black_box(&hex_proof)))
+    });
+    group.bench_function("raw", |b| {
+        b.iter(|| merkle_proof_root(HashAlgo::Sha256, black_box(leaf), black_box(&proof)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, merkle_proof);
+criterion_main!(benches);
@@ -1,21 +1,89 @@
 use std::env::current_dir;
 use std::fs::create_dir_all;
+use std::path::Path;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use cw_cyber_airdrop::hooks::{GiftClaimedHookMsg, HookExecuteMsg, HooksResponse};
+use cw_cyber_airdrop::ibc::{IbcAck, IbcPacketMsg, RegisterMerkleRootAck};
 use cw_cyber_airdrop::msg::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, IsClaimedResponse, MerkleRootResponse, QueryMsg,
+    ActivationResponse, AllClaimsResponse, AllocationResponse, AuditLogResponse,
+    ClaimDiagnosisResponse, ClaimHookMsg, ClaimMsg, ClaimResponse, ClaimedAmountResponse,
+    ClaimedPageResponse, ClaimerGrantResponse, ClaimsByAddressResponse, ClaimsBySourceResponse,
+    ConfigResponse, CurrentCoefficientResponse, CurrentTierResponse, DelegationResponse,
+    DelegationsResponse, ExecuteMsg, ExportStateResponse, HasClaimedResponse, HistoryResponse,
+    InstantiateMsg, IsClaimedResponse, IsEligibleResponse, LatestStageResponse, LotteryResponse,
+    MerkleRootResponse, MigrateMsg, PassportClaimsResponse, PendingRootsResponse, Proof, QueryMsg,
+    ReceiveMsg, ReconciliationResponse, ReferralStatsResponse, ReleaseProjectionResponse,
+    ReleaseStateResponse, SimulateClaimResponse, StageParams, StageResponse, StatsResponse,
+    SudoMsg, VerifyProofResponse,
 };
+use cw_cyber_airdrop::state::{ReleaseState, StageRelease};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
     out_dir.push("../schema");
     create_dir_all(&out_dir).unwrap();
     remove_schemas(&out_dir).unwrap();
+    export_schemas(&out_dir);
+}
+
+/// Exports the schemas of the msgs, of the nested types clients sign or submit and of
+/// the responses. The schema coverage test compares them to the committed ones.
+pub fn export_schemas(out_dir: &Path) {
+    export_schema(&schema_for!(InstantiateMsg), out_dir);
+    export_schema(&schema_for!(ExecuteMsg), out_dir);
+    export_schema(&schema_for!(QueryMsg), out_dir);
+    export_schema(&schema_for!(MigrateMsg), out_dir);
+    export_schema(&schema_for!(ReceiveMsg), out_dir);
+    export_schema(&schema_for!(SudoMsg), out_dir);
+
+    // nested types signed or submitted by clients
+    export_schema(&schema_for!(ClaimMsg), out_dir);
+    export_schema(&schema_for!(Proof), out_dir);
+    export_schema(&schema_for!(StageParams), out_dir);
+    export_schema(&schema_for!(StageRelease), out_dir);
+    export_schema(&schema_for!(ReleaseState), out_dir);
+
+    export_schema(&schema_for!(MerkleRootResponse), out_dir);
+    export_schema(&schema_for!(StageResponse), out_dir);
+    export_schema(&schema_for!(LatestStageResponse), out_dir);
+    export_schema(&schema_for!(IsClaimedResponse), out_dir);
+    export_schema(&schema_for!(IsEligibleResponse), out_dir);
+    export_schema(&schema_for!(ConfigResponse), out_dir);
+    export_schema(&schema_for!(ClaimResponse), out_dir);
+    export_schema(&schema_for!(AllClaimsResponse), out_dir);
+    export_schema(&schema_for!(ClaimsByAddressResponse), out_dir);
+    export_schema(&schema_for!(HasClaimedResponse), out_dir);
+    export_schema(&schema_for!(ClaimedAmountResponse), out_dir);
+    export_schema(&schema_for!(ReleaseStateResponse), out_dir);
+    export_schema(&schema_for!(ReleaseProjectionResponse), out_dir);
+    export_schema(&schema_for!(ExportStateResponse), out_dir);
+    export_schema(&schema_for!(ActivationResponse), out_dir);
+    export_schema(&schema_for!(ReconciliationResponse), out_dir);
+    export_schema(&schema_for!(AuditLogResponse), out_dir);
+    export_schema(&schema_for!(HistoryResponse), out_dir);
+    export_schema(&schema_for!(LotteryResponse), out_dir);
+    export_schema(&schema_for!(VerifyProofResponse), out_dir);
+    export_schema(&schema_for!(ClaimDiagnosisResponse), out_dir);
+    export_schema(&schema_for!(PassportClaimsResponse), out_dir);
+    export_schema(&schema_for!(ReferralStatsResponse), out_dir);
+    export_schema(&schema_for!(ClaimedPageResponse), out_dir);
+    export_schema(&schema_for!(PendingRootsResponse), out_dir);
+    export_schema(&schema_for!(AllocationResponse), out_dir);
+    export_schema(&schema_for!(StatsResponse), out_dir);
+    export_schema(&schema_for!(CurrentCoefficientResponse), out_dir);
+    export_schema(&schema_for!(CurrentTierResponse), out_dir);
+    export_schema(&schema_for!(SimulateClaimResponse), out_dir);
+    export_schema(&schema_for!(ClaimsBySourceResponse), out_dir);
+    export_schema(&schema_for!(ClaimerGrantResponse), out_dir);
+    export_schema(&schema_for!(DelegationResponse), out_dir);
+    export_schema(&schema_for!(DelegationsResponse), out_dir);
 
-    export_schema(&schema_for!(InstantiateMsg), &out_dir);
-    export_schema(&schema_for!(ExecuteMsg), &out_dir);
-    export_schema(&schema_for!(QueryMsg), &out_dir);
-    export_schema(&schema_for!(MerkleRootResponse), &out_dir);
-    export_schema(&schema_for!(IsClaimedResponse), &out_dir);
-    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(ClaimHookMsg), out_dir);
+    export_schema(&schema_for!(HookExecuteMsg), out_dir);
+    export_schema(&schema_for!(GiftClaimedHookMsg), out_dir);
+    export_schema(&schema_for!(HooksResponse), out_dir);
+    export_schema(&schema_for!(IbcPacketMsg), out_dir);
+    export_schema(&schema_for!(IbcAck), out_dir);
+    export_schema(&schema_for!(RegisterMerkleRootAck), out_dir);
 }
@@ -1,4 +1,6 @@
-use cosmwasm_std::{RecoverPubkeyError, StdError, VerificationError};
+use cosmwasm_std::{RecoverPubkeyError, StdError, Timestamp, Uint128, VerificationError};
+use cw0::Expiration;
+use cyber_gift_proofs::ProofError;
 use hex::FromHexError;
 use thiserror::Error;
 
@@ -22,15 +24,179 @@ pub enum ContractError {
     #[error("Invalid input")]
     InvalidInput {},
 
-    #[error("Already claimed")]
-    Claimed {},
-
     #[error("Wrong length")]
     WrongLength {},
 
+    #[error("Proof node {index} must be 32 bytes long")]
+    InvalidProofNode { index: usize },
+
+    #[error("Stage {stage} has not begun, start: {start}")]
+    StageNotBegun { stage: u8, start: Expiration },
+
+    #[error("E1005: Stage {stage} expired at {expiration}")]
+    StageExpired { stage: u8, expiration: Expiration },
+
+    #[error("Stage {stage} has not expired yet")]
+    StageNotExpired { stage: u8 },
+
+    #[error("Stage {stage} is frozen by {frozen_by}")]
+    StageFrozen { stage: u8, frozen_by: String },
+
+    #[error("Campaign parameters can not be changed after activation")]
+    Activated {},
+
+    #[error("No ownership proposal")]
+    NoOwnershipProposal {},
+
+    #[error("Nothing to release")]
+    NothingToRelease {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Too many claims in block {height}, at most {max_claims_per_block} are accepted")]
+    TooManyClaimsThisBlock {
+        height: u64,
+        max_claims_per_block: u64,
+    },
+
+    #[error("Upload of {entries} allocations is over the limit of {max_allocations}")]
+    TooManyAllocations {
+        entries: usize,
+        max_allocations: usize,
+    },
+
+    #[error("Allocation {index} is invalid, {msg}")]
+    InvalidAllocation { index: usize, msg: String },
+
+    #[error("Allocations of stage {stage} can not be uploaded, {msg}")]
+    AllocationsNotAllowed { stage: u8, msg: String },
+
+    #[error("Allocations of stage {stage} sum up to {allocated}, over its total {total_amount}")]
+    AllocationsOverTotal {
+        stage: u8,
+        allocated: Uint128,
+        total_amount: Uint128,
+    },
+
     #[error("Cannot migrate from different contract type: {previous_contract}")]
     CannotMigrate { previous_contract: String },
 
-    #[error("Address is not eligible to claim airdrop, {msg}")]
+    #[error("Cannot migrate from version {previous_version}, {msg}")]
+    CannotMigrateVersion {
+        previous_version: String,
+        msg: String,
+    },
+
+    #[error("E1000: Address is not eligible to claim airdrop, {msg}")]
     IsNotEligible { msg: String },
+
+    #[error("E1001: Signature mismatch, {msg}")]
+    SignatureMismatch { msg: String },
+
+    #[error("Proof {proof} does not match the {claimer} claimer and {signature_type} signature")]
+    ProofMismatch {
+        proof: String,
+        claimer: String,
+        signature_type: String,
+    },
+
+    #[error("E1002: Address mismatch of {address}, {msg}")]
+    AddressMismatch { address: String, msg: String },
+
+    #[error("E1003: Invalid proof of leaf {leaf} in stage {stage}")]
+    ProofInvalid { stage: u8, leaf: String },
+
+    #[error(
+        "E1004: {address} already claimed {amount} of stage {stage} at height {claimed_at_height}"
+    )]
+    AlreadyClaimed {
+        stage: u8,
+        address: String,
+        claimed_at_height: u64,
+        amount: Uint128,
+    },
+
+    #[error("E1007: Campaign ended at {campaign_end}")]
+    CampaignEnded { campaign_end: Expiration },
+
+    #[error("Campaign has not ended")]
+    CampaignNotEnded {},
+
+    #[error("E1006: Releases start after {activation_threshold} claims, {claims_count} claimed")]
+    NotActivated {
+        claims_count: u64,
+        activation_threshold: u64,
+    },
+
+    #[error("Claim amount {amount} is out of range [{minimum}, {maximum}]")]
+    ClaimAmountOutOfRange {
+        amount: Uint128,
+        minimum: Uint128,
+        maximum: Uint128,
+    },
+
+    #[error("IBC channel {channel} is not allowed for payouts")]
+    ChannelNotAllowed { channel: String },
+
+    #[error("Invalid IBC channel, {msg}")]
+    InvalidIbcChannel { msg: String },
+
+    #[error("Invalid target address {address}, {msg}")]
+    InvalidTargetAddress { address: String, msg: String },
+
+    #[error("Gift of the addresses linked in the passport of {passport} is claimed")]
+    LinkedAddressClaimed { passport: String },
+
+    #[error("Root has {approvals} of {threshold} guardian approvals")]
+    RootNotApproved { approvals: u64, threshold: u64 },
+
+    #[error("Root can not be activated before {activate_at}")]
+    RootTimelocked { activate_at: Timestamp },
+
+    #[error("Hook {hook} is already registered")]
+    HookAlreadyRegistered { hook: String },
+
+    #[error("Hook {hook} is not registered")]
+    HookNotRegistered { hook: String },
+
+    #[error("Passport verification of {address} failed, {msg}")]
+    PassportVerificationFailed { address: String, msg: String },
+
+    #[cfg(any(test, feature = "dry-run"))]
+    #[error("Dry run reverted {messages} msgs with attributes {attributes}")]
+    DryRun { attributes: String, messages: usize },
+}
+
+impl ContractError {
+    /// Returns the stable code of claim eligibility errors, it prefixes their message
+    /// so that clients can branch on failures.
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            ContractError::IsNotEligible { .. } => Some(1000),
+            ContractError::SignatureMismatch { .. } => Some(1001),
+            ContractError::AddressMismatch { .. } => Some(1002),
+            ContractError::ProofInvalid { .. } => Some(1003),
+            ContractError::AlreadyClaimed { .. } => Some(1004),
+            ContractError::StageExpired { .. } => Some(1005),
+            ContractError::NotActivated { .. } => Some(1006),
+            ContractError::CampaignEnded { .. } => Some(1007),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProofError> for ContractError {
+    fn from(err: ProofError) -> Self {
+        match err {
+            ProofError::Std(err) => ContractError::Std(err),
+            ProofError::Hex(err) => ContractError::Hex(err),
+            ProofError::Pubkey(err) => ContractError::Pubkey(err),
+            ProofError::SignatureMismatch { msg } => ContractError::SignatureMismatch { msg },
+            ProofError::AddressMismatch { address, msg } => {
+                ContractError::AddressMismatch { address, msg }
+            }
+            ProofError::InvalidAddress { msg } => ContractError::IsNotEligible { msg },
+        }
+    }
 }
@@ -0,0 +1,69 @@
+//! Typed wasm events of the contract. Attribute keys are shared across the events,
+//! e.g. `stage`, `claimer` and `payout`.
+use cosmwasm_std::{Decimal, Event, Uint128};
+use cw0::Expiration;
+
+use crate::state::Config;
+
+pub const CLAIM_EVENT: &str = "cybergift-claim";
+pub const RELEASE_EVENT: &str = "cybergift-release";
+pub const ROOT_REGISTERED_EVENT: &str = "cybergift-root-registered";
+pub const ACTIVATED_EVENT: &str = "cybergift-activated";
+pub const CONFIG_UPDATED_EVENT: &str = "cybergift-config-updated";
+pub const CAMPAIGN_ENDED_EVENT: &str = "cybergift-campaign-ended";
+
+/// Emitted for each claim, payout is the original amount after the coefficient is applied.
+pub fn claim_event(
+    stage: u8,
+    claimer: &str,
+    target: &str,
+    original_amount: Uint128,
+    coefficient: Decimal,
+    payout: Uint128,
+) -> Event {
+    Event::new(CLAIM_EVENT)
+        .add_attribute("stage", stage.to_string())
+        .add_attribute("claimer", claimer)
+        .add_attribute("target", target)
+        .add_attribute("original_amount", original_amount)
+        .add_attribute("coefficient", coefficient.to_string())
+        .add_attribute("payout", payout)
+}
+
+/// Emitted for each release of vested gifts.
+pub fn release_event(target: &str, payout: Uint128) -> Event {
+    Event::new(RELEASE_EVENT)
+        .add_attribute("target", target)
+        .add_attribute("payout", payout)
+}
+
+/// Emitted when the merkle root of a new stage is registered.
+pub fn root_registered_event(stage: u8, merkle_root: &str, total_amount: Uint128) -> Event {
+    Event::new(ROOT_REGISTERED_EVENT)
+        .add_attribute("stage", stage.to_string())
+        .add_attribute("merkle_root", merkle_root)
+        .add_attribute("total_amount", total_amount)
+}
+
+/// Emitted when the claims count reaches the activation threshold.
+pub fn activated_event(claims_count: u64, activation_block: u64) -> Event {
+    Event::new(ACTIVATED_EVENT)
+        .add_attribute("claims_count", claims_count.to_string())
+        .add_attribute("activation_block", activation_block.to_string())
+}
+
+/// Emitted once after the campaign end, by EndCampaign or the first clawback.
+pub fn campaign_ended_event(campaign_end: &Expiration, height: u64) -> Event {
+    Event::new(CAMPAIGN_ENDED_EVENT)
+        .add_attribute("campaign_end", campaign_end.to_string())
+        .add_attribute("height", height.to_string())
+}
+
+/// Emitted when the owner updates the config, carries the resulting campaign parameters.
+pub fn config_updated_event(cfg: &Config) -> Event {
+    Event::new(CONFIG_UPDATED_EVENT)
+        .add_attribute("treasury", cfg.treasury.as_str())
+        .add_attribute("coefficient_up", cfg.coefficient_up.to_string())
+        .add_attribute("coefficient_down", cfg.coefficient_down.to_string())
+        .add_attribute("coefficient_claims", cfg.coefficient_claims.to_string())
+}
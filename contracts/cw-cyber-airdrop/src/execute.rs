@@ -1,28 +1,114 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, has_coins, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Uint128,
+    attr, from_binary, has_coins, to_binary, to_vec, Addr, Attribute, Binary, Coin, Decimal, Deps,
+    DepsMut, Env, Event, IbcTimeout, MessageInfo, Order, Reply, Response, StakingMsg, StdError,
+    StdResult, Storage, SubMsg, Timestamp, Uint128, WasmMsg,
 };
+use cw0::Expiration;
 use cw2::{get_contract_version, set_contract_version};
+use cw20::Cw20ReceiveMsg;
+use cw_storage_plus::{Bound, PrimaryKey};
+use cyber_passport::msg::{
+    AddressesByPassportResponse, ExecuteMsg as PassportExecuteMsg, PassportAddressesResponse,
+    PassportResponse, QueryMsg as PassportQueryMsg,
+};
+use cyber_passport::state::address_key;
+use std::collections::BTreeMap;
 
 use crate::error::ContractError;
+use crate::events::{
+    activated_event, campaign_ended_event, claim_event, config_updated_event, release_event,
+    root_registered_event,
+};
 use crate::helpers;
-use crate::helpers::{update_coefficient, verify_cosmos, verify_merkle_proof};
+use crate::helpers::{
+    claim_hook_msg, config_release, current_coefficient, current_tier, delegate_msgs,
+    eip55_address, expiration_height, expiration_time, gift_msg, gift_msgs, ibc_gift_msgs,
+    is_claimed, is_index_claimed, is_verified_claim, linked_passport, proportional_coins,
+    releasable_amount, releasable_extra_amounts, scale_amount, set_index_claimed, stage_hash_algo,
+    stage_release, take_verified_claim, update_balances, validate_target_addr, verified_claim_key,
+    verify_bitcoin, verify_contract_wallet, verify_cosmos, verify_merkle_leaf, verify_merkle_proof,
+    verify_passport, verify_passport_age, verify_solana,
+};
+use crate::hooks::GiftClaimedHookMsg;
+use crate::lottery;
+use crate::migrations::{migrate_v1, parse_version};
 use crate::msg::{
-    ClaimMsg, ClaimerType, ConfigResponse, ExecuteMsg, InstantiateMsg, IsClaimedResponse,
-    MerkleRootResponse, MigrateMsg, QueryMsg,
+    snapshot_key, ActivationResponse, AllClaimsResponse, AllocationResponse, AuditLogResponse,
+    BalanceReconciliation, ClaimDiagnosisResponse, ClaimEntry, ClaimHookMsg, ClaimMsg,
+    ClaimResponse, ClaimedAmountResponse, ClaimedPageResponse, ClaimerGrantResponse, ClaimerType,
+    ClaimsByAddressResponse, ClaimsBySourceResponse, ConfigResponse, CurrentCoefficientResponse,
+    CurrentTierResponse, DelegationResponse, DelegationsResponse, ExecuteMsg, ExportCursor,
+    ExportStateResponse, ExportedReleaseState, HasClaimedResponse, HistoryResponse, IndexedClaim,
+    InstantiateMsg, IsClaimedResponse, IsEligibleResponse, LatestStageResponse, LotteryResponse,
+    MerkleRootResponse, MigrateMsg, PassportClaim, PassportClaimsResponse, PendingRootsResponse,
+    Proof, ProofNode, QueryMsg, ReceiveMsg, ReconciliationResponse, ReferralStatsResponse,
+    ReleaseProjectionResponse, ReleaseStateResponse, SignatureType, SimulateClaimResponse,
+    SourceClaims, StageParams, StageResponse, StatsResponse, SudoMsg, VerifyProofResponse,
+    EXPORT_STATE_VERSION,
+};
+use crate::receipt::{mint_receipt_msg, GiftReceipt};
+use crate::state::{
+    AuditEntry, BlockClaims, ClaimHook, ClaimState, Config, DenomBalance, GiftAsset, HashAlgo,
+    HistorySnapshot, LeafFormat, LinkedClaimPolicy, Lottery, LotteryWinner, PendingGuardian,
+    PendingRoot, ReleaseState, StageFreeze, StageRelease, Unbonding, VestingPosition,
+    ACTIVATION_BLOCK, ALLOCATIONS, AUDIT_LOG, AUDIT_LOG_COUNT, BLOCK_CLAIMS, CAMPAIGN_ENDED,
+    CLAIMED_PAGES, CLAIMER_GRANTS, CLAIMS, CLAIMS_BY_SOURCE, CLAIMS_BY_TARGET, CLAIMS_COUNT,
+    CONFIG, CONTRACT_WALLET_CLAIMS, DELEGATIONS, DUST_POOL, HISTORY, HISTORY_COUNT, HOOKS,
+    LATEST_STAGE, LINKED_CLAIMERS, LINKED_CLAIMS, LOTTERY, LOTTERY_TICKETS, MERKLE_ROOT,
+    PASSPORT_CLAIMS, PENDING_GUARDIAN, PENDING_RELEASES, PENDING_ROOTS, PROOF_LEVEL_NODES,
+    REFERRAL_STATS, RELEASES_COUNT, RELEASE_STATE, STAGE_ALLOCATED, STAGE_AMOUNT,
+    STAGE_AMOUNT_CLAIMED, STAGE_EXPIRATION, STAGE_FREEZES, STAGE_HASH_ALGO, STAGE_LEAF_FORMAT,
+    STAGE_PASSPORT_CUTOFF, STAGE_PROOF_LEVEL, STAGE_RELEASE, STAGE_START, UNBONDINGS,
+    VERIFIED_CLAIMS, VERIFIED_PROOFS,
 };
-use crate::state::{Config, CLAIM, CONFIG, MERKLE_ROOT};
 
 // Version info, for migration info
 const CONTRACT_NAME: &str = "crates.io:cw-cyber-airdrop";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// IBC payouts time out after 10 minutes by default
+pub const DEFAULT_IBC_TIMEOUT_SECONDS: u64 = 600;
+
+// Reply id of the claim hooks, passport verifications are replied by claim count
+pub const CLAIM_HOOK_REPLY_ID: u64 = u64::MAX;
+
+// Reply ids of the releases of ReleaseAll start from the offset
+pub const RELEASE_REPLY_ID_OFFSET: u64 = 1 << 63;
+
+// Addresses released by ReleaseAll are capped to stay within the block gas limit
+pub const MAX_RELEASE_ADDRESSES: usize = 50;
+
+// Undelegated payouts are withdrawable after 21 days by default
+pub const DEFAULT_UNBONDING_SECONDS: u64 = 21 * 24 * 60 * 60;
+
+// Bech32 prefixes accepted for Cosmos claims by default
+pub const DEFAULT_COSMOS_PREFIXES: [&str; 5] = ["cosmos", "osmo", "terra", "juno", "bostrom"];
+
+// Claim fees are in basis points
+const BPS_DENOMINATOR: u64 = 10_000;
+
+// Allocations uploaded per msg are capped to stay within the block gas limit
+pub const MAX_ALLOCATIONS_PER_UPLOAD: usize = 1000;
+// Claim memos are stored with the claim, so their length is capped
+pub const MAX_MEMO_BYTES: usize = 256;
+// History keeps the latest snapshots only
+pub const HISTORY_CAPACITY: u64 = 100;
+// Lottery prizes are paid in the draw msg, so the winners are capped
+pub const MAX_LOTTERY_WINNERS: u32 = 100;
+// Lottery seeds are revealed within about a week of the draw height
+pub const LOTTERY_REVEAL_BLOCKS: u64 = 100_800;
+// Proof levels of 1024 nodes drop the top 10 nodes of each proof
+pub const MAX_PROOF_LEVEL_NODES: usize = 1024;
+// Pagination info for claim queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -31,213 +117,4158 @@ pub fn instantiate(
     let owner = msg
         .owner
         .map_or(Ok(info.sender), |o| deps.api.addr_validate(&o))?;
+    let treasury = msg
+        .treasury
+        .map_or(Ok(owner.clone()), |t| deps.api.addr_validate(&t))?;
+    let guardian = msg
+        .guardian
+        .map(|g| deps.api.addr_validate(&g))
+        .transpose()?;
+    let passport = msg
+        .passport
+        .map(|p| deps.api.addr_validate(&p))
+        .transpose()?;
+    let receipt_nft = msg
+        .receipt_nft
+        .map(|r| deps.api.addr_validate(&r))
+        .transpose()?;
+    // tiers are sorted by their claims count
+    let milestone_tiers = msg.milestone_tiers.unwrap_or_default();
+    let sorted = milestone_tiers
+        .windows(2)
+        .all(|pair| pair[0].until_claims < pair[1].until_claims);
+    let valid = milestone_tiers
+        .iter()
+        .all(|tier| tier.until_claims > 0 && !tier.multiplier.is_zero());
+    if !sorted || !valid {
+        return Err(ContractError::InvalidInput {});
+    }
+    if msg.coefficient_down > msg.coefficient_up {
+        return Err(ContractError::InvalidInput {});
+    }
+    let liquid_share = msg.liquid_share.unwrap_or_else(Decimal::one);
+    if liquid_share > Decimal::one() {
+        return Err(ContractError::InvalidInput {});
+    }
+    let referral_share = msg.referral_share.unwrap_or_default();
+    if referral_share > Decimal::one() {
+        return Err(ContractError::InvalidInput {});
+    }
+    let referral_balance = msg.referral_balance.unwrap_or_default();
+    let claim_minimum = msg.claim_minimum.unwrap_or_default();
+    let claim_maximum = msg.claim_maximum.unwrap_or_default();
+    if !claim_maximum.is_zero() && claim_minimum > claim_maximum {
+        return Err(ContractError::InvalidInput {});
+    }
+    let fee_recipient = msg
+        .fee_recipient
+        .map(|f| deps.api.addr_validate(&f))
+        .transpose()?;
+    let fee_bps = msg.fee_bps.unwrap_or_default();
+    assert_fee(fee_recipient.as_ref(), fee_bps)?;
+    // campaign must not have ended at instantiation
+    let campaign_end = msg.campaign_end.filter(|end| *end != Expiration::Never {});
+    if campaign_end.is_some_and(|end| end.is_expired(&env.block)) {
+        return Err(ContractError::InvalidInput {});
+    }
+    let root_guardians = validate_root_guardians(
+        deps.as_ref(),
+        msg.root_guardians.unwrap_or_default(),
+        msg.root_guardian_threshold.unwrap_or_default(),
+    )?;
 
-    if !has_coins(
-        &info.funds,
-        &Coin {
-            denom: msg.allowed_native.clone(),
-            amount: msg.initial_balance,
-        },
-    ) {
+    let gift_asset = match msg.cw20_token {
+        Some(token) => GiftAsset::Cw20(deps.api.addr_validate(&token)?),
+        None => GiftAsset::Native(msg.allowed_native.clone()),
+    };
+
+    // native gift and referral pool must be funded at instantiation
+    let native_funds = msg.initial_balance + referral_balance;
+    if matches!(gift_asset, GiftAsset::Native(_))
+        && !native_funds.is_zero()
+        && !has_coins(
+            &info.funds,
+            &Coin {
+                denom: msg.allowed_native.clone(),
+                amount: native_funds,
+            },
+        )
+    {
         return Err(ContractError::InvalidInput {});
     }
+    let mut extra_balances: Vec<DenomBalance> = vec![];
+    for coin in msg.extra_balances {
+        if !has_coins(&info.funds, &coin) || extra_balances.iter().any(|b| b.denom == coin.denom) {
+            return Err(ContractError::InvalidInput {});
+        }
+        extra_balances.push(DenomBalance {
+            denom: coin.denom,
+            initial_balance: coin.amount,
+            current_balance: coin.amount,
+        });
+    }
+
+    // cw20 gift and referral balances are topped up through the receive hook
+    let (current_balance, referral_balance) = match gift_asset {
+        GiftAsset::Native(_) => (msg.initial_balance, referral_balance),
+        GiftAsset::Cw20(_) => (Uint128::zero(), Uint128::zero()),
+    };
 
     let config = Config {
         owner: Some(owner),
-        allowed_native: msg.allowed_native,
-        current_balance: msg.initial_balance,
+        pending_owner: None,
+        gift_asset,
+        extra_balances,
+        current_balance,
         initial_balance: msg.initial_balance,
         coefficient_up: msg.coefficient_up,
         coefficient_down: msg.coefficient_down,
-        coefficient: msg.coefficient,
+        coefficient_claims: msg.coefficient_claims.unwrap_or_default(),
+        eth_chain_id: msg.eth_chain_id,
+        treasury,
+        guardian,
+        passport,
+        target_prefix: msg.target_prefix,
+        paused: false,
+        liquid_share,
+        release_blocks: msg.release_blocks.unwrap_or_default(),
+        activation_threshold: msg.activation_threshold.unwrap_or_default(),
+        referral_share,
+        referral_balance,
+        max_claims_per_block: msg.max_claims_per_block.unwrap_or_default(),
+        claim_minimum,
+        claim_maximum,
+        fee_recipient,
+        fee_bps,
+        ibc_channels: msg.ibc_channels,
+        ibc_timeout_seconds: msg
+            .ibc_timeout_seconds
+            .unwrap_or(DEFAULT_IBC_TIMEOUT_SECONDS),
+        ibc_controller: msg.ibc_controller,
+        ibc_connection_id: msg.ibc_connection_id,
+        unbonding_seconds: msg.unbonding_seconds.unwrap_or(DEFAULT_UNBONDING_SECONDS),
+        claim_hook: None,
+        root_timelock_seconds: msg.root_timelock_seconds.unwrap_or_default(),
+        root_guardians,
+        root_guardian_threshold: msg.root_guardian_threshold.unwrap_or_default(),
+        linked_claim_policy: msg.linked_claim_policy.unwrap_or_default(),
+        campaign_end,
+        verified_claims: msg.verified_claims.unwrap_or_default(),
+        receipt_nft,
+        milestone_tiers,
+        cosmos_prefixes: msg.cosmos_prefixes.unwrap_or_else(|| {
+            DEFAULT_COSMOS_PREFIXES
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect()
+        }),
+        history_interval: msg.history_interval.unwrap_or_default(),
+        lottery_ticket_size: msg.lottery_ticket_size.unwrap_or_default(),
+        verbose_errors: msg.verbose_errors.unwrap_or_default(),
     };
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::default())
+    CLAIMS_COUNT.save(deps.storage, &0)?;
+    if config.activation_threshold == 0 {
+        ACTIVATION_BLOCK.save(deps.storage, &env.block.height)?;
+    }
+
+    let stage = 0;
+    LATEST_STAGE.save(deps.storage, &stage)?;
+
+    match msg.first_stage {
+        Some(params) => submit_root(deps.branch(), &env, &config, params),
+        None => Ok(Response::default()),
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    // admin actions are recorded in the audit log once they succeed
+    let audit = match audit_action(&msg) {
+        Some(action) => Some((action, info.sender.to_string(), audit_params(&msg)?)),
+        None => None,
+    };
+    let res = dispatch(deps.branch(), env.clone(), info, msg)?;
+    if let Some((action, actor, params)) = audit {
+        record_audit(deps.storage, &env, actor, action, params)?;
+    }
+    Ok(res)
+}
+
+/// Returns the audit log action of the admin msgs.
+fn audit_action(msg: &ExecuteMsg) -> Option<&'static str> {
+    let action = match msg {
+        ExecuteMsg::UpdateConfig { .. } => "update_config",
+        ExecuteMsg::ProposeNewOwner { .. } => "propose_new_owner",
+        ExecuteMsg::RevokeOwnershipProposal {} => "revoke_ownership_proposal",
+        ExecuteMsg::RenounceOwnership {} => "renounce_ownership",
+        ExecuteMsg::AddHook { .. } => "add_hook",
+        ExecuteMsg::RemoveHook { .. } => "remove_hook",
+        ExecuteMsg::SetClaimHook { .. } => "set_claim_hook",
+        ExecuteMsg::ApproveContractWalletClaim { .. } => "approve_contract_wallet_claim",
+        ExecuteMsg::RegisterMerkleRoot { .. } => "register_merkle_root",
+        ExecuteMsg::ApproveRoot { .. } => "approve_root",
+        ExecuteMsg::UploadAllocations { .. } => "upload_allocations",
+        ExecuteMsg::SetStageRelease { .. } => "set_stage_release",
+        ExecuteMsg::RegisterProofLevel { .. } => "register_proof_level",
+        ExecuteMsg::SetStagePassportAge { .. } => "set_stage_passport_age",
+        ExecuteMsg::FreezeStage { .. } => "freeze_stage",
+        ExecuteMsg::UnfreezeStage { .. } => "unfreeze_stage",
+        ExecuteMsg::ReplaceGuardian { .. } => "replace_guardian",
+        ExecuteMsg::Clawback { .. } => "clawback",
+        ExecuteMsg::Pause {} => "pause",
+        ExecuteMsg::Unpause {} => "unpause",
+        ExecuteMsg::Fund {} => "fund",
+        ExecuteMsg::SweepDust {} => "sweep_dust",
+        ExecuteMsg::CommitLottery { .. } => "commit_lottery",
+        ExecuteMsg::DrawLottery { .. } => "draw_lottery",
+        ExecuteMsg::ExpireLottery {} => "expire_lottery",
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Returns the JSON msg recorded as the parameters of an admin action, uploaded
+/// allocations are recorded by their count.
+fn audit_params(msg: &ExecuteMsg) -> StdResult<String> {
+    let params = match msg {
+        ExecuteMsg::UploadAllocations { stage, entries } => {
+            format!(r#"{{"stage":{},"entries":{}}}"#, stage, entries.len())
+        }
+        ExecuteMsg::RegisterProofLevel { stage, nodes } => {
+            format!(r#"{{"stage":{},"nodes":{}}}"#, stage, nodes.len())
+        }
+        msg => String::from_utf8(to_vec(msg)?).map_err(StdError::invalid_utf8)?,
+    };
+    Ok(params)
+}
+
+fn record_audit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    actor: String,
+    action: &str,
+    params: String,
+) -> StdResult<()> {
+    let id = AUDIT_LOG_COUNT.may_load(storage)?.unwrap_or_default();
+    let entry = AuditEntry {
+        id,
+        height: env.block.height,
+        actor,
+        action: action.to_string(),
+        params,
+    };
+    AUDIT_LOG.save(storage, id, &entry)?;
+    AUDIT_LOG_COUNT.save(storage, &(id + 1))
+}
+
+fn dispatch(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { new_owner } => execute_update_config(deps, env, info, new_owner),
-        ExecuteMsg::RegisterMerkleRoot { merkle_root } => {
-            execute_register_merkle_root(deps, env, info, merkle_root)
+        ExecuteMsg::UpdateConfig {
+            new_treasury,
+            new_passport,
+            new_coefficient_up,
+            new_coefficient_down,
+            new_coefficient_claims,
+            new_max_claims_per_block,
+            new_fee_recipient,
+            new_fee_bps,
+            new_ibc_channels,
+            new_ibc_timeout_seconds,
+            new_ibc_controller,
+            new_ibc_connection_id,
+            new_root_timelock_seconds,
+            new_linked_claim_policy,
+            new_cosmos_prefixes,
+        } => execute_update_config(
+            deps,
+            env,
+            info,
+            new_treasury,
+            new_passport,
+            new_coefficient_up,
+            new_coefficient_down,
+            new_coefficient_claims,
+            new_max_claims_per_block,
+            new_fee_recipient,
+            new_fee_bps,
+            new_ibc_channels,
+            new_ibc_timeout_seconds,
+            new_ibc_controller,
+            new_ibc_connection_id,
+            new_root_timelock_seconds,
+            new_linked_claim_policy,
+            new_cosmos_prefixes,
+        ),
+        ExecuteMsg::ProposeNewOwner { new_owner } => {
+            execute_propose_new_owner(deps, env, info, new_owner)
         }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::RevokeOwnershipProposal {} => {
+            execute_revoke_ownership_proposal(deps, env, info)
+        }
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+        ExecuteMsg::AddHook { addr } => execute_add_hook(deps, env, info, addr),
+        ExecuteMsg::RemoveHook { addr } => execute_remove_hook(deps, env, info, addr),
+        ExecuteMsg::SetClaimHook { hook } => execute_set_claim_hook(deps, env, info, hook),
+        ExecuteMsg::ApproveContractWalletClaim {
+            stage,
+            gift_claiming_address,
+            target_addr,
+        } => execute_approve_contract_wallet_claim(
+            deps,
+            env,
+            info,
+            stage,
+            gift_claiming_address,
+            target_addr,
+        ),
+        ExecuteMsg::RegisterMerkleRoot {
+            merkle_root,
+            start,
+            expiration,
+            total_amount,
+            leaf_format,
+            hash_algo,
+        } => execute_register_merkle_root(
+            deps,
+            env,
+            info,
+            merkle_root,
+            start,
+            expiration,
+            total_amount,
+            leaf_format,
+            hash_algo,
+        ),
         ExecuteMsg::Claim {
+            stage,
             claim_msg,
             signature,
             claim_amount,
+            extra_amounts,
+            leaf_index,
+            proof,
+        } => execute_claim(
+            deps,
+            env,
+            info,
+            ClaimEntry {
+                stage,
+                claim_msg,
+                signature,
+                claim_amount,
+                extra_amounts,
+                leaf_index,
+                proof,
+            },
+        ),
+        ExecuteMsg::ClaimByNickname {
+            stage,
+            nickname,
+            gift_claiming_address_type,
+            gift_claiming_address,
+            claim_amount,
+            extra_amounts,
+            leaf_index,
             proof,
-        } => execute_claim(deps, env, info, claim_msg, signature, claim_amount, proof),
+        } => {
+            let claim_msg = ClaimMsg::passport(
+                &nickname,
+                gift_claiming_address_type,
+                gift_claiming_address,
+                info.sender.to_string(),
+                &env,
+            );
+            let claim = ClaimEntry {
+                stage,
+                claim_msg,
+                signature: Proof::Passport { nickname },
+                claim_amount,
+                extra_amounts,
+                leaf_index,
+                proof,
+            };
+            execute_claim_by_nickname(deps, env, info, claim)
+        }
+        ExecuteMsg::AuthorizeClaimer { grantee } => {
+            execute_authorize_claimer(deps, env, info, grantee)
+        }
+        ExecuteMsg::RevokeClaimer {} => execute_revoke_claimer(deps, env, info),
+        ExecuteMsg::Release {} => execute_release(deps, env, info),
+        ExecuteMsg::ReleaseAll { addresses } => execute_release_all(deps, env, info, addresses),
+        ExecuteMsg::ReleaseFor { address } => execute_release_for(deps, env, info, address),
+        ExecuteMsg::Undelegate { validator, amount } => {
+            execute_undelegate(deps, env, info, validator, amount)
+        }
+        ExecuteMsg::WithdrawUnbonded {} => execute_withdraw_unbonded(deps, env, info),
+        ExecuteMsg::ApproveRoot { root_hash } => execute_approve_root(deps, env, info, root_hash),
+        ExecuteMsg::ActivateRoot { merkle_root } => {
+            execute_activate_root(deps, env, info, merkle_root)
+        }
+        ExecuteMsg::UploadAllocations { stage, entries } => {
+            execute_upload_allocations(deps, env, info, stage, entries)
+        }
+        ExecuteMsg::SetStageRelease { stage, release } => {
+            execute_set_stage_release(deps, env, info, stage, release)
+        }
+        ExecuteMsg::RegisterProofLevel { stage, nodes } => {
+            execute_register_proof_level(deps, env, info, stage, nodes)
+        }
+        ExecuteMsg::SetStagePassportAge {
+            stage,
+            min_age_blocks,
+        } => execute_set_stage_passport_age(deps, env, info, stage, min_age_blocks),
+        ExecuteMsg::FreezeStage { stage } => execute_freeze_stage(deps, env, info, stage),
+        ExecuteMsg::UnfreezeStage { stage } => execute_unfreeze_stage(deps, env, info, stage),
+        ExecuteMsg::ReplaceGuardian { guardian } => {
+            execute_replace_guardian(deps, env, info, guardian)
+        }
+        ExecuteMsg::ClaimBatch { claims } => execute_claim_batch(deps, env, info, claims),
+        ExecuteMsg::VerifyClaim { claim } => execute_verify_claim(deps, env, info, claim),
+        ExecuteMsg::IndexClaims { stage, claims } => {
+            execute_index_claims(deps, env, info, stage, claims)
+        }
+        ExecuteMsg::Clawback { stage } => execute_clawback(deps, env, info, stage),
+        ExecuteMsg::EndCampaign {} => execute_end_campaign(deps, env),
+        ExecuteMsg::Pause {} => execute_set_paused(deps, env, info, true),
+        ExecuteMsg::Unpause {} => execute_set_paused(deps, env, info, false),
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Fund {} => execute_fund(deps, env, info),
+        ExecuteMsg::SweepDust {} => execute_sweep_dust(deps, env, info),
+        ExecuteMsg::RecalibrateCoefficient {} => execute_recalibrate_coefficient(deps, env, info),
+        ExecuteMsg::CommitLottery {
+            commitment,
+            winners,
+            prize,
+            draw_height,
+        } => execute_commit_lottery(deps, env, info, commitment, winners, prize, draw_height),
+        ExecuteMsg::SealLottery {} => execute_seal_lottery(deps, env),
+        ExecuteMsg::DrawLottery { seed } => execute_draw_lottery(deps, env, info, seed),
+        ExecuteMsg::ExpireLottery {} => execute_expire_lottery(deps, env),
+        #[cfg(any(test, feature = "dry-run"))]
+        ExecuteMsg::DryRun(msg) => execute_dry_run(deps, env, info, *msg),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_owner: Option<String>,
+    new_treasury: Option<String>,
+    new_passport: Option<String>,
+    new_coefficient_up: Option<Decimal>,
+    new_coefficient_down: Option<Decimal>,
+    new_coefficient_claims: Option<u64>,
+    new_max_claims_per_block: Option<u64>,
+    new_fee_recipient: Option<String>,
+    new_fee_bps: Option<u64>,
+    new_ibc_channels: Option<Vec<String>>,
+    new_ibc_timeout_seconds: Option<u64>,
+    new_ibc_controller: Option<String>,
+    new_ibc_connection_id: Option<String>,
+    new_root_timelock_seconds: Option<u64>,
+    new_linked_claim_policy: Option<LinkedClaimPolicy>,
+    new_cosmos_prefixes: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
     // authorize owner
     let cfg = CONFIG.load(deps.storage)?;
-    let owner = cfg.owner.ok_or(ContractError::Unauthorized {})?;
+    let owner = cfg.owner.clone().ok_or(ContractError::Unauthorized {})?;
     if info.sender != owner {
         return Err(ContractError::Unauthorized {});
     }
 
-    // if owner some validated to addr, otherwise set to none
-    let mut tmp_owner = None;
-    if let Some(addr) = new_owner {
-        tmp_owner = Some(deps.api.addr_validate(&addr)?)
+    // lowering the timelock would skip the audit of pending roots
+    if new_root_timelock_seconds.is_some_and(|t| t < cfg.root_timelock_seconds) {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    // coefficients are fixed once releases start
+    let coefficient_changed = new_coefficient_up.is_some()
+        || new_coefficient_down.is_some()
+        || new_coefficient_claims.is_some();
+    if coefficient_changed {
+        if ACTIVATION_BLOCK.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::Activated {});
+        }
+        let coefficient_up = new_coefficient_up.unwrap_or(cfg.coefficient_up);
+        let coefficient_down = new_coefficient_down.unwrap_or(cfg.coefficient_down);
+        // gifts must not be zeroed
+        if coefficient_up.is_zero() || coefficient_down > coefficient_up {
+            return Err(ContractError::InvalidInput {});
+        }
     }
+    // so are the prefixes of Cosmos claims
+    if new_cosmos_prefixes.is_some() && ACTIVATION_BLOCK.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::Activated {});
+    }
+
+    let tmp_treasury = new_treasury
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let tmp_passport = new_passport
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let tmp_fee_recipient = new_fee_recipient
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    assert_fee(
+        tmp_fee_recipient.as_ref().or(cfg.fee_recipient.as_ref()),
+        new_fee_bps.unwrap_or(cfg.fee_bps),
+    )?;
 
-    CONFIG.update(deps.storage, |mut exists| -> StdResult<_> {
-        exists.owner = tmp_owner;
+    let cfg = CONFIG.update(deps.storage, |mut exists| -> StdResult<_> {
+        if let Some(treasury) = tmp_treasury {
+            exists.treasury = treasury;
+        }
+        if tmp_passport.is_some() {
+            exists.passport = tmp_passport;
+        }
+        if let Some(coefficient_up) = new_coefficient_up {
+            exists.coefficient_up = coefficient_up;
+        }
+        if let Some(coefficient_down) = new_coefficient_down {
+            exists.coefficient_down = coefficient_down;
+        }
+        if let Some(coefficient_claims) = new_coefficient_claims {
+            exists.coefficient_claims = coefficient_claims;
+        }
+        if let Some(max_claims_per_block) = new_max_claims_per_block {
+            exists.max_claims_per_block = max_claims_per_block;
+        }
+        if tmp_fee_recipient.is_some() {
+            exists.fee_recipient = tmp_fee_recipient;
+        }
+        if let Some(fee_bps) = new_fee_bps {
+            exists.fee_bps = fee_bps;
+        }
+        if let Some(ibc_channels) = new_ibc_channels {
+            exists.ibc_channels = ibc_channels;
+        }
+        if let Some(ibc_timeout_seconds) = new_ibc_timeout_seconds {
+            exists.ibc_timeout_seconds = ibc_timeout_seconds;
+        }
+        if new_ibc_controller.is_some() {
+            exists.ibc_controller = new_ibc_controller;
+        }
+        if new_ibc_connection_id.is_some() {
+            exists.ibc_connection_id = new_ibc_connection_id;
+        }
+        if let Some(root_timelock_seconds) = new_root_timelock_seconds {
+            exists.root_timelock_seconds = root_timelock_seconds;
+        }
+        if let Some(linked_claim_policy) = new_linked_claim_policy {
+            exists.linked_claim_policy = linked_claim_policy;
+        }
+        if let Some(cosmos_prefixes) = new_cosmos_prefixes {
+            exists.cosmos_prefixes = cosmos_prefixes;
+        }
         Ok(exists)
     })?;
 
-    Ok(Response::new().add_attribute("action", "update_config"))
+    Ok(Response::new()
+        .add_event(config_updated_event(&cfg))
+        .add_attribute("action", "update_config"))
 }
 
-pub fn execute_register_merkle_root(
+pub fn execute_propose_new_owner(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    merkle_root: String,
+    new_owner: String,
 ) -> Result<Response, ContractError> {
-    let cfg = CONFIG.load(deps.storage)?;
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
 
-    // if owner set validate, otherwise unauthorized
-    let owner = cfg.owner.ok_or(ContractError::Unauthorized {})?;
-    if info.sender != owner {
+    let pending_owner = deps.api.addr_validate(&new_owner)?;
+    cfg.pending_owner = Some(pending_owner.clone());
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "propose_new_owner"),
+        attr("pending_owner", pending_owner),
+    ]))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+
+    // only the proposed owner can accept
+    let pending_owner = cfg
+        .pending_owner
+        .take()
+        .ok_or(ContractError::NoOwnershipProposal {})?;
+    if info.sender != pending_owner {
         return Err(ContractError::Unauthorized {});
     }
 
-    // check merkle root length
-    let mut root_buf: [u8; 32] = [0; 32];
-    hex::decode_to_slice(merkle_root.to_string(), &mut root_buf)?;
-
-    MERKLE_ROOT.save(deps.storage, &merkle_root)?;
+    cfg.owner = Some(pending_owner.clone());
+    CONFIG.save(deps.storage, &cfg)?;
 
     Ok(Response::new().add_attributes(vec![
-        attr("action", "register_merkle_root"),
-        attr("merkle_root", merkle_root),
+        attr("action", "accept_ownership"),
+        attr("owner", pending_owner),
     ]))
 }
 
-pub fn execute_claim(
+pub fn execute_revoke_ownership_proposal(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    claim_msg: ClaimMsg,
-    signature: Binary,
-    amount: Uint128,
-    proof: Vec<String>,
 ) -> Result<Response, ContractError> {
-    // verify not claimed
-    let claimed = CLAIM.may_load(deps.storage, claim_msg.target_addr.clone())?;
-    if claimed.is_some() {
-        return Err(ContractError::Claimed {});
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    if cfg.pending_owner.take().is_none() {
+        return Err(ContractError::NoOwnershipProposal {});
     }
+    CONFIG.save(deps.storage, &cfg)?;
 
-    let mut config = CONFIG.load(deps.storage)?;
-    let claim_amount = amount * config.coefficient;
+    Ok(Response::new().add_attribute("action", "revoke_ownership_proposal"))
+}
 
-    is_eligible(deps.as_ref(), &config, &claim_msg, signature, claim_amount)?;
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
 
-    verify_merkle_proof(&deps, &info, amount, proof)?;
+    cfg.owner = None;
+    cfg.pending_owner = None;
+    CONFIG.save(deps.storage, &cfg)?;
 
-    // Update claim index to the current stage
-    CLAIM.save(deps.storage, claim_msg.target_addr.clone(), &true)?;
-
-    // Update coefficient
-    update_coefficient(deps, amount, &mut config)?;
-
-    let res = Response::new()
-        .add_message(BankMsg::Send {
-            to_address: claim_msg.target_addr,
-            amount: vec![Coin {
-                denom: config.allowed_native,
-                amount: claim_amount,
-            }],
-        })
-        .add_attributes(vec![
-            attr("action", "claim"),
-            attr("address", info.sender),
-            attr("amount", amount),
-        ]);
-    Ok(res)
+    Ok(Response::new().add_attribute("action", "renounce_ownership"))
 }
 
-fn is_eligible(
-    deps: Deps,
-    cfg: &Config,
-    claim_msg: &ClaimMsg,
-    signature: Binary,
-    claim_amount: Uint128,
-) -> Result<bool, ContractError> {
-    if cfg.current_balance < claim_amount {
-        return Err(ContractError::IsNotEligible {
-            msg: "".to_string(),
-        });
-    }
-    match claim_msg.gift_claiming_address_type {
-        ClaimerType::Ethereum {} => helpers::verify_eth(deps, &claim_msg, signature),
-        ClaimerType::Cosmos => verify_cosmos(deps, &claim_msg, signature),
-        _ => Err(ContractError::IsNotEligible {
-            msg: "address prefix not allowed".to_string(),
-        }),
+fn assert_owner(cfg: &Config, info: &MessageInfo) -> Result<(), ContractError> {
+    // if owner set validate, otherwise unauthorized
+    match &cfg.owner {
+        Some(owner) if *owner == info.sender => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
     }
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::MerkleRoot {} => to_binary(&query_merkle_root(deps)?),
-        QueryMsg::IsClaimed { address } => to_binary(&query_is_claimed(deps, address)?),
+pub fn execute_approve_contract_wallet_claim(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stage: u8,
+    gift_claiming_address: String,
+    target_addr: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    if !MERKLE_ROOT.has(deps.storage, stage) {
+        return Err(ContractError::InvalidInput {});
     }
+    CONTRACT_WALLET_CLAIMS.save(
+        deps.storage,
+        (gift_claiming_address.clone(), stage),
+        &target_addr,
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "approve_contract_wallet_claim"),
+        attr("stage", stage.to_string()),
+        attr("address", gift_claiming_address),
+        attr("target_addr", target_addr),
+    ]))
 }
 
-pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+pub fn execute_add_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
-    Ok(ConfigResponse {
-        owner: cfg.owner.map(|o| o.to_string()),
-        allowed_native: cfg.allowed_native,
-        current_balance: cfg.current_balance,
-        initial_balance: cfg.initial_balance,
-        coefficient_up: cfg.coefficient_up,
-        coefficient_down: cfg.coefficient_down,
-        coefficient: cfg.coefficient,
-    })
+    assert_owner(&cfg, &info)?;
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.add_hook(deps.storage, hook)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "add_hook"), attr("hook", addr)]))
 }
 
-pub fn query_merkle_root(deps: Deps) -> StdResult<MerkleRootResponse> {
-    let merkle_root = MERKLE_ROOT.load(deps.storage)?;
-    let resp = MerkleRootResponse { merkle_root };
+pub fn execute_remove_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
 
-    Ok(resp)
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.remove_hook(deps.storage, hook)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "remove_hook"), attr("hook", addr)]))
 }
 
-pub fn query_is_claimed(deps: Deps, address: String) -> StdResult<IsClaimedResponse> {
-    let is_claimed = CLAIM.may_load(deps.storage, address)?.unwrap_or(false);
-    let resp = IsClaimedResponse { is_claimed };
+pub fn execute_set_claim_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    hook: Option<ClaimHookMsg>,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
 
-    Ok(resp)
+    cfg.claim_hook = hook
+        .map(|h| -> StdResult<_> {
+            Ok(ClaimHook {
+                contract: deps.api.addr_validate(&h.contract)?,
+                msg: h.msg,
+            })
+        })
+        .transpose()?;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_claim_hook"),
+        attr(
+            "contract",
+            cfg.claim_hook
+                .map_or_else(|| "none".to_string(), |h| h.contract.to_string()),
+        ),
+    ]))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version = get_contract_version(deps.storage)?;
-    if version.contract != CONTRACT_NAME {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: version.contract,
+#[allow(clippy::too_many_arguments)]
+pub fn execute_register_merkle_root(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    merkle_root: String,
+    start: Option<Expiration>,
+    expiration: Option<Expiration>,
+    total_amount: Uint128,
+    leaf_format: Option<LeafFormat>,
+    hash_algo: Option<HashAlgo>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // if owner set validate, otherwise unauthorized
+    let owner = cfg.owner.as_ref().ok_or(ContractError::Unauthorized {})?;
+    if info.sender != *owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let params = StageParams {
+        merkle_root,
+        start,
+        expiration,
+        total_amount,
+        leaf_format,
+        hash_algo,
+    };
+    submit_root(deps, &env, &cfg, params)
+}
+
+fn submit_root(
+    deps: DepsMut,
+    env: &Env,
+    cfg: &Config,
+    params: StageParams,
+) -> Result<Response, ContractError> {
+    let merkle_root = params.merkle_root.clone();
+    let total_amount = params.total_amount;
+    let res = match submit_stage(deps, env, cfg, params)? {
+        SubmittedStage::Pending { activate_at } => Response::new().add_attributes(vec![
+            attr("action", "register_merkle_root"),
+            attr("merkle_root", merkle_root),
+            attr("activate_at", activate_at.to_string()),
+        ]),
+        SubmittedStage::Registered { stage } => Response::new()
+            .add_event(root_registered_event(stage, &merkle_root, total_amount))
+            .add_attributes(vec![
+                attr("action", "register_merkle_root"),
+                attr("stage", stage.to_string()),
+                attr("merkle_root", merkle_root),
+            ]),
+    };
+    Ok(res)
+}
+
+/// SubmittedStage is the stage a submitted root is registered as, or the time its
+/// pending root can be activated at.
+pub(crate) enum SubmittedStage {
+    Registered { stage: u8 },
+    Pending { activate_at: Timestamp },
+}
+
+/// Registers the root as the next stage, or as a pending root if the config has a
+/// root timelock or guardians. Roots of the owner and of the IBC controller alike.
+pub(crate) fn submit_stage(
+    deps: DepsMut,
+    env: &Env,
+    cfg: &Config,
+    params: StageParams,
+) -> Result<SubmittedStage, ContractError> {
+    let StageParams {
+        merkle_root,
+        start,
+        expiration,
+        total_amount,
+        leaf_format,
+        hash_algo,
+    } = params;
+
+    // roots are claimable once the community had time to audit the tree and the
+    // guardians approved it
+    if cfg.root_timelock_seconds > 0 || cfg.root_guardian_threshold > 0 {
+        validate_merkle_root(&merkle_root)?;
+        if total_amount.is_zero() {
+            return Err(ContractError::InvalidInput {});
+        }
+        if PENDING_ROOTS.has(deps.storage, merkle_root.clone()) {
+            return Err(ContractError::InvalidInput {});
+        }
+        let activate_at = env.block.time.plus_seconds(cfg.root_timelock_seconds);
+        PENDING_ROOTS.save(
+            deps.storage,
+            merkle_root.clone(),
+            &PendingRoot {
+                merkle_root: merkle_root.clone(),
+                start,
+                expiration,
+                total_amount,
+                leaf_format,
+                hash_algo,
+                activate_at,
+                approvals: vec![],
+            },
+        )?;
+        return Ok(SubmittedStage::Pending { activate_at });
+    }
+
+    let stage = register_stage(
+        deps,
+        env,
+        &merkle_root,
+        start,
+        expiration,
+        total_amount,
+        leaf_format,
+        hash_algo,
+    )?;
+    Ok(SubmittedStage::Registered { stage })
+}
+
+pub fn execute_activate_root(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    merkle_root: String,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_ROOTS
+        .may_load(deps.storage, merkle_root.clone())?
+        .ok_or(ContractError::InvalidInput {})?;
+    if env.block.time < pending.activate_at {
+        return Err(ContractError::RootTimelocked {
+            activate_at: pending.activate_at,
+        });
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+    let approvals = root_approvals(&cfg, &pending.approvals);
+    if approvals < cfg.root_guardian_threshold {
+        return Err(ContractError::RootNotApproved {
+            approvals,
+            threshold: cfg.root_guardian_threshold,
         });
     }
-    Ok(Response::default())
+    PENDING_ROOTS.remove(deps.storage, merkle_root.clone());
+
+    let stage = register_stage(
+        deps,
+        &env,
+        &merkle_root,
+        pending.start,
+        pending.expiration,
+        pending.total_amount,
+        pending.leaf_format,
+        pending.hash_algo,
+    )?;
+
+    Ok(Response::new()
+        .add_event(root_registered_event(
+            stage,
+            &merkle_root,
+            pending.total_amount,
+        ))
+        .add_attributes(vec![
+            attr("action", "activate_root"),
+            attr("stage", stage.to_string()),
+            attr("merkle_root", merkle_root),
+        ]))
+}
+
+pub fn execute_approve_root(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    root_hash: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.root_guardians.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut pending = PENDING_ROOTS
+        .may_load(deps.storage, root_hash.clone())?
+        .ok_or(ContractError::InvalidInput {})?;
+    if pending.approvals.contains(&info.sender) {
+        return Err(ContractError::InvalidInput {});
+    }
+    pending.approvals.push(info.sender.clone());
+    PENDING_ROOTS.save(deps.storage, root_hash.clone(), &pending)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "approve_root"),
+        attr("merkle_root", root_hash),
+        attr("guardian", info.sender),
+        attr(
+            "approvals",
+            root_approvals(&cfg, &pending.approvals).to_string(),
+        ),
+    ]))
+}
+
+/// Counts the approvals of a pending root or unfreeze by the current root guardians.
+fn root_approvals(cfg: &Config, approvals: &[Addr]) -> u64 {
+    approvals
+        .iter()
+        .filter(|a| cfg.root_guardians.contains(a))
+        .count() as u64
+}
+
+/// Validates the root guardians, the threshold can not exceed the set.
+fn validate_root_guardians(
+    deps: Deps,
+    guardians: Vec<String>,
+    threshold: u64,
+) -> Result<Vec<Addr>, ContractError> {
+    let mut root_guardians: Vec<Addr> = vec![];
+    for guardian in guardians {
+        let guardian = deps.api.addr_validate(&guardian)?;
+        if root_guardians.contains(&guardian) {
+            return Err(ContractError::InvalidInput {});
+        }
+        root_guardians.push(guardian);
+    }
+    if threshold > root_guardians.len() as u64 {
+        return Err(ContractError::InvalidInput {});
+    }
+    Ok(root_guardians)
+}
+
+/// Checks the merkle root is 32 hex encoded bytes, stages of uploaded allocations only
+/// have an empty root.
+fn validate_merkle_root(merkle_root: &str) -> Result<(), ContractError> {
+    if !merkle_root.is_empty() {
+        let mut root_buf: [u8; 32] = [0; 32];
+        hex::decode_to_slice(merkle_root, &mut root_buf)?;
+    }
+    Ok(())
+}
+
+pub fn execute_upload_allocations(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stage: u8,
+    entries: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    // allocations skip the proof, so they only fill stages without a root and can not be
+    // changed once the stage is claimed
+    if !MERKLE_ROOT.load(deps.storage, stage)?.is_empty() {
+        return Err(ContractError::AllocationsNotAllowed {
+            stage,
+            msg: "stage has a merkle root".to_string(),
+        });
+    }
+    let started = STAGE_START
+        .may_load(deps.storage, stage)?
+        .is_none_or(|start| start.is_expired(&env.block));
+    if started {
+        return Err(ContractError::AllocationsNotAllowed {
+            stage,
+            msg: "stage has begun".to_string(),
+        });
+    }
+    if entries.len() > MAX_ALLOCATIONS_PER_UPLOAD {
+        return Err(ContractError::TooManyAllocations {
+            entries: entries.len(),
+            max_allocations: MAX_ALLOCATIONS_PER_UPLOAD,
+        });
+    }
+    let mut allocated = STAGE_ALLOCATED
+        .may_load(deps.storage, stage)?
+        .unwrap_or_default();
+    let mut uploaded = BTreeMap::new();
+    for (index, (address, amount)) in entries.iter().enumerate() {
+        if address.is_empty() {
+            return Err(ContractError::InvalidAllocation {
+                index,
+                msg: "address is empty".to_string(),
+            });
+        }
+        if amount.is_zero() {
+            return Err(ContractError::InvalidAllocation {
+                index,
+                msg: "amount is zero".to_string(),
+            });
+        }
+        // uploading an address again replaces its allocation
+        let previous = match uploaded.get(address.as_str()) {
+            Some(previous) => *previous,
+            None => ALLOCATIONS
+                .may_load(deps.storage, (stage, address.clone()))?
+                .unwrap_or_default(),
+        };
+        allocated = allocated
+            .checked_sub(previous)
+            .and_then(|allocated| allocated.checked_add(*amount))
+            .map_err(StdError::from)?;
+        uploaded.insert(address.as_str(), *amount);
+    }
+    let total_amount = STAGE_AMOUNT.load(deps.storage, stage)?;
+    if allocated > total_amount {
+        return Err(ContractError::AllocationsOverTotal {
+            stage,
+            allocated,
+            total_amount,
+        });
+    }
+    for (address, amount) in uploaded {
+        ALLOCATIONS.save(deps.storage, (stage, address.to_string()), &amount)?;
+    }
+    STAGE_ALLOCATED.save(deps.storage, stage, &allocated)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "upload_allocations"),
+        attr("stage", stage.to_string()),
+        attr("entries", entries.len().to_string()),
+    ]))
+}
+
+pub fn execute_register_proof_level(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stage: u8,
+    nodes: Vec<ProofNode>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    let merkle_root = MERKLE_ROOT
+        .may_load(deps.storage, stage)?
+        .unwrap_or_default();
+    if merkle_root.is_empty()
+        || STAGE_PROOF_LEVEL.has(deps.storage, stage)
+        || nodes.len() < 2
+        || nodes.len() > MAX_PROOF_LEVEL_NODES
+    {
+        return Err(ContractError::InvalidInput {});
+    }
+    let leaf_format = STAGE_LEAF_FORMAT
+        .may_load(deps.storage, stage)?
+        .unwrap_or_default();
+    let hash_algo = stage_hash_algo(deps.storage, stage, &leaf_format)?;
+    if helpers::merkle_level_root(hash_algo, &nodes)?.to_vec() != hex::decode(&merkle_root)? {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    for node in nodes.iter() {
+        PROOF_LEVEL_NODES.save(deps.storage, (stage, node.0.to_vec()), &true)?;
+    }
+    STAGE_PROOF_LEVEL.save(deps.storage, stage, &(nodes.len() as u32))?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_proof_level"),
+        attr("stage", stage.to_string()),
+        attr("nodes", nodes.len().to_string()),
+    ]))
+}
+
+pub fn execute_freeze_stage(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stage: u8,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let is_guardian =
+        cfg.guardian.as_ref() == Some(&info.sender) || cfg.root_guardians.contains(&info.sender);
+    if !is_guardian {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !MERKLE_ROOT.has(deps.storage, stage) || STAGE_FREEZES.has(deps.storage, stage) {
+        return Err(ContractError::InvalidInput {});
+    }
+    let freeze = StageFreeze {
+        frozen_by: info.sender.clone(),
+        frozen_at_height: env.block.height,
+        unfreeze_approvals: vec![],
+    };
+    STAGE_FREEZES.save(deps.storage, stage, &freeze)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "freeze_stage"),
+        attr("stage", stage.to_string()),
+        attr("guardian", info.sender),
+    ]))
+}
+
+pub fn execute_unfreeze_stage(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stage: u8,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut freeze = STAGE_FREEZES
+        .may_load(deps.storage, stage)?
+        .ok_or(ContractError::InvalidInput {})?;
+
+    if assert_owner(&cfg, &info).is_err() {
+        if !cfg.root_guardians.contains(&info.sender) || cfg.root_guardian_threshold == 0 {
+            return Err(ContractError::Unauthorized {});
+        }
+        if freeze.unfreeze_approvals.contains(&info.sender) {
+            return Err(ContractError::InvalidInput {});
+        }
+        freeze.unfreeze_approvals.push(info.sender.clone());
+        let approvals = root_approvals(&cfg, &freeze.unfreeze_approvals);
+        if approvals < cfg.root_guardian_threshold {
+            STAGE_FREEZES.save(deps.storage, stage, &freeze)?;
+            return Ok(Response::new().add_attributes(vec![
+                attr("action", "approve_unfreeze_stage"),
+                attr("stage", stage.to_string()),
+                attr("guardian", info.sender),
+                attr("approvals", approvals.to_string()),
+            ]));
+        }
+    }
+    STAGE_FREEZES.remove(deps.storage, stage);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "unfreeze_stage"),
+        attr("stage", stage.to_string()),
+    ]))
+}
+
+pub fn execute_replace_guardian(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let guardian = guardian
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    // the owner proposes the guardian, root guardians approve the proposed one
+    let mut pending = if assert_owner(&cfg, &info).is_ok() {
+        PendingGuardian {
+            guardian,
+            approvals: vec![],
+        }
+    } else {
+        if !cfg.root_guardians.contains(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        let pending = PENDING_GUARDIAN
+            .may_load(deps.storage)?
+            .ok_or(ContractError::InvalidInput {})?;
+        if pending.guardian != guardian || pending.approvals.contains(&info.sender) {
+            return Err(ContractError::InvalidInput {});
+        }
+        pending
+    };
+    if cfg.root_guardians.contains(&info.sender) {
+        pending.approvals.push(info.sender.clone());
+    }
+    let approvals = root_approvals(&cfg, &pending.approvals);
+    if approvals < cfg.root_guardian_threshold {
+        PENDING_GUARDIAN.save(deps.storage, &pending)?;
+        return Ok(Response::new().add_attributes(vec![
+            attr("action", "propose_guardian"),
+            attr("guardian", guardian_attr(&pending.guardian)),
+            attr("approvals", approvals.to_string()),
+        ]));
+    }
+    PENDING_GUARDIAN.remove(deps.storage);
+    cfg.guardian = pending.guardian;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "replace_guardian"),
+        attr("guardian", guardian_attr(&cfg.guardian)),
+    ]))
+}
+
+fn guardian_attr(guardian: &Option<Addr>) -> String {
+    guardian
+        .as_ref()
+        .map_or_else(|| "none".to_string(), |g| g.to_string())
+}
+
+pub fn execute_set_stage_passport_age(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stage: u8,
+    min_age_blocks: Option<u64>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    // claims of a stage are checked against the same passport age
+    let claimed = STAGE_AMOUNT_CLAIMED.may_load(deps.storage, stage)?;
+    if claimed.is_none_or(|claimed| !claimed.is_zero()) {
+        return Err(ContractError::InvalidInput {});
+    }
+    let cutoff = match min_age_blocks {
+        Some(min_age_blocks) => {
+            let start = match STAGE_START.may_load(deps.storage, stage)? {
+                Some(Expiration::AtHeight(height)) => height,
+                _ => env.block.height,
+            };
+            let cutoff = start.saturating_sub(min_age_blocks);
+            STAGE_PASSPORT_CUTOFF.save(deps.storage, stage, &cutoff)?;
+            cutoff.to_string()
+        }
+        None => {
+            STAGE_PASSPORT_CUTOFF.remove(deps.storage, stage);
+            "none".to_string()
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_stage_passport_age"),
+        attr("stage", stage.to_string()),
+        attr("passport_created_before", cutoff),
+    ]))
+}
+
+pub fn execute_set_stage_release(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stage: u8,
+    release: StageRelease,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    // payouts of a claimed stage keep their split
+    let claimed = STAGE_AMOUNT_CLAIMED.may_load(deps.storage, stage)?;
+    if claimed.is_none_or(|claimed| !claimed.is_zero()) {
+        return Err(ContractError::InvalidInput {});
+    }
+    // shares sum up to one, a vested part needs blocks to be released over
+    let vested = !release.vested_share.is_zero();
+    if release.liquid_share > Decimal::one()
+        || release.vested_share != Decimal::one() - release.liquid_share
+        || vested != (release.release_blocks > 0)
+    {
+        return Err(ContractError::InvalidInput {});
+    }
+    STAGE_RELEASE.save(deps.storage, stage, &release)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_stage_release"),
+        attr("stage", stage.to_string()),
+        attr("liquid_share", release.liquid_share.to_string()),
+        attr("release_blocks", release.release_blocks.to_string()),
+    ]))
+}
+
+/// Registers the merkle root as the next stage and returns the stage, the root and
+/// stage times are validated before anything is saved.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn register_stage(
+    deps: DepsMut,
+    env: &Env,
+    merkle_root: &str,
+    start: Option<Expiration>,
+    expiration: Option<Expiration>,
+    total_amount: Uint128,
+    leaf_format: Option<LeafFormat>,
+    hash_algo: Option<HashAlgo>,
+) -> Result<u8, ContractError> {
+    validate_merkle_root(merkle_root)?;
+    // the gift of the stage is what clawbacks return, a stage always has one
+    if total_amount.is_zero() {
+        return Err(ContractError::InvalidInput {});
+    }
+    // keccak256 leaves are named after their hash algo
+    let leaf_format = leaf_format.unwrap_or_default();
+    let hash_algo = hash_algo.unwrap_or_else(|| leaf_format.default_hash_algo());
+    if leaf_format == LeafFormat::Keccak256 && hash_algo != HashAlgo::Keccak256 {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let stage = LATEST_STAGE
+        .load(deps.storage)?
+        .checked_add(1)
+        .ok_or_else(|| StdError::generic_err("stage overflow"))?;
+
+    // a stage which never starts can not be claimed
+    if start == Some(Expiration::Never {}) {
+        return Err(ContractError::InvalidInput {});
+    }
+    if let Some(expiration) = expiration {
+        if expiration.is_expired(&env.block) {
+            return Err(ContractError::StageExpired { stage, expiration });
+        }
+    }
+
+    LATEST_STAGE.save(deps.storage, &stage)?;
+    MERKLE_ROOT.save(deps.storage, stage, &merkle_root.to_string())?;
+    if let Some(start) = start {
+        STAGE_START.save(deps.storage, stage, &start)?;
+    }
+    if let Some(expiration) = expiration {
+        STAGE_EXPIRATION.save(deps.storage, stage, &expiration)?;
+    }
+    STAGE_AMOUNT.save(deps.storage, stage, &total_amount)?;
+    STAGE_AMOUNT_CLAIMED.save(deps.storage, stage, &Uint128::zero())?;
+    STAGE_LEAF_FORMAT.save(deps.storage, stage, &leaf_format)?;
+    STAGE_HASH_ALGO.save(deps.storage, stage, &hash_algo)?;
+    Ok(stage)
+}
+
+pub fn execute_claim(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    claim: ClaimEntry,
+) -> Result<Response, ContractError> {
+    assert_campaign_active(deps.as_ref(), &env)?;
+    let (send_msgs, attributes, event) = process_claim(deps.branch(), &env, &info.sender, claim)?;
+
+    let mut res = Response::new()
+        .add_submessages(send_msgs)
+        .add_event(event)
+        .add_attribute("action", "claim")
+        .add_attributes(attributes);
+    if let Some(event) = try_activate(deps, &env)? {
+        res = res.add_event(event);
+    }
+    Ok(res)
+}
+
+/// Verifies the signature and proof of a claim without writes, returning the debug
+/// details of a failure.
+pub fn query_diagnose_claim(
+    deps: Deps,
+    env: Env,
+    claim: ClaimEntry,
+) -> StdResult<ClaimDiagnosisResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.verbose_errors {
+        return Err(StdError::generic_err("verbose errors are disabled"));
+    }
+    let mut resp = ClaimDiagnosisResponse {
+        failure_reason: None,
+        proof_depth: claim.proof.len() as u32,
+        recovered_address: None,
+    };
+    if cfg.verified_claims && is_verified_claim(deps.storage, &claim)? {
+        return Ok(resp);
+    }
+    let indexed =
+        STAGE_LEAF_FORMAT.may_load(deps.storage, claim.stage)? == Some(LeafFormat::Indexed);
+    let claim_msg = &claim.claim_msg;
+    let verified = verify_claimer(
+        deps,
+        &env,
+        &cfg,
+        claim.stage,
+        claim_msg,
+        claim.signature.clone(),
+    )
+    .and_then(|_| {
+        verify_merkle_proof(
+            deps,
+            claim.stage,
+            &claim_msg.snapshot_key(),
+            claim.claim_amount,
+            &claim.extra_amounts,
+            claim.leaf_index.filter(|_| indexed),
+            claim.proof.clone(),
+        )
+    });
+    resp.failure_reason = verified.err().map(|err| err.to_string());
+    resp.recovered_address = match &claim.signature {
+        Proof::EthSig {
+            signature,
+            message_encoding,
+        } => helpers::recovered_eth_address(
+            deps,
+            claim_msg,
+            signature,
+            *message_encoding,
+            cfg.eth_chain_id,
+        ),
+        _ => None,
+    };
+    Ok(resp)
+}
+
+/// Claims the gift of an address linked in the passport with the nickname owned by
+/// the sender as a passport claim, so the linkage is verified again by the passport
+/// before the payout.
+pub fn execute_claim_by_nickname(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    claim: ClaimEntry,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let passport = cfg.passport.ok_or(ContractError::IsNotEligible {
+        msg: "passport contract is not set".to_string(),
+    })?;
+    let resp: PassportAddressesResponse = deps
+        .querier
+        .query_wasm_smart(
+            &passport,
+            &PassportQueryMsg::PassportByNickname {
+                nickname: claim.claim_msg.nickname.clone(),
+            },
+        )
+        .map_err(|err| ContractError::IsNotEligible {
+            msg: err.to_string(),
+        })?;
+    if resp.owner != info.sender.as_str() {
+        return Err(ContractError::Unauthorized {});
+    }
+    // the passport returns the keys of the linked addresses, lowercased 0x addresses
+    let address = &claim.claim_msg.gift_claiming_address;
+    if !resp
+        .addresses
+        .iter()
+        .any(|linked| address_key(linked) == address_key(address))
+    {
+        return Err(ContractError::AddressMismatch {
+            address: address.clone(),
+            msg: "address is not linked to the passport".to_string(),
+        });
+    }
+    execute_claim(deps, env, info, claim)
+}
+
+pub fn execute_authorize_claimer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    grantee: String,
+) -> Result<Response, ContractError> {
+    let grantee = deps.api.addr_validate(&grantee)?;
+    if grantee == info.sender {
+        return Err(ContractError::InvalidInput {});
+    }
+    CLAIMER_GRANTS.save(deps.storage, info.sender.to_string(), &grantee)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "authorize_claimer"),
+        attr("address", info.sender),
+        attr("grantee", grantee),
+    ]))
+}
+
+pub fn execute_revoke_claimer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if CLAIMER_GRANTS
+        .may_load(deps.storage, info.sender.to_string())?
+        .is_none()
+    {
+        return Err(ContractError::InvalidInput {});
+    }
+    CLAIMER_GRANTS.remove(deps.storage, info.sender.to_string());
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "revoke_claimer"),
+        attr("address", info.sender),
+    ]))
+}
+
+pub fn execute_claim_batch(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    claims: Vec<ClaimEntry>,
+) -> Result<Response, ContractError> {
+    if claims.is_empty() {
+        return Err(ContractError::InvalidInput {});
+    }
+    assert_campaign_active(deps.as_ref(), &env)?;
+
+    let mut res = Response::new().add_attributes(vec![
+        attr("action", "claim_batch"),
+        attr("claims_count", claims.len().to_string()),
+    ]);
+    for claim in claims {
+        let (send_msgs, attributes, event) =
+            process_claim(deps.branch(), &env, &info.sender, claim)?;
+        res = res
+            .add_submessages(send_msgs)
+            .add_attributes(attributes)
+            .add_event(event);
+    }
+    if let Some(event) = try_activate(deps, &env)? {
+        res = res.add_event(event);
+    }
+    Ok(res)
+}
+
+/// Rejects claims after the campaign end.
+fn assert_campaign_active(deps: Deps, env: &Env) -> Result<(), ContractError> {
+    match CONFIG.load(deps.storage)?.campaign_end {
+        Some(campaign_end) if campaign_end.is_expired(&env.block) => {
+            Err(ContractError::CampaignEnded { campaign_end })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Records the campaign end once it passed, returning the campaign ended event the first
+/// time only.
+fn record_campaign_end(
+    storage: &mut dyn Storage,
+    cfg: &Config,
+    env: &Env,
+) -> StdResult<Option<Event>> {
+    let campaign_end = match &cfg.campaign_end {
+        Some(end) if end.is_expired(&env.block) => end,
+        _ => return Ok(None),
+    };
+    if CAMPAIGN_ENDED.may_load(storage)?.is_some() {
+        return Ok(None);
+    }
+    CAMPAIGN_ENDED.save(storage, &env.block.height)?;
+    Ok(Some(campaign_ended_event(campaign_end, env.block.height)))
+}
+
+pub fn execute_end_campaign(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let ended = cfg
+        .campaign_end
+        .is_some_and(|end| end.is_expired(&env.block));
+    if !ended {
+        return Err(ContractError::CampaignNotEnded {});
+    }
+
+    let mut res = Response::new().add_attribute("action", "end_campaign");
+    if let Some(event) = record_campaign_end(deps.storage, &cfg, &env)? {
+        res = res.add_event(event);
+    }
+    Ok(res)
+}
+
+pub fn execute_verify_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    claim: ClaimEntry,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    // passport and contract wallet claims are verified against state which may change
+    if !cfg.verified_claims || !claim.claim_msg.signature_type.is_signed() {
+        return Err(ContractError::InvalidInput {});
+    }
+    // a target only replaces its own verified claim
+    if info.sender != claim.claim_msg.target_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let key = verified_claim_key(&claim)?;
+    let cached = is_verified_claim(deps.storage, &claim)?;
+    if !cached {
+        verify_claimer(
+            deps.as_ref(),
+            &env,
+            &cfg,
+            claim.stage,
+            &claim.claim_msg,
+            claim.signature.clone(),
+        )?;
+        verify_merkle_proof(
+            deps.as_ref(),
+            claim.stage,
+            &claim.claim_msg.snapshot_key(),
+            claim.claim_amount,
+            &claim.extra_amounts,
+            claim.leaf_index,
+            claim.proof,
+        )?;
+    }
+    VERIFIED_CLAIMS.save(deps.storage, claim.claim_msg.target_addr.clone(), &key)?;
+    VERIFIED_PROOFS.save(
+        deps.storage,
+        (claim.stage, claim.claim_msg.snapshot_key()),
+        &true,
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "verify_claim"),
+        attr("stage", claim.stage.to_string()),
+        attr("address", claim.claim_msg.gift_claiming_address),
+        attr("cached", cached.to_string()),
+    ]))
+}
+
+/// Starts releases once the claims count reaches the activation threshold.
+fn try_activate(deps: DepsMut, env: &Env) -> StdResult<Option<Event>> {
+    if ACTIVATION_BLOCK.may_load(deps.storage)?.is_some() {
+        return Ok(None);
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+    let claims_count = CLAIMS_COUNT.load(deps.storage)?;
+    if claims_count < cfg.activation_threshold {
+        return Ok(None);
+    }
+
+    ACTIVATION_BLOCK.save(deps.storage, &env.block.height)?;
+    Ok(Some(activated_event(claims_count, env.block.height)))
+}
+
+/// Counts the claim in the claims of the block, failing once the cap of the block is reached.
+fn count_block_claim(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+) -> Result<(), ContractError> {
+    if config.max_claims_per_block == 0 {
+        return Ok(());
+    }
+    let mut block_claims = BLOCK_CLAIMS.may_load(storage)?.unwrap_or_default();
+    if block_claims.height != env.block.height {
+        block_claims = BlockClaims {
+            height: env.block.height,
+            count: 0,
+        };
+    }
+    if block_claims.count >= config.max_claims_per_block {
+        return Err(ContractError::TooManyClaimsThisBlock {
+            height: env.block.height,
+            max_claims_per_block: config.max_claims_per_block,
+        });
+    }
+    block_claims.count += 1;
+    BLOCK_CLAIMS.save(storage, &block_claims)?;
+    Ok(())
+}
+
+/// Verifies and records a single claim, returning the payouts, the claim attributes
+/// and the claim event.
+/// Relayer gets the relay reward of the claim if it is not the target itself.
+/// Passport claims are preceded by the passport verification submessage, the
+/// claim is rolled back by its reply if the verification fails.
+/// All the state is written before the first msg is built, following
+/// checks-effects-interactions.
+fn process_claim(
+    mut deps: DepsMut,
+    env: &Env,
+    relayer: &Addr,
+    claim: ClaimEntry,
+) -> Result<(Vec<SubMsg>, Vec<Attribute>, Event), ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    // claims verified with VerifyClaim skip the signature and proof verification
+    let verified = config.verified_claims && take_verified_claim(deps.storage, &claim)?;
+    let ClaimEntry {
+        stage,
+        claim_msg,
+        signature,
+        claim_amount: amount,
+        extra_amounts,
+        leaf_index,
+        proof,
+    } = claim;
+    if claim_msg.relayer_reward > Decimal::one() {
+        return Err(ContractError::InvalidInput {});
+    }
+    // unsigned claims can only be relayed by the grantee of the target, without a
+    // relay reward as the claimer did not sign it
+    if !claim_msg.signature_type.is_signed() && relayer.as_str() != claim_msg.target_addr {
+        let grantee = CLAIMER_GRANTS.may_load(deps.storage, claim_msg.target_addr.clone())?;
+        if grantee.as_ref() != Some(relayer) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if !claim_msg.relayer_reward.is_zero() {
+            return Err(ContractError::InvalidInput {});
+        }
+    }
+
+    if config.paused {
+        return Err(ContractError::Paused {});
+    }
+    // recipient of an IBC payout is an address of the other chain
+    if let Some(channel) = &claim_msg.payout_channel {
+        if !config.ibc_channels.contains(channel) {
+            return Err(ContractError::ChannelNotAllowed {
+                channel: channel.clone(),
+            });
+        }
+    }
+    // payouts are only delegated in the staking denom to validators of the chain
+    if let Some(validator) = &claim_msg.delegate_to {
+        let staking_denom = match &config.gift_asset {
+            GiftAsset::Native(denom) => *denom == deps.querier.query_bonded_denom()?,
+            GiftAsset::Cw20(_) => false,
+        };
+        if !staking_denom
+            || claim_msg.payout_channel.is_some()
+            || deps.querier.query_validator(validator)?.is_none()
+        {
+            return Err(ContractError::InvalidInput {});
+        }
+    }
+    let remote_target = claim_msg.payout_channel.is_some() && claim_msg.recipient.is_none();
+    if !remote_target {
+        validate_target_addr(
+            deps.as_ref(),
+            &claim_msg.target_addr,
+            config.target_prefix.as_deref(),
+        )?;
+    }
+    if let Some(recipient) = claim_msg
+        .recipient
+        .as_ref()
+        .filter(|_| claim_msg.payout_channel.is_none())
+    {
+        validate_target_addr(deps.as_ref(), recipient, config.target_prefix.as_deref())?;
+    }
+
+    if let Some(chain) = &claim_msg.source_chain {
+        let valid = !chain.is_empty()
+            && chain
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid {
+            return Err(ContractError::InvalidInput {});
+        }
+    }
+    if let Some(memo) = &claim_msg.memo {
+        if memo.is_empty() || memo.len() > MAX_MEMO_BYTES || memo.chars().any(char::is_control) {
+            return Err(ContractError::InvalidInput {});
+        }
+    }
+
+    assert_stage_active(deps.as_ref(), env, stage)?;
+    if let Some(cutoff) = STAGE_PASSPORT_CUTOFF.may_load(deps.storage, stage)? {
+        verify_passport_age(deps.as_ref(), &claim_msg, config.passport.as_ref(), cutoff)?;
+    }
+    count_block_claim(deps.storage, env, &config)?;
+
+    // verify not claimed, indexed stages track claims by leaf index and entries of
+    // source chains by the chain and address
+    let claimer = claim_msg.snapshot_key();
+    let claim_key = (claimer.clone(), stage);
+    // the stored claim record tells when and how much was received
+    let already_claimed = |storage: &dyn Storage| -> Result<ContractError, ContractError> {
+        let claim = CLAIMS.may_load(storage, claim_key.clone())?;
+        Ok(ContractError::AlreadyClaimed {
+            stage,
+            address: claimer.clone(),
+            claimed_at_height: claim.as_ref().map_or(0, |claim| claim.height),
+            amount: claim.map(|claim| claim.amount).unwrap_or_default(),
+        })
+    };
+    if is_claimed(deps.storage, stage, &claimer)? {
+        return Err(already_claimed(deps.storage)?);
+    }
+    let indexed = STAGE_LEAF_FORMAT.may_load(deps.storage, stage)? == Some(LeafFormat::Indexed);
+    let leaf_index = match (indexed, leaf_index) {
+        (true, Some(leaf_index)) => Some(leaf_index),
+        (true, None) => return Err(ContractError::InvalidInput {}),
+        (false, _) => None,
+    };
+    if let Some(leaf_index) = leaf_index {
+        if is_index_claimed(deps.storage, stage, leaf_index)? {
+            return Err(already_claimed(deps.storage)?);
+        }
+    }
+
+    // malformed snapshot rows are rejected
+    let out_of_range = amount < config.claim_minimum
+        || (!config.claim_maximum.is_zero() && amount > config.claim_maximum);
+    if out_of_range {
+        return Err(ContractError::ClaimAmountOutOfRange {
+            amount,
+            minimum: config.claim_minimum,
+            maximum: config.claim_maximum,
+        });
+    }
+
+    let coefficient = current_coefficient(&config, CLAIMS_COUNT.load(deps.storage)?);
+    let (scaled_amount, dust) = scale_amount(amount, coefficient)?;
+    let claim_amount = clamp_payout(&config, scaled_amount);
+    // every denom of the leaf is paid in the proportion of the gift payout
+    let extra_claim_amounts = proportional_coins(&extra_amounts, claim_amount, amount);
+
+    is_eligible(
+        deps.as_ref(),
+        env,
+        &config,
+        stage,
+        &claim_msg,
+        signature,
+        claim_amount,
+        &extra_claim_amounts,
+        verified,
+    )?;
+
+    if !verified {
+        verify_merkle_proof(
+            deps.as_ref(),
+            stage,
+            &claimer,
+            amount,
+            &extra_amounts,
+            leaf_index,
+            proof,
+        )?;
+    }
+    let claim_amount =
+        apply_linked_claim_policy(deps.branch(), &config, stage, &claim_msg, claim_amount)?;
+    let extra_claim_amounts = proportional_coins(&extra_amounts, claim_amount, amount);
+
+    // Update claim index to the current stage
+    if let Some(leaf_index) = leaf_index {
+        set_index_claimed(deps.storage, stage, leaf_index)?;
+    }
+    CLAIMS.save(
+        deps.storage,
+        claim_key,
+        &ClaimState {
+            amount: claim_amount,
+            timestamp: env.block.time,
+            height: env.block.height,
+            target_addr: claim_msg.target_addr.clone(),
+            recipient: claim_msg.recipient.clone(),
+            extra_amounts: extra_claim_amounts.clone(),
+            delegated_to: claim_msg.delegate_to.clone(),
+            memo: claim_msg.memo.clone(),
+        },
+    )?;
+    CLAIMS_BY_TARGET.save(
+        deps.storage,
+        (claim_msg.target_addr.clone(), claimer.clone(), stage),
+        &true,
+    )?;
+
+    // Update balances, coefficient follows the claims count
+    update_balances(
+        deps.branch(),
+        claim_amount,
+        &extra_claim_amounts,
+        &mut config,
+    )?;
+    STAGE_AMOUNT_CLAIMED.update(deps.storage, stage, |claimed| -> StdResult<_> {
+        Ok(claimed.unwrap_or_default() + claim_amount)
+    })?;
+    CLAIMS_BY_SOURCE.update(deps.storage, claim_msg.source(), |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.claims += 1;
+        stats.amount += claim_amount;
+        Ok(stats)
+    })?;
+    // clamped payouts are not rounded, so they leave no dust
+    if claim_amount == scaled_amount && !dust.is_zero() {
+        let pool = DUST_POOL.may_load(deps.storage)?.unwrap_or_default();
+        DUST_POOL.save(deps.storage, &(pool + dust))?;
+    }
+    let claims_count =
+        CLAIMS_COUNT.update(deps.storage, |count| -> StdResult<_> { Ok(count + 1) })?;
+    if config.history_interval > 0 && claims_count % config.history_interval == 0 {
+        record_history(deps.storage, env, &config, claims_count)?;
+    }
+    // lottery prizes are paid on this chain, so claims paid over IBC buy no tickets
+    if claim_msg.payout_channel.is_none() {
+        let tickets = lottery::tickets(claim_amount, config.lottery_ticket_size);
+        lottery::buy_tickets(deps.storage, claim_msg.recipient(), tickets)?;
+    }
+
+    let mut attributes = vec![
+        attr("stage", stage.to_string()),
+        attr("address", claim_msg.gift_claiming_address.clone()),
+        attr("amount", amount),
+    ];
+    if claim_amount != scaled_amount {
+        attributes.push(attr("clamped_amount", claim_amount));
+    }
+    if let Some(recipient) = &claim_msg.recipient {
+        attributes.push(attr("recipient", recipient));
+    }
+    if let Some(memo) = &claim_msg.memo {
+        attributes.push(attr("memo", memo));
+    }
+    let relay_reward = if relayer.as_str() != claim_msg.target_addr {
+        claim_amount * claim_msg.relayer_reward
+    } else {
+        Uint128::zero()
+    };
+
+    // fee is charged before the rest of the gift is vested if release is set
+    let fee = claim_fee(&config, claim_amount - relay_reward);
+    let payout = claim_amount - relay_reward - fee;
+    // positions of stages with their own release keep its release blocks
+    let own_release = STAGE_RELEASE.may_load(deps.storage, stage)?;
+    let release = own_release
+        .clone()
+        .unwrap_or_else(|| config_release(&config));
+    let liquid_amount = liquid_amount(&release, payout);
+    let vesting_amount = payout - liquid_amount;
+
+    // extra denoms are split in the same proportions as the gift
+    let extra_relay_reward = proportional_coins(&extra_claim_amounts, relay_reward, claim_amount);
+    let extra_fee = proportional_coins(&extra_claim_amounts, fee, claim_amount);
+    let extra_liquid_amounts =
+        proportional_coins(&extra_claim_amounts, liquid_amount, claim_amount);
+    let extra_vesting_amounts = extra_claim_amounts
+        .iter()
+        .map(|c| {
+            let paid = extra_relay_reward
+                .iter()
+                .chain(extra_fee.iter())
+                .chain(extra_liquid_amounts.iter())
+                .filter(|p| p.denom == c.denom)
+                .map(|p| p.amount)
+                .sum::<Uint128>();
+            Coin {
+                denom: c.denom.clone(),
+                amount: c.amount - paid,
+            }
+        })
+        .filter(|c| !c.amount.is_zero())
+        .collect::<Vec<_>>();
+
+    // vesting positions are released on this chain
+    if claim_msg.payout_channel.is_some() && !vesting_amount.is_zero() {
+        return Err(ContractError::InvalidInput {});
+    }
+    if !vesting_amount.is_zero() {
+        RELEASE_STATE.update(
+            deps.storage,
+            claim_msg.recipient().to_string(),
+            |state| -> StdResult<_> {
+                let mut state = state.unwrap_or_default();
+                state.positions.push(VestingPosition {
+                    amount: vesting_amount,
+                    released: Uint128::zero(),
+                    start_block: env.block.height,
+                    release_blocks: own_release.map(|release| release.release_blocks),
+                    extra_amounts: extra_vesting_amounts,
+                });
+                Ok(state)
+            },
+        )?;
+        attributes.push(attr("vesting_amount", vesting_amount));
+    }
+    if let Some(validator) = claim_msg
+        .delegate_to
+        .as_ref()
+        .filter(|_| !liquid_amount.is_zero())
+    {
+        let key = (claim_msg.recipient().to_string(), validator.clone());
+        DELEGATIONS.update(deps.storage, key, |delegated| -> StdResult<_> {
+            Ok(delegated.unwrap_or_default() + liquid_amount)
+        })?;
+    }
+    let referral_bonus = match &claim_msg.referrer {
+        Some(referrer) => credit_referrer(
+            deps.branch(),
+            &mut config,
+            &claim_msg,
+            referrer,
+            claim_amount,
+        )?,
+        None => Uint128::zero(),
+    };
+    let passport = match claim_msg.signature_type {
+        SignatureType::Passport => {
+            let passport = config
+                .passport
+                .clone()
+                .ok_or(ContractError::InvalidInput {})?;
+            PASSPORT_CLAIMS.save(deps.storage, claims_count, &claim_msg.gift_claiming_address)?;
+            Some(passport)
+        }
+        _ => None,
+    };
+
+    // checks and effects are done, the claim is recorded before any msg is built so
+    // that a contract called back by the msgs sees it as claimed
+    let mut msgs = vec![];
+    if let Some(passport) = passport {
+        let verify_msg = WasmMsg::Execute {
+            contract_addr: passport.to_string(),
+            msg: to_binary(&PassportExecuteMsg::VerifyAddress {
+                address: claim_msg.gift_claiming_address.clone(),
+                owner: claim_msg.target_addr.clone(),
+            })?,
+            funds: vec![],
+        };
+        msgs.push(SubMsg::reply_always(verify_msg, claims_count));
+    }
+    let mut send_msgs = match &claim_msg.payout_channel {
+        Some(channel) => {
+            attributes.push(attr("payout_channel", channel));
+            let timeout =
+                IbcTimeout::with_timestamp(env.block.time.plus_seconds(config.ibc_timeout_seconds));
+            ibc_gift_msgs(
+                &config.gift_asset,
+                channel,
+                claim_msg.recipient(),
+                liquid_amount,
+                extra_liquid_amounts,
+                timeout,
+            )?
+        }
+        None => match &claim_msg.delegate_to {
+            Some(validator) => {
+                if !liquid_amount.is_zero() {
+                    attributes.push(attr("delegated_to", validator));
+                }
+                delegate_msgs(
+                    &config.gift_asset,
+                    validator,
+                    claim_msg.recipient(),
+                    liquid_amount,
+                    extra_liquid_amounts,
+                )?
+            }
+            None => gift_msgs(
+                &config.gift_asset,
+                claim_msg.recipient(),
+                liquid_amount,
+                extra_liquid_amounts,
+            )?,
+        },
+    };
+    if !relay_reward.is_zero() {
+        send_msgs.extend(gift_msgs(
+            &config.gift_asset,
+            relayer.as_str(),
+            relay_reward,
+            extra_relay_reward,
+        )?);
+        attributes.push(attr("relayer", relayer));
+        attributes.push(attr("relayer_reward", relay_reward));
+    }
+    if let Some(fee_recipient) = config.fee_recipient.as_ref().filter(|_| !fee.is_zero()) {
+        send_msgs.extend(gift_msgs(
+            &config.gift_asset,
+            fee_recipient.as_str(),
+            fee,
+            extra_fee,
+        )?);
+        attributes.push(attr("fee_recipient", fee_recipient));
+        attributes.push(attr("fee", fee));
+    }
+    if let Some(referrer) = claim_msg
+        .referrer
+        .as_ref()
+        .filter(|_| !referral_bonus.is_zero())
+    {
+        send_msgs.extend(gift_msgs(
+            &config.gift_asset,
+            referrer,
+            referral_bonus,
+            vec![],
+        )?);
+        attributes.push(attr("referrer", referrer));
+        attributes.push(attr("referral_bonus", referral_bonus));
+    }
+    msgs.extend(send_msgs.into_iter().map(SubMsg::new));
+    if let Some(hook) = &config.claim_hook {
+        msgs.push(SubMsg::reply_on_error(
+            claim_hook_msg(hook, stage, &claim_msg, claim_amount),
+            CLAIM_HOOK_REPLY_ID,
+        ));
+    }
+    if let Some(receipt_nft) = &config.receipt_nft {
+        let receipt = GiftReceipt {
+            stage,
+            address: claimer.clone(),
+            amount,
+            coefficient,
+        };
+        msgs.push(SubMsg::new(mint_receipt_msg(
+            receipt_nft.as_str(),
+            claim_msg.recipient(),
+            receipt,
+        )?));
+    }
+    msgs.extend(HOOKS.prepare_hooks(deps.storage, |hook| {
+        GiftClaimedHookMsg {
+            claimer: claim_msg.gift_claiming_address.clone(),
+            amount: claim_amount,
+            stage,
+        }
+        .into_cosmos_msg(hook)
+        .map(SubMsg::new)
+    })?);
+    let event = claim_event(
+        stage,
+        &claim_msg.gift_claiming_address,
+        &claim_msg.target_addr,
+        amount,
+        coefficient,
+        claim_amount,
+    );
+    Ok((msgs, attributes, event))
+}
+
+/// Records a history snapshot, overwriting the oldest one once the ring buffer is full.
+pub(crate) fn record_history(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    claims_count: u64,
+) -> StdResult<()> {
+    let id = HISTORY_COUNT.may_load(storage)?.unwrap_or_default();
+    let snapshot = HistorySnapshot {
+        id,
+        height: env.block.height,
+        claims_count,
+        coefficient: current_coefficient(config, claims_count),
+        remaining_balance: config.current_balance,
+    };
+    HISTORY.save(storage, id % HISTORY_CAPACITY, &snapshot)?;
+    HISTORY_COUNT.save(storage, &(id + 1))
+}
+
+/// Credits the referrer of a claim its bonus from the referral pool, referrers
+/// without claims of their own and referrals of the claim target are not rewarded.
+fn credit_referrer(
+    deps: DepsMut,
+    config: &mut Config,
+    claim_msg: &ClaimMsg,
+    referrer: &str,
+    payout: Uint128,
+) -> StdResult<Uint128> {
+    if referrer == claim_msg.target_addr || referrer == claim_msg.recipient() {
+        return Ok(Uint128::zero());
+    }
+    let has_claimed = CLAIMS_BY_TARGET
+        .sub_prefix_de(referrer.to_string())
+        .keys_de(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if !has_claimed {
+        return Ok(Uint128::zero());
+    }
+    let bonus = std::cmp::min(payout * config.referral_share, config.referral_balance);
+    if bonus.is_zero() {
+        return Ok(bonus);
+    }
+    config.referral_balance -= bonus;
+    CONFIG.save(deps.storage, config)?;
+    REFERRAL_STATS.update(
+        deps.storage,
+        referrer.to_string(),
+        |stats| -> StdResult<_> {
+            let mut stats = stats.unwrap_or_default();
+            stats.referrals += 1;
+            stats.bonus += bonus;
+            Ok(stats)
+        },
+    )?;
+    Ok(bonus)
+}
+
+pub fn execute_release(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    release(deps, env, info.sender)
+}
+
+/// Releases the vested gifts of many addresses, e.g. by a keeper bot after each unlock
+/// period. Each release is a submessage of the contract to itself, so failed releases
+/// are reported and do not revert the others.
+pub fn execute_release_all(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    if addresses.is_empty() || addresses.len() > MAX_RELEASE_ADDRESSES {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let mut msgs = vec![];
+    for (i, address) in addresses.into_iter().enumerate() {
+        let address = deps.api.addr_validate(&address)?;
+        let id = RELEASE_REPLY_ID_OFFSET + i as u64;
+        PENDING_RELEASES.save(deps.storage, id, &address.to_string())?;
+        let release_msg = WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&ExecuteMsg::ReleaseFor {
+                address: address.to_string(),
+            })?,
+            funds: vec![],
+        };
+        msgs.push(SubMsg::reply_always(release_msg, id));
+    }
+
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_attribute("action", "release_all"))
+}
+
+pub fn execute_release_for(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    // only sent by the contract itself from ReleaseAll
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    release(deps, env, address)
+}
+
+fn release(deps: DepsMut, env: Env, address: Addr) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg.paused {
+        return Err(ContractError::Paused {});
+    }
+
+    let mut state = RELEASE_STATE
+        .may_load(deps.storage, address.to_string())?
+        .unwrap_or_default();
+    let activation_block = ACTIVATION_BLOCK.may_load(deps.storage)?;
+    if activation_block.is_none() {
+        return Err(ContractError::NotActivated {
+            claims_count: CLAIMS_COUNT.load(deps.storage)?,
+            activation_threshold: cfg.activation_threshold,
+        });
+    }
+    let mut amount = Uint128::zero();
+    let mut extra_amounts: Vec<Coin> = vec![];
+    for position in state.positions.iter_mut() {
+        let releasable = releasable_amount(
+            position,
+            env.block.height,
+            cfg.release_blocks,
+            activation_block,
+        );
+        add_coins(
+            &mut extra_amounts,
+            releasable_extra_amounts(position, releasable),
+        );
+        position.released += releasable;
+        amount += releasable;
+    }
+    if amount.is_zero() {
+        return Err(ContractError::NothingToRelease {});
+    }
+
+    // drop fully released positions
+    state.positions.retain(|p| p.released < p.amount);
+    if state.positions.is_empty() {
+        RELEASE_STATE.remove(deps.storage, address.to_string());
+    } else {
+        RELEASE_STATE.save(deps.storage, address.to_string(), &state)?;
+    }
+    let releases_count = RELEASES_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    RELEASES_COUNT.save(deps.storage, &(releases_count + 1))?;
+
+    Ok(Response::new()
+        .add_messages(gift_msgs(
+            &cfg.gift_asset,
+            address.as_str(),
+            amount,
+            extra_amounts,
+        )?)
+        .add_event(release_event(address.as_str(), amount))
+        .add_attributes(vec![
+            attr("action", "release"),
+            attr("address", address),
+            attr("amount", amount),
+        ]))
+}
+
+pub fn execute_undelegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let denom = match &cfg.gift_asset {
+        GiftAsset::Native(denom) => denom.clone(),
+        GiftAsset::Cw20(_) => return Err(ContractError::InvalidInput {}),
+    };
+
+    // only the delegated payout of the sender can be undelegated
+    let key = (info.sender.to_string(), validator.clone());
+    let delegated = DELEGATIONS
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    if amount.is_zero() || amount > delegated {
+        return Err(ContractError::InvalidInput {});
+    }
+    if amount == delegated {
+        DELEGATIONS.remove(deps.storage, key);
+    } else {
+        DELEGATIONS.save(deps.storage, key, &(delegated - amount))?;
+    }
+    UNBONDINGS.update(
+        deps.storage,
+        info.sender.to_string(),
+        |unbondings| -> StdResult<_> {
+            let mut unbondings = unbondings.unwrap_or_default();
+            unbondings.push(Unbonding {
+                amount,
+                release_at: env.block.time.plus_seconds(cfg.unbonding_seconds),
+            });
+            Ok(unbondings)
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_message(StakingMsg::Undelegate {
+            validator: validator.clone(),
+            amount: Coin { denom, amount },
+        })
+        .add_attributes(vec![
+            attr("action", "undelegate"),
+            attr("address", info.sender),
+            attr("validator", validator),
+            attr("amount", amount),
+        ]))
+}
+
+pub fn execute_withdraw_unbonded(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let unbondings = UNBONDINGS
+        .may_load(deps.storage, info.sender.to_string())?
+        .unwrap_or_default();
+    let (released, unbonding): (Vec<_>, Vec<_>) = unbondings
+        .into_iter()
+        .partition(|u| u.release_at <= env.block.time);
+    let amount: Uint128 = released.iter().map(|u| u.amount).sum();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToRelease {});
+    }
+    if unbonding.is_empty() {
+        UNBONDINGS.remove(deps.storage, info.sender.to_string());
+    } else {
+        UNBONDINGS.save(deps.storage, info.sender.to_string(), &unbonding)?;
+    }
+
+    Ok(Response::new()
+        .add_message(gift_msg(&cfg.gift_asset, info.sender.as_str(), amount)?)
+        .add_attributes(vec![
+            attr("action", "withdraw_unbonded"),
+            attr("address", info.sender),
+            attr("amount", amount),
+        ]))
+}
+
+pub fn execute_index_claims(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stage: u8,
+    claims: Vec<IndexedClaim>,
+) -> Result<Response, ContractError> {
+    // authorize owner
+    let cfg = CONFIG.load(deps.storage)?;
+    let owner = cfg.owner.ok_or(ContractError::Unauthorized {})?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // only leaves of indexed stages carry their index
+    let leaf_format = STAGE_LEAF_FORMAT
+        .may_load(deps.storage, stage)?
+        .unwrap_or_default();
+    if leaf_format != LeafFormat::Indexed {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    for claim in claims.iter() {
+        if !CLAIMS.has(deps.storage, (claim.address.clone(), stage)) || claim.proof.is_empty() {
+            return Err(ContractError::InvalidInput {});
+        }
+        verify_merkle_proof(
+            deps.as_ref(),
+            stage,
+            &claim.address,
+            claim.amount,
+            &claim.extra_amounts,
+            Some(claim.leaf_index),
+            claim.proof.clone(),
+        )?;
+        set_index_claimed(deps.storage, stage, claim.leaf_index)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "index_claims"),
+        attr("stage", stage.to_string()),
+        attr("claims", claims.len().to_string()),
+    ]))
+}
+
+pub fn execute_clawback(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stage: u8,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // if owner set validate, otherwise unauthorized
+    let owner = cfg.owner.clone().ok_or(ContractError::Unauthorized {})?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    clawback(deps, env, cfg, stage)
+}
+
+/// Sends the unclaimed gift of an expired stage to the treasury.
+fn clawback(
+    deps: DepsMut,
+    env: Env,
+    mut cfg: Config,
+    stage: u8,
+) -> Result<Response, ContractError> {
+    // only stages that expired can be clawed back, any stage once the campaign ended
+    let campaign_ended = cfg
+        .campaign_end
+        .is_some_and(|end| end.is_expired(&env.block));
+    let expired = STAGE_EXPIRATION
+        .may_load(deps.storage, stage)?
+        .is_some_and(|expiration| expiration.is_expired(&env.block));
+    if !campaign_ended && !expired {
+        return Err(ContractError::StageNotExpired { stage });
+    }
+
+    let total_amount = STAGE_AMOUNT.load(deps.storage, stage)?;
+    let claimed_amount = STAGE_AMOUNT_CLAIMED.load(deps.storage, stage)?;
+    let amount = total_amount
+        .saturating_sub(claimed_amount)
+        .min(cfg.current_balance);
+
+    // zero out the unclaimed gift of the stage
+    STAGE_AMOUNT.save(deps.storage, stage, &claimed_amount)?;
+    cfg.current_balance -= amount;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    let mut res = Response::new().add_attributes(vec![
+        attr("action", "clawback"),
+        attr("stage", stage.to_string()),
+        attr("recipient", cfg.treasury.as_str()),
+        attr("amount", amount),
+    ]);
+    if !amount.is_zero() {
+        res = res.add_message(gift_msg(&cfg.gift_asset, cfg.treasury.as_str(), amount)?);
+    }
+    if let Some(event) = record_campaign_end(deps.storage, &cfg, &env)? {
+        res = res.add_event(event);
+    }
+    Ok(res)
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+
+    // only the gift token is accepted
+    if cfg.gift_asset != GiftAsset::Cw20(info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::Fund {} => {
+            let sender = deps.api.addr_validate(&wrapper.sender)?;
+            if cfg.owner.as_ref() != Some(&sender) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            cfg.current_balance += wrapper.amount;
+            CONFIG.save(deps.storage, &cfg)?;
+
+            Ok(Response::new().add_attributes(vec![
+                attr("action", "fund"),
+                attr("sender", sender),
+                attr("amount", wrapper.amount),
+                attr("current_balance", cfg.current_balance),
+            ]))
+        }
+        ReceiveMsg::FundReferral {} => {
+            let sender = deps.api.addr_validate(&wrapper.sender)?;
+            if cfg.owner.as_ref() != Some(&sender) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            cfg.referral_balance += wrapper.amount;
+            CONFIG.save(deps.storage, &cfg)?;
+
+            Ok(Response::new().add_attributes(vec![
+                attr("action", "fund_referral"),
+                attr("sender", sender),
+                attr("amount", wrapper.amount),
+                attr("referral_balance", cfg.referral_balance),
+            ]))
+        }
+    }
+}
+
+pub fn execute_fund(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    // cw20 gift is funded through the receive hook
+    let gift_denom = match &cfg.gift_asset {
+        GiftAsset::Native(denom) => denom.clone(),
+        GiftAsset::Cw20(_) => return Err(ContractError::InvalidInput {}),
+    };
+    if info.funds.is_empty() {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    // initial and current balances are raised together so claims see the top-up
+    for coin in info.funds.iter() {
+        if coin.denom == gift_denom {
+            cfg.initial_balance += coin.amount;
+            cfg.current_balance += coin.amount;
+            continue;
+        }
+        let balance = cfg
+            .extra_balances
+            .iter_mut()
+            .find(|b| b.denom == coin.denom)
+            .ok_or(ContractError::InvalidInput {})?;
+        balance.initial_balance += coin.amount;
+        balance.current_balance += coin.amount;
+    }
+    CONFIG.save(deps.storage, &cfg)?;
+
+    let mut attributes = vec![attr("action", "fund"), attr("sender", info.sender)];
+    attributes.extend(info.funds.iter().map(|c| attr("amount", c.to_string())));
+    attributes.push(attr("current_balance", cfg.current_balance));
+    Ok(Response::new().add_attributes(attributes))
+}
+
+/// Sends the whole part of the dust pool to the owner, the fractional rest stays in the
+/// pool.
+pub fn execute_sweep_dust(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    let pool = DUST_POOL.may_load(deps.storage)?.unwrap_or_default();
+    let amount = Uint128::new(1) * pool;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidInput {});
+    }
+    let dust = pool - Decimal::from_ratio(amount, 1u128);
+    DUST_POOL.save(deps.storage, &dust)?;
+    cfg.current_balance = cfg
+        .current_balance
+        .checked_sub(amount)
+        .map_err(StdError::from)?;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    let msgs = gift_msgs(&cfg.gift_asset, info.sender.as_str(), amount, vec![])?;
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "sweep_dust"),
+        attr("amount", amount),
+        attr("dust", dust.to_string()),
+    ]))
+}
+
+pub fn execute_recalibrate_coefficient(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+    if ACTIVATION_BLOCK.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    // the gift held for vesting positions, referrals and the lottery is not available
+    let gift = query_reconciliation(deps.as_ref(), env.clone())?.gift;
+    let held = gift.accounted.saturating_sub(cfg.current_balance);
+    let available = gift.balance.saturating_sub(held);
+    let mut remaining = Uint128::zero();
+    for stage in 1..=LATEST_STAGE.load(deps.storage)? {
+        let total_amount = STAGE_AMOUNT.may_load(deps.storage, stage)?;
+        let claimed_amount = STAGE_AMOUNT_CLAIMED.may_load(deps.storage, stage)?;
+        remaining += total_amount
+            .unwrap_or_default()
+            .saturating_sub(claimed_amount.unwrap_or_default());
+    }
+    let coefficient = current_coefficient(&cfg, CLAIMS_COUNT.load(deps.storage)?);
+    if remaining.is_zero() || coefficient.is_zero() {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    // the schedule keeps its shape, the next claim pays available / remaining
+    let recalibrated = Decimal::from_ratio(available, remaining);
+    let scale =
+        |c: Decimal| Decimal::from_ratio((c * recalibrated).atomics(), coefficient.atomics());
+    cfg.coefficient_up = scale(cfg.coefficient_up);
+    cfg.coefficient_down = scale(cfg.coefficient_down);
+    cfg.current_balance = available;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    let attributes = vec![
+        attr("coefficient_up", cfg.coefficient_up.to_string()),
+        attr("coefficient_down", cfg.coefficient_down.to_string()),
+        attr("current_balance", available),
+        attr("remaining_allocations", remaining),
+    ];
+    // the recalibrated values are recorded instead of the msg
+    let params = attributes
+        .iter()
+        .map(|a| format!(r#""{}":"{}""#, a.key, a.value))
+        .collect::<Vec<_>>()
+        .join(",");
+    record_audit(
+        deps.storage,
+        &env,
+        info.sender.to_string(),
+        "recalibrate_coefficient",
+        format!("{{{}}}", params),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "recalibrate_coefficient")
+        .add_attributes(attributes))
+}
+
+/// Executes the msg like the entry point and fails with its response, so that the
+/// transaction is reverted.
+#[cfg(any(test, feature = "dry-run"))]
+pub fn execute_dry_run(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    let res = execute(deps, env, info, msg)?;
+    Err(ContractError::DryRun {
+        attributes: String::from_utf8(to_vec(&res.attributes)?).map_err(StdError::invalid_utf8)?,
+        messages: res.messages.len(),
+    })
+}
+
+pub fn execute_commit_lottery(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    commitment: String,
+    winners: u32,
+    prize: Uint128,
+    draw_height: u64,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    // the seed is committed before the tickets are all bought and the beacon of the
+    // draw height is known, and only revealed after
+    let before_end = cfg
+        .campaign_end
+        .is_some_and(|end| !end.is_expired(&env.block));
+    if cfg.lottery_ticket_size.is_zero()
+        || !before_end
+        || LOTTERY.may_load(deps.storage)?.is_some()
+        || winners == 0
+        || winners > MAX_LOTTERY_WINNERS
+        || prize.is_zero()
+        || draw_height <= env.block.height
+        || !hex::decode(&commitment).is_ok_and(|hash| hash.len() == 32)
+    {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let pool = prize
+        .checked_mul(Uint128::from(winners))
+        .map_err(StdError::from)?;
+    cfg.current_balance = cfg
+        .current_balance
+        .checked_sub(pool)
+        .map_err(StdError::from)?;
+    CONFIG.save(deps.storage, &cfg)?;
+    LOTTERY.save(
+        deps.storage,
+        &Lottery {
+            commitment: commitment.to_lowercase(),
+            winners,
+            prize,
+            committed_at: env.block.height,
+            draw_height,
+            beacon: None,
+            seed: None,
+            drawn_at: None,
+            expired_at: None,
+            results: vec![],
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "commit_lottery"),
+        attr("commitment", commitment),
+        attr("winners", winners.to_string()),
+        attr("pool", pool),
+        attr("draw_height", draw_height.to_string()),
+    ]))
+}
+
+pub fn execute_seal_lottery(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut lottery = LOTTERY
+        .may_load(deps.storage)?
+        .ok_or(ContractError::InvalidInput {})?;
+    let ended = cfg
+        .campaign_end
+        .is_some_and(|end| end.is_expired(&env.block));
+    if !ended
+        || lottery.beacon.is_some()
+        || env.block.height < lottery.draw_height
+        || env.block.height > lottery.draw_height + LOTTERY_REVEAL_BLOCKS
+    {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let beacon = lottery::beacon(&env.block);
+    lottery.beacon = Some(beacon.clone());
+    LOTTERY.save(deps.storage, &lottery)?;
+
+    Ok(
+        Response::new()
+            .add_attributes(vec![attr("action", "seal_lottery"), attr("beacon", beacon)]),
+    )
+}
+
+pub fn execute_draw_lottery(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    seed: String,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    assert_owner(&cfg, &info)?;
+
+    let mut lottery = LOTTERY
+        .may_load(deps.storage)?
+        .ok_or(ContractError::InvalidInput {})?;
+    let beacon = match &lottery.beacon {
+        Some(beacon) => beacon.clone(),
+        None => return Err(ContractError::InvalidInput {}),
+    };
+    if lottery.seed.is_some()
+        || env.block.height > lottery.draw_height + LOTTERY_REVEAL_BLOCKS
+        || lottery::seed_commitment(&seed) != lottery.commitment
+    {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let total_tickets = LOTTERY_TICKETS.may_load(deps.storage)?.unwrap_or_default();
+    let mut res = Response::new();
+    if total_tickets == 0 {
+        // nobody bought a ticket, the pool goes back to the gift balance
+        cfg.current_balance += lottery.prize * Uint128::from(lottery.winners);
+        CONFIG.save(deps.storage, &cfg)?;
+    }
+    for index in (0..lottery.winners).filter(|_| total_tickets > 0) {
+        let ticket = lottery::winning_ticket(&seed, &beacon, index, total_tickets);
+        let entry = lottery::ticket_owner(deps.storage, ticket)?;
+        res = res.add_message(gift_msg(&cfg.gift_asset, &entry.address, lottery.prize)?);
+        lottery.results.push(LotteryWinner {
+            ticket,
+            address: entry.address,
+            prize: lottery.prize,
+        });
+    }
+    lottery.seed = Some(seed.clone());
+    lottery.drawn_at = Some(env.block.height);
+    LOTTERY.save(deps.storage, &lottery)?;
+
+    Ok(res.add_attributes(vec![
+        attr("action", "draw_lottery"),
+        attr("seed", seed),
+        attr("total_tickets", total_tickets.to_string()),
+        attr("winners", lottery.results.len().to_string()),
+    ]))
+}
+
+pub fn execute_expire_lottery(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let mut lottery = LOTTERY
+        .may_load(deps.storage)?
+        .ok_or(ContractError::InvalidInput {})?;
+    // the owner withholding the seed only returns the pool to the gift balance
+    if lottery.seed.is_some()
+        || lottery.expired_at.is_some()
+        || env.block.height <= lottery.draw_height + LOTTERY_REVEAL_BLOCKS
+    {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let pool = lottery.prize * Uint128::from(lottery.winners);
+    cfg.current_balance += pool;
+    CONFIG.save(deps.storage, &cfg)?;
+    lottery.expired_at = Some(env.block.height);
+    LOTTERY.save(deps.storage, &lottery)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "expire_lottery"), attr("pool", pool)]))
+}
+
+pub fn execute_set_paused(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+
+    // owner or guardian can toggle pause
+    let is_owner = cfg.owner.as_ref() == Some(&info.sender);
+    let is_guardian = cfg.guardian.as_ref() == Some(&info.sender);
+    if !is_owner && !is_guardian {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    cfg.paused = paused;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(Response::new().add_attributes(vec![attr("action", action), attr("sender", info.sender)]))
+}
+
+fn assert_stage_active(deps: Deps, env: &Env, stage: u8) -> Result<(), ContractError> {
+    if let Some(freeze) = STAGE_FREEZES.may_load(deps.storage, stage)? {
+        return Err(ContractError::StageFrozen {
+            stage,
+            frozen_by: freeze.frozen_by.to_string(),
+        });
+    }
+    if let Some(start) = STAGE_START.may_load(deps.storage, stage)? {
+        if !start.is_expired(&env.block) {
+            return Err(ContractError::StageNotBegun { stage, start });
+        }
+    }
+    if let Some(expiration) = STAGE_EXPIRATION.may_load(deps.storage, stage)? {
+        if expiration.is_expired(&env.block) {
+            return Err(ContractError::StageExpired { stage, expiration });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the part of the payout paid out right away, rest of it is vested.
+fn liquid_amount(release: &StageRelease, payout: Uint128) -> Uint128 {
+    if release.release_blocks == 0 {
+        payout
+    } else {
+        payout * release.liquid_share
+    }
+}
+
+/// Returns the fee charged of a payout, zero if no fee recipient is set.
+fn claim_fee(config: &Config, payout: Uint128) -> Uint128 {
+    match config.fee_recipient {
+        Some(_) => payout.multiply_ratio(config.fee_bps, BPS_DENOMINATOR),
+        None => Uint128::zero(),
+    }
+}
+
+/// Fee must not exceed the payout and requires a recipient.
+fn assert_fee(fee_recipient: Option<&Addr>, fee_bps: u64) -> Result<(), ContractError> {
+    if fee_bps > BPS_DENOMINATOR || (fee_bps > 0 && fee_recipient.is_none()) {
+        return Err(ContractError::InvalidInput {});
+    }
+    Ok(())
+}
+
+/// Enforces the linked claim policy on claims of addresses linked in a passport and
+/// returns the payout, capped by LinkedClaimPolicy::SumWithCap.
+fn apply_linked_claim_policy(
+    mut deps: DepsMut,
+    config: &Config,
+    stage: u8,
+    claim_msg: &ClaimMsg,
+    payout: Uint128,
+) -> Result<Uint128, ContractError> {
+    let passport = match (&config.linked_claim_policy, &config.passport) {
+        (LinkedClaimPolicy::AllowBoth, _) | (_, None) => return Ok(payout),
+        (_, Some(passport)) => passport,
+    };
+    // an address linked when another address of its passport claimed stays counted with
+    // that passport, whatever the passport links now
+    let claimer = (stage, address_key(&claim_msg.gift_claiming_address));
+    let owner = match LINKED_CLAIMERS.may_load(deps.storage, claimer)? {
+        Some(owner) => owner,
+        None => match linked_passport(deps.as_ref(), passport, &claim_msg.gift_claiming_address)? {
+            Some(linked) => {
+                save_linked_claimers(deps.branch(), passport, stage, &linked)?;
+                linked.owner
+            }
+            None => return Ok(payout),
+        },
+    };
+
+    let key = (owner.clone(), stage);
+    let claimed = LINKED_CLAIMS.may_load(deps.storage, key.clone())?;
+    let payout = match (&config.linked_claim_policy, claimed) {
+        (LinkedClaimPolicy::FirstWins, Some(_)) => None,
+        (LinkedClaimPolicy::SumWithCap { cap }, claimed) => {
+            let left = cap.saturating_sub(claimed.unwrap_or_default());
+            Some(payout.min(left)).filter(|p| !p.is_zero())
+        }
+        _ => Some(payout),
+    }
+    .ok_or(ContractError::LinkedAddressClaimed { passport: owner })?;
+
+    LINKED_CLAIMS.save(deps.storage, key, &(claimed.unwrap_or_default() + payout))?;
+    Ok(payout)
+}
+
+/// Records the addresses linked in the passport as claimers of the passport owner in the
+/// stage, addresses recorded with another passport before are left as they are.
+fn save_linked_claimers(
+    deps: DepsMut,
+    passport: &Addr,
+    stage: u8,
+    linked: &PassportResponse,
+) -> StdResult<()> {
+    let mut start_after = None;
+    loop {
+        let page: AddressesByPassportResponse = deps.querier.query_wasm_smart(
+            passport,
+            &PassportQueryMsg::AddressesByPassport {
+                nickname: linked.nickname.clone(),
+                start_after,
+                limit: None,
+            },
+        )?;
+        start_after = match page.addresses.last() {
+            Some(last) => Some(last.address.clone()),
+            None => return Ok(()),
+        };
+        for address in page.addresses {
+            let key = (stage, address_key(&address.address));
+            if !LINKED_CLAIMERS.has(deps.storage, key.clone()) {
+                LINKED_CLAIMERS.save(deps.storage, key, &linked.owner)?;
+            }
+        }
+    }
+}
+
+/// Clamps a payout into the claim range of the config.
+fn clamp_payout(config: &Config, payout: Uint128) -> Uint128 {
+    let payout = payout.max(config.claim_minimum);
+    if config.claim_maximum.is_zero() {
+        payout
+    } else {
+        payout.min(config.claim_maximum)
+    }
+}
+
+/// Adds the coins to the sum, merging the same denoms.
+fn add_coins(sum: &mut Vec<Coin>, coins: Vec<Coin>) {
+    for coin in coins {
+        match sum.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(c) => c.amount += coin.amount,
+            None => sum.push(coin),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn is_eligible(
+    deps: Deps,
+    env: &Env,
+    cfg: &Config,
+    stage: u8,
+    claim_msg: &ClaimMsg,
+    signature: Proof,
+    claim_amount: Uint128,
+    extra_claim_amounts: &[Coin],
+    verified: bool,
+) -> Result<bool, ContractError> {
+    if cfg.current_balance < claim_amount {
+        return Err(ContractError::IsNotEligible {
+            msg: "".to_string(),
+        });
+    }
+    for coin in extra_claim_amounts {
+        let balance = cfg
+            .extra_balances
+            .iter()
+            .find(|b| b.denom == coin.denom)
+            .ok_or(ContractError::InvalidInput {})?;
+        if balance.current_balance < coin.amount {
+            return Err(ContractError::IsNotEligible {
+                msg: format!("not enough {}", coin.denom),
+            });
+        }
+    }
+    // passport claims are verified by the passport contract in a submessage
+    if claim_msg.signature_type == SignatureType::Passport {
+        if !matches!(signature, Proof::Passport { .. }) {
+            return Err(proof_mismatch(&signature, claim_msg));
+        }
+        if cfg.passport.is_none() {
+            return Err(ContractError::IsNotEligible {
+                msg: "passport contract is not set".to_string(),
+            });
+        }
+        return Ok(true);
+    }
+    if verified {
+        return Ok(true);
+    }
+    verify_claimer(deps, env, cfg, stage, claim_msg, signature)
+}
+
+/// Verifies the claim is authorized by the gift_claiming_address, by its signature,
+/// its passport linkage or the owner approval of its contract wallet.
+fn verify_claimer(
+    deps: Deps,
+    env: &Env,
+    cfg: &Config,
+    stage: u8,
+    claim_msg: &ClaimMsg,
+    signature: Proof,
+) -> Result<bool, ContractError> {
+    let signed = claim_msg.signature_type.is_signed();
+    match (signature, &claim_msg.gift_claiming_address_type) {
+        (Proof::Passport { nickname }, _)
+            if claim_msg.signature_type == SignatureType::Passport =>
+        {
+            verify_passport(deps, claim_msg, cfg.passport.as_ref(), &nickname)
+        }
+        (Proof::ContractWallet {}, _)
+            if claim_msg.signature_type == SignatureType::ContractWallet =>
+        {
+            verify_contract_wallet(deps, stage, claim_msg)
+        }
+        (
+            Proof::EthSig {
+                signature,
+                message_encoding,
+            },
+            ClaimerType::Ethereum,
+        ) if signed => helpers::verify_eth(
+            deps,
+            env,
+            claim_msg,
+            signature,
+            message_encoding,
+            cfg.eth_chain_id,
+        ),
+        (
+            Proof::CosmosSig {
+                pub_key,
+                signature,
+                pub_key_type,
+            },
+            ClaimerType::Cosmos,
+        ) if signed => verify_cosmos(
+            deps,
+            env,
+            claim_msg,
+            pub_key,
+            pub_key_type.as_deref(),
+            signature,
+            &cfg.cosmos_prefixes,
+        ),
+        (Proof::SolanaSig { signature }, ClaimerType::Solana) if signed => {
+            verify_solana(deps, env, claim_msg, signature)
+        }
+        (Proof::BitcoinSig { signature }, ClaimerType::Bitcoin) if signed => {
+            verify_bitcoin(deps, env, claim_msg, signature)
+        }
+        (signature, _) => Err(proof_mismatch(&signature, claim_msg)),
+    }
+}
+
+fn proof_mismatch(signature: &Proof, claim_msg: &ClaimMsg) -> ContractError {
+    ContractError::ProofMismatch {
+        proof: signature.kind().to_string(),
+        claimer: format!("{:?}", claim_msg.gift_claiming_address_type),
+        signature_type: format!("{:?}", claim_msg.signature_type),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::MerkleRoot { stage } => to_binary(&query_merkle_root(deps, stage)?),
+        QueryMsg::Stage { stage } => to_binary(&query_stage(deps, stage)?),
+        QueryMsg::LatestStage {} => to_binary(&query_latest_stage(deps)?),
+        QueryMsg::IsClaimed {
+            stage,
+            address,
+            source_chain,
+        } => {
+            let address = snapshot_key(source_chain.as_deref(), &address);
+            to_binary(&query_is_claimed(deps, stage, address)?)
+        }
+        QueryMsg::IsEligible {
+            stage,
+            claim_msg,
+            signature,
+            claim_amount,
+            extra_amounts,
+            leaf_index,
+            proof,
+        } => to_binary(&query_is_eligible(
+            deps,
+            env,
+            stage,
+            claim_msg,
+            signature,
+            claim_amount,
+            extra_amounts,
+            leaf_index,
+            proof,
+        )?),
+        QueryMsg::AllClaims { start_after, limit } => {
+            to_binary(&query_all_claims(deps, start_after, limit)?)
+        }
+        QueryMsg::ExportState { start_after, limit } => {
+            to_binary(&query_export_state(deps, start_after, limit)?)
+        }
+        QueryMsg::ClaimsByAddress { address } => {
+            to_binary(&query_claims_by_address(deps, env, address)?)
+        }
+        QueryMsg::HasClaimed { address } => to_binary(&query_has_claimed(deps, address)?),
+        QueryMsg::ClaimedAmount { address } => to_binary(&query_claimed_amount(deps, address)?),
+        QueryMsg::ReleaseState { address } => to_binary(&query_release_state(deps, env, address)?),
+        QueryMsg::ReleaseProjection { address, at_height } => {
+            to_binary(&query_release_projection(deps, env, address, at_height)?)
+        }
+        QueryMsg::Activation {} => to_binary(&query_activation(deps)?),
+        QueryMsg::Reconciliation {} => to_binary(&query_reconciliation(deps, env)?),
+        QueryMsg::AuditLog { start_after, limit } => {
+            to_binary(&query_audit_log(deps, start_after, limit)?)
+        }
+        QueryMsg::History { start_after, limit } => {
+            to_binary(&query_history(deps, start_after, limit)?)
+        }
+        QueryMsg::Lottery {} => to_binary(&query_lottery(deps)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps, env)?),
+        QueryMsg::CurrentTier {} => to_binary(&query_current_tier(deps)?),
+        QueryMsg::VerifyProof { stage, leaf, proof } => {
+            to_binary(&query_verify_proof(deps, stage, leaf, proof)?)
+        }
+        QueryMsg::DiagnoseClaim { claim } => to_binary(&query_diagnose_claim(deps, env, claim)?),
+        QueryMsg::CurrentCoefficient {} => to_binary(&query_current_coefficient(deps)?),
+        QueryMsg::SimulateClaim { amount, stage } => {
+            to_binary(&query_simulate_claim(deps, amount, stage)?)
+        }
+        QueryMsg::ClaimedPage { stage, page } => to_binary(&query_claimed_page(deps, stage, page)?),
+        QueryMsg::PendingRoots {} => to_binary(&query_pending_roots(deps)?),
+        QueryMsg::Allocation { stage, address } => {
+            to_binary(&query_allocation(deps, stage, address)?)
+        }
+        QueryMsg::ReferralStats { address } => to_binary(&query_referral_stats(deps, address)?),
+        QueryMsg::ClaimsBySource {} => to_binary(&query_claims_by_source(deps)?),
+        QueryMsg::ClaimerGrant { address } => to_binary(&query_claimer_grant(deps, address)?),
+        QueryMsg::Delegations { address } => to_binary(&query_delegations(deps, address)?),
+        QueryMsg::PassportClaims {
+            stage,
+            nickname,
+            start_after,
+            limit,
+        } => to_binary(&query_passport_claims(
+            deps,
+            stage,
+            nickname,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps.storage)?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: cfg.owner.map(|o| o.to_string()),
+        pending_owner: cfg.pending_owner.map(|o| o.to_string()),
+        allowed_native: cfg.gift_asset.native_denom(),
+        cw20_token: cfg.gift_asset.cw20_token(),
+        extra_denoms: cfg.extra_balances.iter().map(|b| b.denom.clone()).collect(),
+        extra_initial_balances: cfg
+            .extra_balances
+            .iter()
+            .map(|b| b.initial_balance)
+            .collect(),
+        extra_current_balances: cfg
+            .extra_balances
+            .iter()
+            .map(|b| b.current_balance)
+            .collect(),
+        current_balance: cfg.current_balance,
+        initial_balance: cfg.initial_balance,
+        coefficient_up: cfg.coefficient_up,
+        coefficient_down: cfg.coefficient_down,
+        coefficient_claims: cfg.coefficient_claims,
+        eth_chain_id: cfg.eth_chain_id,
+        treasury: cfg.treasury.to_string(),
+        guardian: cfg.guardian.map(|g| g.to_string()),
+        passport: cfg.passport.map(|p| p.to_string()),
+        target_prefix: cfg.target_prefix,
+        paused: cfg.paused,
+        liquid_share: cfg.liquid_share,
+        release_blocks: cfg.release_blocks,
+        activation_threshold: cfg.activation_threshold,
+        referral_share: cfg.referral_share,
+        referral_balance: cfg.referral_balance,
+        max_claims_per_block: cfg.max_claims_per_block,
+        claim_minimum: cfg.claim_minimum,
+        claim_maximum: cfg.claim_maximum,
+        fee_recipient: cfg.fee_recipient.map(|f| f.to_string()),
+        fee_bps: cfg.fee_bps,
+        ibc_channels: cfg.ibc_channels,
+        ibc_timeout_seconds: cfg.ibc_timeout_seconds,
+        ibc_controller: cfg.ibc_controller,
+        ibc_connection_id: cfg.ibc_connection_id,
+        unbonding_seconds: cfg.unbonding_seconds,
+        claim_hook: cfg.claim_hook.as_ref().map(|h| h.contract.to_string()),
+        claim_hook_msg: cfg.claim_hook.map(|h| h.msg),
+        root_timelock_seconds: cfg.root_timelock_seconds,
+        root_guardians: cfg.root_guardians.iter().map(|g| g.to_string()).collect(),
+        root_guardian_threshold: cfg.root_guardian_threshold,
+        linked_claim_policy: cfg.linked_claim_policy.kind().to_string(),
+        linked_claim_cap: cfg.linked_claim_policy.cap(),
+        campaign_end_height: expiration_height(cfg.campaign_end.as_ref()),
+        campaign_end_time: expiration_time(cfg.campaign_end.as_ref()),
+        verified_claims: cfg.verified_claims,
+        receipt_nft: cfg.receipt_nft.map(|r| r.to_string()),
+        milestone_until_claims: cfg.milestone_tiers.iter().map(|t| t.until_claims).collect(),
+        milestone_multipliers: cfg.milestone_tiers.iter().map(|t| t.multiplier).collect(),
+        cosmos_prefixes: cfg.cosmos_prefixes,
+        history_interval: cfg.history_interval,
+        lottery_ticket_size: cfg.lottery_ticket_size,
+        verbose_errors: cfg.verbose_errors,
+    })
+}
+
+pub fn query_referral_stats(deps: Deps, address: String) -> StdResult<ReferralStatsResponse> {
+    let stats = REFERRAL_STATS
+        .may_load(deps.storage, address.clone())?
+        .unwrap_or_default();
+    Ok(ReferralStatsResponse {
+        address,
+        referrals: stats.referrals,
+        bonus: stats.bonus,
+    })
+}
+
+pub fn query_claims_by_source(deps: Deps) -> StdResult<ClaimsBySourceResponse> {
+    let sources = CLAIMS_BY_SOURCE
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (source, stats) = item?;
+            Ok(SourceClaims {
+                source: String::from_utf8(source)?,
+                claims: stats.claims,
+                amount: stats.amount,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ClaimsBySourceResponse { sources })
+}
+
+pub fn query_claimer_grant(deps: Deps, address: String) -> StdResult<ClaimerGrantResponse> {
+    let grantee = CLAIMER_GRANTS.may_load(deps.storage, address)?;
+    Ok(ClaimerGrantResponse {
+        grantee: grantee.map(String::from),
+    })
+}
+
+pub fn query_pending_roots(deps: Deps) -> StdResult<PendingRootsResponse> {
+    let roots = PENDING_ROOTS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, root)| root))
+        .collect::<StdResult<_>>()?;
+    Ok(PendingRootsResponse { roots })
+}
+
+pub fn query_allocation(deps: Deps, stage: u8, address: String) -> StdResult<AllocationResponse> {
+    let amount = ALLOCATIONS.may_load(deps.storage, (stage, address.clone()))?;
+    let is_claimed = CLAIMS.has(deps.storage, (address.clone(), stage));
+    Ok(AllocationResponse {
+        amount,
+        is_claimed,
+        proof_validated: is_claimed || VERIFIED_PROOFS.has(deps.storage, (stage, address)),
+    })
+}
+
+pub fn query_delegations(deps: Deps, address: String) -> StdResult<DelegationsResponse> {
+    let delegations = DELEGATIONS
+        .prefix_de(address.clone())
+        .range_de(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (validator, amount) = item?;
+            Ok(DelegationResponse { validator, amount })
+        })
+        .collect::<StdResult<_>>()?;
+    let unbondings = UNBONDINGS
+        .may_load(deps.storage, address)?
+        .unwrap_or_default();
+    Ok(DelegationsResponse {
+        delegations,
+        unbondings,
+    })
+}
+
+/// Returns a page of the linked addresses of the passport, paginated by the passport
+/// AddressesByPassport, with their claims of the stage.
+pub fn query_passport_claims(
+    deps: Deps,
+    stage: u8,
+    nickname: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PassportClaimsResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let passport = cfg
+        .passport
+        .ok_or_else(|| StdError::generic_err("passport contract is not set"))?;
+    let page: AddressesByPassportResponse = deps.querier.query_wasm_smart(
+        &passport,
+        &PassportQueryMsg::AddressesByPassport {
+            nickname,
+            start_after,
+            limit,
+        },
+    )?;
+    let claims = page
+        .addresses
+        .into_iter()
+        .map(|linked| {
+            // 0x addresses are linked lowercased but may be claimed with their checksum
+            let is_claimed = is_claimed(deps.storage, stage, &linked.address)?
+                || (linked.address.starts_with("0x")
+                    && is_claimed(deps.storage, stage, &eip55_address(&linked.address))?);
+            Ok(PassportClaim {
+                is_claimed,
+                address: linked.address,
+                address_type: linked.address_type,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PassportClaimsResponse {
+        nickname: page.nickname,
+        claims,
+    })
+}
+
+pub fn query_current_coefficient(deps: Deps) -> StdResult<CurrentCoefficientResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let claims_count = CLAIMS_COUNT.load(deps.storage)?;
+    Ok(CurrentCoefficientResponse {
+        coefficient: current_coefficient(&cfg, claims_count),
+        claims_count,
+    })
+}
+
+pub fn query_current_tier(deps: Deps) -> StdResult<CurrentTierResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let claims_count = CLAIMS_COUNT.load(deps.storage)?;
+    let tier = current_tier(&cfg, claims_count);
+    Ok(CurrentTierResponse {
+        tier: tier.map(|(index, _)| index as u32),
+        multiplier: tier.map_or(Decimal::one(), |(_, tier)| tier.multiplier),
+        until_claims: tier.map(|(_, tier)| tier.until_claims),
+        claims_count,
+    })
+}
+
+pub fn query_simulate_claim(
+    deps: Deps,
+    amount: Uint128,
+    stage: Option<u8>,
+) -> StdResult<SimulateClaimResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let release = match stage {
+        Some(stage) => {
+            // fails if the stage is not registered
+            MERKLE_ROOT.load(deps.storage, stage)?;
+            stage_release(deps.storage, &cfg, stage)?
+        }
+        None => config_release(&cfg),
+    };
+    let coefficient = current_coefficient(&cfg, CLAIMS_COUNT.load(deps.storage)?);
+    let payout = clamp_payout(&cfg, scale_amount(amount, coefficient)?.0);
+    let fee = claim_fee(&cfg, payout);
+    let liquid_amount = liquid_amount(&release, payout - fee);
+    Ok(SimulateClaimResponse {
+        allowed_native: cfg.gift_asset.native_denom(),
+        cw20_token: cfg.gift_asset.cw20_token(),
+        coefficient,
+        payout,
+        liquid_amount,
+        vesting_amount: payout - fee - liquid_amount,
+        fee,
+        enough_balance: payout <= cfg.current_balance,
+    })
+}
+
+pub fn query_verify_proof(
+    deps: Deps,
+    stage: u8,
+    leaf: String,
+    proof: Vec<ProofNode>,
+) -> StdResult<VerifyProofResponse> {
+    // fails if the stage is not registered
+    MERKLE_ROOT.load(deps.storage, stage)?;
+    let valid = verify_merkle_leaf(deps, stage, leaf.as_bytes(), proof).is_ok();
+    Ok(VerifyProofResponse { valid })
+}
+
+pub fn query_activation(deps: Deps) -> StdResult<ActivationResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let resp = ActivationResponse {
+        claims_count: CLAIMS_COUNT.load(deps.storage)?,
+        activation_threshold: cfg.activation_threshold,
+        activation_block: ACTIVATION_BLOCK.may_load(deps.storage)?,
+    };
+
+    Ok(resp)
+}
+
+pub fn query_stats(deps: Deps, env: Env) -> StdResult<StatsResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let claims_count = CLAIMS_COUNT.load(deps.storage)?;
+    let total_claimed = STAGE_AMOUNT_CLAIMED
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, claimed)| claimed))
+        .sum::<StdResult<Uint128>>()?;
+    let activation_block = ACTIVATION_BLOCK.may_load(deps.storage)?;
+    let (remaining_blocks, remaining_seconds) = match cfg.campaign_end {
+        Some(Expiration::AtHeight(height)) => (Some(height.saturating_sub(env.block.height)), None),
+        Some(Expiration::AtTime(time)) => (
+            None,
+            Some(time.seconds().saturating_sub(env.block.time.seconds())),
+        ),
+        _ => (None, None),
+    };
+    Ok(StatsResponse {
+        claims_count,
+        total_claimed,
+        remaining_balance: cfg.current_balance,
+        releases_count: RELEASES_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        coefficient: current_coefficient(&cfg, claims_count),
+        activated: activation_block.is_some(),
+        activation_block,
+        campaign_end_height: expiration_height(cfg.campaign_end.as_ref()),
+        campaign_end_time: expiration_time(cfg.campaign_end.as_ref()),
+        remaining_blocks,
+        remaining_seconds,
+        dust_pool: DUST_POOL.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn query_merkle_root(deps: Deps, stage: u8) -> StdResult<MerkleRootResponse> {
+    let merkle_root = MERKLE_ROOT.load(deps.storage, stage)?;
+    let start = STAGE_START.may_load(deps.storage, stage)?;
+    let expiration = STAGE_EXPIRATION.may_load(deps.storage, stage)?;
+    let total_amount = STAGE_AMOUNT.load(deps.storage, stage)?;
+    let claimed_amount = STAGE_AMOUNT_CLAIMED.load(deps.storage, stage)?;
+    let leaf_format = STAGE_LEAF_FORMAT
+        .may_load(deps.storage, stage)?
+        .unwrap_or_default();
+    let hash_algo = stage_hash_algo(deps.storage, stage, &leaf_format)?;
+    let release = stage_release(deps.storage, &CONFIG.load(deps.storage)?, stage)?;
+    let resp = MerkleRootResponse {
+        stage,
+        merkle_root,
+        start,
+        expiration,
+        total_amount,
+        claimed_amount,
+        leaf_format,
+        hash_algo,
+        release,
+        proof_level_nodes: STAGE_PROOF_LEVEL
+            .may_load(deps.storage, stage)?
+            .unwrap_or_default(),
+        passport_created_before: STAGE_PASSPORT_CUTOFF.may_load(deps.storage, stage)?,
+        frozen_by: STAGE_FREEZES
+            .may_load(deps.storage, stage)?
+            .map(|freeze| freeze.frozen_by.to_string()),
+    };
+
+    Ok(resp)
+}
+
+pub fn query_stage(deps: Deps, stage: u8) -> StdResult<StageResponse> {
+    let root = query_merkle_root(deps, stage)?;
+    Ok(StageResponse {
+        stage,
+        merkle_root: root.merkle_root,
+        start_height: expiration_height(root.start.as_ref()),
+        start_time: expiration_time(root.start.as_ref()),
+        expiration_height: expiration_height(root.expiration.as_ref()),
+        expiration_time: expiration_time(root.expiration.as_ref()),
+        total_amount: root.total_amount,
+        claimed_amount: root.claimed_amount,
+        leaf_format: root.leaf_format,
+        hash_algo: root.hash_algo,
+        liquid_share: root.release.liquid_share,
+        vested_share: root.release.vested_share,
+        release_blocks: root.release.release_blocks,
+        proof_level_nodes: root.proof_level_nodes,
+        passport_created_before: root.passport_created_before,
+        frozen_by: root.frozen_by,
+    })
+}
+
+pub fn query_latest_stage(deps: Deps) -> StdResult<LatestStageResponse> {
+    let latest_stage = LATEST_STAGE.load(deps.storage)?;
+    let resp = LatestStageResponse { latest_stage };
+
+    Ok(resp)
+}
+
+pub fn query_is_claimed(deps: Deps, stage: u8, address: String) -> StdResult<IsClaimedResponse> {
+    let is_claimed = is_claimed(deps.storage, stage, &address)?;
+    let resp = IsClaimedResponse { is_claimed };
+
+    Ok(resp)
+}
+
+pub fn query_claimed_page(deps: Deps, stage: u8, page: u32) -> StdResult<ClaimedPageResponse> {
+    let bitmap = CLAIMED_PAGES
+        .may_load(deps.storage, (stage, page))?
+        .unwrap_or_default();
+    Ok(ClaimedPageResponse {
+        stage,
+        page,
+        bitmap,
+        claimed_count: bitmap.u128().count_ones(),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn query_is_eligible(
+    deps: Deps,
+    env: Env,
+    stage: u8,
+    claim_msg: ClaimMsg,
+    signature: Proof,
+    claim_amount: Uint128,
+    extra_amounts: Vec<Coin>,
+    leaf_index: Option<u32>,
+    proof: Vec<ProofNode>,
+) -> StdResult<IsEligibleResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let coefficient = current_coefficient(&cfg, CLAIMS_COUNT.load(deps.storage)?);
+
+    let claimer = claim_msg.snapshot_key();
+    let valid_proof = verify_merkle_proof(
+        deps,
+        stage,
+        &claimer,
+        claim_amount,
+        &extra_amounts,
+        leaf_index,
+        proof,
+    )
+    .is_ok();
+    let valid_signature = verify_claimer(deps, &env, &cfg, stage, &claim_msg, signature).is_ok();
+    let index_claimed = match leaf_index {
+        Some(leaf_index) => is_index_claimed(deps.storage, stage, leaf_index)?,
+        None => false,
+    };
+    let claimed = index_claimed || is_claimed(deps.storage, stage, &claimer)?;
+    let payout = scale_amount(claim_amount, coefficient)?.0;
+    let resp = IsEligibleResponse {
+        valid_proof,
+        valid_signature,
+        is_claimed: claimed,
+        payout,
+        extra_payout: proportional_coins(&extra_amounts, payout, claim_amount),
+    };
+
+    Ok(resp)
+}
+
+pub fn query_all_claims(
+    deps: Deps,
+    start_after: Option<(String, u8)>,
+    limit: Option<u32>,
+) -> StdResult<AllClaimsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|key| Bound::exclusive(key.joined_key()));
+
+    let claims = CLAIMS
+        .range_de(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let ((address, stage), claim) = item?;
+            Ok(claim_response(address, stage, claim))
+        })
+        .collect::<StdResult<_>>()?;
+    let resp = AllClaimsResponse { claims };
+
+    Ok(resp)
+}
+
+pub fn query_export_state(
+    deps: Deps,
+    start_after: Option<ExportCursor>,
+    limit: Option<u32>,
+) -> StdResult<ExportStateResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let version = get_contract_version(deps.storage)?;
+
+    // claims are exported before the release states
+    let mut claims = vec![];
+    if !matches!(start_after, Some(ExportCursor::Release { .. })) {
+        let start = match &start_after {
+            Some(ExportCursor::Claim { address, stage }) => {
+                Some(Bound::exclusive((address.clone(), *stage).joined_key()))
+            }
+            _ => None,
+        };
+        claims = CLAIMS
+            .range_de(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let ((address, stage), claim) = item?;
+                Ok(claim_response(address, stage, claim))
+            })
+            .collect::<StdResult<_>>()?;
+    }
+    let mut release_states = vec![];
+    if claims.len() < limit {
+        let start = match start_after {
+            Some(ExportCursor::Release { address }) => Some(Bound::exclusive(address)),
+            _ => None,
+        };
+        release_states = RELEASE_STATE
+            .range_de(deps.storage, start, None, Order::Ascending)
+            .take(limit - claims.len())
+            .map(|item| {
+                let (address, state) = item?;
+                Ok(ExportedReleaseState {
+                    address,
+                    positions: state.positions,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+    }
+
+    let next = if claims.len() + release_states.len() < limit {
+        None
+    } else if let Some(state) = release_states.last() {
+        Some(ExportCursor::Release {
+            address: state.address.clone(),
+        })
+    } else {
+        claims.last().map(|claim| ExportCursor::Claim {
+            address: claim.address.clone(),
+            stage: claim.stage,
+        })
+    };
+
+    Ok(ExportStateResponse {
+        version: EXPORT_STATE_VERSION,
+        contract: version.contract,
+        contract_version: version.version,
+        claims,
+        release_states,
+        next,
+    })
+}
+
+pub fn query_claims_by_address(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<ClaimsByAddressResponse> {
+    let claims = CLAIMS_BY_TARGET
+        .sub_prefix_de(address.clone())
+        .keys_de(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (claimer, stage) = item?;
+            let claim = CLAIMS.load(deps.storage, (claimer.clone(), stage))?;
+            Ok(claim_response(claimer, stage, claim))
+        })
+        .collect::<StdResult<_>>()?;
+    let release_state = query_release_state(deps, env, address)?;
+    let resp = ClaimsByAddressResponse {
+        claims,
+        release_state,
+    };
+
+    Ok(resp)
+}
+
+pub fn query_has_claimed(deps: Deps, address: String) -> StdResult<HasClaimedResponse> {
+    let has_claimed = CLAIMS_BY_TARGET
+        .sub_prefix_de(address)
+        .keys_de(deps.storage, None, None, Order::Ascending)
+        .next()
+        .transpose()?
+        .is_some();
+
+    Ok(HasClaimedResponse { has_claimed })
+}
+
+pub fn query_claimed_amount(deps: Deps, address: String) -> StdResult<ClaimedAmountResponse> {
+    let amount = CLAIMS_BY_TARGET
+        .sub_prefix_de(address)
+        .keys_de(deps.storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |total, item| -> StdResult<_> {
+            let (claimer, stage) = item?;
+            let claim = CLAIMS.load(deps.storage, (claimer, stage))?;
+            Ok(total.checked_add(claim.amount)?)
+        })?;
+
+    Ok(ClaimedAmountResponse { amount })
+}
+
+pub fn query_release_state(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<ReleaseStateResponse> {
+    let state = RELEASE_STATE
+        .may_load(deps.storage, address.clone())?
+        .unwrap_or_default();
+    let (releasable, releasable_extra) = releasable_at(deps, &state, env.block.height)?;
+    let resp = ReleaseStateResponse {
+        address,
+        positions: state.positions,
+        releasable,
+        releasable_extra_amounts: releasable_extra,
+    };
+
+    Ok(resp)
+}
+
+pub fn query_release_projection(
+    deps: Deps,
+    env: Env,
+    address: String,
+    at_height: u64,
+) -> StdResult<ReleaseProjectionResponse> {
+    // releases below the current height would not account for the released amount
+    if at_height < env.block.height {
+        return Err(StdError::generic_err("at_height is in the past"));
+    }
+    let state = RELEASE_STATE
+        .may_load(deps.storage, address.clone())?
+        .unwrap_or_default();
+    let (releasable, releasable_extra_amounts) = releasable_at(deps, &state, at_height)?;
+    Ok(ReleaseProjectionResponse {
+        address,
+        at_height,
+        releasable,
+        releasable_extra_amounts,
+    })
+}
+
+pub fn query_audit_log(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<AuditLogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(id.to_be_bytes().to_vec()));
+
+    let entries = AUDIT_LOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AuditLogResponse { entries })
+}
+
+pub fn query_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<HistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
+    let count = HISTORY_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    // older snapshots are overwritten in the ring buffer
+    let first = count.saturating_sub(HISTORY_CAPACITY);
+    let start = start_after.map_or(first, |id| first.max(id + 1));
+
+    let snapshots = (start..count.min(start.saturating_add(limit)))
+        .map(|id| HISTORY.load(deps.storage, id % HISTORY_CAPACITY))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(HistoryResponse { snapshots })
+}
+
+pub fn query_lottery(deps: Deps) -> StdResult<LotteryResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let total_tickets = LOTTERY_TICKETS.may_load(deps.storage)?.unwrap_or_default();
+    let resp = match LOTTERY.may_load(deps.storage)? {
+        Some(lottery) => LotteryResponse {
+            ticket_size: cfg.lottery_ticket_size,
+            total_tickets,
+            commitment: Some(lottery.commitment),
+            winners: lottery.winners,
+            prize: lottery.prize,
+            draw_height: Some(lottery.draw_height),
+            reveal_deadline: Some(lottery.draw_height + LOTTERY_REVEAL_BLOCKS),
+            beacon: lottery.beacon,
+            seed: lottery.seed,
+            drawn_at: lottery.drawn_at,
+            expired_at: lottery.expired_at,
+            results: lottery.results,
+        },
+        None => LotteryResponse {
+            ticket_size: cfg.lottery_ticket_size,
+            total_tickets,
+            commitment: None,
+            winners: 0,
+            prize: Uint128::zero(),
+            draw_height: None,
+            reveal_deadline: None,
+            beacon: None,
+            seed: None,
+            drawn_at: None,
+            expired_at: None,
+            results: vec![],
+        },
+    };
+    Ok(resp)
+}
+
+pub fn query_reconciliation(deps: Deps, env: Env) -> StdResult<ReconciliationResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // vesting positions and unbonded payouts are still held for their owners
+    let mut vesting = Uint128::zero();
+    let mut vesting_extra = vec![];
+    for item in RELEASE_STATE.range(deps.storage, None, None, Order::Ascending) {
+        let (_, state) = item?;
+        for position in state.positions.iter() {
+            let locked = position.amount - position.released;
+            vesting += locked;
+            add_coins(
+                &mut vesting_extra,
+                releasable_extra_amounts(position, locked),
+            );
+        }
+    }
+    let mut unbonded = Uint128::zero();
+    for item in UNBONDINGS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, unbondings) = item?;
+        unbonded += unbondings
+            .iter()
+            .filter(|u| u.release_at <= env.block.time)
+            .map(|u| u.amount)
+            .sum::<Uint128>();
+    }
+
+    let contract = env.contract.address.to_string();
+    let (denom, balance) = match &cfg.gift_asset {
+        GiftAsset::Native(denom) => (
+            denom.clone(),
+            deps.querier.query_balance(&contract, denom)?.amount,
+        ),
+        GiftAsset::Cw20(token) => {
+            let resp: cw20::BalanceResponse = deps.querier.query_wasm_smart(
+                token,
+                &cw20::Cw20QueryMsg::Balance {
+                    address: contract.clone(),
+                },
+            )?;
+            (token.to_string(), resp.balance)
+        }
+    };
+    // the bonus pool of a committed lottery is held until the draw
+    let lottery_pool = LOTTERY
+        .may_load(deps.storage)?
+        .filter(|lottery| lottery.seed.is_none())
+        .map_or(Uint128::zero(), |lottery| {
+            lottery.prize * Uint128::from(lottery.winners)
+        });
+    let gift = balance_reconciliation(
+        denom,
+        cfg.current_balance + cfg.referral_balance + vesting + unbonded + lottery_pool,
+        balance,
+    );
+
+    let mut extra_balances = vec![];
+    for extra in cfg.extra_balances.iter() {
+        let vested = vesting_extra
+            .iter()
+            .find(|c| c.denom == extra.denom)
+            .map(|c| c.amount)
+            .unwrap_or_default();
+        let balance = deps.querier.query_balance(&contract, &extra.denom)?.amount;
+        extra_balances.push(balance_reconciliation(
+            extra.denom.clone(),
+            extra.current_balance + vested,
+            balance,
+        ));
+    }
+
+    let drifted = std::iter::once(&gift)
+        .chain(extra_balances.iter())
+        .any(|r| r.accounted != r.balance);
+    Ok(ReconciliationResponse {
+        gift,
+        extra_balances,
+        drifted,
+    })
+}
+
+fn balance_reconciliation(
+    denom: String,
+    accounted: Uint128,
+    balance: Uint128,
+) -> BalanceReconciliation {
+    BalanceReconciliation {
+        denom,
+        accounted,
+        balance,
+        surplus: balance.saturating_sub(accounted),
+        deficit: accounted.saturating_sub(balance),
+    }
+}
+
+/// Returns the amount and the extra denoms of the positions releasable at the height.
+fn releasable_at(deps: Deps, state: &ReleaseState, height: u64) -> StdResult<(Uint128, Vec<Coin>)> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let activation_block = ACTIVATION_BLOCK.may_load(deps.storage)?;
+    let mut releasable = Uint128::zero();
+    let mut releasable_extra = vec![];
+    for position in state.positions.iter() {
+        let amount = releasable_amount(position, height, cfg.release_blocks, activation_block);
+        add_coins(
+            &mut releasable_extra,
+            releasable_extra_amounts(position, amount),
+        );
+        releasable += amount;
+    }
+    Ok((releasable, releasable_extra))
+}
+
+fn claim_response(address: String, stage: u8, claim: ClaimState) -> ClaimResponse {
+    ClaimResponse {
+        address,
+        stage,
+        amount: claim.amount,
+        timestamp: claim.timestamp,
+        height: claim.height,
+        target_addr: claim.target_addr,
+        recipient: claim.recipient,
+        extra_amounts: claim.extra_amounts,
+        delegated_to: claim.delegated_to,
+        memo: claim.memo,
+    }
+}
+
+/// Handles the passport verification replies. Failed verification reverts the claim,
+/// failed claim hooks are only reported.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id == CLAIM_HOOK_REPLY_ID {
+        return Ok(Response::new()
+            .add_attribute("action", "claim_hook_failed")
+            .add_attribute("error", msg.result.unwrap_err()));
+    }
+    if msg.id >= RELEASE_REPLY_ID_OFFSET {
+        let address = PENDING_RELEASES.load(deps.storage, msg.id)?;
+        PENDING_RELEASES.remove(deps.storage, msg.id);
+        let res = Response::new().add_attribute("address", address);
+        return Ok(match msg.result.into_result() {
+            Ok(_) => res.add_attribute("action", "release_succeeded"),
+            Err(err) => res
+                .add_attribute("action", "release_failed")
+                .add_attribute("error", err),
+        });
+    }
+    let address = PASSPORT_CLAIMS.load(deps.storage, msg.id)?;
+    PASSPORT_CLAIMS.remove(deps.storage, msg.id);
+    match msg.result.into_result() {
+        Ok(_) => Ok(Response::new()
+            .add_attribute("action", "verify_passport")
+            .add_attribute("address", address)),
+        Err(msg) => Err(ContractError::PassportVerificationFailed { address, msg }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(mut deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    // governance actions are recorded with the gov actor
+    let params = String::from_utf8(to_vec(&msg)?).map_err(StdError::invalid_utf8)?;
+    let action = match &msg {
+        SudoMsg::Pause { .. } => "sudo_pause",
+        SudoMsg::UpdateOwner { .. } => "sudo_update_owner",
+        SudoMsg::Clawback { .. } => "sudo_clawback",
+        SudoMsg::UpdateRootGuardians { .. } => "sudo_update_root_guardians",
+    };
+    let res = sudo_dispatch(deps.branch(), env.clone(), msg)?;
+    record_audit(deps.storage, &env, "gov".to_string(), action, params)?;
+    Ok(res)
+}
+
+fn sudo_dispatch(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::Pause { paused } => sudo_set_paused(deps, paused),
+        SudoMsg::UpdateOwner { owner } => sudo_update_owner(deps, owner),
+        SudoMsg::Clawback { stage } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(clawback(deps, env, cfg, stage)?.add_attribute("sudo", "true"))
+        }
+        SudoMsg::UpdateRootGuardians {
+            guardians,
+            threshold,
+        } => sudo_update_root_guardians(deps, guardians, threshold),
+    }
+}
+
+/// Replaces the root guardians, approvals of pending roots by removed guardians are
+/// not counted.
+pub fn sudo_update_root_guardians(
+    deps: DepsMut,
+    guardians: Vec<String>,
+    threshold: u64,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    cfg.root_guardians = validate_root_guardians(deps.as_ref(), guardians, threshold)?;
+    cfg.root_guardian_threshold = threshold;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_root_guardians"),
+        attr("guardians", cfg.root_guardians.len().to_string()),
+        attr("threshold", threshold.to_string()),
+        attr("sudo", "true"),
+    ]))
+}
+
+pub fn sudo_set_paused(deps: DepsMut, paused: bool) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    cfg.paused = paused;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(Response::new().add_attributes(vec![attr("action", action), attr("sudo", "true")]))
+}
+
+/// Replaces the owner and drops any pending ownership proposal.
+pub fn sudo_update_owner(deps: DepsMut, owner: Option<String>) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    cfg.owner = owner.map(|o| deps.api.addr_validate(&o)).transpose()?;
+    cfg.pending_owner = None;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_owner"),
+        attr(
+            "owner",
+            cfg.owner
+                .map_or_else(|| "none".to_string(), |o| o.to_string()),
+        ),
+        attr("sudo", "true"),
+    ]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let version = get_contract_version(deps.storage)?;
+    if version.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: version.contract,
+        });
+    }
+    let previous_version = parse_version(&version.version)?;
+    let cannot_migrate = |msg: &str| ContractError::CannotMigrateVersion {
+        previous_version: version.version.clone(),
+        msg: msg.to_string(),
+    };
+    if previous_version > parse_version(CONTRACT_VERSION)? {
+        return Err(cannot_migrate("downgrades are not supported"));
+    }
+
+    let mut res = Response::new().add_attribute("action", "migrate");
+    match msg {
+        MigrateMsg::Bump {} => {
+            if previous_version < (0, 2, 0) {
+                return Err(cannot_migrate("state must be upgraded"));
+            }
+        }
+        MigrateMsg::UpgradeV1 {
+            treasury,
+            coefficient_claims,
+        } => {
+            if previous_version >= (0, 2, 0) {
+                return Err(cannot_migrate("state is already upgraded"));
+            }
+            let claims_count = migrate_v1(deps.branch(), &env, treasury, coefficient_claims)?;
+            res = res.add_attribute("migrated_claims", claims_count.to_string());
+        }
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(res
+        .add_attribute("previous_version", version.version)
+        .add_attribute("version", CONTRACT_VERSION))
 }
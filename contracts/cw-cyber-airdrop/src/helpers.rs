@@ -1,187 +1,922 @@
-use crate::msg::ClaimMsg;
-use crate::state::{Config, CONFIG, MERKLE_ROOT};
+use crate::msg::{ClaimEntry, ClaimMsg, ClaimerType, MessageEncoding, ProofNode, SignatureType};
+use crate::state::{
+    ClaimHook, Config, GiftAsset, HashAlgo, LeafFormat, MilestoneTier, StageRelease,
+    VestingPosition, ALLOCATIONS, CLAIMED_PAGES, CLAIMED_PAGE_BITS, CLAIMS, CONFIG,
+    CONTRACT_WALLET_CLAIMS, MERKLE_ROOT, PROOF_LEVEL_NODES, STAGE_HASH_ALGO, STAGE_LEAF_FORMAT,
+    STAGE_RELEASE, VERIFIED_CLAIMS,
+};
 use crate::ContractError;
 use anyhow::Result;
+use bech32::{ToBase32, Variant};
 use cosmwasm_std::{
-    from_binary, from_slice, to_vec, Binary, Coin, Deps, DepsMut, MessageInfo, StdError, StdResult,
-    Uint128, Uint64, VerificationError,
+    to_binary, to_vec, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, IbcMsg,
+    IbcTimeout, StakingMsg, StdError, StdResult, Storage, Timestamp, Uint128, Uint256, WasmMsg,
+};
+use cw0::Expiration;
+use cw20::Cw20ExecuteMsg;
+use cyber_gift_proofs::compress_pubkey;
+use cyber_passport::msg::{
+    PassportAddressesResponse, PassportResponse, QueryMsg as PassportQueryMsg,
 };
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use ripemd160::Ripemd160;
 use sha2::{Digest, Sha256};
 use sha3::Keccak256;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
+
+/// Builds the message paying amount of the gift asset to the recipient.
+pub fn gift_msg(asset: &GiftAsset, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+    let msg = match asset {
+        GiftAsset::Native(denom) => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }
+        .into(),
+        GiftAsset::Cw20(token) => WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    };
+    Ok(msg)
+}
+
+/// Builds the messages paying amount of the gift asset and the extra denoms to the recipient.
+/// Native gift denom is sent in the same bank message as the extra denoms.
+pub fn gift_msgs(
+    asset: &GiftAsset,
+    recipient: &str,
+    amount: Uint128,
+    extra_amounts: Vec<Coin>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = vec![];
+    let mut coins = vec![];
+    match asset {
+        GiftAsset::Native(denom) if !amount.is_zero() => coins.push(Coin {
+            denom: denom.clone(),
+            amount,
+        }),
+        GiftAsset::Cw20(_) if !amount.is_zero() => msgs.push(gift_msg(asset, recipient, amount)?),
+        _ => {}
+    }
+    coins.extend(extra_amounts.into_iter().filter(|c| !c.amount.is_zero()));
+    if !coins.is_empty() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: coins,
+            }
+            .into(),
+        );
+    }
+    Ok(msgs)
+}
+
+/// Builds the IBC transfers paying amount of the native gift and the extra denoms to the
+/// recipient on the other side of the channel, a transfer carries a single coin.
+pub fn ibc_gift_msgs(
+    asset: &GiftAsset,
+    channel_id: &str,
+    recipient: &str,
+    amount: Uint128,
+    extra_amounts: Vec<Coin>,
+    timeout: IbcTimeout,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let denom = match asset {
+        GiftAsset::Native(denom) => denom,
+        GiftAsset::Cw20(_) => return Err(ContractError::InvalidInput {}),
+    };
+    let coins = std::iter::once(Coin {
+        denom: denom.clone(),
+        amount,
+    })
+    .chain(extra_amounts)
+    .filter(|c| !c.amount.is_zero());
+    Ok(coins
+        .map(|amount| {
+            IbcMsg::Transfer {
+                channel_id: channel_id.to_string(),
+                to_address: recipient.to_string(),
+                amount,
+                timeout: timeout.clone(),
+            }
+            .into()
+        })
+        .collect())
+}
 
-pub fn update_coefficient(deps: DepsMut, amount: Uint128, config: &mut Config) -> StdResult<()> {
-    let coefficient_up = config.coefficient_up;
-    let coefficient_down = config.coefficient_down;
-    let initial_balance = config.initial_balance;
-    let current_balance = config.current_balance;
+/// Returns the height of an expiration at a height, for the flat fields of responses.
+pub fn expiration_height(expiration: Option<&Expiration>) -> Option<u64> {
+    match expiration {
+        Some(Expiration::AtHeight(height)) => Some(*height),
+        _ => None,
+    }
+}
 
-    let new_coefficient =
-        coefficient_up + (coefficient_down - coefficient_up) * initial_balance / current_balance;
+/// Returns the time of an expiration at a time, for the flat fields of responses.
+pub fn expiration_time(expiration: Option<&Expiration>) -> Option<Timestamp> {
+    match expiration {
+        Some(Expiration::AtTime(time)) => Some(*time),
+        _ => None,
+    }
+}
+
+/// Returns the passport the address is linked in, none if the passport contract does not
+/// find it. Other query errors are returned, so that a broken passport contract fails the
+/// claims instead of reading as no links.
+pub fn linked_passport(
+    deps: Deps,
+    passport: &Addr,
+    address: &str,
+) -> StdResult<Option<PassportResponse>> {
+    let resp = deps.querier.query_wasm_smart::<PassportResponse>(
+        passport,
+        &PassportQueryMsg::PassportByAddress {
+            address: address.to_string(),
+        },
+    );
+    match resp {
+        Ok(resp) => Ok(Some(resp)),
+        // errors of the queried contract reach the querier as its message only
+        Err(StdError::NotFound { .. }) => Ok(None),
+        Err(StdError::GenericErr { msg }) if msg.ends_with("not found") => Ok(None),
+        Err(err) => Err(err),
+    }
+}
 
-    config.coefficient = new_coefficient;
-    config.current_balance = current_balance - amount;
-    CONFIG.save(deps.storage, &config)
+/// Returns the EIP-55 mixed case encoding of a 0x address, the form snapshots often
+/// take while the passport returns linked addresses lowercased.
+pub fn eip55_address(address: &str) -> String {
+    let hex_address = address.trim_start_matches("0x").to_lowercase();
+    let hash = Keccak256::digest(hex_address.as_bytes());
+    let checksummed: String = hex_address
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+/// Builds the execute msg of the claim hook from its template.
+pub fn claim_hook_msg(
+    hook: &ClaimHook,
+    stage: u8,
+    claim_msg: &ClaimMsg,
+    amount: Uint128,
+) -> WasmMsg {
+    let msg = hook
+        .msg
+        .replace("{stage}", &stage.to_string())
+        .replace("{address}", &claim_msg.gift_claiming_address)
+        .replace("{target_addr}", &claim_msg.target_addr)
+        .replace("{recipient}", claim_msg.recipient())
+        .replace("{amount}", &amount.to_string());
+    WasmMsg::Execute {
+        contract_addr: hook.contract.to_string(),
+        msg: Binary::from(msg.into_bytes()),
+        funds: vec![],
+    }
+}
+
+/// Builds the messages delegating amount of the native gift to the validator, the extra
+/// denoms are sent to the recipient.
+pub fn delegate_msgs(
+    asset: &GiftAsset,
+    validator: &str,
+    recipient: &str,
+    amount: Uint128,
+    extra_amounts: Vec<Coin>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let denom = match asset {
+        GiftAsset::Native(denom) => denom,
+        GiftAsset::Cw20(_) => return Err(ContractError::InvalidInput {}),
+    };
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !amount.is_zero() {
+        msgs.push(
+            StakingMsg::Delegate {
+                validator: validator.to_string(),
+                amount: Coin {
+                    denom: denom.clone(),
+                    amount,
+                },
+            }
+            .into(),
+        );
+    }
+    msgs.extend(gift_msgs(asset, recipient, Uint128::zero(), extra_amounts)?);
+    Ok(msgs)
+}
+
+/// Scales the coins by part / total, e.g. the extra denoms by the share of the gift paid.
+pub fn proportional_coins(coins: &[Coin], part: Uint128, total: Uint128) -> Vec<Coin> {
+    if total.is_zero() {
+        return vec![];
+    }
+    coins
+        .iter()
+        .map(|c| Coin {
+            denom: c.denom.clone(),
+            amount: c.amount.multiply_ratio(part, total),
+        })
+        .filter(|c| !c.amount.is_zero())
+        .collect()
+}
+
+/// Returns the coefficient applied after claims_count claims. It decreases linearly
+/// from coefficient_up to coefficient_down over coefficient_claims claims.
+pub fn current_coefficient(config: &Config, claims_count: u64) -> Decimal {
+    let multiplier =
+        current_tier(config, claims_count).map_or(Decimal::one(), |(_, tier)| tier.multiplier);
+    if config.coefficient_claims == 0 {
+        return config.coefficient_up * multiplier;
+    }
+    let claims = claims_count.min(config.coefficient_claims);
+    let decrease = (config.coefficient_up - config.coefficient_down)
+        * Decimal::from_ratio(claims, config.coefficient_claims);
+    (config.coefficient_up - decrease) * multiplier
+}
+
+/// Returns the index and the milestone tier of the next claim, none once the claims
+/// count reached all tiers.
+pub fn current_tier(config: &Config, claims_count: u64) -> Option<(usize, &MilestoneTier)> {
+    config
+        .milestone_tiers
+        .iter()
+        .enumerate()
+        .find(|(_, tier)| claims_count < tier.until_claims)
+}
+
+/// Scales the snapshot amount by the coefficient, rounding the payout down. Returns the
+/// payout and the dust left by the rounding. Non-zero payouts rounded down to zero pay
+/// the 1 ugift minimum instead and leave no dust.
+pub fn scale_amount(amount: Uint128, coefficient: Decimal) -> StdResult<(Uint128, Decimal)> {
+    let payout = amount * coefficient;
+    if payout.is_zero() && !amount.is_zero() && !coefficient.is_zero() {
+        return Ok((Uint128::new(1), Decimal::zero()));
+    }
+    let decimal_places = coefficient.decimal_places();
+    let fractional = Uint256::from(10u128.pow(decimal_places));
+    let remainder = amount
+        .full_mul(coefficient.atomics())
+        .checked_rem(fractional)?;
+    let remainder =
+        Uint128::try_from(remainder).map_err(|err| StdError::generic_err(err.to_string()))?;
+    let dust = Decimal::from_atomics(remainder, decimal_places)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok((payout, dust))
+}
+
+/// Decrements the balances by the paid gift.
+pub fn update_balances(
+    deps: DepsMut,
+    amount: Uint128,
+    extra_amounts: &[Coin],
+    config: &mut Config,
+) -> Result<(), ContractError> {
+    config.current_balance = config
+        .current_balance
+        .checked_sub(amount)
+        .map_err(StdError::from)?;
+
+    // extra denoms are tracked with their own balances
+    for coin in extra_amounts {
+        let balance = config
+            .extra_balances
+            .iter_mut()
+            .find(|b| b.denom == coin.denom)
+            .ok_or(ContractError::InvalidInput {})?;
+        balance.current_balance = balance
+            .current_balance
+            .checked_sub(coin.amount)
+            .map_err(StdError::from)?;
+    }
+    CONFIG.save(deps.storage, config)?;
+    Ok(())
+}
+
+/// Returns the key of a claim in the verified claims, the hex sha256 of the claim.
+pub fn verified_claim_key(claim: &ClaimEntry) -> StdResult<String> {
+    Ok(hex::encode(Sha256::digest(&to_vec(claim)?)))
+}
+
+/// Returns whether the claim is the one pre-verified by its target.
+pub fn is_verified_claim(storage: &dyn Storage, claim: &ClaimEntry) -> StdResult<bool> {
+    let verified = VERIFIED_CLAIMS.may_load(storage, claim.claim_msg.target_addr.clone())?;
+    Ok(verified == Some(verified_claim_key(claim)?))
+}
+
+/// Removes the claim from the verified claims of its target, returns whether it was
+/// pre-verified.
+pub fn take_verified_claim(storage: &mut dyn Storage, claim: &ClaimEntry) -> StdResult<bool> {
+    if !is_verified_claim(storage, claim)? {
+        return Ok(false);
+    }
+    VERIFIED_CLAIMS.remove(storage, claim.claim_msg.target_addr.clone());
+    Ok(true)
+}
+
+/// Returns the vested and not yet released amount of a position at the given height.
+/// Nothing is vested before the contract is activated.
+pub fn releasable_amount(
+    position: &VestingPosition,
+    height: u64,
+    release_blocks: u64,
+    activation_block: Option<u64>,
+) -> Uint128 {
+    let activation_block = match activation_block {
+        Some(block) => block,
+        None => return Uint128::zero(),
+    };
+    let release_blocks = position.release_blocks.unwrap_or(release_blocks);
+    let start_block = position.start_block.max(activation_block);
+    let elapsed = height.saturating_sub(start_block);
+    let vested = if elapsed >= release_blocks {
+        position.amount
+    } else {
+        position.amount.multiply_ratio(elapsed, release_blocks)
+    };
+    vested - position.released
+}
+
+/// Returns the release split of the stage, the config split if the stage has none.
+pub fn stage_release(storage: &dyn Storage, config: &Config, stage: u8) -> StdResult<StageRelease> {
+    match STAGE_RELEASE.may_load(storage, stage)? {
+        Some(release) => Ok(release),
+        None => Ok(config_release(config)),
+    }
+}
+
+/// Returns the release split of the config, payouts are liquid without release_blocks.
+pub fn config_release(config: &Config) -> StageRelease {
+    let liquid_share = match config.release_blocks {
+        0 => Decimal::one(),
+        _ => config.liquid_share,
+    };
+    StageRelease {
+        liquid_share,
+        vested_share: Decimal::one() - liquid_share,
+        release_blocks: config.release_blocks,
+    }
+}
+
+/// Returns the extra denoms of a position released along with the releasable amount.
+pub fn releasable_extra_amounts(position: &VestingPosition, releasable: Uint128) -> Vec<Coin> {
+    let vested = position.released + releasable;
+    position
+        .extra_amounts
+        .iter()
+        .map(|c| Coin {
+            denom: c.denom.clone(),
+            amount: c.amount.multiply_ratio(vested, position.amount)
+                - c.amount.multiply_ratio(position.released, position.amount),
+        })
+        .filter(|c| !c.amount.is_zero())
+        .collect()
 }
 
 pub fn verify_merkle_proof(
-    deps: &DepsMut,
-    info: &MessageInfo,
+    deps: Deps,
+    stage: u8,
+    claimer: &str,
     amount: Uint128,
-    proof: Vec<String>,
+    extra_amounts: &[Coin],
+    leaf_index: Option<u32>,
+    proof: Vec<ProofNode>,
 ) -> Result<bool, ContractError> {
-    let merkle_root = MERKLE_ROOT.load(deps.storage)?;
-
-    let user_input = format!("{}{}", info.sender, amount);
-    let hash = sha2::Sha256::digest(user_input.as_bytes())
-        .as_slice()
-        .try_into()
-        .map_err(|_| ContractError::WrongLength {})?;
-
-    let hash = proof.into_iter().try_fold(hash, |hash, p| {
-        let mut proof_buf = [0; 32];
-        hex::decode_to_slice(p, &mut proof_buf)?;
-        let mut hashes = [hash, proof_buf];
-        hashes.sort_unstable();
-        sha2::Sha256::digest(&hashes.concat())
+    // uploaded allocations of stages without a root are claimed without a proof
+    if proof.is_empty() && MERKLE_ROOT.load(deps.storage, stage)?.is_empty() {
+        let allocation = ALLOCATIONS.may_load(deps.storage, (stage, claimer.to_string()))?;
+        if let Some(allocation) = allocation {
+            if allocation != amount || !extra_amounts.is_empty() {
+                return Err(ContractError::ProofInvalid {
+                    stage,
+                    leaf: format!("{}{}", claimer, amount),
+                });
+            }
+            return Ok(true);
+        }
+    }
+
+    let leaf_format = STAGE_LEAF_FORMAT
+        .may_load(deps.storage, stage)?
+        .unwrap_or_default();
+    if leaf_format == LeafFormat::AbiPacked {
+        let leaf = abi_packed_leaf(claimer, amount, extra_amounts).ok_or_else(|| {
+            ContractError::ProofInvalid {
+                stage,
+                leaf: format!("{}{}", claimer, amount),
+            }
+        })?;
+        return verify_merkle_leaf(deps, stage, &leaf, proof);
+    }
+
+    // leaf of a gift with extra denoms encodes all its coins, the gift asset with its
+    // denom too, e.g. "{claimer}1000boot,500hydrogen"
+    let separator = match leaf_format {
+        LeafFormat::Cw20MerkleAirdrop => ":",
+        _ => "",
+    };
+    let mut user_input = format!("{}{}{}", claimer, separator, amount);
+    if leaf_format == LeafFormat::Indexed {
+        let leaf_index = leaf_index.ok_or(ContractError::InvalidInput {})?;
+        user_input = format!("{}:{}", leaf_index, user_input);
+    }
+    if !extra_amounts.is_empty() {
+        user_input.push_str(&CONFIG.load(deps.storage)?.gift_asset.denom());
+    }
+    for coin in extra_amounts {
+        user_input.push_str(&format!(",{}", coin));
+    }
+    verify_merkle_leaf(deps, stage, user_input.as_bytes(), proof)
+}
+
+/// Returns the `abi.encodePacked(address, uint256)` leaf of an Ethereum claimer, none
+/// if the claimer is not an Ethereum address or the gift has extra denoms.
+fn abi_packed_leaf(claimer: &str, amount: Uint128, extra_amounts: &[Coin]) -> Option<Vec<u8>> {
+    let address = hex::decode(claimer.strip_prefix("0x")?).ok()?;
+    if address.len() != 20 || !extra_amounts.is_empty() {
+        return None;
+    }
+    Some([address.as_slice(), &[0; 16], &amount.u128().to_be_bytes()].concat())
+}
+
+/// Returns the hash algo of the stage, the default of its leaf format if none recorded.
+pub fn stage_hash_algo(
+    storage: &dyn Storage,
+    stage: u8,
+    leaf_format: &LeafFormat,
+) -> StdResult<HashAlgo> {
+    Ok(STAGE_HASH_ALGO
+        .may_load(storage, stage)?
+        .unwrap_or_else(|| leaf_format.default_hash_algo()))
+}
+
+/// Returns the root the proof folds the leaf into. Nodes are hashed as sorted pairs
+/// with a single hasher, so the proof is verified without allocations.
+pub fn merkle_proof_root(
+    hash_algo: HashAlgo,
+    leaf: &[u8],
+    proof: &[ProofNode],
+) -> Result<[u8; 32], ContractError> {
+    match hash_algo {
+        HashAlgo::Sha256 => fold_proof(Sha256::new(), leaf, proof),
+        HashAlgo::Keccak256 => fold_proof(Keccak256::new(), leaf, proof),
+    }
+}
+
+fn fold_proof<D: Digest>(
+    mut hasher: D,
+    leaf: &[u8],
+    proof: &[ProofNode],
+) -> Result<[u8; 32], ContractError> {
+    let mut hash = [0u8; 32];
+    hasher.update(leaf);
+    hash.copy_from_slice(&hasher.finalize_reset());
+    for (index, node) in proof.iter().enumerate() {
+        let node: &[u8; 32] = node
+            .0
             .as_slice()
             .try_into()
-            .map_err(|_| ContractError::WrongLength {})
+            .map_err(|_| ContractError::InvalidProofNode { index })?;
+        if hash <= *node {
+            hasher.update(hash);
+            hasher.update(node);
+        } else {
+            hasher.update(node);
+            hasher.update(hash);
+        }
+        hash.copy_from_slice(&hasher.finalize_reset());
+    }
+    Ok(hash)
+}
+
+/// Returns the root a level of the tree folds into. Pairs of nodes are hashed like proof
+/// nodes and odd nodes are promoted to the next level.
+pub fn merkle_level_root(
+    hash_algo: HashAlgo,
+    nodes: &[ProofNode],
+) -> Result<[u8; 32], ContractError> {
+    let mut level = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            <[u8; 32]>::try_from(node.0.as_slice())
+                .map_err(|_| ContractError::InvalidProofNode { index })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(hash_algo, a, b),
+                _ => pair[0],
+            })
+            .collect();
+    }
+    level.pop().ok_or(ContractError::InvalidInput {})
+}
+
+fn hash_pair(hash_algo: HashAlgo, a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    match hash_algo {
+        HashAlgo::Sha256 => Sha256::new().chain(first).chain(second).finalize().into(),
+        HashAlgo::Keccak256 => Keccak256::new()
+            .chain(first)
+            .chain(second)
+            .finalize()
+            .into(),
+    }
+}
+
+/// Returns the page of a leaf index in the claimed pages and its bit in the page.
+fn claimed_page_bit(leaf_index: u32) -> (u32, u128) {
+    (
+        leaf_index / CLAIMED_PAGE_BITS,
+        1u128 << (leaf_index % CLAIMED_PAGE_BITS),
+    )
+}
+
+pub fn is_index_claimed(storage: &dyn Storage, stage: u8, leaf_index: u32) -> StdResult<bool> {
+    let (page, bit) = claimed_page_bit(leaf_index);
+    let bitmap = CLAIMED_PAGES
+        .may_load(storage, (stage, page))?
+        .unwrap_or_default();
+    Ok(bitmap.u128() & bit != 0)
+}
+
+pub fn set_index_claimed(storage: &mut dyn Storage, stage: u8, leaf_index: u32) -> StdResult<()> {
+    let (page, bit) = claimed_page_bit(leaf_index);
+    CLAIMED_PAGES.update(storage, (stage, page), |bitmap| -> StdResult<_> {
+        Ok(Uint128::new(bitmap.unwrap_or_default().u128() | bit))
     })?;
+    Ok(())
+}
+
+/// Returns true if the address claimed the stage, by its claim details.
+pub fn is_claimed(storage: &dyn Storage, stage: u8, address: &str) -> StdResult<bool> {
+    Ok(CLAIMS.has(storage, (address.to_string(), stage)))
+}
 
+/// Verifies the proof of a leaf against the root of the stage, the leaf is hashed
+/// with the hash algo of the stage.
+pub fn verify_merkle_leaf(
+    deps: Deps,
+    stage: u8,
+    leaf: &[u8],
+    proof: Vec<ProofNode>,
+) -> Result<bool, ContractError> {
+    let merkle_root = MERKLE_ROOT.load(deps.storage, stage)?;
+    let leaf_format = STAGE_LEAF_FORMAT
+        .may_load(deps.storage, stage)?
+        .unwrap_or_default();
+    let hash = merkle_proof_root(
+        stage_hash_algo(deps.storage, stage, &leaf_format)?,
+        leaf,
+        &proof,
+    )?;
+    // binary leaves are reported hex encoded
+    let leaf = String::from_utf8(leaf.to_vec()).unwrap_or_else(|_| hex::encode(leaf));
+
+    // stages of uploaded allocations only have no root
+    if merkle_root.is_empty() {
+        return Err(ContractError::ProofInvalid { stage, leaf });
+    }
     let mut root_buf: [u8; 32] = [0; 32];
     hex::decode_to_slice(merkle_root, &mut root_buf)?;
-    if root_buf != hash {
-        return Err(StdError::verification_err(VerificationError::GenericErr {}).into());
+    // compact proofs end at a node of the registered proof level
+    if root_buf != hash && !PROOF_LEVEL_NODES.has(deps.storage, (stage, hash.to_vec())) {
+        return Err(ContractError::ProofInvalid { stage, leaf });
     }
     Ok(true)
 }
 
+/// Validates the claim target is an address of the local chain, with the prefix if set.
+pub fn validate_target_addr(
+    deps: Deps,
+    target_addr: &str,
+    prefix: Option<&str>,
+) -> Result<Addr, ContractError> {
+    let addr =
+        deps.api
+            .addr_validate(target_addr)
+            .map_err(|err| ContractError::InvalidTargetAddress {
+                address: target_addr.to_string(),
+                msg: err.to_string(),
+            })?;
+    if let Some(prefix) = prefix {
+        // bech32 separator is the last "1" of the address
+        let target_prefix = target_addr.rsplit_once('1').map(|(p, _)| p);
+        if target_prefix != Some(prefix) {
+            return Err(ContractError::InvalidTargetAddress {
+                address: target_addr.to_string(),
+                msg: format!("address prefix must be {}", prefix),
+            });
+        }
+    }
+    Ok(addr)
+}
+
+/// Verifies the claim msg is signed for this contract and chain.
+pub fn verify_claim_domain(env: &Env, claim_msg: &ClaimMsg) -> Result<(), ContractError> {
+    if claim_msg.contract_addr != env.contract.address.as_str() {
+        return Err(ContractError::SignatureMismatch {
+            msg: "claim msg is signed for another contract".to_string(),
+        });
+    }
+    if claim_msg.chain_id != env.block.chain_id {
+        return Err(ContractError::SignatureMismatch {
+            msg: "claim msg is signed for another chain".to_string(),
+        });
+    }
+    Ok(())
+}
+
 pub fn verify_eth(
     deps: Deps,
+    env: &Env,
     claim_msg: &ClaimMsg,
     signature: Binary,
+    message_encoding: MessageEncoding,
+    chain_id: Option<u64>,
 ) -> Result<bool, ContractError> {
-    let mut hasher = Keccak256::new();
-    let msg_raw = to_vec(claim_msg)?;
-    let msg: String = from_slice(&msg_raw)?;
-    hasher.update(format!("\x19Ethereum Signed Message:\n{}", msg.len()));
-    hasher.update(msg);
-    let hash = hasher.finalize();
-    // Decompose signature
-    let (v, rs) = match signature.split_last() {
-        Some(pair) => pair,
-        None => {
-            return Err(ContractError::IsNotEligible {
-                msg: "Signature must not be empty".to_string(),
-            })
-        }
-    };
-    let recovery = get_recovery_param(*v)?;
+    verify_claim_domain(env, claim_msg)?;
+    Ok(cyber_gift_proofs::verify_eth(
+        deps.api,
+        claim_msg,
+        &signature,
+        message_encoding,
+        chain_id,
+    )?)
+}
 
-    // Verification
-    let calculated_pubkey = deps.api.secp256k1_recover_pubkey(&hash, rs, recovery)?;
-    let calculated_address = ethereum_address_raw(&calculated_pubkey)?;
-    if claim_msg.gift_claiming_address.as_bytes() != calculated_address {
-        return Err(ContractError::IsNotEligible {
-            msg: "signer address is not calculated addr".to_string(),
+/// Returns the address recovered from an Ethereum signature of the claim msg, cut to
+/// its first and last two bytes, for the debug attributes of a failed claim.
+pub fn recovered_eth_address(
+    deps: Deps,
+    claim_msg: &ClaimMsg,
+    signature: &[u8],
+    message_encoding: MessageEncoding,
+    chain_id: Option<u64>,
+) -> Option<String> {
+    let address = cyber_gift_proofs::recover_eth_address(
+        deps.api,
+        claim_msg,
+        signature,
+        message_encoding,
+        chain_id,
+    )?;
+    let address = hex::encode(address);
+    Some(format!("0x{}..{}", &address[..4], &address[36..]))
+}
+
+/// Verifies the gift_claiming_address is linked in the passport with the nickname
+/// owned by target_addr.
+pub fn verify_passport(
+    deps: Deps,
+    claim_msg: &ClaimMsg,
+    passport: Option<&Addr>,
+    nickname: &str,
+) -> Result<bool, ContractError> {
+    let passport = passport.ok_or(ContractError::IsNotEligible {
+        msg: "passport contract is not set".to_string(),
+    })?;
+    let resp: PassportResponse = deps
+        .querier
+        .query_wasm_smart(
+            passport,
+            &PassportQueryMsg::PassportByAddress {
+                address: claim_msg.gift_claiming_address.clone(),
+            },
+        )
+        .map_err(|err| ContractError::IsNotEligible {
+            msg: err.to_string(),
+        })?;
+    if resp.owner != claim_msg.target_addr || resp.nickname != nickname {
+        return Err(ContractError::AddressMismatch {
+            address: claim_msg.gift_claiming_address.clone(),
+            msg: "address is not linked to the target passport".to_string(),
         });
     }
-    deps.api
-        .secp256k1_verify(&hash, rs, &calculated_pubkey)
+    Ok(true)
+}
+
+/// Verifies the passport with the nickname of the claim is owned by target_addr and was
+/// created at or before the cutoff block.
+pub fn verify_passport_age(
+    deps: Deps,
+    claim_msg: &ClaimMsg,
+    passport: Option<&Addr>,
+    cutoff: u64,
+) -> Result<(), ContractError> {
+    let passport = passport.ok_or(ContractError::IsNotEligible {
+        msg: "passport contract is not set".to_string(),
+    })?;
+    let resp: PassportAddressesResponse = deps
+        .querier
+        .query_wasm_smart(
+            passport,
+            &PassportQueryMsg::PassportByNickname {
+                nickname: claim_msg.nickname.clone(),
+            },
+        )
         .map_err(|err| ContractError::IsNotEligible {
             msg: err.to_string(),
-        })
+        })?;
+    if resp.owner != claim_msg.target_addr {
+        return Err(ContractError::IsNotEligible {
+            msg: "passport is not owned by the target".to_string(),
+        });
+    }
+    if resp.created_at.is_none_or(|created_at| created_at > cutoff) {
+        return Err(ContractError::IsNotEligible {
+            msg: format!("passport must be created at or before block {}", cutoff),
+        });
+    }
+    Ok(())
 }
 
-fn get_recovery_param(v: u8) -> StdResult<u8> {
-    match v {
-        27 => Ok(0),
-        28 => Ok(1),
-        _ => Err(StdError::generic_err("Values of v other than 27 and 28 not supported. Replay protection (EIP-155) cannot be used here."))
+/// Verifies the owner approved the claim of the contract wallet gift_claiming_address
+/// to target_addr.
+pub fn verify_contract_wallet(
+    deps: Deps,
+    stage: u8,
+    claim_msg: &ClaimMsg,
+) -> Result<bool, ContractError> {
+    if claim_msg.gift_claiming_address_type != ClaimerType::Ethereum {
+        return Err(ContractError::IsNotEligible {
+            msg: "contract wallet claims are only supported for ethereum addresses".to_string(),
+        });
+    }
+    let approved = CONTRACT_WALLET_CLAIMS.may_load(
+        deps.storage,
+        (claim_msg.gift_claiming_address.clone(), stage),
+    )?;
+    match approved {
+        Some(target_addr) if target_addr == claim_msg.target_addr => Ok(true),
+        _ => Err(ContractError::IsNotEligible {
+            msg: "contract wallet claim is not approved".to_string(),
+        }),
     }
 }
 
-/// Returns a raw 20 byte Ethereum address
-fn ethereum_address_raw(pubkey: &[u8]) -> StdResult<[u8; 20]> {
-    let (tag, data) = match pubkey.split_first() {
-        Some(pair) => pair,
-        None => return Err(StdError::generic_err("Public key must not be empty")),
-    };
-    if *tag != 0x04 {
-        return Err(StdError::generic_err("Public key must start with 0x04"));
+/// Verifies an ADR-36 signature (Keplr `signArbitrary`) of the claim message
+/// made by the key behind the bech32 `gift_claiming_address`. The amino
+/// pub_key_type, when given, must match the address_derivation of the claim.
+pub fn verify_cosmos(
+    deps: Deps,
+    env: &Env,
+    claim_msg: &ClaimMsg,
+    pub_key: Binary,
+    pub_key_type: Option<&str>,
+    signature: Binary,
+    prefixes: &[String],
+) -> Result<bool, ContractError> {
+    verify_claim_domain(env, claim_msg)?;
+    Ok(cyber_gift_proofs::verify_cosmos(
+        deps.api,
+        claim_msg,
+        &pub_key,
+        pub_key_type,
+        &signature,
+        prefixes,
+    )?)
+}
+
+/// Verifies an ed25519 signature (wallet `signMessage`) of the claim message made
+/// by the key of the base58 `gift_claiming_address`.
+pub fn verify_solana(
+    deps: Deps,
+    env: &Env,
+    claim_msg: &ClaimMsg,
+    signature: Binary,
+) -> Result<bool, ContractError> {
+    verify_claim_domain(env, claim_msg)?;
+    if claim_msg.signature_type != SignatureType::PersonalSign {
+        return Err(ContractError::SignatureMismatch {
+            msg: "signature type is not supported for solana addresses".to_string(),
+        });
     }
-    if data.len() != 64 {
-        return Err(StdError::generic_err("Public key must be 65 bytes long"));
+    // Solana address is the base58 encoded public key
+    let pub_key = bs58::decode(&claim_msg.gift_claiming_address)
+        .into_vec()
+        .map_err(|err| ContractError::IsNotEligible {
+            msg: format!("invalid solana address: {}", err),
+        })?;
+    if pub_key.len() != 32 {
+        return Err(ContractError::IsNotEligible {
+            msg: "solana address must be 32 bytes long".to_string(),
+        });
     }
 
-    let hash = Keccak256::digest(data);
-    Ok(hash[hash.len() - 20..].try_into().unwrap())
+    let verified = deps
+        .api
+        .ed25519_verify(
+            &claim_msg.to_canonical_json()?,
+            signature.as_slice(),
+            &pub_key,
+        )
+        .map_err(|err| ContractError::SignatureMismatch {
+            msg: err.to_string(),
+        })?;
+    if !verified {
+        return Err(ContractError::SignatureMismatch {
+            msg: "signature verification failed".to_string(),
+        });
+    }
+    Ok(true)
 }
 
-pub fn verify_cosmos(
+/// Verifies a "Bitcoin Signed Message" signature (header || r || s) of the claim
+/// message made by the key behind the P2PKH or P2WPKH `gift_claiming_address`.
+pub fn verify_bitcoin(
     deps: Deps,
+    env: &Env,
     claim_msg: &ClaimMsg,
     signature: Binary,
 ) -> Result<bool, ContractError> {
-    let msg_raw = to_vec(claim_msg)?;
+    verify_claim_domain(env, claim_msg)?;
+    if claim_msg.signature_type != SignatureType::PersonalSign {
+        return Err(ContractError::SignatureMismatch {
+            msg: "signature type is not supported for bitcoin addresses".to_string(),
+        });
+    }
+    if signature.len() != 65 {
+        return Err(ContractError::SignatureMismatch {
+            msg: "Signature must be header, r and s".to_string(),
+        });
+    }
+    let (header, rs) = signature.split_at(1);
+    // header is 27 + recovery, +4 for compressed keys, +8 and +12 for segwit addresses
+    let header = header[0];
+    if !(27..=42).contains(&header) {
+        return Err(ContractError::SignatureMismatch {
+            msg: format!("invalid signature header {}", header),
+        });
+    }
+    let recovery = (header - 27) % 4;
+    let compressed = header >= 31;
+
     // Hashing
-    let hash = Sha256::digest(&msg_raw);
+    let msg = claim_msg.to_canonical_json()?;
+    let mut prefixed = b"\x18Bitcoin Signed Message:\n".to_vec();
+    prefixed.extend(bitcoin_varint(msg.len() as u64));
+    prefixed.extend(msg);
+    let hash = Sha256::digest(&Sha256::digest(&prefixed));
 
     // Verification
-    let sig: Signature = from_binary(&signature)?;
-    match sig {
-        Signature::Cosmos { pub_key, signature } =>
-            deps.api
-                .secp256k1_verify(hash.as_ref(), signature.as_slice(), pub_key.as_bytes())
-                .map_err(|err| ContractError::IsNotEligible {
-                    msg: err.to_string(),
-                }),
-        _ => Err(ContractError::InvalidInput {}),
-    }
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Tx {
-    pub chain_id: String,
-    pub account_number: Uint64,
-    pub sequence: Uint64,
-    pub fee: Fee,
-    pub msgs: Vec<Msg>,
-    pub memo: String,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Fee {
-    pub gas: Uint128,
-    pub amount: Coin,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Msg {
-    pub signer: String,
-    pub data: Binary,
-}
-/*
-{
-  "address": "0x0408522089294b8b3f0c9514086e6ae1df00394c",
-  "msg": "{\"nickname\":\"alice-ethereum\",\"avatar_cid\":\"QmRX8qYgeZoYM3M5zzQaWEpVFdpin6FvVXvp6RPQK3oufV\",\"gift_claiming_address_type\":\"ethereum\",\"gift_claiming_address\":\"0x0408522089294b8b3f0c9514086e6ae1df00394c\",\"target_address\":\"bostrom1mww3recahc7s62a75qwnnhv7c4jsf22mph9h0f\",\"relay_reward\":\"0.01\"}",
-  "sig": "0xe2460f2111b44b0ff3e77f181950d651359c4c91a15923165f6cdeac42aeb8162319a18dc860cfbae77def32b7550fc14d211a70c7d7bd2179ef6c0b64ec19681c",
-  "version": "2"
-
- */
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub enum Signature {
-    Eth {
-        address: String,
-        msg: String,
-        sig: String,
-        version: String
-    },
-    Cosmos {
-        pub_key: String,
-        signature: Binary,
+    let pubkey = deps.api.secp256k1_recover_pubkey(&hash, rs, recovery)?;
+    let pubkey = if compressed {
+        compress_pubkey(&pubkey)?
+    } else {
+        pubkey
+    };
+    let hash = Ripemd160::digest(&Sha256::digest(&pubkey));
+    let calculated_address = if claim_msg.gift_claiming_address.starts_with("bc1") {
+        if !compressed {
+            return Err(ContractError::SignatureMismatch {
+                msg: "segwit addresses require compressed keys".to_string(),
+            });
+        }
+        let mut data = vec![bech32::u5::try_from_u8(0).unwrap()];
+        data.extend(hash.to_base32());
+        bech32::encode("bc", data, Variant::Bech32)
+            .map_err(|err| StdError::generic_err(err.to_string()))?
+    } else {
+        let mut payload = vec![0x00];
+        payload.extend(hash);
+        let checksum = Sha256::digest(&Sha256::digest(&payload));
+        payload.extend(&checksum[..4]);
+        bs58::encode(payload).into_string()
+    };
+    if calculated_address != claim_msg.gift_claiming_address {
+        return Err(ContractError::AddressMismatch {
+            address: claim_msg.gift_claiming_address.clone(),
+            msg: "signer address is not calculated addr".to_string(),
+        });
+    }
+    Ok(true)
+}
+
+/// Encodes the message length prefix of "Bitcoin Signed Message"
+fn bitcoin_varint(n: u64) -> Vec<u8> {
+    match n {
+        0..=0xfc => vec![n as u8],
+        0xfd..=0xffff => [&[0xfd], &(n as u16).to_le_bytes()[..]].concat(),
+        0x10000..=0xffff_ffff => [&[0xfe], &(n as u32).to_le_bytes()[..]].concat(),
+        _ => [&[0xff], &n.to_le_bytes()[..]].concat(),
     }
 }
@@ -0,0 +1,92 @@
+//! Registry of contracts notified of claims, modelled on the cw-controllers hooks.
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, Addr, CosmosMsg, StdResult, Storage, SubMsg, Uint128, WasmMsg};
+use cw_storage_plus::Item;
+
+use crate::ContractError;
+
+/// GiftClaimedHookMsg is sent to the hooks after each claim.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GiftClaimedHookMsg {
+    pub claimer: String,
+    pub amount: Uint128,
+    pub stage: u8,
+}
+
+impl GiftClaimedHookMsg {
+    /// Serializes the msg as `{"gift_claimed_hook": {...}}` executed on the hook.
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_binary(&HookExecuteMsg::GiftClaimedHook(self))?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HookExecuteMsg {
+    GiftClaimedHook(GiftClaimedHookMsg),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HooksResponse {
+    pub hooks: Vec<String>,
+}
+
+pub struct Hooks<'a>(Item<'a, Vec<Addr>>);
+
+impl<'a> Hooks<'a> {
+    pub const fn new(key: &'a str) -> Self {
+        Hooks(Item::new(key))
+    }
+
+    pub fn add_hook(&self, storage: &mut dyn Storage, addr: Addr) -> Result<(), ContractError> {
+        let mut hooks = self.0.may_load(storage)?.unwrap_or_default();
+        if hooks.contains(&addr) {
+            return Err(ContractError::HookAlreadyRegistered {
+                hook: addr.to_string(),
+            });
+        }
+        hooks.push(addr);
+        Ok(self.0.save(storage, &hooks)?)
+    }
+
+    pub fn remove_hook(&self, storage: &mut dyn Storage, addr: Addr) -> Result<(), ContractError> {
+        let mut hooks = self.0.may_load(storage)?.unwrap_or_default();
+        let pos =
+            hooks
+                .iter()
+                .position(|h| *h == addr)
+                .ok_or(ContractError::HookNotRegistered {
+                    hook: addr.to_string(),
+                })?;
+        hooks.remove(pos);
+        Ok(self.0.save(storage, &hooks)?)
+    }
+
+    /// Builds the msgs of all hooks with prep.
+    pub fn prepare_hooks<F: Fn(Addr) -> StdResult<SubMsg>>(
+        &self,
+        storage: &dyn Storage,
+        prep: F,
+    ) -> StdResult<Vec<SubMsg>> {
+        self.0
+            .may_load(storage)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(prep)
+            .collect()
+    }
+
+    pub fn query_hooks(&self, storage: &dyn Storage) -> StdResult<HooksResponse> {
+        let hooks = self.0.may_load(storage)?.unwrap_or_default();
+        Ok(HooksResponse {
+            hooks: hooks.into_iter().map(String::from).collect(),
+        })
+    }
+}
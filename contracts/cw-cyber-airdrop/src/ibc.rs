@@ -0,0 +1,223 @@
+//! IBC channel protocol letting the controller on another chain, e.g. a hub DAO, register
+//! stages. Packets carry an `IbcPacketMsg`, acknowledgements an `IbcAck` with the
+//! registered stage or the pending root.
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Binary, DepsMut, Env, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcOrder, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, StdResult,
+    Timestamp, Uint128,
+};
+use cw0::Expiration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::events::root_registered_event;
+use crate::execute::{submit_stage, SubmittedStage};
+use crate::msg::StageParams;
+use crate::state::{HashAlgo, LeafFormat, CONFIG, CONTROLLER_CHANNELS};
+
+pub const IBC_VERSION: &str = "cybergift-1";
+pub const IBC_ORDER: IbcOrder = IbcOrder::Unordered;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcPacketMsg {
+    /// RegisterMerkleRoot registers the next stage like the owner execute msg does, with
+    /// the same root timelock and guardian approvals.
+    RegisterMerkleRoot {
+        /// MerkleRoot is hex-encoded merkle root.
+        merkle_root: String,
+        start: Option<Expiration>,
+        expiration: Option<Expiration>,
+        total_amount: Uint128,
+        leaf_format: Option<LeafFormat>,
+        #[serde(default)]
+        hash_algo: Option<HashAlgo>,
+    },
+}
+
+/// IbcAck is the acknowledgement of a packet, result is the JSON of the packet response.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcAck {
+    Result(Binary),
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegisterMerkleRootAck {
+    /// Stage is the registered stage, none if the root is pending.
+    pub stage: Option<u8>,
+    /// ActivateAt is the time the pending root can be activated at, none if the stage
+    /// is registered.
+    pub activate_at: Option<Timestamp>,
+}
+
+/// Channels must be unordered, speak the cybergift version and end at the controller port.
+fn validate_channel(
+    deps: &DepsMut,
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    let invalid = |msg: &str| ContractError::InvalidIbcChannel {
+        msg: msg.to_string(),
+    };
+    if channel.order != IBC_ORDER {
+        return Err(invalid("channel must be unordered"));
+    }
+    if channel.version != IBC_VERSION || counterparty_version.unwrap_or(IBC_VERSION) != IBC_VERSION
+    {
+        return Err(invalid("channel version must be cybergift-1"));
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg.ibc_controller.as_deref() != Some(channel.counterparty_endpoint.port_id.as_str()) {
+        return Err(invalid("counterparty is not the controller"));
+    }
+    // the connection pins the client of the controller chain, any chain can open a
+    // channel from the controller port id
+    if cfg.ibc_connection_id.as_deref() != Some(channel.connection_id.as_str()) {
+        return Err(invalid("channel is not on the controller connection"));
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    validate_channel(&deps, msg.channel(), msg.counterparty_version())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(&deps, channel, msg.counterparty_version())?;
+    let channel_id = &channel.endpoint.channel_id;
+    CONTROLLER_CHANNELS.save(deps.storage, channel_id.clone(), &true)?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    CONTROLLER_CHANNELS.remove(deps.storage, channel_id.clone());
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", channel_id))
+}
+
+/// Failed packets are acknowledged with the error instead of failing the relayer tx.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    match receive_packet(deps, &env, &msg.packet) {
+        Ok(res) => Ok(res),
+        Err(err) => Ok(IbcReceiveResponse::new()
+            .set_ack(to_binary(&IbcAck::Error(err.to_string()))?)
+            .add_attributes(vec![
+                attr("action", "ibc_packet_receive"),
+                attr("error", err.to_string()),
+            ])),
+    }
+}
+
+fn receive_packet(
+    deps: DepsMut,
+    env: &Env,
+    packet: &IbcPacket,
+) -> Result<IbcReceiveResponse, ContractError> {
+    if !CONTROLLER_CHANNELS.has(deps.storage, packet.dest.channel_id.clone()) {
+        return Err(ContractError::InvalidIbcChannel {
+            msg: "channel is not connected".to_string(),
+        });
+    }
+    match from_binary(&packet.data)? {
+        IbcPacketMsg::RegisterMerkleRoot {
+            merkle_root,
+            start,
+            expiration,
+            total_amount,
+            leaf_format,
+            hash_algo,
+        } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            let params = StageParams {
+                merkle_root: merkle_root.clone(),
+                start,
+                expiration,
+                total_amount,
+                leaf_format,
+                hash_algo,
+            };
+            let res = match submit_stage(deps, env, &cfg, params)? {
+                SubmittedStage::Pending { activate_at } => {
+                    let ack = RegisterMerkleRootAck {
+                        stage: None,
+                        activate_at: Some(activate_at),
+                    };
+                    IbcReceiveResponse::new()
+                        .set_ack(to_binary(&IbcAck::Result(to_binary(&ack)?))?)
+                        .add_attributes(vec![
+                            attr("action", "ibc_register_merkle_root"),
+                            attr("merkle_root", merkle_root),
+                            attr("activate_at", activate_at.to_string()),
+                        ])
+                }
+                SubmittedStage::Registered { stage } => {
+                    let ack = RegisterMerkleRootAck {
+                        stage: Some(stage),
+                        activate_at: None,
+                    };
+                    IbcReceiveResponse::new()
+                        .set_ack(to_binary(&IbcAck::Result(to_binary(&ack)?))?)
+                        .add_event(root_registered_event(stage, &merkle_root, total_amount))
+                        .add_attributes(vec![
+                            attr("action", "ibc_register_merkle_root"),
+                            attr("stage", stage.to_string()),
+                            attr("merkle_root", merkle_root),
+                        ])
+                }
+            };
+            Ok(res)
+        }
+    }
+}
+
+/// The contract sends no packets, acknowledgements and timeouts are not expected.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketAckMsg,
+) -> StdResult<IbcBasicResponse> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_ack"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> StdResult<IbcBasicResponse> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_timeout"))
+}
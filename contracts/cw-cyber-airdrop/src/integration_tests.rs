@@ -0,0 +1,642 @@
+//! End to end tests of the airdrop along with a mock passport contract, and the cyber
+//! passport contract, in cw-multi-test.
+use cosmwasm_std::{
+    coins, to_binary, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Reply,
+    Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
+};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+use cyber_passport::msg::{
+    ExecuteMsg as PassportExecuteMsg, InstantiateMsg as PassportInstantiateMsg, LinkProof,
+    PassportResponse, QueryMsg as PassportQueryMsg,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::execute::{execute, instantiate, query, reply};
+use crate::hooks::HookExecuteMsg;
+use crate::msg::{
+    ActivationResponse, ClaimMsg, ClaimerType, ExecuteMsg, InstantiateMsg, IsClaimedResponse,
+    IsEligibleResponse, MessageEncoding, PassportClaimsResponse, Proof, ProofNode, QueryMsg,
+    ReleaseStateResponse, SignatureType, StatsResponse,
+};
+use crate::testing::merkle_tree;
+use crate::tests::{
+    claim_msg, eth_address, eth_proof, eth_sign, signing_key, ETH_PRIVATE_KEY, NATIVE_TOKEN,
+};
+use crate::ContractError;
+
+const OWNER: &str = "owner0000";
+const TARGET: &str = "bostrom1target";
+const PASSPORT_OWNER: &str = "bostrom1passport";
+const LINKED: &str = "0x1de6f7d3a1bd0fb3ab8b4cf5d59a8d7da4a27c1b";
+const UNLINKED: &str = "0x2de6f7d3a1bd0fb3ab8b4cf5d59a8d7da4a27c1b";
+const OTHER_ETH_PRIVATE_KEY: &str =
+    "8da4ef21b864d2cc526dbdb2a120bd2874c36c9d0a1fb7f8c63d7f7a8b41de8f";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct MockPassportInstantiateMsg {
+    /// Links are the (address, passport owner) pairs of the mock passports.
+    links: Vec<(String, String)>,
+}
+
+const LINKS: Map<String, String> = Map::new("links");
+
+fn mock_passport_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: MockPassportInstantiateMsg,
+) -> StdResult<Response> {
+    for (address, owner) in msg.links {
+        LINKS.save(deps.storage, address, &owner)?;
+    }
+    Ok(Response::default())
+}
+
+fn mock_passport_execute(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: PassportExecuteMsg,
+) -> StdResult<Response> {
+    match msg {
+        PassportExecuteMsg::VerifyAddress { address, owner } => {
+            match LINKS.may_load(deps.storage, address)? {
+                Some(linked_owner) if linked_owner == owner => Ok(Response::default()),
+                _ => Err(StdError::generic_err("address is not linked")),
+            }
+        }
+        _ => Err(StdError::generic_err("unsupported msg")),
+    }
+}
+
+fn mock_passport_query(deps: Deps, _env: Env, msg: PassportQueryMsg) -> StdResult<Binary> {
+    match msg {
+        PassportQueryMsg::PassportByAddress { address } => {
+            let owner = LINKS
+                .may_load(deps.storage, address)?
+                .ok_or_else(|| StdError::not_found("passport"))?;
+            to_binary(&PassportResponse {
+                owner,
+                nickname: "bob".to_string(),
+            })
+        }
+        PassportQueryMsg::Config {}
+        | PassportQueryMsg::PassportByNickname { .. }
+        | PassportQueryMsg::AddressesByPassport { .. } => Err(StdError::not_found("passport")),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct MockReentrantInstantiateMsg {
+    airdrop: String,
+    /// Replay is the msg executed on the airdrop again from its claim hook.
+    replay: ExecuteMsg,
+}
+
+const REPLAY: Item<MockReentrantInstantiateMsg> = Item::new("replay");
+const REPLAY_ERROR: Item<String> = Item::new("replay_error");
+
+fn mock_reentrant_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: MockReentrantInstantiateMsg,
+) -> StdResult<Response> {
+    REPLAY.save(deps.storage, &msg)?;
+    Ok(Response::default())
+}
+
+/// Replays the claim from the hook, the failure is kept by the reply so that the
+/// outer claim goes through.
+fn mock_reentrant_execute(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: HookExecuteMsg,
+) -> StdResult<Response> {
+    let HookExecuteMsg::GiftClaimedHook(_) = msg;
+    let replay = REPLAY.load(deps.storage)?;
+    let msg = WasmMsg::Execute {
+        contract_addr: replay.airdrop,
+        msg: to_binary(&replay.replay)?,
+        funds: vec![],
+    };
+    Ok(Response::new().add_submessage(SubMsg::reply_on_error(msg, 0)))
+}
+
+fn mock_reentrant_reply(deps: DepsMut, _env: Env, msg: Reply) -> StdResult<Response> {
+    REPLAY_ERROR.save(deps.storage, &msg.result.unwrap_err())?;
+    Ok(Response::default())
+}
+
+fn mock_reentrant_query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_binary(&REPLAY_ERROR.may_load(deps.storage)?)
+}
+
+fn airdrop_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query).with_reply(reply))
+}
+
+fn mock_passport_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        mock_passport_execute,
+        mock_passport_instantiate,
+        mock_passport_query,
+    ))
+}
+
+fn mock_reentrant_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            mock_reentrant_execute,
+            mock_reentrant_instantiate,
+            mock_reentrant_query,
+        )
+        .with_reply(mock_reentrant_reply),
+    )
+}
+
+/// Suite is the app with the instantiated airdrop and passport contracts.
+struct Suite {
+    app: App,
+    airdrop: Addr,
+    passport: Addr,
+}
+
+impl Suite {
+    /// Instantiates the airdrop with vesting and an activation threshold of two claims,
+    /// LINKED is linked in the passport of PASSPORT_OWNER.
+    fn new(merkle_root: String) -> Self {
+        let links = vec![(LINKED.to_string(), PASSPORT_OWNER.to_string())];
+        Self::with_passport(
+            merkle_root,
+            mock_passport_contract(),
+            &MockPassportInstantiateMsg { links },
+        )
+    }
+
+    /// Instantiates the airdrop along with the passport contract.
+    fn with_passport<T: Serialize>(
+        merkle_root: String,
+        passport_contract: Box<dyn Contract<Empty>>,
+        passport_msg: &T,
+    ) -> Self {
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(OWNER), coins(10000, NATIVE_TOKEN))
+                .unwrap();
+        });
+        let passport_id = app.store_code(passport_contract);
+        let airdrop_id = app.store_code(airdrop_contract());
+
+        let passport = app
+            .instantiate_contract(
+                passport_id,
+                Addr::unchecked(OWNER),
+                passport_msg,
+                &[],
+                "passport",
+                None,
+            )
+            .unwrap();
+        let msg = InstantiateMsg {
+            owner: None,
+            allowed_native: NATIVE_TOKEN.to_string(),
+            cw20_token: None,
+            initial_balance: Uint128::new(10000),
+            extra_balances: vec![],
+            coefficient_up: Decimal::one(),
+            coefficient_down: Decimal::one(),
+            coefficient_claims: None,
+            eth_chain_id: None,
+            treasury: None,
+            guardian: None,
+            passport: Some(passport.to_string()),
+            target_prefix: None,
+            liquid_share: Some(Decimal::percent(10)),
+            release_blocks: Some(100),
+            activation_threshold: Some(2),
+            referral_share: None,
+            referral_balance: None,
+            max_claims_per_block: None,
+            claim_minimum: None,
+            claim_maximum: None,
+            fee_recipient: None,
+            fee_bps: None,
+            ibc_channels: vec![],
+            ibc_timeout_seconds: None,
+            ibc_controller: None,
+            ibc_connection_id: None,
+            unbonding_seconds: None,
+            root_timelock_seconds: None,
+            root_guardians: None,
+            root_guardian_threshold: None,
+            linked_claim_policy: None,
+            campaign_end: None,
+            verified_claims: None,
+            receipt_nft: None,
+            milestone_tiers: None,
+            cosmos_prefixes: None,
+            history_interval: None,
+            lottery_ticket_size: None,
+            verbose_errors: None,
+            first_stage: None,
+        };
+        let airdrop = app
+            .instantiate_contract(
+                airdrop_id,
+                Addr::unchecked(OWNER),
+                &msg,
+                &coins(10000, NATIVE_TOKEN),
+                "cybergift",
+                None,
+            )
+            .unwrap();
+        let msg = ExecuteMsg::RegisterMerkleRoot {
+            merkle_root,
+            start: None,
+            expiration: None,
+            total_amount: Uint128::new(1_000_000),
+            leaf_format: None,
+            hash_algo: None,
+        };
+        app.execute_contract(Addr::unchecked(OWNER), airdrop.clone(), &msg, &[])
+            .unwrap();
+
+        Suite {
+            app,
+            airdrop,
+            passport,
+        }
+    }
+
+    /// Returns the claim msg signed for the airdrop contract and the app chain.
+    fn claim_msg(&self, claimer_type: ClaimerType, claimer: &str, target: &str) -> ClaimMsg {
+        let mut claim_msg = claim_msg(claimer_type, claimer, target);
+        claim_msg.contract_addr = self.airdrop.to_string();
+        claim_msg.chain_id = self.app.block_info().chain_id;
+        claim_msg
+    }
+
+    fn claim(
+        &mut self,
+        sender: &str,
+        claim_msg: ClaimMsg,
+        signature: Proof,
+        amount: u128,
+        proof: Vec<ProofNode>,
+    ) -> anyhow::Result<AppResponse> {
+        let msg = ExecuteMsg::Claim {
+            stage: 1,
+            claim_msg,
+            signature,
+            claim_amount: Uint128::new(amount),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.airdrop.clone(), &msg, &[])
+    }
+
+    fn release(&mut self, sender: &str) -> anyhow::Result<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.airdrop.clone(),
+            &ExecuteMsg::Release {},
+            &[],
+        )
+    }
+
+    fn balance(&self, address: &str) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(address, NATIVE_TOKEN)
+            .unwrap()
+            .amount
+            .u128()
+    }
+
+    fn query<T: serde::de::DeserializeOwned>(&self, msg: &QueryMsg) -> T {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.airdrop.clone(), msg)
+            .unwrap()
+    }
+}
+
+#[test]
+fn claim_activation_release() {
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[
+        (claimer.as_str(), Uint128::new(1000)),
+        (LINKED, Uint128::new(2000)),
+        (UNLINKED, Uint128::new(3000)),
+    ]);
+    let mut suite = Suite::new(root);
+
+    // signed claim pays the liquid share, the rest is vested
+    let claim_msg = suite.claim_msg(ClaimerType::Ethereum, &claimer, TARGET);
+    let signature = eth_proof(&key, &claim_msg);
+    suite
+        .claim(TARGET, claim_msg, signature, 1000, proofs[0].clone())
+        .unwrap();
+    assert_eq!(100, suite.balance(TARGET));
+    assert_eq!(9900, suite.balance(suite.airdrop.as_str()));
+
+    // nothing is released before the activation
+    suite.app.update_block(|block| block.height += 50);
+    let err = suite.release(TARGET).unwrap_err();
+    assert_eq!(
+        ContractError::NotActivated {
+            claims_count: 1,
+            activation_threshold: 2
+        },
+        err.downcast::<ContractError>().unwrap()
+    );
+    let activation: ActivationResponse = suite.query(&QueryMsg::Activation {});
+    assert_eq!(None, activation.activation_block);
+
+    // the cross contract passport query checks the linkage
+    let mut passport_claim_msg = suite.claim_msg(ClaimerType::Ethereum, LINKED, PASSPORT_OWNER);
+    passport_claim_msg.signature_type = SignatureType::Passport;
+    let is_eligible: IsEligibleResponse = suite.query(&QueryMsg::IsEligible {
+        stage: 1,
+        claim_msg: passport_claim_msg.clone(),
+        signature: Proof::Passport {
+            nickname: "bob".to_string(),
+        },
+        claim_amount: Uint128::new(2000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[1].clone(),
+    });
+    assert!(is_eligible.valid_proof && is_eligible.valid_signature);
+    let passport: PassportResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            suite.passport.clone(),
+            &PassportQueryMsg::PassportByAddress {
+                address: LINKED.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(PASSPORT_OWNER, passport.owner);
+
+    // failed passport verification reverts the whole claim
+    let mut unlinked_claim_msg = passport_claim_msg.clone();
+    unlinked_claim_msg.gift_claiming_address = UNLINKED.to_string();
+    let err = suite
+        .claim(
+            PASSPORT_OWNER,
+            unlinked_claim_msg,
+            Proof::Passport {
+                nickname: "bob".to_string(),
+            },
+            3000,
+            proofs[2].clone(),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PassportVerificationFailed { .. }
+    ));
+    let is_claimed: IsClaimedResponse = suite.query(&QueryMsg::IsClaimed {
+        stage: 1,
+        address: UNLINKED.to_string(),
+        source_chain: None,
+    });
+    assert!(!is_claimed.is_claimed);
+    assert_eq!(0, suite.balance(PASSPORT_OWNER));
+
+    // verified passport claim reaches the activation threshold
+    let res = suite
+        .claim(
+            PASSPORT_OWNER,
+            passport_claim_msg,
+            Proof::Passport {
+                nickname: "bob".to_string(),
+            },
+            2000,
+            proofs[1].clone(),
+        )
+        .unwrap();
+    assert!(res
+        .events
+        .iter()
+        .any(|e| e.attributes.iter().any(|a| a.value == "verify_passport")));
+    assert_eq!(200, suite.balance(PASSPORT_OWNER));
+    let activation: ActivationResponse = suite.query(&QueryMsg::Activation {});
+    assert_eq!(
+        Some(suite.app.block_info().height),
+        activation.activation_block
+    );
+
+    // vesting gifts are released linearly from the activation block
+    suite.app.update_block(|block| block.height += 50);
+    suite.release(TARGET).unwrap();
+    suite.release(PASSPORT_OWNER).unwrap();
+    assert_eq!(100 + 450, suite.balance(TARGET));
+    assert_eq!(200 + 900, suite.balance(PASSPORT_OWNER));
+
+    suite.app.update_block(|block| block.height += 50);
+    suite.release(TARGET).unwrap();
+    let release_state: ReleaseStateResponse = suite.query(&QueryMsg::ReleaseState {
+        address: TARGET.to_string(),
+    });
+    assert!(release_state.positions.is_empty());
+    assert_eq!(1000, suite.balance(TARGET));
+
+    let stats: StatsResponse = suite.query(&QueryMsg::Stats {});
+    assert_eq!(2, stats.claims_count);
+    assert_eq!(Uint128::new(3000), stats.total_claimed);
+    assert_eq!(3, stats.releases_count);
+    assert_eq!(10000 - 1000 - 1100, suite.balance(suite.airdrop.as_str()));
+}
+
+#[test]
+fn reentrant_claim() {
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    let mut suite = Suite::new(root);
+
+    // the claim hook replays the claim while it is being paid out
+    let claim_msg = suite.claim_msg(ClaimerType::Ethereum, &claimer, TARGET);
+    let signature = eth_proof(&key, &claim_msg);
+    let claim = ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg: claim_msg.clone(),
+        signature: signature.clone(),
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let reentrant_id = suite.app.store_code(mock_reentrant_contract());
+    let reentrant = suite
+        .app
+        .instantiate_contract(
+            reentrant_id,
+            Addr::unchecked(OWNER),
+            &MockReentrantInstantiateMsg {
+                airdrop: suite.airdrop.to_string(),
+                replay: claim,
+            },
+            &[],
+            "reentrant",
+            None,
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.airdrop.clone(),
+            &ExecuteMsg::AddHook {
+                addr: reentrant.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // the claim is recorded before the hook runs, so the replay pays nothing
+    suite
+        .claim(TARGET, claim_msg, signature, 1000, proofs[0].clone())
+        .unwrap();
+    let replay_error: Option<String> = suite
+        .app
+        .wrap()
+        .query_wasm_smart(reentrant, &Empty {})
+        .unwrap();
+    assert!(replay_error.unwrap().contains("already claimed"));
+    assert_eq!(100, suite.balance(TARGET));
+    assert_eq!(9900, suite.balance(suite.airdrop.as_str()));
+    let stats: StatsResponse = suite.query(&QueryMsg::Stats {});
+    assert_eq!(1, stats.claims_count);
+    assert_eq!(Uint128::new(1000), stats.total_claimed);
+}
+
+fn passport_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cyber_passport::execute::execute,
+        cyber_passport::execute::instantiate,
+        cyber_passport::execute::query,
+    ))
+}
+
+#[test]
+fn claim_linked_addresses_by_nickname() {
+    let keys = [
+        signing_key(ETH_PRIVATE_KEY),
+        signing_key(OTHER_ETH_PRIVATE_KEY),
+    ];
+    // the first snapshot address is checksummed, the passport links it lowercased
+    let mut claimers: Vec<String> = keys.iter().map(eth_address).collect();
+    claimers[0] = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23".to_string();
+    assert_eq!(eth_address(&keys[0]), claimers[0].to_lowercase());
+    let amounts = [1000, 2000];
+    let (root, proofs) = merkle_tree(&[
+        (claimers[0].as_str(), Uint128::new(amounts[0])),
+        (claimers[1].as_str(), Uint128::new(amounts[1])),
+    ]);
+    let msg = PassportInstantiateMsg {
+        owner: None,
+        max_addresses: Some(2),
+    };
+    let mut suite = Suite::with_passport(root, passport_contract(), &msg);
+    let msg = PassportExecuteMsg::CreatePassport {
+        nickname: "bob".to_string(),
+    };
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(PASSPORT_OWNER),
+            suite.passport.clone(),
+            &msg,
+            &[],
+        )
+        .unwrap();
+
+    // each address is linked with its signature of a link msg for the passport
+    for (key, claimer) in keys.iter().zip(&claimers) {
+        let mut link_msg = claim_msg(ClaimerType::Ethereum, claimer, PASSPORT_OWNER);
+        link_msg.nickname = "bob".to_string();
+        link_msg.contract_addr = suite.passport.to_string();
+        link_msg.chain_id = suite.app.block_info().chain_id;
+        let proof = LinkProof::EthSig {
+            signature: eth_sign(key, &link_msg),
+            message_encoding: MessageEncoding::Utf8,
+        };
+        let msg = PassportExecuteMsg::LinkAddress { link_msg, proof };
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(PASSPORT_OWNER),
+                suite.passport.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+    }
+
+    // the airdrop reads the linked addresses page by page from the passport
+    let passport_claims = |suite: &Suite| {
+        let mut start_after = None;
+        let mut claims = vec![];
+        loop {
+            let msg = QueryMsg::PassportClaims {
+                stage: 1,
+                nickname: "bob".to_string(),
+                start_after: start_after.take(),
+                limit: Some(1),
+            };
+            let page: PassportClaimsResponse = suite.query(&msg);
+            match page.claims.last() {
+                Some(last) => start_after = Some(last.address.clone()),
+                None => break,
+            }
+            claims.extend(page.claims);
+        }
+        claims
+    };
+    let mut linked: Vec<String> = claimers.iter().map(|c| c.to_lowercase()).collect();
+    linked.sort();
+    let claims = passport_claims(&suite);
+    let addresses: Vec<String> = claims.iter().map(|c| c.address.clone()).collect();
+    assert_eq!(linked, addresses);
+    assert!(claims.iter().all(|c| !c.is_claimed));
+
+    // the passport owner claims the gifts of all the linked addresses in their snapshot
+    // form, whatever their case
+    for (i, claimer) in claimers.iter().enumerate() {
+        let msg = ExecuteMsg::ClaimByNickname {
+            stage: 1,
+            nickname: "bob".to_string(),
+            gift_claiming_address_type: ClaimerType::Ethereum,
+            gift_claiming_address: claimer.clone(),
+            claim_amount: Uint128::new(amounts[i]),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[i].clone(),
+        };
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(PASSPORT_OWNER),
+                suite.airdrop.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+    }
+    assert_eq!(100 + 200, suite.balance(PASSPORT_OWNER));
+    let stats: StatsResponse = suite.query(&QueryMsg::Stats {});
+    assert_eq!(2, stats.claims_count);
+    assert!(passport_claims(&suite).iter().all(|c| c.is_claimed));
+}
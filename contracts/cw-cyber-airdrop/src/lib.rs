@@ -1,8 +1,23 @@
 mod error;
+pub mod events;
 pub mod execute;
 mod helpers;
+pub mod hooks;
+pub mod ibc;
+#[cfg(test)]
+mod integration_tests;
+pub mod lottery;
+mod migrations;
 pub mod msg;
+pub mod receipt;
 pub mod state;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(test)]
 mod tests;
+#[cfg(test)]
+mod vm_tests;
 
 pub use crate::error::ContractError;
+pub use crate::helpers::merkle_proof_root;
+pub use cyber_gift_proofs::sign_bytes;
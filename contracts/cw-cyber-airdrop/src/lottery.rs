@@ -0,0 +1,81 @@
+//! Amount-weighted prize drawing over the claims of the campaign. Each claim buys a
+//! ticket per lottery_ticket_size of its payout, tickets are numbered in claim order
+//! so that a claim owns a contiguous range ending at its LotteryEntry end.
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+
+use cosmwasm_std::{BlockInfo, StdError, StdResult, Storage, Uint128};
+
+use crate::state::{LotteryEntry, LOTTERY_ENTRIES, LOTTERY_ENTRY_COUNT, LOTTERY_TICKETS};
+
+/// Returns the number of tickets bought by a payout, none if the lottery is disabled.
+pub fn tickets(payout: Uint128, ticket_size: Uint128) -> u64 {
+    if ticket_size.is_zero() {
+        return 0;
+    }
+    u64::try_from((payout / ticket_size).u128()).unwrap_or(u64::MAX)
+}
+
+/// Records the tickets bought by a claim for the address.
+pub fn buy_tickets(storage: &mut dyn Storage, address: &str, tickets: u64) -> StdResult<()> {
+    if tickets == 0 {
+        return Ok(());
+    }
+    let total = LOTTERY_TICKETS.may_load(storage)?.unwrap_or_default();
+    let end = total
+        .checked_add(tickets)
+        .ok_or_else(|| StdError::generic_err("lottery tickets overflow"))?;
+    let id = LOTTERY_ENTRY_COUNT.may_load(storage)?.unwrap_or_default();
+    let entry = LotteryEntry {
+        address: address.to_string(),
+        end,
+    };
+    LOTTERY_ENTRIES.save(storage, id, &entry)?;
+    LOTTERY_ENTRY_COUNT.save(storage, &(id + 1))?;
+    LOTTERY_TICKETS.save(storage, &end)
+}
+
+/// Returns the hex sha256 commitment of a seed.
+pub fn seed_commitment(seed: &str) -> String {
+    hex::encode(Sha256::digest(seed.as_bytes()))
+}
+
+/// Returns the hex beacon of the block sealing the lottery, the sha256 of its chain id,
+/// height and time. The block time is set by the validators, so the owner only learns
+/// the beacon after it is sealed and can no longer change the committed seed.
+pub fn beacon(block: &BlockInfo) -> String {
+    let hash = Sha256::new()
+        .chain(block.chain_id.as_bytes())
+        .chain(block.height.to_be_bytes())
+        .chain(block.time.nanos().to_be_bytes())
+        .finalize();
+    hex::encode(hash)
+}
+
+/// Returns the ticket drawn for the winner at index, the first 16 bytes of
+/// `sha256(seed || beacon || index)` modulo the number of tickets, so anyone can
+/// recompute the draw from the revealed seed and the sealed beacon.
+pub fn winning_ticket(seed: &str, beacon: &str, index: u32, total_tickets: u64) -> u64 {
+    let hash = Sha256::new()
+        .chain(seed.as_bytes())
+        .chain(beacon.as_bytes())
+        .chain(index.to_be_bytes())
+        .finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    (u128::from_be_bytes(bytes) % total_tickets as u128) as u64
+}
+
+/// Returns the entry owning the ticket, a binary search over the ticket ranges.
+pub fn ticket_owner(storage: &dyn Storage, ticket: u64) -> StdResult<LotteryEntry> {
+    let (mut low, mut high) = (0, LOTTERY_ENTRY_COUNT.load(storage)?);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if LOTTERY_ENTRIES.load(storage, mid)?.end <= ticket {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    LOTTERY_ENTRIES.load(storage, low)
+}
@@ -0,0 +1,153 @@
+//! State upgrades from previous contract versions.
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Decimal, DepsMut, Env, Order, StdError, StdResult, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
+
+use crate::execute::{DEFAULT_IBC_TIMEOUT_SECONDS, DEFAULT_UNBONDING_SECONDS};
+use crate::state::{
+    ClaimState, Config, GiftAsset, LinkedClaimPolicy, ACTIVATION_BLOCK, CLAIMS, CLAIMS_BY_TARGET,
+    CLAIMS_COUNT, CLAIM_PREFIX, CONFIG, CONFIG_KEY, LATEST_STAGE, MERKLE_ROOT, MERKLE_ROOT_PREFIX,
+    STAGE_AMOUNT, STAGE_AMOUNT_CLAIMED, STAGE_LEAF_FORMAT,
+};
+use crate::ContractError;
+
+/// ConfigV1 is the config of the single stage 0.1 contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV1 {
+    pub owner: Option<Addr>,
+    pub allowed_native: String,
+    pub current_balance: Uint128,
+    pub initial_balance: Uint128,
+    pub coefficient_up: Uint128,
+    pub coefficient_down: Uint128,
+    pub coefficient: Uint128,
+}
+
+pub const CONFIG_V1: Item<ConfigV1> = Item::new(CONFIG_KEY);
+pub const MERKLE_ROOT_V1: Item<String> = Item::new(MERKLE_ROOT_PREFIX);
+/// ClaimV1 is keyed by the target address of the claim.
+pub const CLAIM_V1: Map<&[u8], bool> = Map::new(CLAIM_PREFIX);
+
+/// Parses a `major.minor.patch` version.
+pub fn parse_version(version: &str) -> StdResult<(u64, u64, u64)> {
+    let parts = version
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| StdError::generic_err(format!("Invalid version {}", version)))?;
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(StdError::generic_err(format!(
+            "Invalid version {}",
+            version
+        ))),
+    }
+}
+
+/// Upgrades the single stage 0.1 state, its merkle root and claims become stage 1.
+/// Treasury defaults to the owner, it must be set if the contract is frozen.
+/// Returns the number of migrated claims.
+pub fn migrate_v1(
+    deps: DepsMut,
+    env: &Env,
+    treasury: Option<String>,
+    coefficient_claims: Option<u64>,
+) -> Result<u64, ContractError> {
+    let v1 = CONFIG_V1.load(deps.storage)?;
+    let treasury = match treasury {
+        Some(treasury) => deps.api.addr_validate(&treasury)?,
+        None => v1.owner.clone().ok_or(ContractError::InvalidInput {})?,
+    };
+    let config = Config {
+        owner: v1.owner,
+        pending_owner: None,
+        gift_asset: GiftAsset::Native(v1.allowed_native),
+        extra_balances: vec![],
+        current_balance: v1.current_balance,
+        initial_balance: v1.initial_balance,
+        coefficient_up: Decimal::from_ratio(v1.coefficient_up, 1u128),
+        coefficient_down: Decimal::from_ratio(v1.coefficient_down, 1u128),
+        coefficient_claims: coefficient_claims.unwrap_or_default(),
+        eth_chain_id: None,
+        treasury,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        paused: false,
+        liquid_share: Decimal::one(),
+        release_blocks: 0,
+        activation_threshold: 0,
+        referral_share: Decimal::zero(),
+        referral_balance: Uint128::zero(),
+        max_claims_per_block: 0,
+        claim_minimum: Uint128::zero(),
+        claim_maximum: Uint128::zero(),
+        fee_recipient: None,
+        fee_bps: 0,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: DEFAULT_IBC_TIMEOUT_SECONDS,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: DEFAULT_UNBONDING_SECONDS,
+        claim_hook: None,
+        root_timelock_seconds: 0,
+        root_guardians: vec![],
+        root_guardian_threshold: 0,
+        linked_claim_policy: LinkedClaimPolicy::AllowBoth,
+        campaign_end: None,
+        verified_claims: false,
+        receipt_nft: None,
+        milestone_tiers: vec![],
+        cosmos_prefixes: vec![],
+        history_interval: 0,
+        lottery_ticket_size: Uint128::zero(),
+        verbose_errors: false,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    let stage = 1;
+    let merkle_root = MERKLE_ROOT_V1.may_load(deps.storage)?;
+    MERKLE_ROOT_V1.remove(deps.storage);
+    if let Some(merkle_root) = merkle_root {
+        MERKLE_ROOT.save(deps.storage, stage, &merkle_root)?;
+        STAGE_AMOUNT.save(deps.storage, stage, &config.initial_balance)?;
+        STAGE_AMOUNT_CLAIMED.save(
+            deps.storage,
+            stage,
+            &(config.initial_balance - config.current_balance),
+        )?;
+        STAGE_LEAF_FORMAT.save(deps.storage, stage, &Default::default())?;
+        LATEST_STAGE.save(deps.storage, &stage)?;
+    } else {
+        LATEST_STAGE.save(deps.storage, &0)?;
+    }
+
+    // v1 claims are only flags of the target, they become claim records without an
+    // amount or block
+    let claims = CLAIM_V1
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, _) in claims.iter() {
+        let address = String::from_utf8(key.clone()).map_err(StdError::from)?;
+        CLAIM_V1.remove(deps.storage, key);
+        let claim = ClaimState {
+            amount: Uint128::zero(),
+            timestamp: Timestamp::from_nanos(0),
+            height: 0,
+            target_addr: address.clone(),
+            recipient: None,
+            extra_amounts: vec![],
+            delegated_to: None,
+            memo: None,
+        };
+        CLAIMS.save(deps.storage, (address.clone(), stage), &claim)?;
+        CLAIMS_BY_TARGET.save(deps.storage, (address.clone(), address, stage), &true)?;
+    }
+    let claims_count = claims.len() as u64;
+    CLAIMS_COUNT.save(deps.storage, &claims_count)?;
+    ACTIVATION_BLOCK.save(deps.storage, &env.block.height)?;
+
+    Ok(claims_count)
+}
@@ -1,82 +1,795 @@
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 
-use cosmwasm_std::{Binary, Decimal, Uint128};
+use cosmwasm_std::{Binary, Coin, Decimal, Timestamp, Uint128};
+use cw0::Expiration;
+use cw20::Cw20ReceiveMsg;
+pub use cyber_gift_proofs::{
+    snapshot_key, AddressDerivation, ClaimMsg, ClaimerType, MessageEncoding, SignatureType,
+};
 
-#[derive(Serialize, Deserialize, JsonSchema)]
+use crate::state::{
+    AuditEntry, HashAlgo, HistorySnapshot, LeafFormat, LinkedClaimPolicy, LotteryWinner,
+    MilestoneTier, PendingRoot, StageRelease, Unbonding, VestingPosition,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     /// Owner if none set to info.sender.
     pub owner: Option<String>,
     pub allowed_native: String,
+    /// Cw20Token if set gifts are paid in the cw20 token instead of allowed_native.
+    /// Contract is funded with it by the owner through the cw20 receive hook.
+    pub cw20_token: Option<String>,
     pub initial_balance: Uint128,
-    pub coefficient_up: Uint128,
-    pub coefficient_down: Uint128,
-    pub coefficient: Uint128,
+    /// ExtraBalances are native denoms paid along with the gift asset, they must
+    /// be sent with the instantiation.
+    #[serde(default)]
+    pub extra_balances: Vec<Coin>,
+    /// CoefficientUp is the coefficient applied to the first claim.
+    pub coefficient_up: Decimal,
+    /// CoefficientDown is the coefficient applied once coefficient_claims are claimed,
+    /// it must not exceed coefficient_up.
+    pub coefficient_down: Decimal,
+    /// CoefficientClaims is the number of claims the coefficient decreases linearly
+    /// over, if none set the coefficient is coefficient_up.
+    pub coefficient_claims: Option<u64>,
+    /// EthChainId is the chain id expected in EIP-155 signature v values.
+    /// If none set, only v values of 27 and 28 are accepted.
+    pub eth_chain_id: Option<u64>,
+    /// Treasury receives the unclaimed funds of expired stages, if none set owner.
+    pub treasury: Option<String>,
+    /// Guardian can pause and unpause claims along with the owner.
+    pub guardian: Option<String>,
+    /// Passport is the cyber passport contract, required for passport claims.
+    pub passport: Option<String>,
+    /// TargetPrefix if set is the bech32 prefix claim target addresses must have.
+    pub target_prefix: Option<String>,
+    /// LiquidShare is the share of a claimed gift paid out right away, if none set
+    /// the whole gift is liquid.
+    pub liquid_share: Option<Decimal>,
+    /// ReleaseBlocks is the number of blocks the rest of the gift is released linearly over.
+    pub release_blocks: Option<u64>,
+    /// ActivationThreshold is the number of claims after which releases start,
+    /// if none set releases start right away.
+    pub activation_threshold: Option<u64>,
+    /// ReferralShare is the share of a referred claim payout credited to the referrer,
+    /// if none set referrals are not rewarded.
+    pub referral_share: Option<Decimal>,
+    /// ReferralBalance is the pool referral bonuses are paid from, native pools are
+    /// funded at instantiation along with the gift balance.
+    pub referral_balance: Option<Uint128>,
+    /// MaxClaimsPerBlock caps the claims accepted in a block, if none set claims
+    /// are not capped.
+    #[serde(default)]
+    pub max_claims_per_block: Option<u64>,
+    /// ClaimMinimum if set is the least snapshot amount accepted, payouts below it
+    /// are raised to it.
+    #[serde(default)]
+    pub claim_minimum: Option<Uint128>,
+    /// ClaimMaximum if set is the most snapshot amount accepted, payouts above it
+    /// are lowered to it.
+    #[serde(default)]
+    pub claim_maximum: Option<Uint128>,
+    /// FeeRecipient receives fee_bps of each claim payout, e.g. a community pool or DAO.
+    #[serde(default)]
+    pub fee_recipient: Option<String>,
+    /// FeeBps is the fee in basis points of each claim payout, it requires fee_recipient.
+    #[serde(default)]
+    pub fee_bps: Option<u64>,
+    /// IbcChannels are the channels claims can be paid out over.
+    #[serde(default)]
+    pub ibc_channels: Vec<String>,
+    /// IbcTimeoutSeconds is the timeout of IBC payouts, if none set 600 seconds.
+    #[serde(default)]
+    pub ibc_timeout_seconds: Option<u64>,
+    /// IbcController is the counterparty port id of the controller allowed to register
+    /// stages over IBC, if none set no channels are accepted.
+    #[serde(default)]
+    pub ibc_controller: Option<String>,
+    /// IbcConnectionId is the connection controller channels must be opened on, the
+    /// connection is bound to the client of the controller chain. If none set no channels
+    /// are accepted.
+    #[serde(default)]
+    pub ibc_connection_id: Option<String>,
+    /// UnbondingSeconds is the unbonding time of the chain, undelegated payouts are
+    /// withdrawable after it. If none set 21 days.
+    #[serde(default)]
+    pub unbonding_seconds: Option<u64>,
+    /// RootTimelockSeconds is the delay after which registered roots can be activated,
+    /// so that the published tree can be audited. If none set roots are claimable right away.
+    #[serde(default)]
+    pub root_timelock_seconds: Option<u64>,
+    /// RootGuardians approve registered roots, root_guardian_threshold of them must
+    /// approve a root before it is activated. The set is only changed by governance.
+    #[serde(default)]
+    pub root_guardians: Option<Vec<String>>,
+    #[serde(default)]
+    pub root_guardian_threshold: Option<u64>,
+    /// LinkedClaimPolicy is enforced on claims of addresses linked in a passport, if
+    /// none set LinkedClaimPolicy::AllowBoth.
+    #[serde(default)]
+    pub linked_claim_policy: Option<LinkedClaimPolicy>,
+    /// CampaignEnd if set is the height or time after which claims are rejected with
+    /// CampaignEnded.
+    #[serde(default)]
+    pub campaign_end: Option<Expiration>,
+    /// VerifiedClaims if set lets claim targets pre-verify their claim with VerifyClaim,
+    /// if none set claims are not pre-verified.
+    #[serde(default)]
+    pub verified_claims: Option<bool>,
+    /// ReceiptNft if set is the cw721 contract minting a gift receipt to the recipient
+    /// of each claim, the gift contract must be its minter.
+    #[serde(default)]
+    pub receipt_nft: Option<String>,
+    /// MilestoneTiers are bonus multipliers of the early claims, sorted by until_claims.
+    #[serde(default)]
+    pub milestone_tiers: Option<Vec<MilestoneTier>>,
+    /// CosmosPrefixes are the bech32 prefixes accepted for Cosmos claims, if none set
+    /// cosmos, osmo, terra, juno and bostrom. Empty accepts any prefix.
+    #[serde(default)]
+    pub cosmos_prefixes: Option<Vec<String>>,
+    /// HistoryInterval if set records a History snapshot every history_interval claims.
+    #[serde(default)]
+    pub history_interval: Option<u64>,
+    /// LotteryTicketSize if set makes each claim buy a lottery ticket per
+    /// lottery_ticket_size of its payout, see CommitLottery.
+    #[serde(default)]
+    pub lottery_ticket_size: Option<Uint128>,
+    /// VerboseErrors if set enables the DiagnoseClaim query, debugging the signature and
+    /// proof verification of claims. Only for testnets.
+    #[serde(default)]
+    pub verbose_errors: Option<bool>,
+    /// FirstStage if set is registered at instantiation like RegisterMerkleRoot, so that
+    /// the campaign is funded and launched in one transaction.
+    #[serde(default)]
+    pub first_stage: Option<StageParams>,
+}
+
+/// StageParams are the merkle root of a stage with the params of RegisterMerkleRoot.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StageParams {
+    pub merkle_root: String,
+    pub start: Option<Expiration>,
+    pub expiration: Option<Expiration>,
+    pub total_amount: Uint128,
+    #[serde(default)]
+    pub leaf_format: Option<LeafFormat>,
+    #[serde(default)]
+    pub hash_algo: Option<HashAlgo>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     UpdateConfig {
-        /// NewOwner if non sent, contract gets locked. Recipients can receive airdrops
-        /// but owner cannot register new stages.
-        new_owner: Option<String>,
+        /// NewTreasury if non sent, treasury is not changed.
+        new_treasury: Option<String>,
+        /// NewPassport if non sent, passport is not changed.
+        new_passport: Option<String>,
+        /// NewCoefficientUp if non sent, coefficient_up is not changed.
+        /// Coefficients can only be changed before activation.
+        #[serde(default)]
+        new_coefficient_up: Option<Decimal>,
+        /// NewCoefficientDown if non sent, coefficient_down is not changed.
+        #[serde(default)]
+        new_coefficient_down: Option<Decimal>,
+        /// NewCoefficientClaims if non sent, coefficient_claims is not changed.
+        #[serde(default)]
+        new_coefficient_claims: Option<u64>,
+        /// NewMaxClaimsPerBlock if non sent, max_claims_per_block is not changed.
+        /// Zero removes the cap.
+        #[serde(default)]
+        new_max_claims_per_block: Option<u64>,
+        /// NewFeeRecipient if non sent, fee_recipient is not changed.
+        #[serde(default)]
+        new_fee_recipient: Option<String>,
+        /// NewFeeBps if non sent, fee_bps is not changed.
+        #[serde(default)]
+        new_fee_bps: Option<u64>,
+        /// NewIbcChannels if non sent, ibc_channels are not changed.
+        #[serde(default)]
+        new_ibc_channels: Option<Vec<String>>,
+        /// NewIbcTimeoutSeconds if non sent, ibc_timeout_seconds is not changed.
+        #[serde(default)]
+        new_ibc_timeout_seconds: Option<u64>,
+        /// NewIbcController if non sent, ibc_controller is not changed.
+        #[serde(default)]
+        new_ibc_controller: Option<String>,
+        /// NewIbcConnectionId if non sent, ibc_connection_id is not changed.
+        #[serde(default)]
+        new_ibc_connection_id: Option<String>,
+        /// NewRootTimelockSeconds if non sent, root_timelock_seconds is not changed. The
+        /// timelock can only be raised.
+        #[serde(default)]
+        new_root_timelock_seconds: Option<u64>,
+        /// NewLinkedClaimPolicy if non sent, linked_claim_policy is not changed.
+        #[serde(default)]
+        new_linked_claim_policy: Option<LinkedClaimPolicy>,
+        /// NewCosmosPrefixes if non sent, cosmos_prefixes are not changed.
+        /// Prefixes can only be changed before activation.
+        #[serde(default)]
+        new_cosmos_prefixes: Option<Vec<String>>,
     },
+    /// ProposeNewOwner proposes an owner, ownership is transferred once it is accepted.
+    ProposeNewOwner { new_owner: String },
+    /// AcceptOwnership is sent by the proposed owner to become the owner.
+    AcceptOwnership {},
+    /// RevokeOwnershipProposal cancels the pending ownership proposal.
+    RevokeOwnershipProposal {},
+    /// RenounceOwnership locks the contract. Recipients can receive airdrops
+    /// but nobody can register new stages.
+    RenounceOwnership {},
+    /// ApproveContractWalletClaim approves the claim of an Ethereum contract wallet,
+    /// e.g. a Gnosis Safe, which can not sign the claim msg.
+    ApproveContractWalletClaim {
+        stage: u8,
+        gift_claiming_address: String,
+        target_addr: String,
+    },
+    /// AddHook registers a contract notified of claims.
+    AddHook { addr: String },
+    /// RemoveHook deregisters a contract notified of claims.
+    RemoveHook { addr: String },
+    /// SetClaimHook sets the contract executed after each claim, none removes it.
+    SetClaimHook { hook: Option<ClaimHookMsg> },
+    /// RegisterMerkleRoot registers the merkle root of a new stage.
     RegisterMerkleRoot {
-        /// MerkleRoot is hex-encoded merkle root.
+        /// MerkleRoot is hex-encoded merkle root, stages of uploaded allocations only
+        /// can leave it empty.
         merkle_root: String,
+        /// Start of the claims of the stage, if none set claims are accepted right away.
+        start: Option<Expiration>,
+        /// Expiration of the claims of the stage, if none set it never expires.
+        expiration: Option<Expiration>,
+        /// TotalAmount is the gift of the stage, unclaimed part of it can be clawed back.
+        /// It can not be zero.
+        total_amount: Uint128,
+        /// LeafFormat of the merkle tree, if none set LeafFormat::Concat.
+        leaf_format: Option<LeafFormat>,
+        /// HashAlgo of the merkle leaves and nodes, if none set keccak256 for the
+        /// keccak256 and abi_packed leaf formats and sha256 otherwise.
+        #[serde(default)]
+        hash_algo: Option<HashAlgo>,
+    },
+    /// ApproveRoot approves the pending root, sent by the root guardians.
+    ApproveRoot { root_hash: String },
+    /// ActivateRoot registers the pending root as a stage once its timelock passed,
+    /// anyone can send it.
+    ActivateRoot { merkle_root: String },
+    /// UploadAllocations adds gifts of the stage claimed without a merkle proof, for
+    /// small campaigns. Lists longer than MAX_ALLOCATIONS_PER_UPLOAD are uploaded in chunks.
+    /// Only stages without a merkle root take them, before the stage starts and up to its
+    /// total_amount.
+    UploadAllocations {
+        stage: u8,
+        entries: Vec<(String, Uint128)>,
+    },
+    /// SetStageRelease splits the payouts of the stage into a liquid and a vested part
+    /// instead of the config split, only before the stage is claimed.
+    SetStageRelease { stage: u8, release: StageRelease },
+    /// RegisterProofLevel caches a full level of the merkle tree of the stage, ordered
+    /// like the leaves, once it is verified to fold into the root. Claims then submit
+    /// compact proofs ending at a node of the level, dropping the nodes shared by the
+    /// leaves below it. A stage has a single level of at most MAX_PROOF_LEVEL_NODES, only
+    /// the owner can register it.
+    RegisterProofLevel { stage: u8, nodes: Vec<ProofNode> },
+    /// SetStagePassportAge requires the passport with the claim nickname to be owned by
+    /// the target and created at least min_age_blocks before the stage start, or before
+    /// now for stages without a start height, to deter passport farming. None removes
+    /// the requirement, only before the stage is claimed.
+    SetStagePassportAge {
+        stage: u8,
+        min_age_blocks: Option<u64>,
     },
+    /// FreezeStage blocks the claims of a stage, any single guardian or root guardian
+    /// can freeze it when an error of the tree is discovered.
+    FreezeStage { stage: u8 },
+    /// UnfreezeStage lifts a freeze right away for the owner, root guardians approve it
+    /// and the stage unfreezes once the approvals reach the root guardian threshold.
+    UnfreezeStage { stage: u8 },
+    /// ReplaceGuardian proposes the pause guardian sent by the owner, none removes it.
+    /// Root guardians approve it with the same msg and it is set once the approvals
+    /// reach the root guardian threshold, right away if there are no root guardians.
+    ReplaceGuardian { guardian: Option<String> },
     /// Claim does not check if contract has enough funds, owner must ensure it.
     Claim {
+        stage: u8,
         claim_msg: ClaimMsg,
-        signature: Binary,
+        signature: Proof,
+        claim_amount: Uint128,
+        /// ExtraAmounts are the extra denoms of the gift, encoded in the merkle leaf.
+        #[serde(default)]
+        extra_amounts: Vec<Coin>,
+        /// LeafIndex is the index of the merkle leaf, required by indexed stages.
+        #[serde(default)]
+        leaf_index: Option<u32>,
+        /// Proof is the merkle proof, see ProofNode. Uploaded allocations have none.
+        #[serde(default)]
+        proof: Vec<ProofNode>,
+    },
+    /// ClaimByNickname claims the gift of an address linked in the passport with the
+    /// nickname without a signature, the sender must own the passport and receives the
+    /// gift.
+    ClaimByNickname {
+        stage: u8,
+        nickname: String,
+        gift_claiming_address_type: ClaimerType,
+        gift_claiming_address: String,
         claim_amount: Uint128,
-        /// Proof is hex-encoded merkle proof.
-        proof: Vec<String>,
+        #[serde(default)]
+        extra_amounts: Vec<Coin>,
+        #[serde(default)]
+        leaf_index: Option<u32>,
+        #[serde(default)]
+        proof: Vec<ProofNode>,
     },
+    /// AuthorizeClaimer lets the grantee submit the unsigned claims of the sender as
+    /// target address, e.g. passport claims of a sender with no balance for fees. The
+    /// payout still goes to the sender. It replaces the previous grantee.
+    AuthorizeClaimer { grantee: String },
+    /// RevokeClaimer removes the grantee authorized by the sender.
+    RevokeClaimer {},
+    /// Release withdraws the vested gifts credited to the sender.
+    Release {},
+    /// ReleaseAll releases the vested gifts of the addresses to them, anyone can send it.
+    /// Failed releases are reported with a release_failed action.
+    ReleaseAll { addresses: Vec<String> },
+    /// ReleaseFor releases the vested gifts of the address, only sent by the contract
+    /// itself for ReleaseAll.
+    ReleaseFor { address: String },
+    /// Undelegate starts unbonding the payout the sender delegated at claim.
+    Undelegate { validator: String, amount: Uint128 },
+    /// WithdrawUnbonded withdraws the undelegated payouts of the sender that finished
+    /// unbonding.
+    WithdrawUnbonded {},
+    /// ClaimBatch processes several claims atomically, e.g. relayed by a multisig.
+    ClaimBatch { claims: Vec<ClaimEntry> },
+    /// VerifyClaim verifies the signature and proof of a claim ahead of the claim, which
+    /// then skips the verification. Only the target of the claim verifies it and keeps
+    /// its last verified claim. Queries can not write, so an IsEligible check is not kept.
+    VerifyClaim { claim: ClaimEntry },
+    /// IndexClaims sets the claimed bits of claims of an indexed stage which are not in
+    /// the claimed pages bitmap yet, e.g. migrated ones. Each index is verified against
+    /// the leaf of the claim, the claims keep their details. Only the owner can index claims.
+    IndexClaims {
+        stage: u8,
+        claims: Vec<IndexedClaim>,
+    },
+    /// Clawback sends the unclaimed gift of an expired stage to the treasury.
+    Clawback { stage: u8 },
+    /// EndCampaign emits the campaign ended event once the campaign end passed, if no
+    /// clawback emitted it before. Anyone can send it.
+    EndCampaign {},
+    /// Pause stops accepting claims, e.g. if a wrong merkle root was registered.
+    Pause {},
+    /// Unpause resumes accepting claims.
+    Unpause {},
+    /// Receive handles the cw20 tokens sent to the contract.
+    Receive(Cw20ReceiveMsg),
+    /// Fund tops up the native gift and extra balances with the sent coins, only the owner
+    /// can fund.
+    Fund {},
+    /// SweepDust sends the whole part of the dust pool left by the rounding of payouts to
+    /// the owner, only the owner can sweep.
+    SweepDust {},
+    /// RecalibrateCoefficient scales the coefficient schedule so that the remaining
+    /// stage amounts pay out the gift balance held by the contract, and syncs the
+    /// current balance to it, fixing drift after top-ups or clawbacks. Only the owner can
+    /// recalibrate, before releases are activated.
+    RecalibrateCoefficient {},
+    /// CommitLottery holds back winners * prize of the gift balance as the bonus pool
+    /// and commits to the hex sha256 of the seed of the draw and to the draw height, a
+    /// future height. Only the owner can commit, once and before the campaign end.
+    CommitLottery {
+        commitment: String,
+        winners: u32,
+        prize: Uint128,
+        draw_height: u64,
+    },
+    /// SealLottery records the beacon of the current block, mixed into the seed of the
+    /// draw. Anyone can seal, once after the campaign end and the draw height.
+    SealLottery {},
+    /// DrawLottery reveals the committed seed after the lottery is sealed and pays the
+    /// prize to the owner of each drawn ticket, a claim can win more than once. Only the
+    /// owner can draw, before the reveal deadline.
+    DrawLottery { seed: String },
+    /// ExpireLottery returns the pool of a lottery not drawn by the reveal deadline to
+    /// the gift balance. Anyone can expire it.
+    ExpireLottery {},
+    /// DryRun executes the msg and reverts it, failing with the attributes it would have
+    /// returned, e.g. to rehearse a root registration. Only the owner can dry run, in
+    /// builds with the `dry-run` feature.
+    #[cfg(any(test, feature = "dry-run"))]
+    DryRun(Box<ExecuteMsg>),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct ClaimMsg {
-    pub nickname: String,
-    pub avatar_cid: String,
-    pub gift_claiming_address_type: ClaimerType,
-    pub gift_claiming_address: String,
-    pub target_addr: String,
-    pub relay_reward: Decimal,
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    /// Fund tops up the gift balance, only the owner can fund.
+    Fund {},
+    /// FundReferral tops up the referral pool, only the owner can fund.
+    FundReferral {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimHookMsg {
+    pub contract: String,
+    /// Msg is the JSON template of the execute msg, see ClaimHook.
+    pub msg: String,
+}
+
+/// ClaimEntry is a signed claim with its merkle leaf, one of the ClaimBatch claims or the claim of VerifyClaim.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimEntry {
+    pub stage: u8,
+    pub claim_msg: ClaimMsg,
+    pub signature: Proof,
+    pub claim_amount: Uint128,
+    /// ExtraAmounts are the extra denoms of the gift, encoded in the merkle leaf.
+    #[serde(default)]
+    pub extra_amounts: Vec<Coin>,
+    /// LeafIndex is the index of the merkle leaf, required by indexed stages.
+    #[serde(default)]
+    pub leaf_index: Option<u32>,
+    /// Proof is the merkle proof, see ProofNode. Uploaded allocations have none.
+    #[serde(default)]
+    pub proof: Vec<ProofNode>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IndexedClaim {
+    pub address: String,
+    pub leaf_index: u32,
+    /// Amount and ExtraAmounts are the gift of the merkle leaf at the index.
+    pub amount: Uint128,
+    #[serde(default)]
+    pub extra_amounts: Vec<Coin>,
+    pub proof: Vec<ProofNode>,
+}
+
+/// ProofNode is a base64 encoded 32 byte merkle proof node. Hex encoded nodes
+/// of earlier clients are accepted too.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProofNode(pub Binary);
+
+impl<'de> Deserialize<'de> for ProofNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        // 32 bytes are 64 hex characters, but 44 base64 characters
+        let decoded = if encoded.len() == 64 && encoded.chars().all(|c| c.is_ascii_hexdigit()) {
+            hex::decode(&encoded).map_err(de::Error::custom)?
+        } else {
+            Binary::from_base64(&encoded)
+                .map_err(de::Error::custom)?
+                .to_vec()
+        };
+        Ok(ProofNode(Binary::from(decoded)))
+    }
+}
+
+/// Proof authorizes a claim on behalf of gift_claiming_address, it must match the
+/// gift_claiming_address_type and signature_type of the claim msg.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum ClaimerType {
-    Ethereum,
-    Cosmos,
+pub enum Proof {
+    /// EthSig is the r, s and v of an Ethereum signature, v may take more than one
+    /// byte with EIP-155.
+    EthSig {
+        signature: Binary,
+        /// MessageEncoding is how the claim msg JSON was passed to `personal_sign`.
+        #[serde(default)]
+        message_encoding: MessageEncoding,
+    },
+    /// CosmosSig is an ADR-36 or Ledger signature with the compressed secp256k1 public
+    /// key of the signer. PubKeyType is the amino type of the key, e.g.
+    /// `ethermint/PubKeyEthSecp256k1`, as returned by Keplr `signArbitrary`.
+    CosmosSig {
+        pub_key: Binary,
+        signature: Binary,
+        #[serde(default)]
+        pub_key_type: Option<String>,
+    },
+    /// SolanaSig is the ed25519 signature of a Solana wallet `signMessage`.
+    SolanaSig { signature: Binary },
+    /// BitcoinSig is the header, r and s of a Bitcoin signed message.
+    BitcoinSig { signature: Binary },
+    /// Passport claims are authorized by the passport with the nickname, it must link
+    /// gift_claiming_address and be owned by target_addr.
+    Passport { nickname: String },
+    /// ContractWallet claims are authorized by the owner approval.
+    ContractWallet {},
+}
+
+impl Proof {
+    /// Returns the snake case name of the proof variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Proof::EthSig { .. } => "eth_sig",
+            Proof::CosmosSig { .. } => "cosmos_sig",
+            Proof::SolanaSig { .. } => "solana_sig",
+            Proof::BitcoinSig { .. } => "bitcoin_sig",
+            Proof::Passport { .. } => "passport",
+            Proof::ContractWallet {} => "contract_wallet",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum QueryMsg {
     Config {},
-    MerkleRoot {},
-    IsClaimed { address: String },
+    MerkleRoot {
+        stage: u8,
+    },
+    /// Stage returns the merkle root and params of a stage with flat fields.
+    Stage {
+        stage: u8,
+    },
+    LatestStage {},
+    IsClaimed {
+        stage: u8,
+        address: String,
+        /// SourceChain is the snapshot chain of the entry, see ClaimMsg.
+        #[serde(default)]
+        source_chain: Option<String>,
+    },
+    /// IsEligible runs the merkle and signature verification of a claim without executing it.
+    IsEligible {
+        stage: u8,
+        claim_msg: ClaimMsg,
+        signature: Proof,
+        claim_amount: Uint128,
+        #[serde(default)]
+        extra_amounts: Vec<Coin>,
+        #[serde(default)]
+        leaf_index: Option<u32>,
+        /// Proof is the merkle proof, see ProofNode. Uploaded allocations have none.
+        #[serde(default)]
+        proof: Vec<ProofNode>,
+    },
+    /// AllClaims lists claims ordered by (gift_claiming_address, stage).
+    AllClaims {
+        start_after: Option<(String, u8)>,
+        limit: Option<u32>,
+    },
+    /// ClaimsByAddress lists claims credited to the target address.
+    ClaimsByAddress {
+        address: String,
+    },
+    /// HasClaimed tells if any gift was credited to the target address, for contracts
+    /// that gate features on gift participation.
+    HasClaimed {
+        address: String,
+    },
+    /// ClaimedAmount is the total gift credited to the target address over all stages.
+    ClaimedAmount {
+        address: String,
+    },
+    ReleaseState {
+        address: String,
+    },
+    /// ExportState pages through the claims and then the release states in key order
+    /// for archival of the campaign, pass the returned next cursor as start_after.
+    ExportState {
+        start_after: Option<ExportCursor>,
+        limit: Option<u32>,
+    },
+    /// ReleaseProjection returns the amount releasable by the address at a future height,
+    /// so that wallets can graph the unlock curve.
+    ReleaseProjection {
+        address: String,
+        at_height: u64,
+    },
+    Activation {},
+    /// Reconciliation compares the accounted balances of the contract to its actual balances.
+    Reconciliation {},
+    /// AuditLog lists the admin actions ordered by their id.
+    AuditLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// History lists the retained snapshots of the campaign progress ordered by their id.
+    History {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Lottery returns the tickets bought so far and the commitment and results of the
+    /// drawing.
+    Lottery {},
+    /// VerifyProof verifies the proof of an arbitrary leaf, e.g. `{address}{amount}`,
+    /// against the root of the stage without any signature checks.
+    VerifyProof {
+        stage: u8,
+        leaf: String,
+        proof: Vec<ProofNode>,
+    },
+    /// DiagnoseClaim returns why the signature or proof verification of the claim fails,
+    /// if it does. Only with verbose_errors.
+    DiagnoseClaim {
+        claim: ClaimEntry,
+    },
+    /// CurrentCoefficient returns the coefficient applied to the next claim.
+    CurrentCoefficient {},
+    /// SimulateClaim returns the payout of a claim of the amount sent right now by
+    /// the target itself, split by the release of the stage or the config if none set.
+    SimulateClaim {
+        amount: Uint128,
+        stage: Option<u8>,
+    },
+    /// Stats returns the claim, release and balance totals of the campaign.
+    Stats {},
+    /// CurrentTier returns the milestone tier applied to the next claim.
+    CurrentTier {},
+    /// ClaimedPage returns a page of the claimed leaf indexes of an indexed stage.
+    ClaimedPage {
+        stage: u8,
+        page: u32,
+    },
+    /// PendingRoots lists the registered roots awaiting their timelock.
+    PendingRoots {},
+    /// Allocation tells whether the address is in the gift of the stage: the uploaded
+    /// gift, if it was claimed and if a proof of it was ever validated.
+    Allocation {
+        stage: u8,
+        address: String,
+    },
+    /// ReferralStats returns the referrals and the bonus credited to the address.
+    ReferralStats {
+        address: String,
+    },
+    /// ClaimsBySource returns the claims count and the amount paid out per source chain.
+    ClaimsBySource {},
+    /// ClaimerGrant returns the grantee authorized to submit the claims of the address.
+    ClaimerGrant {
+        address: String,
+    },
+    /// Delegations returns the payouts delegated and unbonding on behalf of the address.
+    Delegations {
+        address: String,
+    },
+    /// PassportClaims returns a page of the addresses linked in the passport with the
+    /// nickname, read from the passport AddressesByPassport, and whether each claimed
+    /// the stage.
+    PassportClaims {
+        stage: u8,
+        nickname: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Hooks returns the contracts notified of claims.
+    Hooks {},
+}
+
+/// EXPORT_STATE_VERSION is the version of the ExportState envelope, bumped when its
+/// layout changes.
+pub const EXPORT_STATE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportCursor {
+    Claim { address: String, stage: u8 },
+    Release { address: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct ConfigResponse {
     pub owner: Option<String>,
-    pub allowed_native: String,
+    pub pending_owner: Option<String>,
+    /// AllowedNative is the denom of a native gift asset, exclusive with cw20_token.
+    pub allowed_native: Option<String>,
+    /// Cw20Token is the token address of a cw20 gift asset.
+    pub cw20_token: Option<String>,
+    /// ExtraDenoms are the extra gift denoms, with their balances at the same index in
+    /// extra_initial_balances and extra_current_balances.
+    pub extra_denoms: Vec<String>,
+    pub extra_initial_balances: Vec<Uint128>,
+    pub extra_current_balances: Vec<Uint128>,
     pub current_balance: Uint128,
     pub initial_balance: Uint128,
-    pub coefficient_up: Uint128,
-    pub coefficient_down: Uint128,
-    pub coefficient: Uint128,
+    pub coefficient_up: Decimal,
+    pub coefficient_down: Decimal,
+    pub coefficient_claims: u64,
+    pub eth_chain_id: Option<u64>,
+    pub treasury: String,
+    pub guardian: Option<String>,
+    pub passport: Option<String>,
+    pub target_prefix: Option<String>,
+    pub paused: bool,
+    pub liquid_share: Decimal,
+    pub release_blocks: u64,
+    pub activation_threshold: u64,
+    pub referral_share: Decimal,
+    pub referral_balance: Uint128,
+    pub max_claims_per_block: u64,
+    pub claim_minimum: Uint128,
+    pub claim_maximum: Uint128,
+    pub fee_recipient: Option<String>,
+    pub fee_bps: u64,
+    pub ibc_channels: Vec<String>,
+    pub ibc_timeout_seconds: u64,
+    pub ibc_controller: Option<String>,
+    pub ibc_connection_id: Option<String>,
+    pub unbonding_seconds: u64,
+    /// ClaimHook is the contract executed after each claim.
+    pub claim_hook: Option<String>,
+    /// ClaimHookMsg is the JSON template of the claim hook msg.
+    pub claim_hook_msg: Option<String>,
+    pub root_timelock_seconds: u64,
+    pub root_guardians: Vec<String>,
+    pub root_guardian_threshold: u64,
+    /// LinkedClaimPolicy is `allow_both`, `first_wins` or `sum_with_cap` with the cap in
+    /// linked_claim_cap.
+    pub linked_claim_policy: String,
+    pub linked_claim_cap: Option<Uint128>,
+    /// CampaignEndHeight and CampaignEndTime are the campaign end at a height or time.
+    pub campaign_end_height: Option<u64>,
+    pub campaign_end_time: Option<Timestamp>,
+    pub verified_claims: bool,
+    pub receipt_nft: Option<String>,
+    /// MilestoneUntilClaims are the until_claims of the milestone tiers, with their
+    /// multipliers at the same index in milestone_multipliers.
+    pub milestone_until_claims: Vec<u64>,
+    pub milestone_multipliers: Vec<Decimal>,
+    pub cosmos_prefixes: Vec<String>,
+    pub history_interval: u64,
+    pub lottery_ticket_size: Uint128,
+    pub verbose_errors: bool,
+}
+
+/// StageResponse is a MerkleRootResponse with the start, expiration and release of the
+/// stage as flat fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StageResponse {
+    pub stage: u8,
+    /// MerkleRoot is hex-encoded merkle root.
+    pub merkle_root: String,
+    pub start_height: Option<u64>,
+    pub start_time: Option<Timestamp>,
+    pub expiration_height: Option<u64>,
+    pub expiration_time: Option<Timestamp>,
+    pub total_amount: Uint128,
+    pub claimed_amount: Uint128,
+    pub leaf_format: LeafFormat,
+    pub hash_algo: HashAlgo,
+    pub liquid_share: Decimal,
+    pub vested_share: Decimal,
+    pub release_blocks: u64,
+    pub proof_level_nodes: u32,
+    pub passport_created_before: Option<u64>,
+    pub frozen_by: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MerkleRootResponse {
+    pub stage: u8,
     /// MerkleRoot is hex-encoded merkle root.
     pub merkle_root: String,
+    pub start: Option<Expiration>,
+    pub expiration: Option<Expiration>,
+    pub total_amount: Uint128,
+    pub claimed_amount: Uint128,
+    pub leaf_format: LeafFormat,
+    pub hash_algo: HashAlgo,
+    /// Release is the liquid and vested split of the payouts of the stage.
+    pub release: StageRelease,
+    /// ProofLevelNodes is the number of nodes of the registered proof level, zero if
+    /// claims need full proofs.
+    pub proof_level_nodes: u32,
+    /// PassportCreatedBefore is the block passports claiming the stage must be created
+    /// at or before, none if any target can claim.
+    pub passport_created_before: Option<u64>,
+    /// FrozenBy is the guardian who froze the stage, none if claims are accepted.
+    pub frozen_by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LatestStageResponse {
+    pub latest_stage: u8,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -85,4 +798,326 @@ pub struct IsClaimedResponse {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct IsEligibleResponse {
+    pub valid_proof: bool,
+    pub valid_signature: bool,
+    pub is_claimed: bool,
+    /// Payout is the claim amount after the current coefficient is applied.
+    pub payout: Uint128,
+    pub extra_payout: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimResponse {
+    pub address: String,
+    pub stage: u8,
+    /// Amount is the gift paid out after the coefficient is applied.
+    pub amount: Uint128,
+    pub timestamp: Timestamp,
+    pub height: u64,
+    pub target_addr: String,
+    pub recipient: Option<String>,
+    pub extra_amounts: Vec<Coin>,
+    pub delegated_to: Option<String>,
+    pub memo: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegationResponse {
+    pub validator: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegationsResponse {
+    pub delegations: Vec<DelegationResponse>,
+    pub unbondings: Vec<Unbonding>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PassportClaim {
+    pub address: String,
+    pub address_type: ClaimerType,
+    pub is_claimed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PassportClaimsResponse {
+    pub nickname: String,
+    pub claims: Vec<PassportClaim>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllClaimsResponse {
+    pub claims: Vec<ClaimResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExportStateResponse {
+    /// Version is the EXPORT_STATE_VERSION of the envelope.
+    pub version: u32,
+    pub contract: String,
+    pub contract_version: String,
+    pub claims: Vec<ClaimResponse>,
+    pub release_states: Vec<ExportedReleaseState>,
+    /// Next is the cursor of the following page, none after the last one.
+    pub next: Option<ExportCursor>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExportedReleaseState {
+    pub address: String,
+    pub positions: Vec<VestingPosition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HasClaimedResponse {
+    pub has_claimed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimedAmountResponse {
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimsByAddressResponse {
+    pub claims: Vec<ClaimResponse>,
+    pub release_state: ReleaseStateResponse,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReleaseStateResponse {
+    pub address: String,
+    pub positions: Vec<VestingPosition>,
+    /// Releasable is the vested amount which can be released at the current height.
+    pub releasable: Uint128,
+    pub releasable_extra_amounts: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReleaseProjectionResponse {
+    pub address: String,
+    pub at_height: u64,
+    /// Releasable is the amount releasable at at_height if nothing is released before,
+    /// zero before releases are activated.
+    pub releasable: Uint128,
+    pub releasable_extra_amounts: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoryResponse {
+    pub snapshots: Vec<HistorySnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotteryResponse {
+    pub ticket_size: Uint128,
+    pub total_tickets: u64,
+    /// Commitment is the hex sha256 of the seed, none until committed.
+    pub commitment: Option<String>,
+    pub winners: u32,
+    pub prize: Uint128,
+    /// DrawHeight is the height from which the lottery can be sealed.
+    pub draw_height: Option<u64>,
+    /// RevealDeadline is the last height the seed can be revealed at, after it the
+    /// lottery can be expired.
+    pub reveal_deadline: Option<u64>,
+    /// Beacon is the hex hash of the sealing block, none until sealed.
+    pub beacon: Option<String>,
+    /// Seed is the revealed seed, none until drawn.
+    pub seed: Option<String>,
+    pub drawn_at: Option<u64>,
+    pub expired_at: Option<u64>,
+    pub results: Vec<LotteryWinner>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReconciliationResponse {
+    pub gift: BalanceReconciliation,
+    pub extra_balances: Vec<BalanceReconciliation>,
+    /// Drifted is true if any accounted balance differs from the actual balance.
+    pub drifted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BalanceReconciliation {
+    /// Denom is the native denom or the cw20 token address.
+    pub denom: String,
+    /// Accounted is the gift balance, referral pool, unreleased vesting and withdrawable
+    /// unbonded payouts.
+    pub accounted: Uint128,
+    pub balance: Uint128,
+    /// Surplus is the balance above the accounted amount, e.g. funds sent directly.
+    pub surplus: Uint128,
+    /// Deficit is the accounted amount missing from the balance.
+    pub deficit: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivationResponse {
+    pub claims_count: u64,
+    pub activation_threshold: u64,
+    /// ActivationBlock is the height releases started from, none if not activated yet.
+    pub activation_block: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrentCoefficientResponse {
+    pub coefficient: Decimal,
+    pub claims_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrentTierResponse {
+    /// Tier is the index of the milestone tier, none once all tiers are reached.
+    pub tier: Option<u32>,
+    /// Multiplier is applied on top of the base coefficient, one without a tier.
+    pub multiplier: Decimal,
+    pub until_claims: Option<u64>,
+    pub claims_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateClaimResponse {
+    pub allowed_native: Option<String>,
+    pub cw20_token: Option<String>,
+    pub coefficient: Decimal,
+    /// Payout is the amount after the coefficient is applied.
+    pub payout: Uint128,
+    /// LiquidAmount is the part of the payout paid out right away.
+    pub liquid_amount: Uint128,
+    /// VestingAmount is the part of the payout released over release_blocks.
+    pub vesting_amount: Uint128,
+    /// Fee is the part of the payout paid to the fee recipient.
+    pub fee: Uint128,
+    /// EnoughBalance is false if the claim would fail for the lack of funds.
+    pub enough_balance: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    pub claims_count: u64,
+    /// TotalClaimed is the gift paid out over all stages, after the coefficient is applied.
+    pub total_claimed: Uint128,
+    /// RemainingBalance is the gift balance left for claims.
+    pub remaining_balance: Uint128,
+    pub releases_count: u64,
+    /// Coefficient is the coefficient applied to the next claim.
+    pub coefficient: Decimal,
+    pub activated: bool,
+    pub activation_block: Option<u64>,
+    pub campaign_end_height: Option<u64>,
+    pub campaign_end_time: Option<Timestamp>,
+    /// RemainingBlocks is the number of blocks to a campaign end at a height.
+    pub remaining_blocks: Option<u64>,
+    /// RemainingSeconds is the time to a campaign end at a time.
+    pub remaining_seconds: Option<u64>,
+    /// DustPool is the gift left over by the rounding down of payouts, see SweepDust.
+    pub dust_pool: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimedPageResponse {
+    pub stage: u8,
+    pub page: u32,
+    /// Bitmap has the bit of leaf index page * 128 + i set if it is claimed.
+    pub bitmap: Uint128,
+    pub claimed_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRootsResponse {
+    pub roots: Vec<PendingRoot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllocationResponse {
+    /// Amount is the uploaded gift, none for entries of merkle trees.
+    pub amount: Option<Uint128>,
+    pub is_claimed: bool,
+    /// ProofValidated is whether the entry was claimed or verified by VerifyClaim.
+    pub proof_validated: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralStatsResponse {
+    pub address: String,
+    /// Referrals is the number of claims referred by the address.
+    pub referrals: u64,
+    /// Bonus is the total referral bonus paid to the address.
+    pub bonus: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SourceClaims {
+    /// Source is the source chain of the snapshot entries, or the claimer type of
+    /// entries without one.
+    pub source: String,
+    pub claims: u64,
+    /// Amount is the gift paid out after the coefficient is applied.
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimsBySourceResponse {
+    pub sources: Vec<SourceClaims>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimerGrantResponse {
+    pub grantee: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VerifyProofResponse {
+    pub valid: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimDiagnosisResponse {
+    /// FailureReason is the error of the verification, none if the claim verifies.
+    pub failure_reason: Option<String>,
+    pub proof_depth: u32,
+    /// RecoveredAddress is the signer of an Ethereum signature cut to its first and
+    /// last two bytes.
+    pub recovered_address: Option<String>,
+}
+
+/// SudoMsg is sent by the chain governance, it can intervene without the owner key.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Pause pauses or unpauses claims.
+    Pause { paused: bool },
+    /// UpdateOwner sets the owner right away, none set locks the contract.
+    UpdateOwner { owner: Option<String> },
+    /// Clawback sends the unclaimed gift of an expired stage to the treasury.
+    Clawback { stage: u8 },
+    /// UpdateRootGuardians replaces the root guardian set, a zero threshold disables
+    /// the approvals.
+    UpdateRootGuardians {
+        guardians: Vec<String>,
+        threshold: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrateMsg {
+    /// Bump updates the stored contract version, the state is kept as is.
+    Bump {},
+    /// UpgradeV1 upgrades the single stage 0.1 state, its merkle root and claims
+    /// become stage 1.
+    UpgradeV1 {
+        /// Treasury if none set, owner.
+        treasury: Option<String>,
+        /// CoefficientClaims replaces the balance based coefficient of 0.1.
+        coefficient_claims: Option<u64>,
+    },
+}
@@ -0,0 +1,51 @@
+//! Interface of the cw721 contract minting the soulbound gift receipts of claims.
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, Decimal, StdResult, Uint128, WasmMsg};
+
+/// GiftReceipt is the token metadata of the receipt of a claim.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GiftReceipt {
+    pub stage: u8,
+    pub address: String,
+    /// Amount is the snapshot amount of the claim, before the coefficient is applied.
+    pub amount: Uint128,
+    pub coefficient: Decimal,
+}
+
+/// MintMsg is the cw721-base mint msg with the receipt as extension.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintMsg {
+    pub token_id: String,
+    pub owner: String,
+    pub token_uri: Option<String>,
+    pub extension: GiftReceipt,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Mint mints the receipt to the owner, the gift contract must be the minter and
+    /// the cw721 contract must not allow transfers.
+    Mint(MintMsg),
+}
+
+/// Builds the mint msg of the receipt, token ids are `{stage}:{address}` so each claim
+/// has a single receipt.
+pub fn mint_receipt_msg(
+    contract_addr: &str,
+    owner: &str,
+    receipt: GiftReceipt,
+) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: contract_addr.to_string(),
+        msg: to_binary(&ExecuteMsg::Mint(MintMsg {
+            token_id: format!("{}:{}", receipt.stage, receipt.address),
+            owner: owner.to_string(),
+            token_uri: None,
+            extension: receipt,
+        }))?,
+        funds: vec![],
+    })
+}
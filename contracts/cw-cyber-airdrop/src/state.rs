@@ -1,26 +1,674 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
+use cw0::Expiration;
 use cw_storage_plus::{Item, Map};
 
+use crate::hooks::Hooks;
+
+/// Layout is the storage layout of the contract. Indexers read the state with raw
+/// queries, so the keys must not change, new state gets new keys.
+pub mod layout {
+    // Items are stored under their key
+    pub const CONFIG_KEY: &str = "config";
+    pub const LATEST_STAGE_KEY: &str = "stage";
+    pub const CLAIMS_COUNT_KEY: &str = "claims_count";
+    pub const RELEASES_COUNT_KEY: &str = "releases_count";
+    pub const ACTIVATION_BLOCK_KEY: &str = "activation_block";
+    pub const BLOCK_CLAIMS_KEY: &str = "block_claims";
+    pub const HOOKS_KEY: &str = "hooks";
+    pub const CAMPAIGN_ENDED_KEY: &str = "campaign_ended";
+    pub const AUDIT_LOG_COUNT_KEY: &str = "audit_log_count";
+    pub const DUST_POOL_KEY: &str = "dust_pool";
+    pub const HISTORY_COUNT_KEY: &str = "history_count";
+    pub const LOTTERY_KEY: &str = "lottery";
+    pub const LOTTERY_TICKETS_KEY: &str = "lottery_tickets";
+    pub const LOTTERY_ENTRY_COUNT_KEY: &str = "lottery_entry_count";
+    pub const PENDING_GUARDIAN_KEY: &str = "pending_guardian";
+
+    // Map entries are stored under the length prefixed namespace and key parts
+    pub const MERKLE_ROOT_PREFIX: &str = "merkle_root";
+    pub const ALLOCATIONS_PREFIX: &str = "allocations";
+    pub const PENDING_ROOTS_PREFIX: &str = "pending_roots";
+    pub const LINKED_CLAIMS_PREFIX: &str = "linked_claims";
+    pub const LINKED_CLAIMERS_PREFIX: &str = "linked_claimers";
+    pub const CONTROLLER_CHANNELS_PREFIX: &str = "controller_channels";
+    pub const CONTRACT_WALLET_CLAIMS_PREFIX: &str = "contract_wallet_claims";
+    pub const PASSPORT_CLAIMS_PREFIX: &str = "passport_claims";
+    pub const PENDING_RELEASES_PREFIX: &str = "pending_releases";
+    pub const AUDIT_LOG_PREFIX: &str = "audit_log";
+    pub const VERIFIED_CLAIMS_PREFIX: &str = "verified_claims";
+    pub const VERIFIED_PROOFS_PREFIX: &str = "verified_proofs";
+    pub const STAGE_LEAF_FORMAT_PREFIX: &str = "stage_leaf_format";
+    pub const STAGE_HASH_ALGO_PREFIX: &str = "stage_hash_algo";
+    pub const STAGE_RELEASE_PREFIX: &str = "stage_release";
+    pub const STAGE_PROOF_LEVEL_PREFIX: &str = "stage_proof_level";
+    pub const PROOF_LEVEL_NODES_PREFIX: &str = "proof_level_nodes";
+    pub const STAGE_PASSPORT_CUTOFF_PREFIX: &str = "stage_passport_cutoff";
+    pub const STAGE_FREEZES_PREFIX: &str = "stage_freezes";
+    pub const STAGE_START_PREFIX: &str = "stage_start";
+    pub const STAGE_EXPIRATION_PREFIX: &str = "stage_exp";
+    pub const STAGE_AMOUNT_PREFIX: &str = "stage_amount";
+    pub const STAGE_AMOUNT_CLAIMED_PREFIX: &str = "stage_claimed_amount";
+    pub const STAGE_ALLOCATED_PREFIX: &str = "stage_allocated";
+    pub const CLAIMED_PAGES_PREFIX: &str = "claimed_pages";
+    pub const CLAIM_PREFIX: &str = "claim";
+    pub const CLAIMS_PREFIX: &str = "claims";
+    pub const CLAIMS_BY_TARGET_PREFIX: &str = "claims_by_target";
+    pub const REFERRAL_STATS_PREFIX: &str = "referral_stats";
+    pub const CLAIMS_BY_SOURCE_PREFIX: &str = "claims_by_source";
+    pub const CLAIMER_GRANTS_PREFIX: &str = "claimer_grants";
+    pub const DELEGATIONS_PREFIX: &str = "delegations";
+    pub const UNBONDINGS_PREFIX: &str = "unbondings";
+    pub const RELEASE_STATE_PREFIX: &str = "release_state";
+    pub const HISTORY_PREFIX: &str = "history";
+    pub const LOTTERY_ENTRIES_PREFIX: &str = "lottery_entries";
+
+    /// Returns the raw storage key of a map entry, every key part but the last is
+    /// length prefixed like the namespace. Integer parts are big endian.
+    pub fn map_key(namespace: &str, keys: &[&[u8]]) -> Vec<u8> {
+        let mut out = vec![];
+        let (last, prefix) = match keys.split_last() {
+            Some((last, prefix)) => (*last, prefix),
+            None => (&[][..], &[][..]),
+        };
+        for part in std::iter::once(namespace.as_bytes()).chain(prefix.iter().copied()) {
+            out.extend_from_slice(&(part.len() as u16).to_be_bytes());
+            out.extend_from_slice(part);
+        }
+        out.extend_from_slice(last);
+        out
+    }
+}
+
+pub use layout::*;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     /// Owner If None set, contract is frozen.
     pub owner: Option<Addr>,
-    pub allowed_native: String,
+    /// PendingOwner is the proposed owner, it becomes owner once it accepts ownership.
+    pub pending_owner: Option<Addr>,
+    pub gift_asset: GiftAsset,
+    /// ExtraBalances are the native denoms paid along with the gift asset.
+    pub extra_balances: Vec<DenomBalance>,
     pub current_balance: Uint128,
     pub initial_balance: Uint128,
-    pub coefficient_up: Uint128,
-    pub coefficient_down: Uint128,
-    pub coefficient: Uint128,
+    /// CoefficientUp is the ceiling coefficient the gifts start from.
+    pub coefficient_up: Decimal,
+    /// CoefficientDown is the floor coefficient the gifts decrease to.
+    pub coefficient_down: Decimal,
+    /// CoefficientClaims is the number of claims the coefficient decreases linearly over,
+    /// if zero the coefficient is CoefficientUp.
+    pub coefficient_claims: u64,
+    pub eth_chain_id: Option<u64>,
+    /// Treasury receives the unclaimed funds of expired stages.
+    pub treasury: Addr,
+    /// Guardian can pause and unpause claims along with the owner.
+    pub guardian: Option<Addr>,
+    /// Passport is the cyber passport contract verifying passport claims.
+    pub passport: Option<Addr>,
+    /// TargetPrefix if set is the bech32 prefix claim target addresses must have.
+    pub target_prefix: Option<String>,
+    /// Paused contract does not accept claims.
+    pub paused: bool,
+    /// LiquidShare is the share of a claimed gift paid out right away, rest is vested.
+    pub liquid_share: Decimal,
+    /// ReleaseBlocks is the number of blocks the vested gift is released linearly over.
+    pub release_blocks: u64,
+    /// ActivationThreshold is the number of claims after which releases start.
+    pub activation_threshold: u64,
+    /// ReferralShare is the share of a referred claim payout credited to the referrer.
+    #[serde(default)]
+    pub referral_share: Decimal,
+    /// ReferralBalance is the remaining referral pool, tracked apart from current_balance.
+    #[serde(default)]
+    pub referral_balance: Uint128,
+    /// MaxClaimsPerBlock caps the claims accepted in a block, if zero claims are not capped.
+    #[serde(default)]
+    pub max_claims_per_block: u64,
+    /// ClaimMinimum is the least snapshot amount and payout of a claim.
+    #[serde(default)]
+    pub claim_minimum: Uint128,
+    /// ClaimMaximum is the most snapshot amount and payout of a claim, if zero
+    /// claims are not capped.
+    #[serde(default)]
+    pub claim_maximum: Uint128,
+    /// FeeRecipient receives fee_bps of each claim payout.
+    #[serde(default)]
+    pub fee_recipient: Option<Addr>,
+    /// FeeBps is the claim fee in basis points, if zero claims are not charged.
+    #[serde(default)]
+    pub fee_bps: u64,
+    /// IbcChannels are the channels claims can be paid out over.
+    #[serde(default)]
+    pub ibc_channels: Vec<String>,
+    /// IbcTimeoutSeconds is the timeout of IBC payouts.
+    #[serde(default)]
+    pub ibc_timeout_seconds: u64,
+    /// IbcController is the counterparty port id allowed to open controller channels.
+    #[serde(default)]
+    pub ibc_controller: Option<String>,
+    /// IbcConnectionId is the connection controller channels must be opened on, it pins
+    /// the client and so the chain of the controller.
+    #[serde(default)]
+    pub ibc_connection_id: Option<String>,
+    /// UnbondingSeconds is the time undelegated payouts take to be withdrawable.
+    #[serde(default)]
+    pub unbonding_seconds: u64,
+    /// ClaimHook if set is executed after each claim, its failures do not revert claims.
+    #[serde(default)]
+    pub claim_hook: Option<ClaimHook>,
+    /// RootTimelockSeconds is the delay before registered roots can be activated, if
+    /// zero roots are registered as stages right away.
+    #[serde(default)]
+    pub root_timelock_seconds: u64,
+    /// RootGuardians approve registered roots, if root_guardian_threshold is zero no
+    /// approvals are required.
+    #[serde(default)]
+    pub root_guardians: Vec<Addr>,
+    #[serde(default)]
+    pub root_guardian_threshold: u64,
+    /// LinkedClaimPolicy is enforced on claims of addresses linked in a passport.
+    #[serde(default)]
+    pub linked_claim_policy: LinkedClaimPolicy,
+    /// CampaignEnd is the height or time after which claims end, only releases and
+    /// clawbacks remain enabled.
+    #[serde(default)]
+    pub campaign_end: Option<Expiration>,
+    /// VerifiedClaims if set lets claim targets pre-verify their claim with VerifyClaim.
+    #[serde(default)]
+    pub verified_claims: bool,
+    /// ReceiptNft is the cw721 contract minting the gift receipts of claims.
+    #[serde(default)]
+    pub receipt_nft: Option<Addr>,
+    /// MilestoneTiers are sorted by until_claims, the first tier above the claims count
+    /// applies on top of the coefficient.
+    #[serde(default)]
+    pub milestone_tiers: Vec<MilestoneTier>,
+    /// CosmosPrefixes are the bech32 prefixes accepted for Cosmos claims, any prefix is
+    /// accepted if empty.
+    #[serde(default)]
+    pub cosmos_prefixes: Vec<String>,
+    /// HistoryInterval is the number of claims between history snapshots, if zero no
+    /// snapshots are recorded.
+    #[serde(default)]
+    pub history_interval: u64,
+    /// LotteryTicketSize is the payout buying one lottery ticket, if zero claims buy no
+    /// tickets.
+    #[serde(default)]
+    pub lottery_ticket_size: Uint128,
+    /// VerboseErrors makes failed verifications of claims return debug attributes.
+    #[serde(default)]
+    pub verbose_errors: bool,
+}
+
+/// ClaimHook is a contract notified of claims, e.g. to create cyberlinks of the
+/// claimer. Msg is a JSON template where `{stage}`, `{address}`, `{target_addr}`,
+/// `{recipient}` and `{amount}` are replaced with the values of the claim.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimHook {
+    pub contract: Addr,
+    pub msg: String,
+}
+
+/// Hooks are the contracts notified with a GiftClaimedHookMsg after each claim.
+pub const HOOKS: Hooks = Hooks::new(HOOKS_KEY);
+
+/// GiftAsset is the token gifts are paid in.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GiftAsset {
+    Native(String),
+    Cw20(Addr),
+}
+
+impl GiftAsset {
+    /// NativeDenom is the denom of a native gift asset.
+    pub fn native_denom(&self) -> Option<String> {
+        match self {
+            GiftAsset::Native(denom) => Some(denom.clone()),
+            GiftAsset::Cw20(_) => None,
+        }
+    }
+
+    /// Denom is the denom of the gift asset in merkle leaves, the token address of a
+    /// cw20 gift asset.
+    pub fn denom(&self) -> String {
+        match self {
+            GiftAsset::Native(denom) => denom.clone(),
+            GiftAsset::Cw20(addr) => addr.to_string(),
+        }
+    }
+
+    /// Cw20Token is the token address of a cw20 gift asset.
+    pub fn cw20_token(&self) -> Option<String> {
+        match self {
+            GiftAsset::Native(_) => None,
+            GiftAsset::Cw20(addr) => Some(addr.to_string()),
+        }
+    }
+}
+
+/// LeafFormat is the encoding of the merkle leaves and nodes of a stage.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeafFormat {
+    /// sha256 of `{address}{amount}`.
+    #[default]
+    Concat,
+    /// sha256 of `{address}:{amount}`, as generated by cw20-merkle-airdrop tooling.
+    Cw20MerkleAirdrop,
+    /// keccak256 of `{address}{amount}`, nodes are hashed with keccak256 too.
+    Keccak256,
+    /// sha256 of `{index}:{address}{amount}`, claims are tracked by leaf index in
+    /// the claimed pages bitmap.
+    Indexed,
+    /// keccak256 of `abi.encodePacked(address, uint256 amount)` of Ethereum claimers,
+    /// as generated by Ethereum merkle distributor tooling. Extra denoms and source
+    /// chains are not encoded.
+    AbiPacked,
+}
+
+impl LeafFormat {
+    /// Returns the hash algo of stages registered without one.
+    pub fn default_hash_algo(&self) -> HashAlgo {
+        match self {
+            LeafFormat::Keccak256 | LeafFormat::AbiPacked => HashAlgo::Keccak256,
+            _ => HashAlgo::Sha256,
+        }
+    }
+}
+
+/// HashAlgo is the hash function of the merkle leaves and nodes of a stage.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgo {
+    #[default]
+    Sha256,
+    Keccak256,
+}
+
+/// LinkedClaimPolicy decides the claims of addresses linked in one passport, e.g. an
+/// Ethereum and a Cosmos address both found in the snapshot.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkedClaimPolicy {
+    /// Every linked address claims its gift.
+    #[default]
+    AllowBoth,
+    /// Only the first linked address to claim in a stage is paid.
+    FirstWins,
+    /// Linked addresses claim until their payouts in a stage sum up to the cap.
+    SumWithCap { cap: Uint128 },
+}
+
+impl LinkedClaimPolicy {
+    /// Kind is the snake case name of the policy.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LinkedClaimPolicy::AllowBoth => "allow_both",
+            LinkedClaimPolicy::FirstWins => "first_wins",
+            LinkedClaimPolicy::SumWithCap { .. } => "sum_with_cap",
+        }
+    }
+
+    /// Cap is the cap of a sum_with_cap policy.
+    pub fn cap(&self) -> Option<Uint128> {
+        match self {
+            LinkedClaimPolicy::SumWithCap { cap } => Some(*cap),
+            _ => None,
+        }
+    }
+}
+
+/// MilestoneTier multiplies the coefficient of claims made while the claims count is
+/// below until_claims, e.g. the first 10000 claims get 1.3.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MilestoneTier {
+    pub until_claims: u64,
+    pub multiplier: Decimal,
+}
+
+/// DenomBalance tracks the balance of an extra gift denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomBalance {
+    pub denom: String,
+    pub initial_balance: Uint128,
+    pub current_balance: Uint128,
 }
 
-pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 
-pub const MERKLE_ROOT_PREFIX: &str = "merkle_root";
-pub const MERKLE_ROOT: Item<String> = Item::new(MERKLE_ROOT_PREFIX);
+pub const LATEST_STAGE: Item<u8> = Item::new(LATEST_STAGE_KEY);
+
+pub const MERKLE_ROOT: Map<u8, String> = Map::new(MERKLE_ROOT_PREFIX);
+
+pub const CLAIMS_COUNT: Item<u64> = Item::new(CLAIMS_COUNT_KEY);
+
+/// CampaignEnded is the height the first claim after the campaign end was rejected at.
+pub const CAMPAIGN_ENDED: Item<u64> = Item::new(CAMPAIGN_ENDED_KEY);
+
+/// DustPool is the gift left over by the rounding down of coefficient scaled payouts,
+/// its whole part is swept by the owner with SweepDust.
+pub const DUST_POOL: Item<Decimal> = Item::new(DUST_POOL_KEY);
+
+/// ReleasesCount is the number of executed releases.
+pub const RELEASES_COUNT: Item<u64> = Item::new(RELEASES_COUNT_KEY);
+
+/// ActivationBlock is the height the activation threshold was reached at, releases start from it.
+pub const ACTIVATION_BLOCK: Item<u64> = Item::new(ACTIVATION_BLOCK_KEY);
+
+/// BlockClaims counts the claims of the latest block claimed in, the counter starts
+/// over on the next block.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct BlockClaims {
+    pub height: u64,
+    pub count: u64,
+}
+
+pub const BLOCK_CLAIMS: Item<BlockClaims> = Item::new(BLOCK_CLAIMS_KEY);
+
+/// ControllerChannels are the connected channels of the ibc controller, keyed by channel id.
+pub const CONTROLLER_CHANNELS: Map<String, bool> = Map::new(CONTROLLER_CHANNELS_PREFIX);
+
+/// PendingRoot is a registered root awaiting its timelock, with the stage params.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRoot {
+    pub merkle_root: String,
+    pub start: Option<Expiration>,
+    pub expiration: Option<Expiration>,
+    pub total_amount: Uint128,
+    pub leaf_format: Option<LeafFormat>,
+    #[serde(default)]
+    pub hash_algo: Option<HashAlgo>,
+    /// ActivateAt is when the root can be activated as a stage.
+    pub activate_at: Timestamp,
+    /// Approvals are the root guardians who approved the root.
+    #[serde(default)]
+    pub approvals: Vec<Addr>,
+}
+
+/// PendingRoots are keyed by merkle root.
+pub const PENDING_ROOTS: Map<String, PendingRoot> = Map::new(PENDING_ROOTS_PREFIX);
+
+/// LinkedClaims are the payouts claimed by the addresses linked in a passport, keyed by
+/// (passport owner, stage).
+pub const LINKED_CLAIMS: Map<(String, u8), Uint128> = Map::new(LINKED_CLAIMS_PREFIX);
+
+/// LinkedClaimers are the addresses linked in a passport when one of its addresses
+/// claimed, keyed by (stage, address_key) to the passport owner their claims count for.
+/// Unlinking or relinking an address afterwards does not free its claims.
+pub const LINKED_CLAIMERS: Map<(u8, String), String> = Map::new(LINKED_CLAIMERS_PREFIX);
+
+/// Allocations are the gifts uploaded by the owner, claimed without a merkle proof,
+/// keyed by (stage, gift_claiming_address).
+pub const ALLOCATIONS: Map<(u8, String), Uint128> = Map::new(ALLOCATIONS_PREFIX);
+
+/// ContractWalletClaims are the approved contract wallet claims keyed by
+/// (gift_claiming_address, stage), the value is the approved target_addr.
+pub const CONTRACT_WALLET_CLAIMS: Map<(String, u8), String> =
+    Map::new(CONTRACT_WALLET_CLAIMS_PREFIX);
+
+/// PassportClaims keeps the gift_claiming_address of passport claims awaiting
+/// the verification reply, keyed by the reply id.
+pub const PASSPORT_CLAIMS: Map<u64, String> = Map::new(PASSPORT_CLAIMS_PREFIX);
+
+/// VerifiedClaims are the hex hashes of the claims pre-verified by their target, each
+/// target keeps the last claim it verified.
+pub const VERIFIED_CLAIMS: Map<String, String> = Map::new(VERIFIED_CLAIMS_PREFIX);
+
+/// VerifiedProofs are the (stage, snapshot key) entries validated by VerifyClaim, they
+/// are kept when the verified claim is evicted or claimed.
+pub const VERIFIED_PROOFS: Map<(u8, String), bool> = Map::new(VERIFIED_PROOFS_PREFIX);
+
+/// AuditEntry records an admin action, params are the JSON msg of the action.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub height: u64,
+    /// Actor is the sender of the action, `gov` for sudo actions.
+    pub actor: String,
+    pub action: String,
+    pub params: String,
+}
+
+/// AuditLog is the append-only log of admin actions keyed by the entry id.
+pub const AUDIT_LOG: Map<u64, AuditEntry> = Map::new(AUDIT_LOG_PREFIX);
+
+pub const AUDIT_LOG_COUNT: Item<u64> = Item::new(AUDIT_LOG_COUNT_KEY);
+
+/// HistorySnapshot records the campaign progress after every history_interval claims.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistorySnapshot {
+    pub id: u64,
+    pub height: u64,
+    pub claims_count: u64,
+    /// Coefficient is the coefficient applied to the next claim.
+    pub coefficient: Decimal,
+    pub remaining_balance: Uint128,
+}
+
+/// History is a ring buffer of the latest snapshots keyed by id modulo its capacity.
+pub const HISTORY: Map<u64, HistorySnapshot> = Map::new(HISTORY_PREFIX);
+
+/// HistoryCount is the number of snapshots ever recorded.
+pub const HISTORY_COUNT: Item<u64> = Item::new(HISTORY_COUNT_KEY);
+
+/// LotteryEntry is the range of tickets bought by a claim, ending before end.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotteryEntry {
+    /// Address is the recipient of the claim, paid the prizes of the tickets.
+    pub address: String,
+    pub end: u64,
+}
+
+/// LotteryEntries are the ticket ranges of the claims keyed by their claim order.
+pub const LOTTERY_ENTRIES: Map<u64, LotteryEntry> = Map::new(LOTTERY_ENTRIES_PREFIX);
+
+pub const LOTTERY_ENTRY_COUNT: Item<u64> = Item::new(LOTTERY_ENTRY_COUNT_KEY);
+
+/// LotteryTickets is the number of tickets bought by all claims.
+pub const LOTTERY_TICKETS: Item<u64> = Item::new(LOTTERY_TICKETS_KEY);
+
+/// Lottery is the committed drawing of the bonus pool, winners * prize is held back
+/// from the gift balance until the draw or the reveal deadline.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Lottery {
+    /// Commitment is the hex sha256 of the seed revealed at the draw.
+    pub commitment: String,
+    pub winners: u32,
+    pub prize: Uint128,
+    pub committed_at: u64,
+    /// DrawHeight is the height from which the lottery can be sealed, committed with
+    /// the seed.
+    #[serde(default)]
+    pub draw_height: u64,
+    /// Beacon is the hex hash of the block sealing the lottery, set once sealed.
+    pub beacon: Option<String>,
+    /// Seed is the revealed seed, set once drawn.
+    pub seed: Option<String>,
+    pub drawn_at: Option<u64>,
+    /// ExpiredAt is the height the pool was returned to the gift balance, set if the
+    /// seed was not revealed by the deadline.
+    pub expired_at: Option<u64>,
+    pub results: Vec<LotteryWinner>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotteryWinner {
+    pub ticket: u64,
+    pub address: String,
+    pub prize: Uint128,
+}
+
+pub const LOTTERY: Item<Lottery> = Item::new(LOTTERY_KEY);
+
+/// PendingReleases keeps the addresses released by ReleaseAll awaiting their reply,
+/// keyed by the reply id.
+pub const PENDING_RELEASES: Map<u64, String> = Map::new(PENDING_RELEASES_PREFIX);
+
+/// StageLeafFormat is the leaf format of a stage, if none set LeafFormat::Concat.
+pub const STAGE_LEAF_FORMAT: Map<u8, LeafFormat> = Map::new(STAGE_LEAF_FORMAT_PREFIX);
+
+/// StageHashAlgo is the hash algo of the stage, stages registered before it was recorded
+/// use the default of their leaf format.
+pub const STAGE_HASH_ALGO: Map<u8, HashAlgo> = Map::new(STAGE_HASH_ALGO_PREFIX);
+
+/// StageRelease is the split of the payouts of a stage into the liquid and the vested
+/// part, the shares sum up to one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StageRelease {
+    pub liquid_share: Decimal,
+    pub vested_share: Decimal,
+    /// ReleaseBlocks is the number of blocks the vested part is released over.
+    pub release_blocks: u64,
+}
+
+/// StageReleases override the liquid_share and release_blocks of the config per stage.
+pub const STAGE_RELEASE: Map<u8, StageRelease> = Map::new(STAGE_RELEASE_PREFIX);
+
+/// StageProofLevel is the number of nodes of the registered proof level of a stage.
+pub const STAGE_PROOF_LEVEL: Map<u8, u32> = Map::new(STAGE_PROOF_LEVEL_PREFIX);
+
+/// ProofLevelNodes are the nodes of the registered proof levels keyed by (stage, node),
+/// compact proofs end at one of them instead of the root.
+pub const PROOF_LEVEL_NODES: Map<(u8, Vec<u8>), bool> = Map::new(PROOF_LEVEL_NODES_PREFIX);
+
+/// StagePassportCutoff is the block the passports of the targets claiming a stage must
+/// be created at or before, see SetStagePassportAge.
+pub const STAGE_PASSPORT_CUTOFF: Map<u8, u64> = Map::new(STAGE_PASSPORT_CUTOFF_PREFIX);
+
+/// StageFreeze blocks the claims of a stage while a tree error is investigated.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StageFreeze {
+    pub frozen_by: Addr,
+    pub frozen_at_height: u64,
+    /// UnfreezeApprovals are the root guardians who approved to unfreeze the stage.
+    pub unfreeze_approvals: Vec<Addr>,
+}
+
+/// PendingGuardian is the pause guardian proposed by the owner, see ReplaceGuardian.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingGuardian {
+    pub guardian: Option<Addr>,
+    /// Approvals are the root guardians who approved the guardian.
+    pub approvals: Vec<Addr>,
+}
+
+pub const PENDING_GUARDIAN: Item<PendingGuardian> = Item::new(PENDING_GUARDIAN_KEY);
+
+/// StageFreezes are the frozen stages, see FreezeStage.
+pub const STAGE_FREEZES: Map<u8, StageFreeze> = Map::new(STAGE_FREEZES_PREFIX);
+
+/// StageStart is when claims of a stage are accepted from, if none set right away.
+pub const STAGE_START: Map<u8, Expiration> = Map::new(STAGE_START_PREFIX);
+
+/// StageExpiration is when claims of a stage are not accepted anymore, if none set never.
+pub const STAGE_EXPIRATION: Map<u8, Expiration> = Map::new(STAGE_EXPIRATION_PREFIX);
+
+/// StageAmount is the total gift of a stage, unclaimed part of it can be clawed back.
+pub const STAGE_AMOUNT: Map<u8, Uint128> = Map::new(STAGE_AMOUNT_PREFIX);
+
+pub const STAGE_AMOUNT_CLAIMED: Map<u8, Uint128> = Map::new(STAGE_AMOUNT_CLAIMED_PREFIX);
+
+/// StageAllocated is the sum of the allocations uploaded to a stage, it stays within the
+/// total gift of the stage.
+pub const STAGE_ALLOCATED: Map<u8, Uint128> = Map::new(STAGE_ALLOCATED_PREFIX);
+
+/// ClaimedPages is the bitmap of the claimed leaf indexes of indexed stages keyed by
+/// (stage, page), a page covers CLAIMED_PAGE_BITS indexes.
+pub const CLAIMED_PAGE_BITS: u32 = 128;
+pub const CLAIMED_PAGES: Map<(u8, u32), Uint128> = Map::new(CLAIMED_PAGES_PREFIX);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimState {
+    /// Amount is the gift paid out after the coefficient is applied.
+    pub amount: Uint128,
+    pub timestamp: Timestamp,
+    /// Height is the block of the claim, zero for claims made before it was recorded.
+    #[serde(default)]
+    pub height: u64,
+    pub target_addr: String,
+    /// Recipient is the address the gift is paid to, if none set target_addr.
+    #[serde(default)]
+    pub recipient: Option<String>,
+    /// ExtraAmounts are the extra denoms paid out after the coefficient is applied.
+    #[serde(default)]
+    pub extra_amounts: Vec<Coin>,
+    /// DelegatedTo is the validator the liquid payout was delegated to.
+    #[serde(default)]
+    pub delegated_to: Option<String>,
+    /// Memo is the message the claimer signed with the claim.
+    #[serde(default)]
+    pub memo: Option<String>,
+}
+
+/// Claims records the details of claims keyed by (gift_claiming_address, stage).
+pub const CLAIMS: Map<(String, u8), ClaimState> = Map::new(CLAIMS_PREFIX);
+
+/// ClaimsByTarget indexes claims by (target_addr, gift_claiming_address, stage).
+pub const CLAIMS_BY_TARGET: Map<(String, String, u8), bool> = Map::new(CLAIMS_BY_TARGET_PREFIX);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ReleaseState {
+    /// Positions are the vesting gifts credited to the address.
+    pub positions: Vec<VestingPosition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingPosition {
+    pub amount: Uint128,
+    pub released: Uint128,
+    /// StartBlock is the height of the claim, release starts from it.
+    pub start_block: u64,
+    /// ReleaseBlocks of the stage release of the claim, if none set the config ones.
+    #[serde(default)]
+    pub release_blocks: Option<u64>,
+    /// ExtraAmounts are the extra denoms vested along with amount, they are
+    /// released proportionally to it.
+    #[serde(default)]
+    pub extra_amounts: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ReferralStats {
+    pub referrals: u64,
+    pub bonus: Uint128,
+}
+
+/// ReferralStats are keyed by referrer address.
+pub const REFERRAL_STATS: Map<String, ReferralStats> = Map::new(REFERRAL_STATS_PREFIX);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct SourceStats {
+    pub claims: u64,
+    /// Amount is the gift paid out after the coefficient is applied.
+    pub amount: Uint128,
+}
+
+/// ClaimsBySource aggregates the claims by the source chain of the snapshot entry, or
+/// the claimer type of entries without one.
+pub const CLAIMS_BY_SOURCE: Map<String, SourceStats> = Map::new(CLAIMS_BY_SOURCE_PREFIX);
+
+/// ClaimerGrants are the relayers authorized to submit the unsigned claims of a target
+/// address, keyed by target address.
+pub const CLAIMER_GRANTS: Map<String, Addr> = Map::new(CLAIMER_GRANTS_PREFIX);
+
+/// Delegations are the payouts the contract delegated on behalf of the recipient,
+/// keyed by (recipient, validator).
+pub const DELEGATIONS: Map<(String, String), Uint128> = Map::new(DELEGATIONS_PREFIX);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Unbonding {
+    pub amount: Uint128,
+    /// ReleaseAt is when the undelegated payout can be withdrawn.
+    pub release_at: Timestamp,
+}
+
+/// Unbondings are the undelegated payouts keyed by recipient.
+pub const UNBONDINGS: Map<String, Vec<Unbonding>> = Map::new(UNBONDINGS_PREFIX);
 
-pub const CLAIM_PREFIX: &str = "claim";
-pub const CLAIM: Map<String, bool> = Map::new(CLAIM_PREFIX);
+/// ReleaseState is keyed by target address.
+pub const RELEASE_STATE: Map<String, ReleaseState> = Map::new(RELEASE_STATE_PREFIX);
@@ -0,0 +1,41 @@
+//! Off-chain merkle tree helpers for the tests built on cybergift-merkle, enabled
+//! outside of the crate by the `testing` feature.
+use cosmwasm_std::{Binary, Uint128};
+use cybergift_merkle::MerkleTree;
+use sha2::{Digest, Sha256};
+
+use crate::msg::ProofNode;
+
+/// Builds a sorted pair sha256 merkle tree, returning the hex root and a proof per entry
+pub fn merkle_tree(entries: &[(&str, Uint128)]) -> (String, Vec<Vec<ProofNode>>) {
+    let leaves: Vec<String> = entries
+        .iter()
+        .map(|(address, amount)| format!("{}{}", address, amount))
+        .collect();
+    merkle_tree_leaves(&leaves)
+}
+
+/// Builds a sorted pair sha256 merkle tree of the leaves
+pub fn merkle_tree_leaves(leaves: &[String]) -> (String, Vec<Vec<ProofNode>>) {
+    merkle_tree_hashed(leaves, |data| Sha256::digest(data).into())
+}
+
+/// Builds a sorted pair merkle tree of the leaves with the hash function
+pub fn merkle_tree_hashed(
+    leaves: &[String],
+    hash: fn(&[u8]) -> [u8; 32],
+) -> (String, Vec<Vec<ProofNode>>) {
+    let tree = MerkleTree::from_leaves(leaves, hash).unwrap();
+    let proofs = (0..tree.len())
+        .map(|index| proof_nodes(tree.proof(index).unwrap()))
+        .collect();
+    (tree.root_hex(), proofs)
+}
+
+/// Converts the proof of a tree to the claim proof nodes
+pub fn proof_nodes(proof: &[[u8; 32]]) -> Vec<ProofNode> {
+    proof
+        .iter()
+        .map(|node| ProofNode(Binary::from(node.as_ref())))
+        .collect()
+}
@@ -1,338 +1,8246 @@
-#[cfg(test)]
-mod tests {
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{attr, from_binary, from_slice, to_binary, BankMsg, Coin, CosmosMsg, SubMsg, Uint128, WasmMsg, Binary};
-    use serde::Deserialize;
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_ibc_channel_close_init, mock_ibc_channel_connect_ack,
+    mock_ibc_channel_open_try, mock_ibc_packet_recv, mock_info, MockApi, MockQuerier, MockStorage,
+    MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    attr, coin, from_binary, from_slice, to_binary, to_vec, Addr, BankMsg, Binary, Coin,
+    ContractResult, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Event, IbcChannelConnectMsg,
+    IbcChannelOpenMsg, IbcMsg, IbcOrder, IbcTimeout, OwnedDeps, Querier, QuerierResult,
+    QueryRequest, Reply, StakingMsg, StdError, Storage, SubMsg, SubMsgExecutionResponse,
+    SystemResult, Uint128, Validator, WasmMsg, WasmQuery,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cyber_passport::msg::{
+    AddressesByPassportResponse, ExecuteMsg as PassportExecuteMsg, LinkProof,
+    LinkedAddressResponse, PassportAddressesResponse, PassportResponse,
+    QueryMsg as PassportQueryMsg,
+};
+use k256::ecdsa::signature::DigestSigner;
+use k256::ecdsa::{recoverable, Signature as Secp256k1Signature, SigningKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
-    use crate::execute::*;
-    use crate::msg::{ClaimMsg, ConfigResponse, ExecuteMsg, InstantiateMsg, IsClaimedResponse, MerkleRootResponse, QueryMsg};
-    use crate::ContractError;
-    use cw20::Cw20ExecuteMsg;
+use crate::events::{
+    ACTIVATED_EVENT, CAMPAIGN_ENDED_EVENT, CLAIM_EVENT, CONFIG_UPDATED_EVENT, RELEASE_EVENT,
+    ROOT_REGISTERED_EVENT,
+};
+use crate::execute::*;
+use crate::helpers::{
+    is_verified_claim, scale_amount, verify_bitcoin, verify_cosmos, verify_eth, verify_merkle_proof,
+};
+use crate::hooks::{GiftClaimedHookMsg, HooksResponse};
+use crate::ibc::{
+    ibc_channel_close, ibc_channel_connect, ibc_channel_open, ibc_packet_receive, IbcAck,
+    IbcPacketMsg, RegisterMerkleRootAck, IBC_VERSION,
+};
+use crate::lottery;
+use crate::migrations::{ConfigV1, CLAIM_V1, CONFIG_V1, MERKLE_ROOT_V1};
+use crate::msg::{
+    ActivationResponse, AddressDerivation, AllClaimsResponse, AllocationResponse, AuditLogResponse,
+    BalanceReconciliation, ClaimDiagnosisResponse, ClaimEntry, ClaimHookMsg, ClaimMsg,
+    ClaimResponse, ClaimedAmountResponse, ClaimedPageResponse, ClaimerGrantResponse, ClaimerType,
+    ClaimsByAddressResponse, ClaimsBySourceResponse, ConfigResponse, CurrentCoefficientResponse,
+    CurrentTierResponse, DelegationResponse, DelegationsResponse, ExecuteMsg, ExportCursor,
+    ExportStateResponse, HasClaimedResponse, HistoryResponse, IndexedClaim, InstantiateMsg,
+    IsClaimedResponse, IsEligibleResponse, LatestStageResponse, LotteryResponse,
+    MerkleRootResponse, MessageEncoding, MigrateMsg, PendingRootsResponse, Proof, ProofNode,
+    QueryMsg, ReceiveMsg, ReconciliationResponse, ReferralStatsResponse, ReleaseProjectionResponse,
+    ReleaseStateResponse, SignatureType, SimulateClaimResponse, SourceClaims, StageParams,
+    StageResponse, StatsResponse, SudoMsg, VerifyProofResponse, EXPORT_STATE_VERSION,
+};
+use crate::receipt::{ExecuteMsg as ReceiptExecuteMsg, GiftReceipt, MintMsg};
+use crate::state::layout::{
+    map_key, CLAIMS_BY_TARGET_PREFIX, CLAIMS_COUNT_KEY, CLAIMS_PREFIX, CLAIM_PREFIX, CONFIG_KEY,
+    LATEST_STAGE_KEY, MERKLE_ROOT_PREFIX, RELEASE_STATE_PREFIX, STAGE_AMOUNT_CLAIMED_PREFIX,
+    STAGE_AMOUNT_PREFIX, STAGE_EXPIRATION_PREFIX,
+};
+use crate::state::{
+    AuditEntry, ClaimState, Config, HashAlgo, HistorySnapshot, LeafFormat, LinkedClaimPolicy,
+    LotteryWinner, MilestoneTier, PendingRoot, StageRelease, Unbonding, CLAIMED_PAGES, CLAIMS,
+    CONFIG, DUST_POOL, MERKLE_ROOT, PENDING_RELEASES, STAGE_LEAF_FORMAT,
+};
+use crate::testing::{merkle_tree, merkle_tree_hashed, merkle_tree_leaves};
+use crate::ContractError;
+use cw0::Expiration;
+use cw2::set_contract_version;
+use proptest::prelude::*;
 
-    const NATIVE_TOKEN: &str = "boot";
+pub(crate) const NATIVE_TOKEN: &str = "boot";
 
-    #[test]
-    fn proper_instantiation() {
-        let mut deps = mock_dependencies();
+#[test]
+fn proper_instantiation() {
+    let mut deps = mock_dependencies();
 
-        let msg = InstantiateMsg {
-            owner: Some("owner0000".to_string()),
-            allowed_native: NATIVE_TOKEN.to_string(),
-            initial_balance: Default::default(),
-            coefficient_up: Default::default(),
-            coefficient_down: Default::default(),
-            coefficient: Default::default(),
-        };
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Default::default(),
+        extra_balances: vec![],
+        coefficient_up: Default::default(),
+        coefficient_down: Default::default(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), env, QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!("owner0000", config.owner.unwrap().as_str());
+    assert_eq!(Some("boot".to_string()), config.allowed_native);
+    assert_eq!(None, config.cw20_token);
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        owner: None,
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100),
+        extra_balances: vec![],
+        coefficient_up: Default::default(),
+        coefficient_down: Default::default(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info(
+        "owner0000",
+        &[Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(100),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // update treasury
+    let env = mock_env();
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: Some("treasury0000".to_string()),
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), env, QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!("owner0000", config.owner.unwrap().as_str());
+    assert_eq!("treasury0000", config.treasury.as_str());
+
+    // Unauthorized err
+    let env = mock_env();
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+}
+
+#[test]
+fn update_campaign_parameters() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    setup_vesting_contract(deps.as_mut(), root, Decimal::one(), 0, Some(1));
+
+    let update = |up: Option<Decimal>, down: Option<Decimal>, claims: Option<u64>| {
+        ExecuteMsg::UpdateConfig {
+            new_treasury: None,
+            new_passport: None,
+            new_coefficient_up: up,
+            new_coefficient_down: down,
+            new_coefficient_claims: claims,
+            new_max_claims_per_block: None,
+            new_fee_recipient: None,
+            new_fee_bps: None,
+            new_ibc_channels: None,
+            new_ibc_timeout_seconds: None,
+            new_ibc_controller: None,
+            new_ibc_connection_id: None,
+            new_root_timelock_seconds: None,
+            new_linked_claim_policy: None,
+            new_cosmos_prefixes: None,
+        }
+    };
+    let info = mock_info("owner0000", &[]);
+
+    // coefficient_down can not exceed coefficient_up
+    let msg = update(None, Some(Decimal::percent(150)), None);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = update(Some(Decimal::zero()), Some(Decimal::zero()), None);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    let msg = update(Some(Decimal::percent(200)), None, Some(10));
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new(CONFIG_UPDATED_EVENT)
+            .add_attribute("treasury", "owner0000")
+            .add_attribute("coefficient_up", "2")
+            .add_attribute("coefficient_down", "1")
+            .add_attribute("coefficient_claims", "10")]
+    );
+
+    // first claim activates the campaign
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 2000)]);
+
+    let msg = update(None, None, Some(20));
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::Activated {});
+}
+
+#[test]
+fn cosmos_prefixes() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(COSMOS_PRIVATE_KEY);
+    let juno_claimer = cosmos_address(&key, "juno");
+    let cosmos_claimer = cosmos_address(&key, "cosmos");
+    let (root, proofs) = merkle_tree(&[
+        (juno_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(1000)),
+    ]);
+    setup_vesting_contract(deps.as_mut(), root, Decimal::one(), 0, Some(1));
+
+    let update = |prefixes: &[&str]| ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: Some(prefixes.iter().map(|p| p.to_string()).collect()),
+    };
+    let claim = |claimer: &str, proof: Vec<ProofNode>| {
+        let claim_msg = claim_msg(ClaimerType::Cosmos, claimer, "bostrom1target");
+        ExecuteMsg::Claim {
+            stage: 1,
+            signature: cosmos_sign(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof,
+        }
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), mock_env(), info.clone(), update(&["cosmos"])).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.cosmos_prefixes, vec!["cosmos".to_string()]);
+
+    // claims of addresses with other prefixes are rejected
+    let target = mock_info("bostrom1target", &[]);
+    let msg = claim(&juno_claimer, proofs[0].clone());
+    let err = execute(deps.as_mut(), mock_env(), target.clone(), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::IsNotEligible {
+            msg: "bech32 prefix juno is not accepted".to_string()
+        }
+    );
+    let msg = claim(&cosmos_claimer, proofs[1].clone());
+    execute(deps.as_mut(), mock_env(), target, msg).unwrap();
+
+    // prefixes are fixed once activated
+    let err = execute(deps.as_mut(), mock_env(), info, update(&[])).unwrap_err();
+    assert_eq!(err, ContractError::Activated {});
+}
+
+#[test]
+fn ownership_transfer() {
+    let mut deps = mock_dependencies();
+    setup_contract(
+        deps.as_mut(),
+        "5d4f48f147cb6cb742b376dce5626b2a036f69faec10cd73631c791780e150fc".to_string(),
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoOwnershipProposal {});
+
+    // only owner can propose
+    let msg = ExecuteMsg::ProposeNewOwner {
+        new_owner: "owner0001".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0001", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "propose_new_owner"),
+            attr("pending_owner", "owner0001"),
+        ]
+    );
+
+    // proposal can be revoked
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::RevokeOwnershipProposal {},
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0001", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoOwnershipProposal {});
+
+    // only proposed owner can accept
+    let msg = ExecuteMsg::ProposeNewOwner {
+        new_owner: "owner0002".to_string(),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(Some("owner0000".to_string()), config.owner);
+    assert_eq!(Some("owner0002".to_string()), config.pending_owner);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0001", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0002", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(Some("owner0002".to_string()), config.owner);
+    assert_eq!(None, config.pending_owner);
+}
+
+#[test]
+fn register_merkle_root() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Default::default(),
+        extra_balances: vec![],
+        coefficient_up: Default::default(),
+        coefficient_down: Default::default(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(100),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // register new merkle root
+    let env = mock_env();
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: "634de21cde1044f41d90373733b0f0fb1c1c71f9652b905cdf159e73c4cf0d37".to_string(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "register_merkle_root"),
+            attr("stage", "1"),
+            attr(
+                "merkle_root",
+                "634de21cde1044f41d90373733b0f0fb1c1c71f9652b905cdf159e73c4cf0d37"
+            )
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MerkleRoot { stage: 1 },
+    )
+    .unwrap();
+    let merkle_root: MerkleRootResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        "634de21cde1044f41d90373733b0f0fb1c1c71f9652b905cdf159e73c4cf0d37".to_string(),
+        merkle_root.merkle_root
+    );
+
+    // the stage query flattens the stage params
+    let res = query(deps.as_ref(), env, QueryMsg::Stage { stage: 1 }).unwrap();
+    assert_eq!(
+        from_binary::<StageResponse>(&res).unwrap(),
+        StageResponse {
+            stage: 1,
+            merkle_root: merkle_root.merkle_root,
+            start_height: None,
+            start_time: None,
+            expiration_height: None,
+            expiration_time: None,
+            total_amount: Uint128::new(1_000_000),
+            claimed_amount: Uint128::zero(),
+            leaf_format: LeafFormat::Concat,
+            hash_algo: HashAlgo::Sha256,
+            liquid_share: merkle_root.release.liquid_share,
+            vested_share: merkle_root.release.vested_share,
+            release_blocks: merkle_root.release.release_blocks,
+            proof_level_nodes: 0,
+            passport_created_before: None,
+            frozen_by: None,
+        }
+    );
+}
+
+#[test]
+fn first_stage() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    // the funded campaign is claimable once instantiated
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: Some(StageParams {
+            merkle_root: root.clone(),
+            start: None,
+            expiration: None,
+            total_amount: Uint128::new(1000),
+            leaf_format: None,
+            hash_algo: None,
+        }),
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(100000),
+        }],
+    );
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "register_merkle_root"),
+            attr("stage", "1"),
+            attr("merkle_root", root.clone()),
+        ]
+    );
+    assert_eq!(res.events[0].ty, ROOT_REGISTERED_EVENT);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let claim = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        claim,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+
+    // a root timelock also applies to the first stage
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        root_timelock_seconds: Some(100),
+        ..msg
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::LatestStage {}).unwrap();
+    assert_eq!(
+        from_binary::<LatestStageResponse>(&res)
+            .unwrap()
+            .latest_stage,
+        0
+    );
+    let res: PendingRootsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PendingRoots {}).unwrap()).unwrap();
+    assert_eq!(res.roots[0].merkle_root, root);
+}
+
+#[test]
+fn dry_run() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root.clone());
+
+    let msg = ExecuteMsg::DryRun(Box::new(ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root.clone(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    }));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // the response is returned as the error reverting the transaction
+    let owner = mock_info("owner0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap_err();
+    let attributes = vec![
+        attr("action", "register_merkle_root"),
+        attr("stage", "2"),
+        attr("merkle_root", root),
+    ];
+    assert_eq!(
+        err,
+        ContractError::DryRun {
+            attributes: String::from_utf8(to_vec(&attributes).unwrap()).unwrap(),
+            messages: 0,
+        }
+    );
+
+    // errors of the msg are returned as is
+    let msg = ExecuteMsg::DryRun(Box::new(ExecuteMsg::UploadAllocations {
+        stage: 5,
+        entries: vec![],
+    }));
+    let err = execute(deps.as_mut(), mock_env(), owner, msg).unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::NotFound { .. })));
+}
+
+#[test]
+fn verbose_errors() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[
+        (claimer.as_str(), Uint128::new(1000)),
+        ("osmo1random", Uint128::new(4000)),
+    ]);
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: Some(true),
+        first_stage: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(100000),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // a proof failure fails the claim and is diagnosed by the query
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let claim = |proof: Vec<ProofNode>| ClaimEntry {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg: claim_msg.clone(),
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof,
+    };
+    let claim_tx = |claim: ClaimEntry| ExecuteMsg::Claim {
+        stage: claim.stage,
+        claim_msg: claim.claim_msg,
+        signature: claim.signature,
+        claim_amount: claim.claim_amount,
+        extra_amounts: claim.extra_amounts,
+        leaf_index: claim.leaf_index,
+        proof: claim.proof,
+    };
+    let proof_invalid = ContractError::ProofInvalid {
+        stage: 1,
+        leaf: format!("{}1000", claimer),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        claim_tx(claim(proofs[1].clone())),
+    )
+    .unwrap_err();
+    assert_eq!(err, proof_invalid);
+    let msg = QueryMsg::DiagnoseClaim {
+        claim: claim(proofs[1].clone()),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let diagnosis: ClaimDiagnosisResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        diagnosis,
+        ClaimDiagnosisResponse {
+            failure_reason: Some(proof_invalid.to_string()),
+            proof_depth: 1,
+            recovered_address: Some(format!("{}..{}", &claimer[..6], &claimer[38..])),
+        }
+    );
+
+    // a valid claim has no failure reason
+    let msg = QueryMsg::DiagnoseClaim {
+        claim: claim(proofs[0].clone()),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let diagnosis: ClaimDiagnosisResponse = from_binary(&res).unwrap();
+    assert_eq!(None, diagnosis.failure_reason);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        claim_tx(claim(proofs[0].clone())),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+}
+
+pub(crate) const ETH_PRIVATE_KEY: &str =
+    "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+const COSMOS_PRIVATE_KEY: &str = "6c6b0e0f4f4a3b8e9b71a5e26a1dfe3b8a3e0d5a4c1f17e9a7c3b2a1f0e9d8c7";
+
+pub(crate) fn signing_key(private_key: &str) -> SigningKey {
+    SigningKey::from_bytes(&hex::decode(private_key).unwrap()).unwrap()
+}
 
-        let env = mock_env();
-        let info = mock_info("addr0000", &[]);
+pub(crate) fn eth_address(key: &SigningKey) -> String {
+    let pubkey = key.verifying_key().to_encoded_point(false);
+    let hash = Keccak256::digest(&pubkey.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
 
-        // we can just call .unwrap() to assert this was a success
-        let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+/// Signs the claim message like `personal_sign` does, returning r || s || v
+pub(crate) fn eth_sign(key: &SigningKey, claim_msg: &ClaimMsg) -> Binary {
+    let msg = claim_msg.to_canonical_json().unwrap();
+    let digest = Keccak256::new()
+        .chain(format!("\x19Ethereum Signed Message:\n{}", msg.len()))
+        .chain(&msg);
+    let signature: recoverable::Signature = key.sign_digest(digest);
+    let mut signature = signature.as_ref().to_vec();
+    signature[64] += 27;
+    Binary::from(signature)
+}
 
-        // it worked, let's query the state
-        let res = query(deps.as_ref(), env, QueryMsg::Config {}).unwrap();
-        let config: ConfigResponse = from_binary(&res).unwrap();
-        assert_eq!("owner0000", config.owner.unwrap().as_str());
-        assert_eq!("boot", config.allowed_native.as_str());
+/// Signs the claim message like `personal_sign` does, returning the claim proof
+pub(crate) fn eth_proof(key: &SigningKey, claim_msg: &ClaimMsg) -> Proof {
+    Proof::EthSig {
+        signature: eth_sign(key, claim_msg),
+        message_encoding: MessageEncoding::Utf8,
     }
+}
 
-    #[test]
-    fn update_config() {
-        let mut deps = mock_dependencies();
+fn cosmos_address(key: &SigningKey, prefix: &str) -> String {
+    let hash = Ripemd160::digest(&Sha256::digest(&key.verifying_key().to_bytes()));
+    bech32::encode(prefix, hash.to_base32(), Variant::Bech32).unwrap()
+}
 
-        let msg = InstantiateMsg {
-            owner: None,
-            allowed_native: NATIVE_TOKEN.to_string(),
-            initial_balance: Uint128::new(100),
-            coefficient_up: Default::default(),
-            coefficient_down: Default::default(),
-            coefficient: Default::default(),
-        };
+/// Builds the ADR-36 sign doc of the claim message like Keplr `signArbitrary` does
+fn cosmos_sign_doc(claim_msg: &ClaimMsg) -> String {
+    format!(
+        "{{\"account_number\":\"0\",\"chain_id\":\"\",\"fee\":{{\"amount\":[],\"gas\":\"0\"}},\"memo\":\"\",\"msgs\":[{{\"type\":\"sign/MsgSignData\",\"value\":{{\"data\":\"{}\",\"signer\":\"{}\"}}}}],\"sequence\":\"0\"}}",
+        Binary::from(claim_msg.to_canonical_json().unwrap()),
+        claim_msg.gift_claiming_address
+    )
+}
 
-        let env = mock_env();
-        let info = mock_info(
-            "owner0000",
-            &[Coin {
-                denom: NATIVE_TOKEN.to_string(),
-                amount: Uint128::new(100),
-            }],
+/// Signs the claim message like Keplr `signArbitrary` does
+fn cosmos_sign(key: &SigningKey, claim_msg: &ClaimMsg) -> Proof {
+    let signature: Secp256k1Signature =
+        key.sign_digest(Sha256::new().chain(cosmos_sign_doc(claim_msg)));
+    Proof::CosmosSig {
+        pub_key: Binary::from(key.verifying_key().to_bytes().as_slice()),
+        signature: Binary::from(signature.as_ref()),
+        pub_key_type: None,
+    }
+}
+
+/// Verifies a CosmosSig proof of the claim message
+fn verify_cosmos_proof(
+    deps: Deps,
+    env: &Env,
+    claim_msg: &ClaimMsg,
+    proof: Proof,
+) -> Result<bool, ContractError> {
+    match proof {
+        Proof::CosmosSig {
+            pub_key,
+            signature,
+            pub_key_type,
+        } => verify_cosmos(
+            deps,
+            env,
+            claim_msg,
+            pub_key,
+            pub_key_type.as_deref(),
+            signature,
+            &[],
+        ),
+        _ => unreachable!(),
+    }
+}
+
+pub(crate) fn claim_msg(claimer_type: ClaimerType, claimer: &str, target: &str) -> ClaimMsg {
+    ClaimMsg {
+        nickname: "alice".to_string(),
+        avatar_cid: "QmRX8qYgeZoYM3M5zzQaWEpVFdpin6FvVXvp6RPQK3oufV".to_string(),
+        gift_claiming_address_type: claimer_type,
+        gift_claiming_address: claimer.to_string(),
+        target_addr: target.to_string(),
+        recipient: None,
+        referrer: None,
+        payout_channel: None,
+        delegate_to: None,
+        source_chain: None,
+        memo: None,
+        relayer_reward: Decimal::zero(),
+        contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+        chain_id: mock_env().block.chain_id,
+        signature_type: SignatureType::PersonalSign,
+        address_derivation: AddressDerivation::Cosmos,
+    }
+}
+
+#[test]
+fn claim() {
+    // Run test 1
+    let mut deps = mock_dependencies();
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let eth_claimer = eth_address(&eth_key);
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "cosmos");
+    let (root, proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(2000)),
+        ("osmo1random", Uint128::new(3000)),
+    ]);
+
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(100000),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    let env = mock_env();
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let claim_msg_eth = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1eth");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&eth_key, &claim_msg_eth),
+        claim_msg: claim_msg_eth,
+        leaf_index: None,
+        proof: proofs[0].clone(),
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+    };
+
+    let env = mock_env();
+    let info = mock_info(eth_claimer.as_str(), &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let expected = SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+        to_address: "bostrom1eth".to_string(),
+        amount: vec![Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(1000),
+        }],
+    }));
+    assert_eq!(res.messages, vec![expected]);
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim"),
+            attr("stage", "1"),
+            attr("address", eth_claimer.clone()),
+            attr("amount", "1000")
+        ]
+    );
+
+    assert!(
+        from_binary::<IsClaimedResponse>(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::IsClaimed {
+                    stage: 1,
+                    address: eth_claimer.clone(),
+                    source_chain: None,
+                }
+            )
+            .unwrap()
+        )
+        .unwrap()
+        .is_claimed
+    );
+
+    // Second test
+
+    // check claimed, also with another target address
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::AlreadyClaimed {
+            stage: 1,
+            address: eth_claimer.clone(),
+            claimed_at_height: mock_env().block.height,
+            amount: Uint128::new(1000),
+        }
+    );
+    let claim_msg_eth = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1other");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&eth_key, &claim_msg_eth),
+        claim_msg: claim_msg_eth.clone(),
+        leaf_index: None,
+        proof: proofs[0].clone(),
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::AlreadyClaimed {
+            stage: 1,
+            address: eth_claimer.clone(),
+            claimed_at_height: mock_env().block.height,
+            amount: Uint128::new(1000),
+        }
+    );
+
+    // register new drop, claimer can claim again in the new stage
+    let (stage_2_root, stage_2_proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(500)),
+        ("osmo1random", Uint128::new(3000)),
+    ]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: stage_2_root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner0000", &[]), msg).unwrap();
+    assert_eq!(res.attributes[1], attr("stage", "2"));
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::LatestStage {}).unwrap();
+    assert_eq!(
+        from_binary::<LatestStageResponse>(&res).unwrap(),
+        LatestStageResponse { latest_stage: 2 }
+    );
+
+    // stage 1 proof is not valid for stage 2
+    let msg = ExecuteMsg::Claim {
+        stage: 2,
+        signature: eth_proof(&eth_key, &claim_msg_eth),
+        claim_msg: claim_msg_eth.clone(),
+        leaf_index: None,
+        proof: proofs[0].clone(),
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert!(matches!(res, ContractError::ProofInvalid { stage: 2, .. }));
+
+    let msg = ExecuteMsg::Claim {
+        stage: 2,
+        signature: eth_proof(&eth_key, &claim_msg_eth),
+        claim_msg: claim_msg_eth,
+        leaf_index: None,
+        proof: stage_2_proofs[0].clone(),
+        claim_amount: Uint128::new(500),
+        extra_amounts: vec![],
+    };
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1other", 500)]);
+
+    // signature of another claim message is rejected
+    let claim_msg_cosmos = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1cosmos");
+    let forged_claim_msg = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1thief");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: cosmos_sign(&cosmos_key, &claim_msg_cosmos),
+        claim_msg: forged_claim_msg,
+        leaf_index: None,
+        proof: proofs[1].clone(),
+        claim_amount: Uint128::new(2000),
+        extra_amounts: vec![],
+    };
+    let env = mock_env();
+    let info = mock_info(cosmos_claimer.as_str(), &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert!(matches!(res, ContractError::SignatureMismatch { .. }));
+
+    // signature of another key is rejected
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: cosmos_sign(&eth_key, &claim_msg_cosmos),
+        claim_msg: claim_msg_cosmos.clone(),
+        leaf_index: None,
+        proof: proofs[1].clone(),
+        claim_amount: Uint128::new(2000),
+        extra_amounts: vec![],
+    };
+    let env = mock_env();
+    let info = mock_info(cosmos_claimer.as_str(), &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::AddressMismatch {
+            address: cosmos_claimer.clone(),
+            msg: "signer address is not calculated addr".to_string()
+        }
+    );
+
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: cosmos_sign(&cosmos_key, &claim_msg_cosmos),
+        claim_msg: claim_msg_cosmos,
+        leaf_index: None,
+        proof: proofs[1].clone(),
+        claim_amount: Uint128::new(2000),
+        extra_amounts: vec![],
+    };
+
+    let env = mock_env();
+    let info = mock_info(cosmos_claimer.as_str(), &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let expected = SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+        to_address: "bostrom1cosmos".to_string(),
+        amount: vec![Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(2000),
+        }],
+    }));
+    assert_eq!(res.messages, vec![expected]);
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim"),
+            attr("stage", "1"),
+            attr("address", cosmos_claimer),
+            attr("amount", "2000")
+        ]
+    );
+}
+
+/// Instantiates the contract with coefficient of 1 and registers the merkle root
+fn setup_contract(deps: DepsMut, merkle_root: String) {
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(100000),
+        }],
+    );
+    let mut deps = deps;
+    let _res = instantiate(deps.branch(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps, mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+}
+
+fn setup_vesting_contract(
+    deps: DepsMut,
+    merkle_root: String,
+    liquid_share: Decimal,
+    release_blocks: u64,
+    activation_threshold: Option<u64>,
+) {
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: Some(liquid_share),
+        release_blocks: Some(release_blocks),
+        activation_threshold,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(100000),
+        }],
+    );
+    let mut deps = deps;
+    let _res = instantiate(deps.branch(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps, mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+}
+
+fn bank_send(to_address: &str, amount: u128) -> SubMsg {
+    SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+        to_address: to_address.to_string(),
+        amount: vec![Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(amount),
+        }],
+    }))
+}
+
+#[test]
+fn proof_nodes() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[
+        (claimer.as_str(), Uint128::new(1000)),
+        ("osmo1random", Uint128::new(3000)),
+    ]);
+    setup_contract(deps.as_mut(), root);
+
+    // hex encoded nodes of earlier clients are decoded like base64 ones
+    let node = &proofs[0][0];
+    let hex_node: ProofNode =
+        from_slice(&to_vec(&hex::encode(node.0.as_slice())).unwrap()).unwrap();
+    let base64_node: ProofNode = from_slice(&to_vec(node).unwrap()).unwrap();
+    assert_eq!(&hex_node, node);
+    assert_eq!(&base64_node, node);
+
+    // nodes are validated before the proof is folded
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: vec![node.clone(), ProofNode(Binary::from(vec![1, 2, 3]))],
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidProofNode { index: 1 }
+    );
+}
+
+#[test]
+fn leaf_formats() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1))]);
+    setup_contract(deps.as_mut(), root);
+
+    // cw20-merkle-airdrop and keccak256 trees are registered as stages 2 and 3
+    let leaves = |leaf: String| vec![leaf, "osmo1random:1".to_string()];
+    let (cw20_root, cw20_proofs) = merkle_tree_leaves(&leaves(format!("{}:1000", claimer)));
+    let (keccak_root, keccak_proofs) =
+        merkle_tree_hashed(&leaves(format!("{}1000", claimer)), |data| {
+            Keccak256::digest(data).into()
+        });
+    let trees = [
+        (2, cw20_root, LeafFormat::Cw20MerkleAirdrop, cw20_proofs),
+        (3, keccak_root, LeafFormat::Keccak256, keccak_proofs),
+    ];
+    for (stage, root, leaf_format, proofs) in trees {
+        let msg = ExecuteMsg::RegisterMerkleRoot {
+            merkle_root: root,
+            start: None,
+            expiration: None,
+            total_amount: Uint128::new(1_000_000),
+            leaf_format: Some(leaf_format.clone()),
+            hash_algo: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::MerkleRoot { stage }).unwrap();
+        assert_eq!(
+            from_binary::<MerkleRootResponse>(&res).unwrap().leaf_format,
+            leaf_format
         );
-        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
 
-        // update owner
-        let env = mock_env();
-        let info = mock_info("owner0000", &[]);
-        let msg = ExecuteMsg::UpdateConfig {
-            new_owner: Some("owner0001".to_string()),
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+        let msg = ExecuteMsg::Claim {
+            stage,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
         };
+        let info = mock_info("bostrom1target", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+    }
+}
 
-        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+#[test]
+fn hash_algos() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1))]);
+    setup_contract(deps.as_mut(), root);
+    let register = |root: String, leaf_format: LeafFormat, hash_algo: Option<HashAlgo>| {
+        ExecuteMsg::RegisterMerkleRoot {
+            merkle_root: root,
+            start: None,
+            expiration: None,
+            total_amount: Uint128::new(1_000_000),
+            leaf_format: Some(leaf_format),
+            hash_algo,
+        }
+    };
+    let owner = mock_info("owner0000", &[]);
 
-        // it worked, let's query the state
-        let res = query(deps.as_ref(), env, QueryMsg::Config {}).unwrap();
-        let config: ConfigResponse = from_binary(&res).unwrap();
-        assert_eq!("owner0001", config.owner.unwrap().as_str());
+    // keccak256 leaves are only hashed with keccak256
+    let msg = register(
+        "00".repeat(32),
+        LeafFormat::Keccak256,
+        Some(HashAlgo::Sha256),
+    );
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
 
-        // Unauthorized err
-        let env = mock_env();
-        let info = mock_info("owner0000", &[]);
-        let msg = ExecuteMsg::UpdateConfig { new_owner: None };
+    // abi packed keccak256 tree of Ethereum tooling is registered as stage 2
+    let abi_leaf = |address: &str, amount: u128| {
+        let address = hex::decode(address.trim_start_matches("0x")).unwrap();
+        let hash: [u8; 32] =
+            Keccak256::digest(&[address.as_slice(), &[0; 16], &amount.to_be_bytes()].concat())
+                .into();
+        hash
+    };
+    let mut leaves = [
+        abi_leaf(&claimer, 1000),
+        abi_leaf("0x1de6f7d3a1bd0fb3ab8b4cf5d59a8d7da4a27c1b", 2000),
+    ];
+    let proof = vec![ProofNode(Binary::from(leaves[1].as_ref()))];
+    leaves.sort_unstable();
+    let root = hex::encode(Keccak256::digest(&leaves.concat()));
+    let msg = register(root, LeafFormat::AbiPacked, None);
+    execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::MerkleRoot { stage: 2 }).unwrap();
+    let res: MerkleRootResponse = from_binary(&res).unwrap();
+    assert_eq!(res.hash_algo, HashAlgo::Keccak256);
+    let verify = |address: &str, amount: u128| {
+        verify_merkle_proof(
+            deps.as_ref(),
+            2,
+            address,
+            Uint128::new(amount),
+            &[],
+            None,
+            proof.clone(),
+        )
+        .is_ok()
+    };
+    assert!(verify(&claimer, 1000));
+    assert!(!verify(&claimer, 2000));
+    assert!(!verify("osmo1random", 1000));
 
-        let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(res, ContractError::Unauthorized {});
-    }
+    // string leaves can be hashed with keccak256 too
+    let (root, proofs) = merkle_tree_hashed(
+        &[format!("{}:1000", claimer), "osmo1random:1".to_string()],
+        |data| Keccak256::digest(data).into(),
+    );
+    let msg = register(
+        root,
+        LeafFormat::Cw20MerkleAirdrop,
+        Some(HashAlgo::Keccak256),
+    );
+    execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 3,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("bostrom1target", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+}
 
-    #[test]
-    fn register_merkle_root() {
-        let mut deps = mock_dependencies();
+#[test]
+fn verify_proof() {
+    let mut deps = mock_dependencies();
+    let (root, proofs) = merkle_tree(&[
+        ("osmo1random", Uint128::new(1000)),
+        ("osmo1other", Uint128::new(2000)),
+    ]);
+    setup_contract(deps.as_mut(), root);
 
-        let msg = InstantiateMsg {
-            owner: Some("owner0000".to_string()),
-            allowed_native: NATIVE_TOKEN.to_string(),
-            initial_balance: Default::default(),
-            coefficient_up: Default::default(),
-            coefficient_down: Default::default(),
-            coefficient: Default::default(),
+    let verify = |leaf: &str, proof: Vec<ProofNode>| -> bool {
+        let msg = QueryMsg::VerifyProof {
+            stage: 1,
+            leaf: leaf.to_string(),
+            proof,
         };
+        let res: VerifyProofResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        res.valid
+    };
+    assert!(verify("osmo1random1000", proofs[0].clone()));
+    assert!(verify("osmo1other2000", proofs[1].clone()));
+    assert!(!verify("osmo1random2000", proofs[0].clone()));
+    assert!(!verify(
+        "osmo1random1000",
+        vec![ProofNode(Binary::from(vec![1]))]
+    ));
 
-        let env = mock_env();
-        let info = mock_info(
-            "addr0000",
-            &[Coin {
-                denom: NATIVE_TOKEN.to_string(),
-                amount: Uint128::new(100),
-            }],
-        );
-        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    // stage must be registered
+    let msg = QueryMsg::VerifyProof {
+        stage: 2,
+        leaf: "osmo1random1000".to_string(),
+        proof: proofs[0].clone(),
+    };
+    assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+}
 
-        // register new merkle root
-        let env = mock_env();
-        let info = mock_info("owner0000", &[]);
+#[test]
+fn merkle_snapshot_round_trip() {
+    let mut deps = mock_dependencies();
+    let entries = cybergift_merkle::parse_csv(
+        "address,amount\nbostrom1alice,1000\nbostrom1bob,2000boot,500hydrogen\nbostrom1carol,3000\n",
+    )
+    .unwrap();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1))]);
+    setup_contract(deps.as_mut(), root);
+
+    let formats = [
+        LeafFormat::Concat,
+        LeafFormat::Cw20MerkleAirdrop,
+        LeafFormat::Keccak256,
+        LeafFormat::Indexed,
+    ];
+    for (stage, leaf_format) in (2..).zip(formats) {
+        // leaf formats of the library mirror the contract ones
+        let format: cybergift_merkle::LeafFormat =
+            from_slice(&to_vec(&leaf_format).unwrap()).unwrap();
+        let tree = cybergift_merkle::MerkleTree::new(format, &entries).unwrap();
         let msg = ExecuteMsg::RegisterMerkleRoot {
-            merkle_root: "634de21cde1044f41d90373733b0f0fb1c1c71f9652b905cdf159e73c4cf0d37"
-                .to_string(),
+            merkle_root: tree.root_hex(),
+            start: None,
+            expiration: None,
+            total_amount: Uint128::new(1_000_000),
+            leaf_format: Some(leaf_format),
+            hash_algo: None,
         };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
 
-        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-        assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "register_merkle_root"),
-                attr(
-                    "merkle_root",
-                    "634de21cde1044f41d90373733b0f0fb1c1c71f9652b905cdf159e73c4cf0d37"
-                )
-            ]
-        );
+        for (index, entry) in entries.iter().enumerate() {
+            // hex proofs are accepted as claim proof nodes
+            let proof: Vec<ProofNode> =
+                from_slice(&to_vec(&tree.proof_hex(index).unwrap()).unwrap()).unwrap();
+            let res = verify_merkle_proof(
+                deps.as_ref(),
+                stage,
+                &entry.address,
+                entry.amount,
+                &entry.extra_amounts,
+                Some(index as u32),
+                proof,
+            );
+            assert!(res.is_ok());
+        }
+    }
+}
 
-        let res = query(deps.as_ref(), env, QueryMsg::MerkleRoot {}).unwrap();
-        let merkle_root: MerkleRootResponse = from_binary(&res).unwrap();
-        assert_eq!(
-            "634de21cde1044f41d90373733b0f0fb1c1c71f9652b905cdf159e73c4cf0d37".to_string(),
-            merkle_root.merkle_root
-        );
+#[test]
+fn compact_proofs() {
+    let mut deps = mock_dependencies();
+    let snapshot: String = (0..9)
+        .map(|index| format!("bostrom1addr{},1000\n", index))
+        .collect();
+    let entries = cybergift_merkle::parse_csv(&snapshot).unwrap();
+    let tree =
+        cybergift_merkle::MerkleTree::new(cybergift_merkle::LeafFormat::Concat, &entries).unwrap();
+    setup_contract(deps.as_mut(), tree.root_hex());
+
+    let nodes = |nodes: &[[u8; 32]]| -> Vec<ProofNode> {
+        nodes
+            .iter()
+            .map(|node| ProofNode(Binary::from(node.as_ref())))
+            .collect()
+    };
+    let level = nodes(tree.level(2).unwrap());
+    let msg = ExecuteMsg::RegisterProofLevel {
+        stage: 1,
+        nodes: level.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    // the level must fold into the root
+    let wrong_level = ExecuteMsg::RegisterProofLevel {
+        stage: 1,
+        nodes: level[1..].to_vec(),
+    };
+    let owner = mock_info("owner0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), wrong_level).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    execute(deps.as_mut(), mock_env(), owner.clone(), msg.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), owner, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::MerkleRoot { stage: 1 }).unwrap();
+    let stage: MerkleRootResponse = from_binary(&res).unwrap();
+    assert_eq!(level.len() as u32, stage.proof_level_nodes);
+
+    let verify = |leaf: String, proof: Vec<ProofNode>| -> bool {
+        let msg = QueryMsg::VerifyProof {
+            stage: 1,
+            leaf,
+            proof,
+        };
+        let res: VerifyProofResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        res.valid
+    };
+    for (index, entry) in entries.iter().enumerate() {
+        let leaf = format!("{}{}", entry.address, entry.amount);
+        let proof = nodes(tree.compact_proof(index, 2).unwrap());
+        assert!(proof.len() < tree.proof(index).unwrap().len());
+        assert!(verify(leaf.clone(), proof.clone()));
+        // full proofs are still accepted
+        assert!(verify(leaf, nodes(tree.proof(index).unwrap())));
+        assert!(!verify(format!("{}2000", entry.address), proof));
     }
+}
+
+/// Formats the leaf of the entry like verify_merkle_proof does for the leaf format
+fn format_leaf(leaf_format: &LeafFormat, index: usize, address: &str, amount: u128) -> String {
+    match leaf_format {
+        LeafFormat::Cw20MerkleAirdrop => format!("{}:{}", address, amount),
+        LeafFormat::Indexed => format!("{}:{}{}", index, address, amount),
+        _ => format!("{}{}", address, amount),
+    }
+}
+
+/// Registers the root of a random tree of the entries as stage 1 and returns the proofs
+fn random_tree(
+    storage: &mut dyn Storage,
+    leaf_format: &LeafFormat,
+    entries: &[(String, u128)],
+) -> Vec<Vec<ProofNode>> {
+    let leaves: Vec<String> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, (address, amount))| format_leaf(leaf_format, index, address, *amount))
+        .collect();
+    let (root, proofs) = match leaf_format {
+        LeafFormat::Keccak256 => merkle_tree_hashed(&leaves, |data| Keccak256::digest(data).into()),
+        _ => merkle_tree_leaves(&leaves),
+    };
+    MERKLE_ROOT.save(storage, 1, &root).unwrap();
+    STAGE_LEAF_FORMAT.save(storage, 1, leaf_format).unwrap();
+    proofs
+}
+
+fn leaf_format_strategy() -> impl Strategy<Value = LeafFormat> {
+    prop_oneof![
+        Just(LeafFormat::Concat),
+        Just(LeafFormat::Cw20MerkleAirdrop),
+        Just(LeafFormat::Keccak256),
+        Just(LeafFormat::Indexed),
+    ]
+}
 
-    const ETH_TEST: &[u8] = include_bytes!("../testdata/airdrop_stage_1_test_data_ethereum_address.json");
-    const COSMOS_TEST: &[u8] = include_bytes!("../testdata/airdrop_stage_1_test_data_cosmos_address.json");
-
-    #[derive(Deserialize, Debug)]
-    struct Encoded {
-        address: String,
-        claim_msg: Binary,
-        signature: Binary,
-        root: String,
-        proofs: Vec<String>,
-        amount: Uint128
+fn entries_strategy(min_len: usize) -> impl Strategy<Value = Vec<(String, u128)>> {
+    prop::collection::vec(("[a-z0-9]{8,20}", 1u128..1_000_000), min_len..40)
+}
+
+proptest! {
+    #[test]
+    fn merkle_proofs_verify(leaf_format in leaf_format_strategy(), entries in entries_strategy(1)) {
+        let mut deps = mock_dependencies();
+        let proofs = random_tree(deps.as_mut().storage, &leaf_format, &entries);
+        for (index, (address, amount)) in entries.iter().enumerate() {
+            let res = verify_merkle_proof(
+                deps.as_ref(),
+                1,
+                address,
+                Uint128::new(*amount),
+                &[],
+                Some(index as u32),
+                proofs[index].clone(),
+            );
+            prop_assert!(res.is_ok());
+        }
     }
 
     #[test]
-    fn claim() {
-        // Run test 1
+    fn mutated_merkle_proofs_are_rejected(
+        leaf_format in leaf_format_strategy(),
+        entries in entries_strategy(2),
+        entry in any::<prop::sample::Index>(),
+        node in any::<prop::sample::Index>(),
+        bit in 0usize..256,
+    ) {
         let mut deps = mock_dependencies();
-        let eth_test_data: Encoded = from_slice(ETH_TEST).unwrap();
-
-        let msg = InstantiateMsg {
-            owner: Some("owner0000".to_string()),
-            allowed_native: NATIVE_TOKEN.to_string(),
-            initial_balance: Uint128::new(100000),
-            coefficient_up: Default::default(),
-            coefficient_down: Default::default(),
-            coefficient: Default::default(),
+        let proofs = random_tree(deps.as_mut().storage, &leaf_format, &entries);
+        let index = entry.index(entries.len());
+        let (address, amount) = &entries[index];
+        let verify = |address: &str, amount: u128, leaf_index: u32, proof: Vec<ProofNode>| {
+            verify_merkle_proof(
+                deps.as_ref(),
+                1,
+                address,
+                Uint128::new(amount),
+                &[],
+                Some(leaf_index),
+                proof,
+            )
+            .is_ok()
         };
 
-        let env = mock_env();
-        let info = mock_info(
-            "addr0000",
-            &[Coin {
-                denom: NATIVE_TOKEN.to_string(),
-                amount: Uint128::new(100000),
+        // flipped bit of a proof node
+        let mut proof = proofs[index].clone();
+        let node = node.index(proof.len());
+        let mut bytes = proof[node].0.to_vec();
+        bytes[bit / 8] ^= 1 << (bit % 8);
+        proof[node] = ProofNode(Binary::from(bytes));
+        prop_assert!(!verify(address, *amount, index as u32, proof));
+
+        // truncated proof
+        let mut proof = proofs[index].clone();
+        proof.pop();
+        prop_assert!(!verify(address, *amount, index as u32, proof));
+
+        // other amount
+        prop_assert!(!verify(address, amount + 1, index as u32, proofs[index].clone()));
+
+        // proof of another entry
+        let other = (index + 1) % entries.len();
+        if entries[other] != entries[index] {
+            prop_assert!(!verify(address, *amount, index as u32, proofs[other].clone()));
+        }
+    }
+}
+
+#[test]
+fn indexed_claims() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[
+        (claimer.as_str(), Uint128::new(1000)),
+        ("osmo1random", Uint128::new(1)),
+    ]);
+    setup_contract(deps.as_mut(), root);
+
+    // stage 2 leaves carry their index
+    let (indexed_root, indexed_proofs) =
+        merkle_tree_leaves(&[format!("0:{}1000", claimer), "1:osmo1random1".to_string()]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: indexed_root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: Some(LeafFormat::Indexed),
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let claim = |stage: u8, leaf_index: Option<u32>, proof: Vec<ProofNode>| {
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+        ExecuteMsg::Claim {
+            stage,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index,
+            proof,
+        }
+    };
+    let info = mock_info("bostrom1target", &[]);
+    let msg = claim(2, None, indexed_proofs[0].clone());
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = claim(2, Some(1), indexed_proofs[0].clone());
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::ProofInvalid { stage: 2, .. }));
+
+    let msg = claim(2, Some(0), indexed_proofs[0].clone());
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AlreadyClaimed {
+            stage: 2,
+            address: claimer.clone(),
+            claimed_at_height: mock_env().block.height,
+            amount: Uint128::new(1000),
+        }
+    );
+
+    let page = |deps: Deps, stage: u8| -> ClaimedPageResponse {
+        let msg = QueryMsg::ClaimedPage { stage, page: 0 };
+        from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+    };
+    assert_eq!(
+        page(deps.as_ref(), 2),
+        ClaimedPageResponse {
+            stage: 2,
+            page: 0,
+            bitmap: Uint128::new(1),
+            claimed_count: 1,
+        }
+    );
+    let msg = QueryMsg::IsClaimed {
+        stage: 2,
+        address: claimer.clone(),
+        source_chain: None,
+    };
+    let res: IsClaimedResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert!(res.is_claimed);
+
+    // claims of stage 2 missing from the bitmap, e.g. migrated ones, are indexed
+    CLAIMED_PAGES.remove(deps.as_mut().storage, (2, 0));
+    let index = |stage: u8, address: &str, leaf_index: u32, proof: &Vec<ProofNode>| {
+        ExecuteMsg::IndexClaims {
+            stage,
+            claims: vec![IndexedClaim {
+                address: address.to_string(),
+                leaf_index,
+                amount: Uint128::new(1000),
+                extra_amounts: vec![],
+                proof: proof.clone(),
             }],
+        }
+    };
+    let msg = index(2, &claimer, 0, &indexed_proofs[0]);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let owner = mock_info("owner0000", &[]);
+    // leaves of stage 1 carry no index
+    let msg = claim(1, None, proofs[0].clone());
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = index(1, &claimer, 0, &proofs[0]);
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    // only claimed leaves are indexed
+    let msg = index(2, "osmo1random", 1, &indexed_proofs[1]);
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    // the index is verified against the leaf
+    let msg = index(2, &claimer, 1, &indexed_proofs[0]);
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::ProofInvalid { stage: 2, .. }));
+    let msg = index(2, &claimer, 0, &indexed_proofs[0]);
+    let _res = execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+    assert_eq!(page(deps.as_ref(), 2).claimed_count, 1);
+
+    // claim is still recognized by its details
+    let msg = claim(2, Some(0), indexed_proofs[0].clone());
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AlreadyClaimed {
+            stage: 2,
+            address: claimer,
+            claimed_at_height: mock_env().block.height,
+            amount: Uint128::new(1000),
+        }
+    );
+}
+
+#[test]
+fn coefficient_schedule() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    let mut msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::percent(200),
+        coefficient_claims: Some(2),
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+
+    // floor must not exceed the ceiling
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::InvalidInput {});
+
+    msg.coefficient_up = Decimal::percent(200);
+    msg.coefficient_down = Decimal::percent(50);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // same tree is claimed in several stages, coefficient decreases with each claim
+    let expected = [(200, 2000), (125, 1250), (50, 500), (50, 500)];
+    for (stage, (percent, payout)) in (1..).zip(expected) {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CurrentCoefficient {}).unwrap();
+        assert_eq!(
+            from_binary::<CurrentCoefficientResponse>(&res).unwrap(),
+            CurrentCoefficientResponse {
+                coefficient: Decimal::percent(percent),
+                claims_count: stage as u64 - 1,
+            }
         );
-        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
 
-        let env = mock_env();
-        let info = mock_info("owner0000", &[]);
         let msg = ExecuteMsg::RegisterMerkleRoot {
-            merkle_root: eth_test_data.root,
+            merkle_root: root.clone(),
+            start: None,
+            expiration: None,
+            total_amount: Uint128::new(1_000_000),
+            leaf_format: None,
+            hash_algo: None,
         };
-        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
-
-        let claim_msg = from_binary(&eth_test_data.claim_msg).unwrap();
-        let signature = from_binary(&eth_test_data.signature).unwrap();
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
         let msg = ExecuteMsg::Claim {
+            stage,
+            signature: eth_proof(&key, &claim_msg),
             claim_msg,
-            signature,
-            proof: eth_test_data.proofs,
-            claim_amount: eth_test_data.amount
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
         };
+        let info = mock_info("bostrom1target", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages, vec![bank_send("bostrom1target", payout)]);
+    }
 
-        let env = mock_env();
-        let info = mock_info(eth_test_data.address.as_str(), &[]);
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-        let expected = SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.clone().into_string(),
-            amount: vec![],
-        }));
-        assert_eq!(res.messages, vec![expected]);
+    // balance is decremented by the paid gifts
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.current_balance, Uint128::new(100000 - 4250));
+}
 
-        assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "claim"),
-                attr("address", "random"),
-                attr("amount", eth_test_data.amount)
-            ]
-        );
+#[test]
+fn claim_stage_window() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root.clone());
 
-        assert!(
-            from_binary::<IsClaimedResponse>(
-                &query(
-                    deps.as_ref(),
-                    env.clone(),
-                    QueryMsg::IsClaimed {
-                        address: eth_test_data.address
-                    }
-                )
-                .unwrap()
-            )
-            .unwrap()
-            .is_claimed
-        );
+    let env = mock_env();
+    let start = Expiration::AtHeight(env.block.height + 10);
+    let expiration = Expiration::AtHeight(env.block.height + 20);
 
-        // Second test
+    // expiration can not be in the past
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root.clone(),
+        start: None,
+        expiration: Some(Expiration::AtHeight(env.block.height)),
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner0000", &[]), msg).unwrap_err();
+    assert!(matches!(res, ContractError::StageExpired { .. }));
 
-        let cosmos_test_data: Encoded = from_slice(COSMOS_TEST).unwrap();
-        // check claimed
-        let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(res, ContractError::Claimed {});
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root.clone(),
+        start: Some(start),
+        expiration: Some(expiration),
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner0000", &[]), msg).unwrap();
 
-        // register new drop
-        let env = mock_env();
-        let info = mock_info("owner0000", &[]);
-        let msg = ExecuteMsg::RegisterMerkleRoot {
-            merkle_root: cosmos_test_data.root,
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MerkleRoot { stage: 2 },
+    )
+    .unwrap();
+    assert_eq!(
+        from_binary::<MerkleRootResponse>(&res).unwrap(),
+        MerkleRootResponse {
+            stage: 2,
+            merkle_root: root,
+            start: Some(start),
+            expiration: Some(expiration),
+            total_amount: Uint128::new(1_000_000),
+            claimed_amount: Uint128::zero(),
+            leaf_format: LeafFormat::Concat,
+            hash_algo: HashAlgo::Sha256,
+            release: StageRelease {
+                liquid_share: Decimal::one(),
+                vested_share: Decimal::zero(),
+                release_blocks: 0,
+            },
+            proof_level_nodes: 0,
+            passport_created_before: None,
+            frozen_by: None,
+        }
+    );
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 2,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("relayer", &[]),
+        msg.clone(),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::StageNotBegun { stage: 2, start }
+    );
+
+    let mut expired_env = env.clone();
+    expired_env.block.height += 20;
+    let res = execute(
+        deps.as_mut(),
+        expired_env,
+        mock_info("relayer", &[]),
+        msg.clone(),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::StageExpired {
+            stage: 2,
+            expiration
+        }
+    );
+
+    let mut active_env = env;
+    active_env.block.height += 10;
+    let _res = execute(deps.as_mut(), active_env, mock_info("relayer", &[]), msg).unwrap();
+}
+
+#[test]
+fn is_eligible() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+
+    let other_signature = eth_proof(
+        &key,
+        &claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1other"),
+    );
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let signature = eth_proof(&key, &claim_msg);
+    let query_msg = |claim_amount: u128, signature: &Proof| QueryMsg::IsEligible {
+        stage: 1,
+        claim_msg: claim_msg.clone(),
+        signature: signature.clone(),
+        claim_amount: Uint128::new(claim_amount),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+
+    let res = query(deps.as_ref(), mock_env(), query_msg(1000, &signature)).unwrap();
+    let eligible: IsEligibleResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        eligible,
+        IsEligibleResponse {
+            valid_proof: true,
+            valid_signature: true,
+            is_claimed: false,
+            payout: Uint128::new(1000),
+            extra_payout: vec![],
+        }
+    );
+
+    // wrong amount and signature of another message
+    let res = query(deps.as_ref(), mock_env(), query_msg(2000, &other_signature)).unwrap();
+    let eligible: IsEligibleResponse = from_binary(&res).unwrap();
+    assert!(!eligible.valid_proof);
+    assert!(!eligible.valid_signature);
+
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg: claim_msg.clone(),
+        signature: signature.clone(),
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    let res = query(deps.as_ref(), mock_env(), query_msg(1000, &signature)).unwrap();
+    let eligible: IsEligibleResponse = from_binary(&res).unwrap();
+    assert!(eligible.is_claimed);
+}
+
+#[test]
+fn all_claims() {
+    let mut deps = mock_dependencies();
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let eth_claimer = eth_address(&eth_key);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "cosmos");
+    let (root, proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(2000)),
+    ]);
+    setup_contract(deps.as_mut(), root);
+
+    let claim_msg_eth = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1target");
+    let claim_msg_cosmos = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1other");
+    let msg = ExecuteMsg::ClaimBatch {
+        claims: vec![
+            ClaimEntry {
+                stage: 1,
+                signature: eth_proof(&eth_key, &claim_msg_eth),
+                claim_msg: claim_msg_eth,
+                claim_amount: Uint128::new(1000),
+                extra_amounts: vec![],
+                leaf_index: None,
+                proof: proofs[0].clone(),
+            },
+            ClaimEntry {
+                stage: 1,
+                signature: cosmos_sign(&cosmos_key, &claim_msg_cosmos),
+                claim_msg: claim_msg_cosmos,
+                claim_amount: Uint128::new(2000),
+                extra_amounts: vec![],
+                leaf_index: None,
+                proof: proofs[1].clone(),
+            },
+        ],
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), msg).unwrap();
+
+    let eth_claim = ClaimResponse {
+        address: eth_claimer.clone(),
+        stage: 1,
+        amount: Uint128::new(1000),
+        timestamp: mock_env().block.time,
+        height: mock_env().block.height,
+        target_addr: "bostrom1target".to_string(),
+        recipient: None,
+        extra_amounts: vec![],
+        delegated_to: None,
+        memo: None,
+    };
+    let cosmos_claim = ClaimResponse {
+        address: cosmos_claimer,
+        stage: 1,
+        amount: Uint128::new(2000),
+        timestamp: mock_env().block.time,
+        height: mock_env().block.height,
+        target_addr: "bostrom1other".to_string(),
+        recipient: None,
+        extra_amounts: vec![],
+        delegated_to: None,
+        memo: None,
+    };
+
+    let msg = QueryMsg::AllClaims {
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let claims: AllClaimsResponse = from_binary(&res).unwrap();
+    assert_eq!(claims.claims, vec![eth_claim.clone(), cosmos_claim.clone()]);
+
+    // paginate
+    let msg = QueryMsg::AllClaims {
+        start_after: None,
+        limit: Some(1),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let claims: AllClaimsResponse = from_binary(&res).unwrap();
+    assert_eq!(claims.claims, vec![eth_claim.clone()]);
+    let msg = QueryMsg::AllClaims {
+        start_after: Some((eth_claimer, 1)),
+        limit: Some(1),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let claims: AllClaimsResponse = from_binary(&res).unwrap();
+    assert_eq!(claims.claims, vec![cosmos_claim]);
+
+    // claims credited to a target address
+    let msg = QueryMsg::ClaimsByAddress {
+        address: "bostrom1target".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let claims: ClaimsByAddressResponse = from_binary(&res).unwrap();
+    assert_eq!(claims.claims, vec![eth_claim]);
+    let msg = QueryMsg::ClaimsByAddress {
+        address: "bostrom1random".to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let claims: ClaimsByAddressResponse = from_binary(&res).unwrap();
+    assert!(claims.claims.is_empty());
+
+    // attestation for other contracts
+    let has_claimed = |address: &str| -> bool {
+        let msg = QueryMsg::HasClaimed {
+            address: address.to_string(),
         };
-        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let res: HasClaimedResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        res.has_claimed
+    };
+    assert!(has_claimed("bostrom1target"));
+    assert!(!has_claimed("bostrom1random"));
+    let msg = QueryMsg::ClaimedAmount {
+        address: "bostrom1other".to_string(),
+    };
+    let res: ClaimedAmountResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::new(2000));
+    let msg = QueryMsg::ClaimedAmount {
+        address: "bostrom1random".to_string(),
+    };
+    let res: ClaimedAmountResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::zero());
 
-        let claim_msg = from_binary(&cosmos_test_data.claim_msg).unwrap();
-        let signature = from_binary(&cosmos_test_data.signature).unwrap();
-        let msg = ExecuteMsg::Claim {
-            claim_msg,
-            signature,
-            proof: cosmos_test_data.proofs,
-            claim_amount: cosmos_test_data.amount
+    // entries without a source chain are aggregated by claimer type
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::ClaimsBySource {}).unwrap();
+    let sources: ClaimsBySourceResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        sources.sources,
+        vec![
+            SourceClaims {
+                source: "cosmos".to_string(),
+                claims: 1,
+                amount: Uint128::new(2000),
+            },
+            SourceClaims {
+                source: "ethereum".to_string(),
+                claims: 1,
+                amount: Uint128::new(1000),
+            },
+        ]
+    );
+}
+
+#[test]
+fn simulate_claim() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_vesting_contract(deps.as_mut(), root, Decimal::percent(10), 100, None);
+
+    let simulate = |amount: u128| -> SimulateClaimResponse {
+        let msg = QueryMsg::SimulateClaim {
+            amount: Uint128::new(amount),
+            stage: None,
         };
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+    assert_eq!(
+        simulate(1000),
+        SimulateClaimResponse {
+            allowed_native: Some(NATIVE_TOKEN.to_string()),
+            cw20_token: None,
+            coefficient: Decimal::one(),
+            payout: Uint128::new(1000),
+            liquid_amount: Uint128::new(100),
+            vesting_amount: Uint128::new(900),
+            fee: Uint128::zero(),
+            enough_balance: true,
+        }
+    );
+    assert!(!simulate(100001).enough_balance);
+}
 
-        let env = mock_env();
-        let info = mock_info(cosmos_test_data.address.as_str(), &[]);
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        let expected: SubMsg<_> = SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "token0000".to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: cosmos_test_data.address.clone(),
-                amount: cosmos_test_data.amount,
-            })
-            .unwrap(),
-        }));
-        assert_eq!(res.messages, vec![expected]);
+#[test]
+fn release() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
 
-        assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "claim"),
-                attr("stage", "2"),
-                attr("address", cosmos_test_data.address),
-                attr("amount", cosmos_test_data.amount)
-            ]
-        );
-    }
+    setup_vesting_contract(deps.as_mut(), root, Decimal::percent(10), 100, None);
 
-    #[test]
-    fn owner_freeze() {
-        let mut deps = mock_dependencies();
+    // only liquid share is paid at claim
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 100)]);
+    assert!(res.attributes.contains(&attr("vesting_amount", "900")));
+
+    let info = mock_info("bostrom1target", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::Release {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NothingToRelease {});
 
-        let msg = InstantiateMsg {
-            owner: Some("owner0000".to_string()),
-            allowed_native: NATIVE_TOKEN.to_string(),
-            initial_balance: Default::default(),
-            coefficient_up: Default::default(),
-            coefficient_down: Default::default(),
-            coefficient: Default::default(),
+    // half of the vesting gift is released
+    let mut env = mock_env();
+    env.block.height += 50;
+    let msg = QueryMsg::ReleaseState {
+        address: "bostrom1target".to_string(),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+    let state: ReleaseStateResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::new(450), state.releasable);
+    assert_eq!(1, state.positions.len());
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::Release {},
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 450)]);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "release"),
+            attr("address", "bostrom1target"),
+            attr("amount", "450"),
+        ]
+    );
+
+    // paused contract does not release
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    env.block.height += 100;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::Release {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+
+    // rest is released after the release period
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Release {}).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 450)]);
+    let res = query(deps.as_ref(), env, msg).unwrap();
+    let state: ReleaseStateResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::zero(), state.releasable);
+    assert!(state.positions.is_empty());
+}
+
+#[test]
+fn stage_release() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_vesting_contract(deps.as_mut(), root.clone(), Decimal::one(), 0, None);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // shares must sum up to one and a vested share needs release blocks
+    let release = StageRelease {
+        liquid_share: Decimal::percent(10),
+        vested_share: Decimal::percent(90),
+        release_blocks: 100,
+    };
+    for invalid in [
+        StageRelease {
+            vested_share: Decimal::percent(80),
+            ..release.clone()
+        },
+        StageRelease {
+            release_blocks: 0,
+            ..release.clone()
+        },
+        StageRelease {
+            liquid_share: Decimal::one(),
+            vested_share: Decimal::zero(),
+            release_blocks: 100,
+        },
+    ] {
+        let msg = ExecuteMsg::SetStageRelease {
+            stage: 2,
+            release: invalid,
         };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidInput {});
+    }
+    let msg = ExecuteMsg::SetStageRelease {
+        stage: 3,
+        release: release.clone(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = ExecuteMsg::SetStageRelease {
+        stage: 2,
+        release: release.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    assert!(res.attributes.contains(&attr("liquid_share", "0.1")));
 
-        let env = mock_env();
-        let info = mock_info("addr0000", &[]);
-        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::MerkleRoot { stage: 2 }).unwrap();
+    let stage: MerkleRootResponse = from_binary(&res).unwrap();
+    assert_eq!(stage.release, release);
 
-        // can register merkle root
-        let env = mock_env();
-        let info = mock_info("owner0000", &[]);
-        let msg = ExecuteMsg::RegisterMerkleRoot {
-            merkle_root: "5d4f48f147cb6cb742b376dce5626b2a036f69faec10cd73631c791780e150fc"
-                .to_string(),
+    // simulations split the payout by the release of the stage
+    for (stage, liquid_amount) in [(None, 1000), (Some(1), 1000), (Some(2), 100)] {
+        let msg = QueryMsg::SimulateClaim {
+            amount: Uint128::new(1000),
+            stage,
         };
-        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let simulation: SimulateClaimResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(simulation.liquid_amount, Uint128::new(liquid_amount));
+        assert_eq!(
+            simulation.vesting_amount,
+            Uint128::new(1000 - liquid_amount)
+        );
+    }
 
-        // can update owner
-        let env = mock_env();
-        let info = mock_info("owner0000", &[]);
-        let msg = ExecuteMsg::UpdateConfig {
-            new_owner: Some("owner0001".to_string()),
+    // stage 1 keeps the config split and pays out at once
+    for stage in [1, 2] {
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+        let msg = ExecuteMsg::Claim {
+            stage,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
         };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bostrom1target", &[]),
+            msg,
+        )
+        .unwrap();
+        let liquid = if stage == 1 { 1000 } else { 100 };
+        assert_eq!(res.messages, vec![bank_send("bostrom1target", liquid)]);
+    }
 
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+    // vested part of stage 2 is released over its own blocks
+    let mut env = mock_env();
+    env.block.height += 50;
+    let msg = QueryMsg::ReleaseState {
+        address: "bostrom1target".to_string(),
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap();
+    let state: ReleaseStateResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::new(450), state.releasable);
 
-        // freeze contract
-        let env = mock_env();
-        let info = mock_info("owner0001", &[]);
-        let msg = ExecuteMsg::UpdateConfig { new_owner: None };
+    // claimed stages keep their split
+    let msg = ExecuteMsg::SetStageRelease { stage: 2, release };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+}
 
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+#[test]
+fn export_state() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
 
-        // cannot register new drop
-        let env = mock_env();
-        let info = mock_info("owner0001", &[]);
-        let msg = ExecuteMsg::RegisterMerkleRoot {
-            merkle_root: "ebaa83c7eaf7467c378d2f37b5e46752d904d2d17acd380b24b02e3b398b3e5a"
-                .to_string(),
-        };
-        let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(res, ContractError::Unauthorized {});
+    setup_vesting_contract(deps.as_mut(), root, Decimal::percent(10), 100, None);
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
 
-        // cannot update config
-        let env = mock_env();
-        let info = mock_info("owner0001", &[]);
-        let msg = ExecuteMsg::RegisterMerkleRoot {
-            merkle_root: "ebaa83c7eaf7467c378d2f37b5e46752d904d2d17acd380b24b02e3b398b3e5a"
-                .to_string(),
+    let export = |deps: Deps, start_after, limit| {
+        let msg = QueryMsg::ExportState { start_after, limit };
+        from_binary::<ExportStateResponse>(&query(deps, mock_env(), msg).unwrap()).unwrap()
+    };
+    let res = export(deps.as_ref(), None, None);
+    assert_eq!(res.version, EXPORT_STATE_VERSION);
+    assert_eq!(res.contract, "crates.io:cw-cyber-airdrop");
+    assert_eq!(res.claims.len(), 1);
+    assert_eq!(res.claims[0].address, claimer);
+    assert_eq!(res.release_states.len(), 1);
+    assert_eq!(res.release_states[0].address, "bostrom1target");
+    assert_eq!(res.release_states[0].positions[0].amount, Uint128::new(900));
+    assert_eq!(res.next, None);
+
+    // claims are paged before the release states
+    let page = export(deps.as_ref(), None, Some(1));
+    assert_eq!(page.claims, res.claims);
+    assert!(page.release_states.is_empty());
+    assert_eq!(
+        page.next,
+        Some(ExportCursor::Claim {
+            address: claimer,
+            stage: 1,
+        })
+    );
+    let page = export(deps.as_ref(), page.next, Some(1));
+    assert!(page.claims.is_empty());
+    assert_eq!(page.release_states, res.release_states);
+    assert_eq!(
+        page.next,
+        Some(ExportCursor::Release {
+            address: "bostrom1target".to_string(),
+        })
+    );
+    let page = export(deps.as_ref(), page.next, Some(1));
+    assert!(page.claims.is_empty() && page.release_states.is_empty());
+    assert_eq!(page.next, None);
+}
+
+#[test]
+fn release_projection() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_vesting_contract(deps.as_mut(), root, Decimal::percent(10), 100, None);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer0000", &[]),
+        msg,
+    )
+    .unwrap();
+
+    let projection = |at_height: u64| {
+        let msg = QueryMsg::ReleaseProjection {
+            address: "bostrom1target".to_string(),
+            at_height,
         };
-        let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(res, ContractError::Unauthorized {});
-    }
+        query(deps.as_ref(), mock_env(), msg)
+            .and_then(|res| from_binary::<ReleaseProjectionResponse>(&res))
+    };
+    let height = mock_env().block.height;
+    assert_eq!(
+        projection(height + 50).unwrap().releasable,
+        Uint128::new(450)
+    );
+    assert_eq!(
+        projection(height + 200).unwrap().releasable,
+        Uint128::new(900)
+    );
+    assert!(projection(height - 1).is_err());
+}
+
+#[test]
+fn release_all() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_vesting_contract(deps.as_mut(), root, Decimal::percent(10), 100, None);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+
+    // anyone dispatches a release for each address
+    let mut env = mock_env();
+    env.block.height += 50;
+    let msg = ExecuteMsg::ReleaseAll {
+        addresses: vec!["bostrom1target".to_string(), "bostrom1empty".to_string()],
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg).unwrap();
+    assert_eq!(2, res.messages.len());
+    assert_eq!(
+        res.messages[0],
+        SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::ReleaseFor {
+                    address: "bostrom1target".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            RELEASE_REPLY_ID_OFFSET,
+        )
+    );
+
+    let msg = ExecuteMsg::ReleaseAll { addresses: vec![] };
+    let err = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    // only the contract itself releases for an address
+    let msg = ExecuteMsg::ReleaseFor {
+        address: "bostrom1target".to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 450)]);
+
+    // results are reported per address and failures are tolerated
+    let reply_msg = Reply {
+        id: RELEASE_REPLY_ID_OFFSET,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("address", "bostrom1target"),
+            attr("action", "release_succeeded"),
+        ]
+    );
+    let reply_msg = Reply {
+        id: RELEASE_REPLY_ID_OFFSET + 1,
+        result: ContractResult::Err("Nothing to release".to_string()),
+    };
+    let res = reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("address", "bostrom1empty"),
+            attr("action", "release_failed"),
+            attr("error", "Nothing to release"),
+        ]
+    );
+    assert!(PENDING_RELEASES
+        .may_load(&deps.storage, RELEASE_REPLY_ID_OFFSET + 1)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn fund_reconciliation() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_vesting_contract(deps.as_mut(), root, Decimal::percent(10), 100, None);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![coin(99900, NATIVE_TOKEN)]);
+
+    // vesting positions are accounted along with the gift balance
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Reconciliation {}).unwrap();
+    let reconciliation: ReconciliationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        reconciliation.gift,
+        BalanceReconciliation {
+            denom: NATIVE_TOKEN.to_string(),
+            accounted: Uint128::new(99900),
+            balance: Uint128::new(99900),
+            surplus: Uint128::zero(),
+            deficit: Uint128::zero(),
+        }
+    );
+    assert!(!reconciliation.drifted);
+
+    // only the owner funds known denoms
+    let msg = ExecuteMsg::Fund {};
+    let info = mock_info("bostrom1random", &[coin(5000, NATIVE_TOKEN)]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let info = mock_info("owner0000", &[coin(5000, "hydrogen")]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    let info = mock_info("owner0000", &[coin(5000, NATIVE_TOKEN)]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "fund"),
+            attr("sender", "owner0000"),
+            attr("amount", format!("5000{}", NATIVE_TOKEN)),
+            attr("current_balance", "104000"),
+        ]
+    );
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(Uint128::new(105000), config.initial_balance);
+    assert_eq!(Uint128::new(104000), config.current_balance);
+
+    // funds sent directly are flagged as drift
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![coin(104910, NATIVE_TOKEN)]);
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Reconciliation {}).unwrap();
+    let reconciliation: ReconciliationResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::new(10), reconciliation.gift.surplus);
+    assert!(reconciliation.drifted);
+}
+
+#[test]
+fn recalibrate_coefficient() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: Some(10),
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(50000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // the gift was topped up by a bank send
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![coin(150000, NATIVE_TOKEN)]);
+    let recalibrate = ExecuteMsg::RecalibrateCoefficient {};
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random0000", &[]),
+        recalibrate.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        recalibrate.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "recalibrate_coefficient"),
+            attr("coefficient_up", "3"),
+            attr("coefficient_down", "3"),
+            attr("current_balance", "150000"),
+            attr("remaining_allocations", "50000"),
+        ]
+    );
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::new(150000), config.current_balance);
+    let msg = QueryMsg::AuditLog {
+        start_after: Some(0),
+        limit: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let log: AuditLogResponse = from_binary(&res).unwrap();
+    assert_eq!(log.entries[0].action, "recalibrate_coefficient");
+    assert_eq!(
+        log.entries[0].params,
+        r#"{"coefficient_up":"3","coefficient_down":"3","current_balance":"150000","remaining_allocations":"50000"}"#
+    );
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 3000)]);
+
+    // releases are activated with the coefficient
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        recalibrate,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+}
+
+#[test]
+fn stats() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    setup_vesting_contract(deps.as_mut(), root, Decimal::percent(10), 100, None);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("bostrom1target", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let mut env = mock_env();
+    env.block.height += 50;
+    let _res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Release {}).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Stats {}).unwrap();
+    assert_eq!(
+        from_binary::<StatsResponse>(&res).unwrap(),
+        StatsResponse {
+            claims_count: 1,
+            total_claimed: Uint128::new(1000),
+            remaining_balance: Uint128::new(99000),
+            releases_count: 1,
+            coefficient: Decimal::one(),
+            activated: true,
+            activation_block: Some(mock_env().block.height),
+            campaign_end_height: None,
+            campaign_end_time: None,
+            remaining_blocks: None,
+            remaining_seconds: None,
+            dust_pool: Decimal::zero(),
+        }
+    );
+}
+
+#[test]
+fn campaign_end() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    let env = mock_env();
+    let mut msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: Some("treasury".to_string()),
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: Some(Expiration::AtHeight(env.block.height)),
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("owner0000", &[coin(100000, NATIVE_TOKEN)]);
+    // campaign must not end at instantiation
+    let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    msg.campaign_end = Some(Expiration::AtHeight(env.block.height + 100));
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Stats {}).unwrap();
+    let stats: StatsResponse = from_binary(&res).unwrap();
+    assert_eq!(Some(100), stats.remaining_blocks);
+    assert_eq!(None, stats.remaining_seconds);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        ExecuteMsg::EndCampaign {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::CampaignNotEnded {});
+
+    // claims after the end fail
+    let mut env = mock_env();
+    env.block.height += 100;
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("bostrom1target", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CampaignEnded {
+            campaign_end: Expiration::AtHeight(env.block.height),
+        }
+    );
+    assert_eq!(err.code(), Some(1007));
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::CampaignEnded { .. }));
+
+    // stages without an expiration are clawed back, the first clawback emits the
+    // campaign ended event
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Clawback { stage: 1 },
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("treasury", 1000)]);
+    assert_eq!(
+        res.events,
+        vec![Event::new(CAMPAIGN_ENDED_EVENT)
+            .add_attribute(
+                "campaign_end",
+                format!("expiration height: {}", env.block.height)
+            )
+            .add_attribute("height", env.block.height.to_string())]
+    );
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        ExecuteMsg::EndCampaign {},
+    )
+    .unwrap();
+    assert!(res.events.is_empty());
+    let res = query(deps.as_ref(), env, QueryMsg::Stats {}).unwrap();
+    let stats: StatsResponse = from_binary(&res).unwrap();
+    assert_eq!(Some(0), stats.remaining_blocks);
+}
+
+#[test]
+fn lottery() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: Some("treasury".to_string()),
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: Some(Expiration::AtHeight(env.block.height + 100)),
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: Some(Uint128::new(100)),
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("owner0000", &[coin(100000, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let owner = mock_info("owner0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+    // the pool is held back from the gift balance at the commitment
+    let seed = "campaign seed";
+    let draw_height = env.block.height + 200;
+    let commit = |commitment: String| ExecuteMsg::CommitLottery {
+        commitment,
+        winners: 2,
+        prize: Uint128::new(100),
+        draw_height,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        commit("seed".to_string()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = commit(lottery::seed_commitment(seed));
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), env.clone(), owner.clone(), msg.clone()).unwrap();
+    let err = execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::new(99800), config.current_balance);
+
+    // the claim buys a ticket per 100 of its payout
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("bostrom1target", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Lottery {}).unwrap();
+    let lottery: LotteryResponse = from_binary(&res).unwrap();
+    assert_eq!(10, lottery.total_tickets);
+    assert_eq!(Some(lottery::seed_commitment(seed)), lottery.commitment);
+
+    // the lottery is sealed by anyone after the campaign end and the draw height
+    let seal = ExecuteMsg::SealLottery {};
+    let mut env = mock_env();
+    env.block.height += 100;
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        seal.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = ExecuteMsg::DrawLottery {
+        seed: seed.to_string(),
+    };
+    let err = execute(deps.as_mut(), env.clone(), owner.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    env.block.height = draw_height;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        seal.clone(),
+    )
+    .unwrap();
+    let err = execute(deps.as_mut(), env.clone(), mock_info("random", &[]), seal).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let beacon = lottery::beacon(&env.block);
+
+    // the seed is revealed after the seal
+    let wrong_seed = ExecuteMsg::DrawLottery {
+        seed: "other seed".to_string(),
+    };
+    let err = execute(deps.as_mut(), env.clone(), owner.clone(), wrong_seed).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let res = execute(deps.as_mut(), env.clone(), owner.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            bank_send("bostrom1target", 100),
+            bank_send("bostrom1target", 100)
+        ]
+    );
+    let err = execute(deps.as_mut(), env.clone(), owner, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Lottery {}).unwrap();
+    let lottery: LotteryResponse = from_binary(&res).unwrap();
+    assert_eq!(Some(beacon.clone()), lottery.beacon);
+    assert_eq!(Some(seed.to_string()), lottery.seed);
+    assert_eq!(Some(env.block.height), lottery.drawn_at);
+    assert_eq!(
+        lottery.results,
+        (0..2)
+            .map(|index| LotteryWinner {
+                ticket: lottery::winning_ticket(seed, &beacon, index, 10),
+                address: "bostrom1target".to_string(),
+                prize: Uint128::new(100),
+            })
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn lottery_expiry() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("bostrom1target", Uint128::new(1000))]);
+
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: Some("treasury".to_string()),
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: Some(Expiration::AtHeight(env.block.height + 100)),
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: Some(Uint128::new(100)),
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("owner0000", &[coin(100000, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let owner = mock_info("owner0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+    let seed = "campaign seed";
+    let draw_height = env.block.height + 200;
+    let commit = |commitment: String| ExecuteMsg::CommitLottery {
+        commitment,
+        winners: 2,
+        prize: Uint128::new(100),
+        draw_height,
+    };
+    let msg = commit(lottery::seed_commitment(seed));
+    execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+    let mut env = mock_env();
+    env.block.height = draw_height;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        ExecuteMsg::SealLottery {},
+    )
+    .unwrap();
+
+    // a seed withheld past the reveal deadline returns the pool to the gift balance
+    let expire = ExecuteMsg::ExpireLottery {};
+    env.block.height = draw_height + LOTTERY_REVEAL_BLOCKS;
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        expire.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    env.block.height += 1;
+    let msg = ExecuteMsg::DrawLottery {
+        seed: seed.to_string(),
+    };
+    let err = execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        expire.clone(),
+    )
+    .unwrap();
+    let err = execute(deps.as_mut(), env.clone(), mock_info("random", &[]), expire).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::new(100000), config.current_balance);
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Lottery {}).unwrap();
+    let lottery: LotteryResponse = from_binary(&res).unwrap();
+    assert_eq!(Some(env.block.height), lottery.expired_at);
+    assert!(lottery.results.is_empty());
+}
+
+#[test]
+fn dust_pool() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1001))]);
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::percent(50),
+        coefficient_down: Decimal::percent(50),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let dust_pool = |deps: Deps| {
+        let res = query(deps, mock_env(), QueryMsg::Stats {}).unwrap();
+        from_binary::<StatsResponse>(&res).unwrap().dust_pool
+    };
+
+    // payout is rounded down and the remainder is pooled
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1001),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 500)]);
+    assert_eq!(Decimal::percent(50), dust_pool(deps.as_ref()));
+
+    // nothing is swept before the pool holds a whole ugift
+    let info = mock_info("owner0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::SweepDust {}).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    // amounts rounded down to zero pay the 1 ugift minimum without dust
+    assert_eq!(
+        (Uint128::new(1), Decimal::zero()),
+        scale_amount(Uint128::new(1), Decimal::percent(50)).unwrap()
+    );
+    assert_eq!(
+        (Uint128::zero(), Decimal::zero()),
+        scale_amount(Uint128::zero(), Decimal::percent(50)).unwrap()
+    );
+    assert_eq!(
+        (Uint128::new(1), Decimal::percent(50)),
+        scale_amount(Uint128::new(3), Decimal::percent(50)).unwrap()
+    );
+    assert_eq!(
+        (Uint128::new(u128::MAX / 2), Decimal::percent(50)),
+        scale_amount(Uint128::new(u128::MAX), Decimal::percent(50)).unwrap()
+    );
+
+    // whole part of the pool is swept by the owner
+    DUST_POOL
+        .save(deps.as_mut().storage, &Decimal::percent(250))
+        .unwrap();
+    let info = mock_info("random0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::SweepDust {}).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let info = mock_info("owner0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::SweepDust {}).unwrap();
+    assert_eq!(res.messages, vec![bank_send("owner0000", 2)]);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "sweep_dust"),
+            attr("amount", "2"),
+            attr("dust", "0.5"),
+        ]
+    );
+    assert_eq!(Decimal::percent(50), dust_pool(deps.as_ref()));
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(Uint128::new(100000 - 500 - 2), config.current_balance);
+}
+
+#[test]
+fn milestone_tiers() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    let mut tiers = vec![
+        MilestoneTier {
+            until_claims: 2,
+            multiplier: Decimal::percent(115),
+        },
+        MilestoneTier {
+            until_claims: 1,
+            multiplier: Decimal::percent(130),
+        },
+    ];
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: Some(tiers.clone()),
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+
+    // tiers must be sorted by their claims count
+    let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    tiers.reverse();
+    let msg = InstantiateMsg {
+        milestone_tiers: Some(tiers),
+        ..msg
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.milestone_until_claims, vec![1, 2]);
+    assert_eq!(
+        config.milestone_multipliers,
+        vec![Decimal::percent(130), Decimal::percent(115)]
+    );
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let current_tier = |deps: Deps| {
+        let res = query(deps, mock_env(), QueryMsg::CurrentTier {}).unwrap();
+        from_binary::<CurrentTierResponse>(&res).unwrap()
+    };
+    assert_eq!(
+        current_tier(deps.as_ref()),
+        CurrentTierResponse {
+            tier: Some(0),
+            multiplier: Decimal::percent(130),
+            until_claims: Some(1),
+            claims_count: 0,
+        }
+    );
+
+    // the first claimer gets the first tier bonus
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1300)]);
+    assert_eq!(
+        current_tier(deps.as_ref()),
+        CurrentTierResponse {
+            tier: Some(1),
+            multiplier: Decimal::percent(115),
+            until_claims: Some(2),
+            claims_count: 1,
+        }
+    );
+    let msg = QueryMsg::SimulateClaim {
+        amount: Uint128::new(1000),
+        stage: None,
+    };
+    let simulation: SimulateClaimResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(simulation.payout, Uint128::new(1150));
+
+    // the base coefficient applies once all tiers are passed
+    deps.storage
+        .set(CLAIMS_COUNT_KEY.as_bytes(), &to_vec(&2u64).unwrap());
+    assert_eq!(
+        current_tier(deps.as_ref()),
+        CurrentTierResponse {
+            tier: None,
+            multiplier: Decimal::one(),
+            until_claims: None,
+            claims_count: 2,
+        }
+    );
+}
+
+#[test]
+fn history() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: Some(1),
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // each claim of the interval records the progress after it
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    let history = |deps: Deps, start_after, limit| {
+        let msg = QueryMsg::History { start_after, limit };
+        from_binary::<HistoryResponse>(&query(deps, mock_env(), msg).unwrap())
+            .unwrap()
+            .snapshots
+    };
+    let stats: StatsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap()).unwrap();
+    assert_eq!(
+        history(deps.as_ref(), None, None),
+        vec![HistorySnapshot {
+            id: 0,
+            height: mock_env().block.height,
+            claims_count: 1,
+            coefficient: stats.coefficient,
+            remaining_balance: stats.remaining_balance,
+        }]
+    );
+
+    // the oldest snapshots are overwritten once the buffer is full
+    let config = CONFIG.load(&deps.storage).unwrap();
+    for claims_count in 2..=HISTORY_CAPACITY + 1 {
+        record_history(deps.as_mut().storage, &mock_env(), &config, claims_count).unwrap();
+    }
+    let snapshots = history(deps.as_ref(), None, Some(2));
+    assert_eq!(
+        snapshots.iter().map(|s| s.id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(snapshots[0].claims_count, 2);
+    let snapshots = history(deps.as_ref(), Some(HISTORY_CAPACITY - 1), None);
+    assert_eq!(
+        snapshots.iter().map(|s| s.id).collect::<Vec<_>>(),
+        vec![HISTORY_CAPACITY]
+    );
+}
+
+#[test]
+fn claim_target_validation() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: Some("bostrom".to_string()),
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let claim = |target: &str| {
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, target);
+        ExecuteMsg::Claim {
+            stage: 1,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
+        }
+    };
+    let info = mock_info(claimer.as_str(), &[]);
+
+    // invalid address
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), claim("b1")).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTargetAddress { address, .. } if address == "b1"));
+
+    // address of another chain
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        claim("cosmos1target"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidTargetAddress {
+            address: "cosmos1target".to_string(),
+            msg: "address prefix must be bostrom".to_string(),
+        }
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), info, claim("bostrom1target")).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+}
+
+#[test]
+fn claim_recipient() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_vesting_contract(deps.as_mut(), root, Decimal::percent(10), 100, None);
+
+    let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    claim_msg.recipient = Some("bostrom1cold".to_string());
+    let signature = eth_proof(&key, &claim_msg);
+
+    // recipient is covered by the signature
+    let mut front_run = claim_msg.clone();
+    front_run.recipient = Some("bostrom1thief".to_string());
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: signature.clone(),
+        claim_msg: front_run,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("bostrom1thief", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AddressMismatch {
+            address: claimer.clone(),
+            msg: "signer address is not calculated addr".to_string()
+        }
+    );
+
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature,
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1cold", 100)]);
+    assert!(res.attributes.contains(&attr("recipient", "bostrom1cold")));
+
+    // vesting position is credited to the recipient
+    let mut env = mock_env();
+    env.block.height += 100;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bostrom1target", &[]),
+        ExecuteMsg::Release {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NothingToRelease {});
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("bostrom1cold", &[]),
+        ExecuteMsg::Release {},
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1cold", 900)]);
+
+    let msg = QueryMsg::ClaimsByAddress {
+        address: "bostrom1target".to_string(),
+    };
+    let res: ClaimsByAddressResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.claims[0].recipient, Some("bostrom1cold".to_string()));
+}
+
+#[test]
+fn claim_memo() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_contract(deps.as_mut(), root);
+
+    // memos are not empty, without control characters and at most 256 bytes
+    let info = mock_info("bostrom1target", &[]);
+    for memo in [
+        String::new(),
+        "why\ncyber".to_string(),
+        "x".repeat(MAX_MEMO_BYTES + 1),
+    ] {
+        let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+        claim_msg.memo = Some(memo);
+        let msg = ExecuteMsg::Claim {
+            stage: 1,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidInput {});
+    }
+
+    // memo is covered by the signature
+    let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    claim_msg.memo = Some("superintelligence".to_string());
+    let signature = eth_proof(&key, &claim_msg);
+    let mut forged = claim_msg.clone();
+    forged.memo = Some("spam".to_string());
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: signature.clone(),
+        claim_msg: forged,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::AddressMismatch { .. }));
+
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature,
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("memo", "superintelligence")));
+
+    let msg = QueryMsg::ClaimsByAddress {
+        address: "bostrom1target".to_string(),
+    };
+    let res: ClaimsByAddressResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.claims[0].memo, Some("superintelligence".to_string()));
+}
+
+#[test]
+fn referral() {
+    let mut deps = mock_dependencies();
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let eth_claimer = eth_address(&eth_key);
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "cosmos");
+    let (root, proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(2000)),
+    ]);
+
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: Some(Decimal::percent(10)),
+        referral_balance: Some(Uint128::new(150)),
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    // referral pool is funded along with the gift
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+    let err = instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let info = mock_info("addr0000", &[coin(100150, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // referrer without a claim of its own is not rewarded
+    let mut claim_msg_eth = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1eth");
+    claim_msg_eth.referrer = Some("bostrom1cosmos".to_string());
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&eth_key, &claim_msg_eth),
+        claim_msg: claim_msg_eth,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1eth", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1eth", 1000)]);
+
+    // bonus is capped by the referral pool
+    let mut claim_msg_cosmos = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1cosmos");
+    claim_msg_cosmos.referrer = Some("bostrom1eth".to_string());
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: cosmos_sign(&cosmos_key, &claim_msg_cosmos),
+        claim_msg: claim_msg_cosmos,
+        claim_amount: Uint128::new(2000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[1].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1cosmos", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            bank_send("bostrom1cosmos", 2000),
+            bank_send("bostrom1eth", 150)
+        ]
+    );
+    assert!(res.attributes.contains(&attr("referral_bonus", "150")));
+
+    let msg = QueryMsg::ReferralStats {
+        address: "bostrom1eth".to_string(),
+    };
+    let stats: ReferralStatsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        stats,
+        ReferralStatsResponse {
+            address: "bostrom1eth".to_string(),
+            referrals: 1,
+            bonus: Uint128::new(150),
+        }
+    );
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.referral_balance, Uint128::zero());
+    assert_eq!(config.current_balance, Uint128::new(97000));
+}
+
+#[test]
+fn events() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_vesting_contract(deps.as_mut(), root.clone(), Decimal::percent(10), 100, None);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("bostrom1target", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let event = res.events.iter().find(|e| e.ty == CLAIM_EVENT).unwrap();
+    for (key, value) in [
+        ("stage", "1"),
+        ("claimer", claimer.as_str()),
+        ("target", "bostrom1target"),
+        ("original_amount", "1000"),
+        ("coefficient", "1"),
+        ("payout", "1000"),
+    ] {
+        assert!(event.attributes.contains(&attr(key, value)), "{}", key);
+    }
+
+    let mut env = mock_env();
+    env.block.height += 50;
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::Release {}).unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new(RELEASE_EVENT)
+            .add_attribute("target", "bostrom1target")
+            .add_attribute("payout", "450")]
+    );
+
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root.clone(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new(ROOT_REGISTERED_EVENT)
+            .add_attribute("stage", "2")
+            .add_attribute("merkle_root", root)
+            .add_attribute("total_amount", "1000")]
+    );
+}
+
+#[test]
+fn error_codes() {
+    let err = ContractError::ProofInvalid {
+        stage: 1,
+        leaf: "00".to_string(),
+    };
+    assert_eq!(err.code(), Some(1003));
+    assert_eq!(
+        err.to_string(),
+        "E1003: Invalid proof of leaf 00 in stage 1"
+    );
+    let err = ContractError::NotActivated {
+        claims_count: 1,
+        activation_threshold: 2,
+    };
+    assert_eq!(err.code(), Some(1006));
+    assert!(err.to_string().starts_with("E1006: "));
+    assert_eq!(ContractError::Unauthorized {}.code(), None);
+}
+
+#[test]
+fn activation() {
+    let mut deps = mock_dependencies();
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let eth_claimer = eth_address(&eth_key);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "cosmos");
+    let (root, proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(2000)),
+    ]);
+    setup_vesting_contract(deps.as_mut(), root, Decimal::zero(), 10, Some(2));
+
+    let claim_msg_eth = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&eth_key, &claim_msg_eth),
+        claim_msg: claim_msg_eth,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert!(res.events.iter().all(|e| e.ty != ACTIVATED_EVENT));
+
+    // releases are locked until activation
+    let mut env = mock_env();
+    env.block.height += 20;
+    let info = mock_info("bostrom1target", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::Release {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::NotActivated {
+            claims_count: 1,
+            activation_threshold: 2
+        }
+    );
+
+    let claim_msg_cosmos = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1other");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: cosmos_sign(&cosmos_key, &claim_msg_cosmos),
+        claim_msg: claim_msg_cosmos,
+        claim_amount: Uint128::new(2000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[1].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bostrom1other", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.events[0].ty, CLAIM_EVENT);
+    assert_eq!(
+        res.events[1],
+        Event::new(ACTIVATED_EVENT).add_attributes(vec![
+            attr("claims_count", "2"),
+            attr("activation_block", env.block.height.to_string()),
+        ])
+    );
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Activation {}).unwrap();
+    let activation: ActivationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        activation,
+        ActivationResponse {
+            claims_count: 2,
+            activation_threshold: 2,
+            activation_block: Some(env.block.height),
+        }
+    );
+
+    // release schedule starts from activation
+    env.block.height += 5;
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::Release {}).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 500)]);
+}
+
+#[test]
+fn pause() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+
+    // without root guardians the owner replaces the guardian right away
+    let msg = ExecuteMsg::ReplaceGuardian {
+        guardian: Some("guardian0000".to_string()),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random0000", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0000", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "pause"), attr("sender", "guardian0000")]
+    );
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert!(config.paused);
+    assert_eq!(Some("guardian0000".to_string()), config.guardian);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+}
+
+#[test]
+fn replace_guardian() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+    let msg = SudoMsg::UpdateRootGuardians {
+        guardians: vec!["guardian1".to_string(), "guardian2".to_string()],
+        threshold: 2,
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    let guardian = |deps: Deps| -> Option<String> {
+        let config: ConfigResponse =
+            from_binary(&query(deps, mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        config.guardian
+    };
+    let replace = |guardian: Option<&str>| ExecuteMsg::ReplaceGuardian {
+        guardian: guardian.map(str::to_string),
+    };
+
+    // root guardians only approve what the owner proposed
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian1", &[]),
+        replace(Some("pauser0000")),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random0000", &[]),
+        replace(Some("pauser0000")),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        replace(Some("pauser0000")),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("action", "propose_guardian")));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian1", &[]),
+        replace(Some("other0000")),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian1", &[]),
+        replace(Some("pauser0000")),
+    )
+    .unwrap();
+    assert_eq!(guardian(deps.as_ref()), None);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian2", &[]),
+        replace(Some("pauser0000")),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("action", "replace_guardian")));
+    assert_eq!(guardian(deps.as_ref()), Some("pauser0000".to_string()));
+
+    // none removes the guardian with the same quorum
+    for sender in ["owner0000", "guardian1", "guardian2"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            replace(None),
+        )
+        .unwrap();
+    }
+    assert_eq!(guardian(deps.as_ref()), None);
+}
+
+#[test]
+fn freeze_stage() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: Some("guardian0000".to_string()),
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: Some(vec!["guardian0001".to_string(), "guardian0002".to_string()]),
+        root_guardian_threshold: Some(2),
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: NATIVE_TOKEN.to_string(),
+            amount: Uint128::new(100000),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root.clone(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    for guardian in ["guardian0001", "guardian0002"] {
+        let msg = ExecuteMsg::ApproveRoot {
+            root_hash: root.clone(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info(guardian, &[]), msg).unwrap();
+    }
+    let msg = ExecuteMsg::ActivateRoot { merkle_root: root };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // any single guardian can freeze a stage
+    let freeze = ExecuteMsg::FreezeStage { stage: 1 };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random0000", &[]),
+        freeze.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0000", &[]),
+        ExecuteMsg::FreezeStage { stage: 2 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0000", &[]),
+        freeze.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "freeze_stage"),
+            attr("stage", "1"),
+            attr("guardian", "guardian0000"),
+        ]
+    );
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::MerkleRoot { stage: 1 }).unwrap();
+    let merkle_root: MerkleRootResponse = from_binary(&res).unwrap();
+    assert_eq!(Some("guardian0000".to_string()), merkle_root.frozen_by);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let claim = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::StageFrozen {
+            stage: 1,
+            frozen_by: "guardian0000".to_string(),
+        }
+    );
+
+    // the root guardians unfreeze it by quorum
+    let unfreeze = ExecuteMsg::UnfreezeStage { stage: 1 };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0000", &[]),
+        unfreeze.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0001", &[]),
+        unfreeze.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "approve_unfreeze_stage"),
+            attr("stage", "1"),
+            attr("guardian", "guardian0001"),
+            attr("approvals", "1"),
+        ]
+    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0001", &[]),
+        unfreeze.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0002", &[]),
+        unfreeze.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "unfreeze_stage"), attr("stage", "1")]
+    );
+
+    // the owner lifts a freeze right away
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0001", &[]),
+        freeze,
+    )
+    .unwrap();
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        unfreeze,
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        claim,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+}
+
+#[test]
+fn clawback() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[
+        (claimer.as_str(), Uint128::new(1000)),
+        ("osmo1random", Uint128::new(4000)),
+    ]);
+    setup_contract(deps.as_mut(), root.clone());
+
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: Some("treasury0000".to_string()),
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let env = mock_env();
+    let mut msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: Some(Expiration::AtHeight(env.block.height + 10)),
+        total_amount: Uint128::zero(),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    // a stage without a gift would leave nothing to claw back
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InvalidInput {});
+    if let ExecuteMsg::RegisterMerkleRoot { total_amount, .. } = &mut msg {
+        *total_amount = Uint128::new(5000);
+    }
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 2,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), msg).unwrap();
+
+    // stage without expiration or not yet expired can not be clawed back
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Clawback { stage: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::StageNotExpired { stage: 1 });
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Clawback { stage: 2 },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::StageNotExpired { stage: 2 });
+
+    let mut env = env;
+    env.block.height += 10;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random0000", &[]),
+        ExecuteMsg::Clawback { stage: 2 },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Clawback { stage: 2 },
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("treasury0000", 4000)]);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "clawback"),
+            attr("stage", "2"),
+            attr("recipient", "treasury0000"),
+            attr("amount", "4000"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MerkleRoot { stage: 2 },
+    )
+    .unwrap();
+    let stage: MerkleRootResponse = from_binary(&res).unwrap();
+    assert_eq!(stage.total_amount, stage.claimed_amount);
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::new(95000), config.current_balance);
+
+    // nothing is left to claw back
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Clawback { stage: 2 },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn sudo_governance() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root.clone());
+
+    let config = |deps: Deps| -> ConfigResponse {
+        from_binary(&query(deps, mock_env(), QueryMsg::Config {}).unwrap()).unwrap()
+    };
+
+    sudo(deps.as_mut(), mock_env(), SudoMsg::Pause { paused: true }).unwrap();
+    assert!(config(deps.as_ref()).paused);
+    sudo(deps.as_mut(), mock_env(), SudoMsg::Pause { paused: false }).unwrap();
+    assert!(!config(deps.as_ref()).paused);
+
+    // governance replaces the owner without its key
+    let msg = SudoMsg::UpdateOwner {
+        owner: Some("gov0000".to_string()),
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(config(deps.as_ref()).owner, Some("gov0000".to_string()));
+    let env = mock_env();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: Some(Expiration::AtHeight(env.block.height + 10)),
+        total_amount: Uint128::new(5000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), env.clone(), mock_info("gov0000", &[]), msg).unwrap();
+
+    let err = sudo(deps.as_mut(), env.clone(), SudoMsg::Clawback { stage: 2 }).unwrap_err();
+    assert_eq!(err, ContractError::StageNotExpired { stage: 2 });
+    let mut env = env;
+    env.block.height += 10;
+    let res = sudo(deps.as_mut(), env, SudoMsg::Clawback { stage: 2 }).unwrap();
+    assert_eq!(res.messages, vec![bank_send("owner0000", 5000)]);
+}
+
+#[test]
+fn audit_log() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+
+    let env = mock_env();
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    // failed and user actions are not recorded
+    let _err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bostrom1random", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap_err();
+    let _err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bostrom1random", &[]),
+        ExecuteMsg::Release {},
+    )
+    .unwrap_err();
+    let _res = sudo(deps.as_mut(), env.clone(), SudoMsg::Pause { paused: false }).unwrap();
+
+    let msg = QueryMsg::AuditLog {
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let log: AuditLogResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        log.entries
+            .iter()
+            .map(|e| (e.actor.as_str(), e.action.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("owner0000", "register_merkle_root"),
+            ("owner0000", "pause"),
+            ("gov", "sudo_pause"),
+        ]
+    );
+    assert_eq!(
+        log.entries[2],
+        AuditEntry {
+            id: 2,
+            height: env.block.height,
+            actor: "gov".to_string(),
+            action: "sudo_pause".to_string(),
+            params: r#"{"pause":{"paused":false}}"#.to_string(),
+        }
+    );
+
+    let msg = QueryMsg::AuditLog {
+        start_after: Some(0),
+        limit: Some(1),
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap();
+    let log: AuditLogResponse = from_binary(&res).unwrap();
+    assert_eq!(1, log.entries.len());
+    assert_eq!("pause", log.entries[0].action);
+    assert_eq!(r#"{"pause":{}}"#, log.entries[0].params);
+}
+
+#[test]
+fn claim_cw20() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    // cw20 gift is not funded with native coins
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: Some("token0000".to_string()),
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // only owner can fund with the gift token
+    let fund = |sender: &str| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::new(100000),
+            msg: to_binary(&ReceiveMsg::Fund {}).unwrap(),
+        })
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("token0001", &[]),
+        fund("owner0000"),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("token0000", &[]),
+        fund("random0000"),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // nothing is claimable before the gift is funded
+    let unfunded_claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &unfunded_claim_msg),
+        claim_msg: unfunded_claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert!(matches!(res, ContractError::IsNotEligible { .. }));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("token0000", &[]),
+        fund("owner0000"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "fund"),
+            attr("sender", "owner0000"),
+            attr("amount", "100000"),
+            attr("current_balance", "100000"),
+        ]
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(None, config.allowed_native);
+    assert_eq!(Some("token0000".to_string()), config.cw20_token);
+    assert_eq!(Uint128::new(100000), config.current_balance);
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "token0000".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "bostrom1target".to_string(),
+                amount: Uint128::new(1000),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn claim_multi_denom() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree_leaves(&[
+        format!("{}1000{},500hydrogen", claimer, NATIVE_TOKEN),
+        "osmo1random2000".to_string(),
+    ]);
+
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![coin(50000, "hydrogen")],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: Some(Decimal::percent(50)),
+        release_blocks: Some(10),
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    // extra denoms must be funded
+    let info = mock_info("owner0000", &[coin(100000, NATIVE_TOKEN)]);
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::InvalidInput {});
+    let info = mock_info(
+        "owner0000",
+        &[coin(100000, NATIVE_TOKEN), coin(50000, "hydrogen")],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // relay reward, liquid and vesting parts are split in the same proportions
+    let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    claim_msg.relayer_reward = Decimal::percent(10);
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![coin(500, "hydrogen")],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: "bostrom1target".to_string(),
+                amount: vec![coin(450, NATIVE_TOKEN), coin(225, "hydrogen")],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: "relayer".to_string(),
+                amount: vec![coin(100, NATIVE_TOKEN), coin(50, "hydrogen")],
+            }),
+        ]
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.extra_denoms, vec!["hydrogen".to_string()]);
+    assert_eq!(config.extra_initial_balances, vec![Uint128::new(50000)]);
+    assert_eq!(config.extra_current_balances, vec![Uint128::new(49500)]);
+
+    let mut env = mock_env();
+    env.block.height += 10;
+    let info = mock_info("bostrom1target", &[]);
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::Release {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "bostrom1target".to_string(),
+            amount: vec![coin(450, NATIVE_TOKEN), coin(225, "hydrogen")],
+        })]
+    );
+}
+
+#[test]
+fn claim_relayed() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+
+    let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    claim_msg.relayer_reward = Decimal::percent(5);
+    let signature = eth_proof(&key, &claim_msg);
+
+    // relay reward is covered by the signature
+    let mut forged_claim_msg = claim_msg.clone();
+    forged_claim_msg.relayer_reward = Decimal::percent(50);
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg: forged_claim_msg,
+        signature: signature.clone(),
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("relayer0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(res, ContractError::AddressMismatch { .. }));
+
+    // relay reward can not exceed the gift
+    let mut greedy_claim_msg = claim_msg.clone();
+    greedy_claim_msg.relayer_reward = Decimal::percent(101);
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &greedy_claim_msg),
+        claim_msg: greedy_claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("relayer0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidInput {});
+
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg,
+        signature,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("relayer0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            bank_send("bostrom1target", 950),
+            bank_send("relayer0000", 50)
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim"),
+            attr("stage", "1"),
+            attr("address", claimer),
+            attr("amount", "1000"),
+            attr("relayer", "relayer0000"),
+            attr("relayer_reward", "50"),
+        ]
+    );
+}
+
+#[test]
+fn claim_batch() {
+    let mut deps = mock_dependencies();
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let eth_claimer = eth_address(&eth_key);
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "osmo");
+    let (root, proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(2000)),
+    ]);
+
+    setup_contract(deps.as_mut(), root);
+
+    // empty batch is rejected
+    let msg = ExecuteMsg::ClaimBatch { claims: vec![] };
+    let info = mock_info("relayer0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidInput {});
+
+    let claim_msg_eth = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1eth");
+    let claim_msg_cosmos = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1cosmos");
+    let msg = ExecuteMsg::ClaimBatch {
+        claims: vec![
+            ClaimEntry {
+                stage: 1,
+                signature: eth_proof(&eth_key, &claim_msg_eth),
+                claim_msg: claim_msg_eth,
+                claim_amount: Uint128::new(1000),
+                extra_amounts: vec![],
+                leaf_index: None,
+                proof: proofs[0].clone(),
+            },
+            ClaimEntry {
+                stage: 1,
+                signature: cosmos_sign(&cosmos_key, &claim_msg_cosmos),
+                claim_msg: claim_msg_cosmos,
+                claim_amount: Uint128::new(2000),
+                extra_amounts: vec![],
+                leaf_index: None,
+                proof: proofs[1].clone(),
+            },
+        ],
+    };
+
+    // claims are relayed by a sender that is not in the snapshot
+    let info = mock_info("relayer0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            bank_send("bostrom1eth", 1000),
+            bank_send("bostrom1cosmos", 2000)
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_batch"),
+            attr("claims_count", "2"),
+            attr("stage", "1"),
+            attr("address", eth_claimer),
+            attr("amount", "1000"),
+            attr("stage", "1"),
+            attr("address", cosmos_claimer),
+            attr("amount", "2000"),
+        ]
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(Uint128::new(97000), config.current_balance);
+}
+
+#[test]
+fn claim_receipt() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: Some("receipt0000".to_string()),
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // receipt is minted to the recipient with the snapshot amount
+    let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    claim_msg.recipient = Some("bostrom1cold".to_string());
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    let mint_msg = WasmMsg::Execute {
+        contract_addr: "receipt0000".to_string(),
+        msg: to_binary(&ReceiptExecuteMsg::Mint(MintMsg {
+            token_id: format!("1:{}", claimer),
+            owner: "bostrom1cold".to_string(),
+            token_uri: None,
+            extension: GiftReceipt {
+                stage: 1,
+                address: claimer.clone(),
+                amount: Uint128::new(1000),
+                coefficient: Decimal::one(),
+            },
+        }))
+        .unwrap(),
+        funds: vec![],
+    };
+    assert_eq!(
+        res.messages,
+        vec![bank_send("bostrom1cold", 1000), SubMsg::new(mint_msg)]
+    );
+}
+
+#[test]
+fn verified_claims() {
+    let mut deps = mock_dependencies();
+    let key_a = signing_key(ETH_PRIVATE_KEY);
+    let key_b = signing_key(COSMOS_PRIVATE_KEY);
+    let (claimer_a, claimer_b) = (eth_address(&key_a), eth_address(&key_b));
+    let (root, proofs) = merkle_tree(&[
+        (claimer_a.as_str(), Uint128::new(1000)),
+        (claimer_b.as_str(), Uint128::new(2000)),
+    ]);
+
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: Some(true),
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("owner0000", &[coin(100000, NATIVE_TOKEN)]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let entry = |key: &SigningKey, claimer: &str, amount: u128, proof: Vec<ProofNode>| {
+        let claim_msg = claim_msg(ClaimerType::Ethereum, claimer, "bostrom1target");
+        ClaimEntry {
+            stage: 1,
+            signature: eth_proof(key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(amount),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof,
+        }
+    };
+    let entry_a = entry(&key_a, &claimer_a, 1000, proofs[0].clone());
+    let entry_b = entry(&key_b, &claimer_b, 2000, proofs[1].clone());
+    let verify = |deps: DepsMut, claim: ClaimEntry| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("bostrom1target", &[]),
+            ExecuteMsg::VerifyClaim { claim },
+        )
+    };
+
+    // only the target verifies its claims
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::VerifyClaim {
+            claim: entry_a.clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // claims are verified once
+    let res = verify(deps.as_mut(), entry_a.clone()).unwrap();
+    assert!(res.attributes.contains(&attr("cached", "false")));
+    let res = verify(deps.as_mut(), entry_a.clone()).unwrap();
+    assert!(res.attributes.contains(&attr("cached", "true")));
+
+    // invalid claims are not kept
+    let invalid = entry(&key_b, &claimer_a, 1000, proofs[0].clone());
+    let _err = verify(deps.as_mut(), invalid).unwrap_err();
+
+    // the target keeps its last verified claim
+    let allocation = |deps: Deps, address: &str| -> AllocationResponse {
+        let msg = QueryMsg::Allocation {
+            stage: 1,
+            address: address.to_string(),
+        };
+        from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+    };
+    assert!(!allocation(deps.as_ref(), &claimer_b).proof_validated);
+    let _res = verify(deps.as_mut(), entry_b.clone()).unwrap();
+    let cached = |deps: Deps, claim: &ClaimEntry| is_verified_claim(deps.storage, claim).unwrap();
+    assert!(!cached(deps.as_ref(), &entry_a));
+    assert!(cached(deps.as_ref(), &entry_b));
+    // the validated proof is kept
+    assert_eq!(
+        allocation(deps.as_ref(), &claimer_a),
+        AllocationResponse {
+            amount: None,
+            is_claimed: false,
+            proof_validated: true,
+        }
+    );
+
+    // claim consumes its verification
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        ExecuteMsg::ClaimBatch {
+            claims: vec![entry_b.clone(), entry_a.clone()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            bank_send("bostrom1target", 2000),
+            bank_send("bostrom1target", 1000)
+        ]
+    );
+    assert!(!cached(deps.as_ref(), &entry_b));
+
+    // claims are not pre-verified by default
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+    let err = verify(deps.as_mut(), entry_a).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+}
+
+#[test]
+fn claims_per_block() {
+    let mut deps = mock_dependencies();
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let eth_claimer = eth_address(&eth_key);
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "osmo");
+    let (root, proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(2000)),
+    ]);
+
+    setup_contract(deps.as_mut(), root);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: Some(1),
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let claim_msg_eth = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1eth");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&eth_key, &claim_msg_eth),
+        claim_msg: claim_msg_eth,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("relayer0000", &[]);
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    // block cap is reached
+    let claim_msg_cosmos = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1cosmos");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: cosmos_sign(&cosmos_key, &claim_msg_cosmos),
+        claim_msg: claim_msg_cosmos,
+        claim_amount: Uint128::new(2000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[1].clone(),
+    };
+    let env = mock_env();
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TooManyClaimsThisBlock {
+            height: env.block.height,
+            max_claims_per_block: 1
+        }
+    );
+
+    // counter starts over on the next block
+    let mut env = mock_env();
+    env.block.height += 1;
+    execute(deps.as_mut(), env, info, msg).unwrap();
+}
+
+#[test]
+fn claim_range() {
+    let mut deps = mock_dependencies();
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let eth_claimer = eth_address(&eth_key);
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "osmo");
+    let (root, proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(3000)),
+    ]);
+
+    let mut msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::percent(200),
+        coefficient_down: Decimal::percent(200),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: Some(Uint128::new(2000)),
+        claim_maximum: Some(Uint128::new(1500)),
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &[coin(100000, NATIVE_TOKEN)]);
+
+    // minimum can not exceed maximum
+    let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    msg.claim_minimum = Some(Uint128::new(500));
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // entry above the maximum is rejected
+    let claim_msg_cosmos = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1cosmos");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: cosmos_sign(&cosmos_key, &claim_msg_cosmos),
+        claim_msg: claim_msg_cosmos,
+        claim_amount: Uint128::new(3000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[1].clone(),
+    };
+    let info = mock_info("bostrom1cosmos", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ClaimAmountOutOfRange {
+            amount: Uint128::new(3000),
+            minimum: Uint128::new(500),
+            maximum: Uint128::new(1500),
+        }
+    );
+
+    // payout is clamped after the coefficient
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateClaim {
+            amount: Uint128::new(1000),
+            stage: None,
+        },
+    )
+    .unwrap();
+    let simulation: SimulateClaimResponse = from_binary(&res).unwrap();
+    assert_eq!(simulation.payout, Uint128::new(1500));
+
+    let claim_msg_eth = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1eth");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&eth_key, &claim_msg_eth),
+        claim_msg: claim_msg_eth,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("bostrom1eth", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1eth", 1500)]);
+    assert!(res.attributes.contains(&attr("clamped_amount", "1500")));
+}
+
+#[test]
+fn claim_fee() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_contract(deps.as_mut(), root);
+
+    let update = |fee_recipient: Option<&str>, fee_bps: u64| ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: fee_recipient.map(|f| f.to_string()),
+        new_fee_bps: Some(fee_bps),
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    let info = mock_info("owner0000", &[]);
+
+    // fee requires a recipient and can not exceed the payout
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), update(None, 250)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = update(Some("dao0000"), 10001);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = update(Some("dao0000"), 250);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = QueryMsg::SimulateClaim {
+        amount: Uint128::new(1000),
+        stage: None,
+    };
+    let simulation: SimulateClaimResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(simulation.fee, Uint128::new(25));
+    assert_eq!(simulation.liquid_amount, Uint128::new(975));
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let info = mock_info("bostrom1target", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![bank_send("bostrom1target", 975), bank_send("dao0000", 25)]
+    );
+    assert!(res.attributes.contains(&attr("fee", "25")));
+}
+
+#[test]
+fn ibc_payout() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_contract(deps.as_mut(), root);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: Some(vec!["channel-0".to_string()]),
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let claim = |channel: &str| {
+        let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "osmo1remote");
+        claim_msg.payout_channel = Some(channel.to_string());
+        ExecuteMsg::Claim {
+            stage: 1,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
+        }
+    };
+    let info = mock_info("relayer0000", &[]);
+
+    // only allowed channels are paid out over
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), claim("channel-1")).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ChannelNotAllowed {
+            channel: "channel-1".to_string()
+        }
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), info, claim("channel-0")).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(IbcMsg::Transfer {
+            channel_id: "channel-0".to_string(),
+            to_address: "osmo1remote".to_string(),
+            amount: coin(1000, NATIVE_TOKEN),
+            timeout: IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(600)),
+        })]
+    );
+    assert!(res
+        .attributes
+        .contains(&attr("payout_channel", "channel-0")));
+}
+
+#[test]
+fn claim_delegation() {
+    let mut deps = mock_dependencies();
+    deps.querier.update_staking(
+        NATIVE_TOKEN,
+        &[Validator {
+            address: "bostromvaloper1".to_string(),
+            commission: Decimal::percent(5),
+            max_commission: Decimal::percent(10),
+            max_change_rate: Decimal::percent(1),
+        }],
+        &[],
+    );
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_contract(deps.as_mut(), root);
+
+    let claim = |validator: &str| {
+        let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+        claim_msg.delegate_to = Some(validator.to_string());
+        ExecuteMsg::Claim {
+            stage: 1,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
+        }
+    };
+    let info = mock_info("relayer0000", &[]);
+
+    // payouts are only delegated to validators of the chain
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        claim("bostromvaloper2"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    let res = execute(deps.as_mut(), mock_env(), info, claim("bostromvaloper1")).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(StakingMsg::Delegate {
+            validator: "bostromvaloper1".to_string(),
+            amount: coin(1000, NATIVE_TOKEN),
+        })]
+    );
+    let claims: ClaimsByAddressResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ClaimsByAddress {
+                address: "bostrom1target".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        claims.claims[0].delegated_to,
+        Some("bostromvaloper1".to_string())
+    );
+
+    // the recipient undelegates and withdraws after the unbonding time
+    let info = mock_info("bostrom1target", &[]);
+    let msg = ExecuteMsg::Undelegate {
+        validator: "bostromvaloper1".to_string(),
+        amount: Uint128::new(1001),
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = ExecuteMsg::Undelegate {
+        validator: "bostromvaloper1".to_string(),
+        amount: Uint128::new(400),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(StakingMsg::Undelegate {
+            validator: "bostromvaloper1".to_string(),
+            amount: coin(400, NATIVE_TOKEN),
+        })]
+    );
+
+    let delegations: DelegationsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Delegations {
+                address: "bostrom1target".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let release_at = mock_env()
+        .block
+        .time
+        .plus_seconds(DEFAULT_UNBONDING_SECONDS);
+    assert_eq!(
+        delegations,
+        DelegationsResponse {
+            delegations: vec![DelegationResponse {
+                validator: "bostromvaloper1".to_string(),
+                amount: Uint128::new(600),
+            }],
+            unbondings: vec![Unbonding {
+                amount: Uint128::new(400),
+                release_at,
+            }],
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::WithdrawUnbonded {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToRelease {});
+
+    let mut env = mock_env();
+    env.block.time = release_at;
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::WithdrawUnbonded {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "bostrom1target".to_string(),
+            amount: vec![coin(400, NATIVE_TOKEN)],
+        })]
+    );
+}
+
+#[test]
+fn claim_hook() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_contract(deps.as_mut(), root);
+
+    let msg = ExecuteMsg::SetClaimHook {
+        hook: Some(ClaimHookMsg {
+            contract: "graph0000".to_string(),
+            msg: r#"{"cyberlink":{"from":"{address}","to":"{target_addr}","amount":"{amount}"}}"#
+                .to_string(),
+        }),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(Some("graph0000".to_string()), config.claim_hook);
+    assert!(config
+        .claim_hook_msg
+        .unwrap()
+        .starts_with(r#"{"cyberlink""#));
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer0000", &[]),
+        msg,
+    )
+    .unwrap();
+    let hook_msg = format!(
+        r#"{{"cyberlink":{{"from":"{}","to":"bostrom1target","amount":"1000"}}}}"#,
+        claimer
+    );
+    assert_eq!(
+        res.messages.last(),
+        Some(&SubMsg::reply_on_error(
+            WasmMsg::Execute {
+                contract_addr: "graph0000".to_string(),
+                msg: Binary::from(hook_msg.into_bytes()),
+                funds: vec![],
+            },
+            CLAIM_HOOK_REPLY_ID,
+        ))
+    );
+
+    // failed hooks do not revert the claim
+    let reply_msg = Reply {
+        id: CLAIM_HOOK_REPLY_ID,
+        result: ContractResult::Err("cyberlink failed".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_hook_failed"),
+            attr("error", "cyberlink failed")
+        ]
+    );
+}
+
+#[test]
+fn hooks() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_contract(deps.as_mut(), root);
+
+    let owner = mock_info("owner0000", &[]);
+    let add = |addr: &str| ExecuteMsg::AddHook {
+        addr: addr.to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random", &[]),
+        add("loyalty"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), owner.clone(), add("loyalty")).unwrap();
+    execute(deps.as_mut(), mock_env(), owner.clone(), add("analytics")).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), add("loyalty")).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::HookAlreadyRegistered {
+            hook: "loyalty".to_string()
+        }
+    );
+
+    let msg = ExecuteMsg::RemoveHook {
+        addr: "analytics".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), owner.clone(), msg.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), owner, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::HookNotRegistered {
+            hook: "analytics".to_string()
+        }
+    );
+    let res: HooksResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Hooks {}).unwrap()).unwrap();
+    assert_eq!(res.hooks, vec!["loyalty".to_string()]);
+
+    // the registered hooks are notified of each claim
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer0000", &[]),
+        msg,
+    )
+    .unwrap();
+    let hook_msg = GiftClaimedHookMsg {
+        claimer,
+        amount: Uint128::new(1000),
+        stage: 1,
+    };
+    assert_eq!(
+        res.messages.last(),
+        Some(&SubMsg::new(hook_msg.into_cosmos_msg("loyalty").unwrap()))
+    );
+}
+
+#[test]
+fn storage_layout() {
+    // indexers read these keys with raw queries, changing them is a breaking change
+    assert_eq!(
+        [
+            CONFIG_KEY,
+            LATEST_STAGE_KEY,
+            CLAIMS_COUNT_KEY,
+            MERKLE_ROOT_PREFIX,
+            STAGE_EXPIRATION_PREFIX,
+            STAGE_AMOUNT_PREFIX,
+            STAGE_AMOUNT_CLAIMED_PREFIX,
+            CLAIM_PREFIX,
+            CLAIMS_PREFIX,
+            CLAIMS_BY_TARGET_PREFIX,
+            RELEASE_STATE_PREFIX,
+        ],
+        [
+            "config",
+            "stage",
+            "claims_count",
+            "merkle_root",
+            "stage_exp",
+            "stage_amount",
+            "stage_claimed_amount",
+            "claim",
+            "claims",
+            "claims_by_target",
+            "release_state",
+        ]
+    );
+
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+
+    setup_contract(deps.as_mut(), root.clone());
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer0000", &[]),
+        msg,
+    )
+    .unwrap();
+
+    let raw = |key: &[u8]| deps.storage.get(key).unwrap();
+    let config: Config = from_slice(&raw(b"config")).unwrap();
+    assert_eq!(config.owner, Some(Addr::unchecked("owner0000")));
+    assert_eq!(raw(b"stage"), b"1");
+    assert_eq!(raw(b"claims_count"), b"1");
+
+    // map entries are keyed by the length prefixed namespace and key parts
+    let root_key = b"\x00\x0bmerkle_root\x01";
+    assert_eq!(map_key(MERKLE_ROOT_PREFIX, &[&[1]]), root_key);
+    assert_eq!(MERKLE_ROOT.key(1).to_vec(), root_key);
+    assert_eq!(raw(root_key), to_vec(&root).unwrap());
+
+    let claim_key = map_key(CLAIMS_PREFIX, &[claimer.as_bytes(), &[1]]);
+    assert_eq!(claim_key[..8], *b"\x00\x06claims");
+    assert_eq!(CLAIMS.key((claimer.clone(), 1)).to_vec(), claim_key);
+    let claim: ClaimState = from_slice(&raw(&claim_key)).unwrap();
+    assert_eq!(claim.target_addr, "bostrom1target");
+}
+
+#[test]
+fn upload_allocations() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+
+    setup_contract(deps.as_mut(), root);
+
+    // stages of uploaded allocations only have no root
+    let owner = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: "".to_string(),
+        start: Some(Expiration::AtHeight(mock_env().block.height + 10)),
+        expiration: None,
+        total_amount: Uint128::new(1000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+    let mut started_env = mock_env();
+    started_env.block.height += 10;
+
+    // allocations can not be added to merkle stages
+    let msg = ExecuteMsg::UploadAllocations {
+        stage: 1,
+        entries: vec![(claimer.clone(), Uint128::new(500))],
+    };
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::AllocationsNotAllowed { stage: 1, .. }
+    ));
+
+    let upload =
+        |entries: Vec<(String, Uint128)>| ExecuteMsg::UploadAllocations { stage: 2, entries };
+    let entries = vec![(claimer.clone(), Uint128::new(500))];
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random", &[]),
+        upload(entries.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let too_many =
+        vec![("osmo1random".to_string(), Uint128::new(1)); MAX_ALLOCATIONS_PER_UPLOAD + 1];
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), upload(too_many)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TooManyAllocations {
+            entries: MAX_ALLOCATIONS_PER_UPLOAD + 1,
+            max_allocations: MAX_ALLOCATIONS_PER_UPLOAD,
+        }
+    );
+    let zero = vec![
+        (claimer.clone(), Uint128::new(500)),
+        ("osmo1random".to_string(), Uint128::zero()),
+    ];
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), upload(zero)).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::InvalidAllocation { index: 1, .. }
+    ));
+    let unknown_stage = ExecuteMsg::UploadAllocations {
+        stage: 3,
+        entries: entries.clone(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), unknown_stage).unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::NotFound { .. })));
+
+    // uploads sum up to at most the total of the stage, an address uploaded again is
+    // replaced
+    let over_total = vec![("osmo1random".to_string(), Uint128::new(501))];
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        upload(vec![(claimer.clone(), Uint128::new(900))]),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        upload(over_total.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AllocationsOverTotal {
+            stage: 2,
+            allocated: Uint128::new(1401),
+            total_amount: Uint128::new(1000),
+        }
+    );
+    execute(deps.as_mut(), mock_env(), owner.clone(), upload(entries)).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), upload(over_total)).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::AllocationsOverTotal { stage: 2, .. }
+    ));
+
+    // allocations are fixed once the stage begins
+    let err = execute(
+        deps.as_mut(),
+        started_env.clone(),
+        owner,
+        upload(vec![("osmo1random".to_string(), Uint128::new(500))]),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::AllocationsNotAllowed { stage: 2, .. }
+    ));
+
+    let res: AllocationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Allocation {
+                stage: 2,
+                address: claimer.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        AllocationResponse {
+            amount: Some(Uint128::new(500)),
+            is_claimed: false,
+            proof_validated: false,
+        }
+    );
+
+    let claim = |amount: u128| {
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+        ExecuteMsg::Claim {
+            stage: 2,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(amount),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: vec![],
+        }
+    };
+    let info = mock_info("relayer0000", &[]);
+    let err = execute(
+        deps.as_mut(),
+        started_env.clone(),
+        info.clone(),
+        claim(1000),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ProofInvalid { stage: 2, .. }));
+
+    // allocations are claimed without a proof
+    let res = execute(deps.as_mut(), started_env, info, claim(500)).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "bostrom1target".to_string(),
+            amount: vec![coin(500, NATIVE_TOKEN)],
+        })]
+    );
+    let res: AllocationResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Allocation {
+                stage: 2,
+                address: claimer,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.is_claimed && res.proof_validated);
+}
+
+#[test]
+fn root_timelock() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+
+    let owner = mock_info("owner0000", &[]);
+    let update_timelock = |seconds: u64| ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: Some(seconds),
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        update_timelock(86400),
+    )
+    .unwrap();
+    // the timelock can only be raised
+    let err = execute(deps.as_mut(), mock_env(), owner.clone(), update_timelock(0)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    let (root, _) = merkle_tree(&[("osmo1other", Uint128::new(500))]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root.clone(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(500),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+
+    let activate_at = mock_env().block.time.plus_seconds(86400);
+    let res: PendingRootsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PendingRoots {}).unwrap()).unwrap();
+    assert_eq!(
+        res.roots,
+        vec![PendingRoot {
+            merkle_root: root.clone(),
+            start: None,
+            expiration: None,
+            total_amount: Uint128::new(500),
+            leaf_format: None,
+            hash_algo: None,
+            activate_at,
+            approvals: vec![],
+        }]
+    );
+    let res: LatestStageResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::LatestStage {}).unwrap()).unwrap();
+    assert_eq!(res.latest_stage, 1);
+
+    let activate = ExecuteMsg::ActivateRoot {
+        merkle_root: root.clone(),
+    };
+    let info = mock_info("random", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), activate.clone()).unwrap_err();
+    assert_eq!(err, ContractError::RootTimelocked { activate_at });
+
+    // anyone activates the root once the timelock passed
+    let mut env = mock_env();
+    env.block.time = activate_at;
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), activate.clone()).unwrap();
+    assert!(res.attributes.contains(&attr("stage", "2")));
+    let res: MerkleRootResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::MerkleRoot { stage: 2 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.merkle_root, root);
+    let err = execute(deps.as_mut(), env, info, activate).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+}
+
+#[test]
+fn root_guardians() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+
+    // the guardian set is only changed by governance
+    let msg = SudoMsg::UpdateRootGuardians {
+        guardians: vec!["guardian1".to_string(), "guardian2".to_string()],
+        threshold: 3,
+    };
+    let err = sudo(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let msg = SudoMsg::UpdateRootGuardians {
+        guardians: vec![
+            "guardian1".to_string(),
+            "guardian2".to_string(),
+            "guardian3".to_string(),
+        ],
+        threshold: 2,
+    };
+    sudo(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let (root, _) = merkle_tree(&[("osmo1other", Uint128::new(500))]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root.clone(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let activate = ExecuteMsg::ActivateRoot {
+        merkle_root: root.clone(),
+    };
+    let approve = ExecuteMsg::ApproveRoot {
+        root_hash: root.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        activate.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RootNotApproved {
+            approvals: 0,
+            threshold: 2
+        }
+    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        approve.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let guardian = mock_info("guardian1", &[]);
+    execute(deps.as_mut(), mock_env(), guardian.clone(), approve.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), guardian.clone(), approve.clone()).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+    let err = execute(deps.as_mut(), mock_env(), guardian, activate.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RootNotApproved {
+            approvals: 1,
+            threshold: 2
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian3", &[]),
+        approve,
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("approvals", "2")));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random", &[]),
+        activate,
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("stage", "2")));
+}
+
+#[test]
+fn source_chain_entries() {
+    let mut deps = mock_dependencies();
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let osmosis = format!("osmosis:{}", claimer);
+    let cosmoshub = format!("cosmoshub:{}", claimer);
+    let (root, proofs) = merkle_tree(&[
+        (osmosis.as_str(), Uint128::new(1000)),
+        (cosmoshub.as_str(), Uint128::new(500)),
+    ]);
+
+    setup_contract(deps.as_mut(), root);
+
+    let claim = |chain: &str, amount: u128, proof: Vec<ProofNode>| {
+        let mut claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+        claim_msg.source_chain = Some(chain.to_string());
+        ExecuteMsg::Claim {
+            stage: 1,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(amount),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof,
+        }
+    };
+    let info = mock_info("relayer0000", &[]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        claim("Osmosis", 1000, proofs[0].clone()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    // each snapshot entry of the address is claimed separately
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        claim("osmosis", 1000, proofs[0].clone()),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        claim("cosmoshub", 500, proofs[1].clone()),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        claim("osmosis", 1000, proofs[0].clone()),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AlreadyClaimed {
+            stage: 1,
+            address: osmosis,
+            claimed_at_height: mock_env().block.height,
+            amount: Uint128::new(1000),
+        }
+    );
+
+    let is_claimed = |source_chain: Option<&str>| {
+        let msg = QueryMsg::IsClaimed {
+            stage: 1,
+            address: claimer.clone(),
+            source_chain: source_chain.map(String::from),
+        };
+        let res: IsClaimedResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        res.is_claimed
+    };
+    assert!(is_claimed(Some("cosmoshub")));
+    assert!(!is_claimed(None));
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::ClaimsBySource {}).unwrap();
+    let sources: ClaimsBySourceResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        sources.sources,
+        vec![
+            SourceClaims {
+                source: "cosmoshub".to_string(),
+                claims: 1,
+                amount: Uint128::new(500),
+            },
+            SourceClaims {
+                source: "osmosis".to_string(),
+                claims: 1,
+                amount: Uint128::new(1000),
+            },
+        ]
+    );
+}
+
+#[test]
+fn ibc_register_merkle_root() {
+    let mut deps = mock_dependencies();
+    let (root, _) = merkle_tree(&[("osmo1random", Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root.clone());
+
+    // channels are only accepted from the controller
+    let msg = mock_ibc_channel_open_try("channel-1", IbcOrder::Unordered, IBC_VERSION);
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidIbcChannel { .. }));
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: Some("their_port".to_string()),
+        new_ibc_connection_id: Some("connection-2".to_string()),
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let msg = mock_ibc_channel_open_try("channel-1", IbcOrder::Ordered, IBC_VERSION);
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidIbcChannel { .. }));
+    // channels of the controller port on other connections are rejected
+    let mut msg = mock_ibc_channel_open_try("channel-1", IbcOrder::Unordered, IBC_VERSION);
+    if let IbcChannelOpenMsg::OpenTry { channel, .. } = &mut msg {
+        channel.connection_id = "connection-9".to_string();
+    }
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidIbcChannel { .. }));
+    let mut msg = mock_ibc_channel_connect_ack("channel-1", IbcOrder::Unordered, IBC_VERSION);
+    if let IbcChannelConnectMsg::OpenAck { channel, .. } = &mut msg {
+        channel.connection_id = "connection-9".to_string();
+    }
+    let err = ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidIbcChannel { .. }));
+    let msg = mock_ibc_channel_open_try("channel-1", IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let packet = IbcPacketMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+
+    // packets of channels which are not connected are acknowledged with the error
+    let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    let ack: IbcAck = from_binary(&res.acknowledgement).unwrap();
+    assert!(matches!(ack, IbcAck::Error(_)));
+
+    let msg = mock_ibc_channel_connect_ack("channel-1", IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap();
+    let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    let ack: IbcAck = from_binary(&res.acknowledgement).unwrap();
+    let registered = RegisterMerkleRootAck {
+        stage: Some(2),
+        activate_at: None,
+    };
+    assert_eq!(ack, IbcAck::Result(to_binary(&registered).unwrap()));
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::LatestStage {}).unwrap();
+    assert_eq!(
+        from_binary::<LatestStageResponse>(&res).unwrap(),
+        LatestStageResponse { latest_stage: 2 }
+    );
+
+    // roots of the controller are timelocked like the ones of the owner
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: None,
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: Some(100),
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    let ack: IbcAck = from_binary(&res.acknowledgement).unwrap();
+    let activate_at = mock_env().block.time.plus_seconds(100);
+    let pending = RegisterMerkleRootAck {
+        stage: None,
+        activate_at: Some(activate_at),
+    };
+    assert_eq!(ack, IbcAck::Result(to_binary(&pending).unwrap()));
+    let res: PendingRootsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PendingRoots {}).unwrap()).unwrap();
+    assert_eq!(res.roots[0].activate_at, activate_at);
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::LatestStage {}).unwrap();
+    assert_eq!(
+        from_binary::<LatestStageResponse>(&res)
+            .unwrap()
+            .latest_stage,
+        2
+    );
+
+    // closed channels are not accepted anymore
+    let msg = mock_ibc_channel_close_init("channel-1", IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_close(deps.as_mut(), mock_env(), msg).unwrap();
+    let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    let ack: IbcAck = from_binary(&res.acknowledgement).unwrap();
+    assert!(matches!(ack, IbcAck::Error(_)));
+}
+
+/// Block the passport of alice was minted at
+const PASSPORT_CREATED_AT: u64 = 12_000;
+
+/// Answers the passport contract queries with the given address to passport owner links
+struct PassportQuerier {
+    base: MockQuerier,
+    links: HashMap<String, String>,
+}
+
+impl Querier for PassportQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+        match request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr == "passport0000" =>
+            {
+                // all links are in the passport of alice
+                let passport = match from_binary(&msg).unwrap() {
+                    PassportQueryMsg::PassportByAddress { address } => {
+                        self.links.get(&address).map(|owner| {
+                            to_binary(&PassportResponse {
+                                owner: owner.clone(),
+                                nickname: "alice".to_string(),
+                            })
+                        })
+                    }
+                    PassportQueryMsg::PassportByNickname { nickname } => self
+                        .links
+                        .values()
+                        .next()
+                        .filter(|_| nickname == "alice")
+                        .map(|owner| {
+                            let mut addresses: Vec<String> = self.links.keys().cloned().collect();
+                            addresses.sort();
+                            to_binary(&PassportAddressesResponse {
+                                owner: owner.clone(),
+                                nickname,
+                                addresses,
+                                created_at: Some(PASSPORT_CREATED_AT),
+                            })
+                        }),
+                    PassportQueryMsg::AddressesByPassport {
+                        nickname,
+                        start_after,
+                        limit,
+                    } => Some(nickname).filter(|n| n == "alice").map(|nickname| {
+                        let mut addresses: Vec<String> = self
+                            .links
+                            .keys()
+                            .filter(|a| start_after.as_ref().is_none_or(|s| *a > s))
+                            .cloned()
+                            .collect();
+                        addresses.sort();
+                        addresses.truncate(limit.unwrap_or(1) as usize);
+                        to_binary(&AddressesByPassportResponse {
+                            nickname,
+                            addresses: addresses
+                                .into_iter()
+                                .map(|address| LinkedAddressResponse {
+                                    address_type: if address.starts_with("0x") {
+                                        ClaimerType::Ethereum
+                                    } else {
+                                        ClaimerType::Cosmos
+                                    },
+                                    address,
+                                    proof: LinkProof::CosmosSig {
+                                        pub_key: Binary::default(),
+                                        signature: Binary::default(),
+                                        pub_key_type: None,
+                                    },
+                                    linked_at: PASSPORT_CREATED_AT,
+                                })
+                                .collect(),
+                        })
+                    }),
+                    PassportQueryMsg::Config {} => None,
+                };
+                let result = match passport {
+                    Some(passport) => ContractResult::Ok(passport.unwrap()),
+                    None => ContractResult::Err("passport not found".to_string()),
+                };
+                SystemResult::Ok(result)
+            }
+            _ => self.base.raw_query(bin_request),
+        }
+    }
+}
+
+fn mock_passport_dependencies(
+    links: &[(&str, &str)],
+) -> OwnedDeps<MockStorage, MockApi, PassportQuerier> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: PassportQuerier {
+            base: MockQuerier::default(),
+            links: links
+                .iter()
+                .map(|(address, owner)| (address.to_string(), owner.to_string()))
+                .collect(),
+        },
+        custom_query_type: PhantomData,
+    }
+}
+
+#[test]
+fn claim_passport() {
+    let claimer = "0x1de6f7d3a1bd0fb3ab8b4cf5d59a8d7da4a27c1b";
+    let mut deps = mock_passport_dependencies(&[(claimer, "bostrom1target")]);
+    let (root, proofs) = merkle_tree(&[
+        (claimer, Uint128::new(1000)),
+        ("0xunlinked", Uint128::new(2000)),
+    ]);
+    setup_contract(deps.as_mut(), root);
+
+    let mut claim_msg = claim_msg(ClaimerType::Ethereum, claimer, "bostrom1target");
+    claim_msg.signature_type = SignatureType::Passport;
+    let claim = |claim_msg: ClaimMsg, proof: Vec<ProofNode>, amount: u128| ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg,
+        signature: Proof::Passport {
+            nickname: "alice".to_string(),
+        },
+        claim_amount: Uint128::new(amount),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof,
+    };
+
+    // passport contract is not set
+    let info = mock_info("bostrom1target", &[]);
+    let msg = claim(claim_msg.clone(), proofs[0].clone(), 1000);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(res, ContractError::IsNotEligible { .. }));
+
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: Some("passport0000".to_string()),
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    // passport claims can not be relayed without a grant
+    let info = mock_info("relayer0000", &[]);
+    let msg = claim(claim_msg.clone(), proofs[0].clone(), 1000);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // linkage is checked by the is eligible query
+    let is_eligible = |deps: Deps, claim_msg: ClaimMsg, nickname: &str| -> IsEligibleResponse {
+        let query_msg = QueryMsg::IsEligible {
+            stage: 1,
+            claim_msg,
+            signature: Proof::Passport {
+                nickname: nickname.to_string(),
+            },
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
+        };
+        from_binary(&query(deps, mock_env(), query_msg).unwrap()).unwrap()
+    };
+    let res = is_eligible(deps.as_ref(), claim_msg.clone(), "alice");
+    assert!(res.valid_proof && res.valid_signature);
+    let mut other_claim_msg = claim_msg.clone();
+    other_claim_msg.target_addr = "bostrom1other".to_string();
+    assert!(!is_eligible(deps.as_ref(), other_claim_msg, "alice").valid_signature);
+    // passport must have the nickname of the proof
+    assert!(!is_eligible(deps.as_ref(), claim_msg.clone(), "mallory").valid_signature);
+
+    // claim is verified by the passport contract before the payout
+    let info = mock_info("bostrom1target", &[]);
+    let msg = claim(claim_msg.clone(), proofs[0].clone(), 1000);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let verify_msg = WasmMsg::Execute {
+        contract_addr: "passport0000".to_string(),
+        msg: to_binary(&PassportExecuteMsg::VerifyAddress {
+            address: claimer.to_string(),
+            owner: "bostrom1target".to_string(),
+        })
+        .unwrap(),
+        funds: vec![],
+    };
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::reply_always(verify_msg, 1),
+            bank_send("bostrom1target", 1000)
+        ]
+    );
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "verify_passport"), attr("address", claimer)]
+    );
+
+    // failed verification reverts the claim with the passport error
+    let mut unlinked_claim_msg = claim_msg;
+    unlinked_claim_msg.gift_claiming_address = "0xunlinked".to_string();
+    let info = mock_info("bostrom1target", &[]);
+    let msg = claim(unlinked_claim_msg, proofs[1].clone(), 2000);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let reply_msg = Reply {
+        id: 2,
+        result: ContractResult::Err("passport not found".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::PassportVerificationFailed {
+            address: "0xunlinked".to_string(),
+            msg: "passport not found".to_string(),
+        }
+    );
+}
+
+#[test]
+fn claim_by_nickname() {
+    let claimer = "0x1de6f7d3a1bd0fb3ab8b4cf5d59a8d7da4a27c1b";
+    let mut deps = mock_passport_dependencies(&[(claimer, "bostrom1target")]);
+    let (root, proofs) = merkle_tree(&[
+        (claimer, Uint128::new(1000)),
+        ("0xunlinked", Uint128::new(2000)),
+    ]);
+    setup_contract(deps.as_mut(), root);
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: Some("passport0000".to_string()),
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let claim = |nickname: &str, address: &str, proof: Vec<ProofNode>, amount: u128| {
+        ExecuteMsg::ClaimByNickname {
+            stage: 1,
+            nickname: nickname.to_string(),
+            gift_claiming_address_type: ClaimerType::Ethereum,
+            gift_claiming_address: address.to_string(),
+            claim_amount: Uint128::new(amount),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof,
+        }
+    };
+
+    // sender must own the passport
+    let info = mock_info("bostrom1other", &[]);
+    let msg = claim("alice", claimer, proofs[0].clone(), 1000);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    let info = mock_info("bostrom1target", &[]);
+    let msg = claim("bob", claimer, proofs[0].clone(), 1000);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(res, ContractError::IsNotEligible { .. }));
+
+    // address must be linked in the passport
+    let info = mock_info("bostrom1target", &[]);
+    let msg = claim("alice", "0xunlinked", proofs[1].clone(), 2000);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(res, ContractError::AddressMismatch { .. }));
+
+    // merkle leaf of the linked address is verified
+    let info = mock_info("bostrom1target", &[]);
+    let msg = claim("alice", claimer, proofs[0].clone(), 2000);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(res, ContractError::ProofInvalid { .. }));
+
+    let info = mock_info("bostrom1target", &[]);
+    let msg = claim("alice", claimer, proofs[0].clone(), 1000);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let verify_msg = WasmMsg::Execute {
+        contract_addr: "passport0000".to_string(),
+        msg: to_binary(&PassportExecuteMsg::VerifyAddress {
+            address: claimer.to_string(),
+            owner: "bostrom1target".to_string(),
+        })
+        .unwrap(),
+        funds: vec![],
+    };
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::reply_always(verify_msg, 1),
+            bank_send("bostrom1target", 1000)
+        ]
+    );
+}
+
+#[test]
+fn passport_age() {
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let mut deps = mock_passport_dependencies(&[(&claimer, "bostrom1target")]);
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: Some("passport0000".to_string()),
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    let owner = mock_info("owner0000", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+
+    let set_age = |min_age_blocks: u64| ExecuteMsg::SetStagePassportAge {
+        stage: 1,
+        min_age_blocks: Some(min_age_blocks),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("random", &[]),
+        set_age(1000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = execute(deps.as_mut(), mock_env(), owner.clone(), set_age(1000)).unwrap();
+    let cutoff = mock_env().block.height - 1000;
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "set_stage_passport_age"),
+            attr("stage", "1"),
+            attr("passport_created_before", cutoff.to_string()),
+        ]
+    );
+
+    let claim = |target: &str| {
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, target);
+        ExecuteMsg::Claim {
+            stage: 1,
+            signature: eth_proof(&key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
+        }
+    };
+    // the passport of alice was minted after the cutoff
+    let info = mock_info("relayer0000", &[]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        claim("bostrom1target"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::IsNotEligible {
+            msg: format!("passport must be created at or before block {}", cutoff),
+        }
+    );
+
+    execute(deps.as_mut(), mock_env(), owner.clone(), set_age(300)).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::MerkleRoot { stage: 1 }).unwrap();
+    let stage: MerkleRootResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        Some(mock_env().block.height - 300),
+        stage.passport_created_before
+    );
+    assert!(stage.passport_created_before.unwrap() >= PASSPORT_CREATED_AT);
+    // the passport must be owned by the target
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        claim("bostrom1other"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::IsNotEligible {
+            msg: "passport is not owned by the target".to_string(),
+        }
+    );
+    execute(deps.as_mut(), mock_env(), info, claim("bostrom1target")).unwrap();
+
+    // claimed stages keep their passport age
+    let err = execute(deps.as_mut(), mock_env(), owner, set_age(0)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+}
+
+#[test]
+fn authorize_claimer() {
+    let claimer = "0x1de6f7d3a1bd0fb3ab8b4cf5d59a8d7da4a27c1b";
+    let mut deps = mock_passport_dependencies(&[(claimer, "bostrom1target")]);
+    let (root, proofs) = merkle_tree(&[(claimer, Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: Some("passport0000".to_string()),
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    let mut claim_msg = claim_msg(ClaimerType::Ethereum, claimer, "bostrom1target");
+    claim_msg.signature_type = SignatureType::Passport;
+    let claim = |claim_msg: ClaimMsg| ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg,
+        signature: Proof::Passport {
+            nickname: "alice".to_string(),
+        },
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let relayer = mock_info("relayer0000", &[]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        relayer.clone(),
+        claim(claim_msg.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // target authorizes the relayer
+    let info = mock_info("bostrom1target", &[]);
+    let msg = ExecuteMsg::AuthorizeClaimer {
+        grantee: "relayer0000".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = QueryMsg::ClaimerGrant {
+        address: "bostrom1target".to_string(),
+    };
+    let res: ClaimerGrantResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.grantee, Some("relayer0000".to_string()));
+
+    // relay reward is not signed by the claimer
+    let mut rewarded_claim_msg = claim_msg.clone();
+    rewarded_claim_msg.relayer_reward = Decimal::percent(10);
+    let msg = claim(rewarded_claim_msg);
+    let err = execute(deps.as_mut(), mock_env(), relayer.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    // payout goes to the target
+    let res = execute(deps.as_mut(), mock_env(), relayer, claim(claim_msg)).unwrap();
+    assert_eq!(res.messages[1], bank_send("bostrom1target", 1000));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::RevokeClaimer {},
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::RevokeClaimer {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+}
+
+#[test]
+fn linked_claim_policy() {
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let eth_claimer = eth_address(&eth_key);
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "cosmos");
+    let (root, proofs) = merkle_tree(&[
+        (eth_claimer.as_str(), Uint128::new(1000)),
+        (cosmos_claimer.as_str(), Uint128::new(800)),
+    ]);
+
+    let setup = |policy: LinkedClaimPolicy| {
+        let mut deps = mock_passport_dependencies(&[
+            (&eth_claimer, "bostrom1passport"),
+            (&cosmos_claimer, "bostrom1passport"),
+        ]);
+        setup_contract(deps.as_mut(), root.clone());
+        let msg = ExecuteMsg::UpdateConfig {
+            new_treasury: None,
+            new_passport: Some("passport0000".to_string()),
+            new_coefficient_up: None,
+            new_coefficient_down: None,
+            new_coefficient_claims: None,
+            new_max_claims_per_block: None,
+            new_fee_recipient: None,
+            new_fee_bps: None,
+            new_ibc_channels: None,
+            new_ibc_timeout_seconds: None,
+            new_ibc_controller: None,
+            new_ibc_connection_id: None,
+            new_root_timelock_seconds: None,
+            new_linked_claim_policy: Some(policy),
+            new_cosmos_prefixes: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+        deps
+    };
+    let eth_claim = {
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &eth_claimer, "bostrom1target");
+        ExecuteMsg::Claim {
+            stage: 1,
+            signature: eth_proof(&eth_key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(1000),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[0].clone(),
+        }
+    };
+    let cosmos_claim = {
+        let claim_msg = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1target");
+        ExecuteMsg::Claim {
+            stage: 1,
+            signature: cosmos_sign(&cosmos_key, &claim_msg),
+            claim_msg,
+            claim_amount: Uint128::new(800),
+            extra_amounts: vec![],
+            leaf_index: None,
+            proof: proofs[1].clone(),
+        }
+    };
+    let info = mock_info("relayer0000", &[]);
+
+    // only the first linked address is paid
+    let mut deps = setup(LinkedClaimPolicy::FirstWins);
+    execute(deps.as_mut(), mock_env(), info.clone(), eth_claim.clone()).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        cosmos_claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::LinkedAddressClaimed {
+            passport: "bostrom1passport".to_string()
+        }
+    );
+
+    // unlinking or relinking the other address after the claim does not free it
+    deps.querier.links.remove(&cosmos_claimer);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        cosmos_claim.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::LinkedAddressClaimed { .. }));
+    deps.querier
+        .links
+        .insert(cosmos_claimer.clone(), "bostrom1other".to_string());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        cosmos_claim.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::LinkedAddressClaimed { .. }));
+
+    // passport errors other than not found fail the claim
+    let mut deps = setup(LinkedClaimPolicy::FirstWins);
+    let msg = ExecuteMsg::UpdateConfig {
+        new_treasury: None,
+        new_passport: Some("broken0000".to_string()),
+        new_coefficient_up: None,
+        new_coefficient_down: None,
+        new_coefficient_claims: None,
+        new_max_claims_per_block: None,
+        new_fee_recipient: None,
+        new_fee_bps: None,
+        new_ibc_channels: None,
+        new_ibc_timeout_seconds: None,
+        new_ibc_controller: None,
+        new_ibc_connection_id: None,
+        new_root_timelock_seconds: None,
+        new_linked_claim_policy: None,
+        new_cosmos_prefixes: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), eth_claim.clone()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Std(StdError::GenericErr { .. })
+    ));
+
+    // linked addresses are paid up to the cap
+    let mut deps = setup(LinkedClaimPolicy::SumWithCap {
+        cap: Uint128::new(1500),
+    });
+    execute(deps.as_mut(), mock_env(), info.clone(), eth_claim).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info, cosmos_claim).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 500)]);
+}
+
+#[test]
+fn claim_contract_wallet() {
+    let mut deps = mock_dependencies();
+    let safe = "0x5afe5afe5afe5afe5afe5afe5afe5afe5afe5afe";
+    let (root, proofs) = merkle_tree(&[(safe, Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+
+    let mut claim_msg = claim_msg(ClaimerType::Ethereum, safe, "bostrom1target");
+    claim_msg.signature_type = SignatureType::ContractWallet;
+    let claim = |claim_msg: ClaimMsg| ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg,
+        signature: Proof::ContractWallet {},
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+
+    // claim must be approved
+    let info = mock_info("bostrom1target", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, claim(claim_msg.clone())).unwrap_err();
+    assert!(matches!(res, ContractError::IsNotEligible { .. }));
+
+    // only owner can approve
+    let approve_msg = ExecuteMsg::ApproveContractWalletClaim {
+        stage: 1,
+        gift_claiming_address: safe.to_string(),
+        target_addr: "bostrom1target".to_string(),
+    };
+    let info = mock_info("random0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, approve_msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    let info = mock_info("owner0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, approve_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "approve_contract_wallet_claim"),
+            attr("stage", "1"),
+            attr("address", safe),
+            attr("target_addr", "bostrom1target"),
+        ]
+    );
+
+    // approval is bound to the target
+    let mut other_claim_msg = claim_msg.clone();
+    other_claim_msg.target_addr = "bostrom1other".to_string();
+    let info = mock_info("bostrom1other", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, claim(other_claim_msg)).unwrap_err();
+    assert!(matches!(res, ContractError::IsNotEligible { .. }));
+
+    // unsigned claims can not be relayed
+    let info = mock_info("relayer0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, claim(claim_msg.clone())).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("bostrom1target", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, claim(claim_msg)).unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+}
+
+#[test]
+fn claim_solana() {
+    let mut deps = mock_dependencies();
+    let key = ed25519_zebra::SigningKey::from([7u8; 32]);
+    let pub_key: [u8; 32] = ed25519_zebra::VerificationKey::from(&key).into();
+    let claimer = bs58::encode(pub_key).into_string();
+    let (root, proofs) = merkle_tree(&[(claimer.as_str(), Uint128::new(1000))]);
+    setup_contract(deps.as_mut(), root);
+
+    let claim_msg = claim_msg(ClaimerType::Solana, &claimer, "bostrom1target");
+    let sign = |claim_msg: &ClaimMsg| -> Binary {
+        let signature: [u8; 64] = key.sign(&claim_msg.to_canonical_json().unwrap()).into();
+        Binary::from(signature.as_ref())
+    };
+    let claim = |claim_msg: ClaimMsg, signature: Binary| ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg,
+        signature: Proof::SolanaSig { signature },
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+
+    // signature must cover the claim msg
+    let mut forged_claim_msg = claim_msg.clone();
+    forged_claim_msg.target_addr = "bostrom1forged".to_string();
+    let msg = claim(forged_claim_msg, sign(&claim_msg));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    );
+    assert!(matches!(
+        res.unwrap_err(),
+        ContractError::SignatureMismatch { .. }
+    ));
+
+    // proof must match the claimer type
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        claim_msg: claim_msg.clone(),
+        signature: Proof::EthSig {
+            signature: sign(&claim_msg),
+            message_encoding: MessageEncoding::Utf8,
+        },
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::ProofMismatch {
+            proof: "eth_sig".to_string(),
+            claimer: "Solana".to_string(),
+            signature_type: "PersonalSign".to_string(),
+        }
+    );
+
+    let msg = claim(claim_msg.clone(), sign(&claim_msg));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bostrom1target", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![bank_send("bostrom1target", 1000)]);
+}
+
+#[test]
+fn verify_claim_domain() {
+    let deps = mock_dependencies();
+    let eth_key = signing_key(ETH_PRIVATE_KEY);
+    let mut claim_msg_eth = claim_msg(
+        ClaimerType::Ethereum,
+        &eth_address(&eth_key),
+        "bostrom1target",
+    );
+    claim_msg_eth.contract_addr = "cosmos2other".to_string();
+    let signature = eth_sign(&eth_key, &claim_msg_eth);
+    let err = verify_eth(
+        deps.as_ref(),
+        &mock_env(),
+        &claim_msg_eth,
+        signature,
+        MessageEncoding::Utf8,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::SignatureMismatch {
+            msg: "claim msg is signed for another contract".to_string()
+        }
+    );
+
+    let cosmos_key = signing_key(COSMOS_PRIVATE_KEY);
+    let cosmos_claimer = cosmos_address(&cosmos_key, "cosmos");
+    let mut claim_msg_cosmos = claim_msg(ClaimerType::Cosmos, &cosmos_claimer, "bostrom1target");
+    claim_msg_cosmos.chain_id = "bostrom-testnet".to_string();
+    let signature = cosmos_sign(&cosmos_key, &claim_msg_cosmos);
+    let err = verify_cosmos_proof(
+        deps.as_ref(),
+        &mock_env(),
+        &claim_msg_cosmos,
+        signature.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::SignatureMismatch {
+            msg: "claim msg is signed for another chain".to_string()
+        }
+    );
+
+    // signature is valid on the chain it was made for
+    let mut env = mock_env();
+    env.block.chain_id = "bostrom-testnet".to_string();
+    assert!(verify_cosmos_proof(deps.as_ref(), &env, &claim_msg_cosmos, signature).unwrap());
+}
+
+/// Signs the claim message like Bitcoin `signmessage` does, returning header || r || s
+fn bitcoin_sign(key: &SigningKey, claim_msg: &ClaimMsg, header_offset: u8) -> Binary {
+    let msg = claim_msg.to_canonical_json().unwrap();
+    let mut prefixed = b"\x18Bitcoin Signed Message:\n".to_vec();
+    match msg.len() {
+        len if len < 0xfd => prefixed.push(len as u8),
+        len => prefixed.extend([&[0xfd], &(len as u16).to_le_bytes()[..]].concat()),
+    }
+    prefixed.extend(msg);
+    let digest = Sha256::new().chain(Sha256::digest(&prefixed));
+    let signature: recoverable::Signature = key.sign_digest(digest);
+    let (rs, v) = signature.as_ref().split_at(64);
+    Binary::from([&[27 + header_offset + v[0]], rs].concat())
+}
+
+#[test]
+fn verify_bitcoin_addresses() {
+    let deps = mock_dependencies();
+    let key = signing_key(COSMOS_PRIVATE_KEY);
+    let hash = Ripemd160::digest(&Sha256::digest(&key.verifying_key().to_bytes()));
+    let mut payload = vec![0x00];
+    payload.extend(hash);
+    let checksum = Sha256::digest(&Sha256::digest(&payload));
+    payload.extend(&checksum[..4]);
+    let p2pkh = bs58::encode(payload).into_string();
+    let mut data = vec![bech32::u5::try_from_u8(0).unwrap()];
+    data.extend(hash.to_base32());
+    let p2wpkh = bech32::encode("bc", data, Variant::Bech32).unwrap();
+
+    // compressed P2PKH and P2WPKH headers
+    for (address, header_offset) in [(p2pkh.as_str(), 4), (p2wpkh.as_str(), 8)] {
+        let claim_msg = claim_msg(ClaimerType::Bitcoin, address, "bostrom1target");
+        let signature = bitcoin_sign(&key, &claim_msg, header_offset);
+        assert!(verify_bitcoin(deps.as_ref(), &mock_env(), &claim_msg, signature).unwrap());
+    }
+
+    // uncompressed key does not hash to the compressed key address
+    let claim_msg = claim_msg(ClaimerType::Bitcoin, &p2pkh, "bostrom1target");
+    let signature = bitcoin_sign(&key, &claim_msg, 0);
+    let err = verify_bitcoin(deps.as_ref(), &mock_env(), &claim_msg, signature).unwrap_err();
+    assert!(matches!(err, ContractError::AddressMismatch { .. }));
+
+    // signature must cover the claim msg
+    let signature = bitcoin_sign(&key, &claim_msg, 4);
+    let mut forged_claim_msg = claim_msg;
+    forged_claim_msg.target_addr = "bostrom1forged".to_string();
+    let err = verify_bitcoin(deps.as_ref(), &mock_env(), &forged_claim_msg, signature).unwrap_err();
+    assert!(matches!(err, ContractError::AddressMismatch { .. }));
+}
+
+#[test]
+fn owner_freeze() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Default::default(),
+        extra_balances: vec![],
+        coefficient_up: Default::default(),
+        coefficient_down: Default::default(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // can register merkle root
+    let env = mock_env();
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: "5d4f48f147cb6cb742b376dce5626b2a036f69faec10cd73631c791780e150fc".to_string(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // can update owner
+    let env = mock_env();
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::ProposeNewOwner {
+        new_owner: "owner0001".to_string(),
+    };
+    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let env = mock_env();
+    let info = mock_info("owner0001", &[]);
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::AcceptOwnership {}).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // freeze contract
+    let env = mock_env();
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::RenounceOwnership {};
+
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // cannot register new drop
+    let env = mock_env();
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: "ebaa83c7eaf7467c378d2f37b5e46752d904d2d17acd380b24b02e3b398b3e5a".to_string(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // cannot update config
+    let env = mock_env();
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: "ebaa83c7eaf7467c378d2f37b5e46752d904d2d17acd380b24b02e3b398b3e5a".to_string(),
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+}
+
+#[test]
+fn migrate_v1() {
+    let mut deps = mock_dependencies();
+    set_contract_version(&mut deps.storage, "crates.io:cw-cyber-airdrop", "0.1.0").unwrap();
+    let v1 = ConfigV1 {
+        owner: Some(Addr::unchecked("owner0000")),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        current_balance: Uint128::new(9000),
+        initial_balance: Uint128::new(10000),
+        coefficient_up: Uint128::new(2),
+        coefficient_down: Uint128::new(1),
+        coefficient: Uint128::new(2),
+    };
+    CONFIG_V1.save(&mut deps.storage, &v1).unwrap();
+    MERKLE_ROOT_V1
+        .save(&mut deps.storage, &"aa".repeat(32))
+        .unwrap();
+    CLAIM_V1
+        .save(&mut deps.storage, b"bostrom1claimed", &true)
+        .unwrap();
+
+    // v1 state can not be kept as is
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Bump {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CannotMigrateVersion {
+            previous_version: "0.1.0".to_string(),
+            msg: "state must be upgraded".to_string(),
+        }
+    );
+
+    let msg = MigrateMsg::UpgradeV1 {
+        treasury: None,
+        coefficient_claims: Some(100),
+    };
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res.attributes.contains(&attr("migrated_claims", "1")));
+    assert!(res
+        .attributes
+        .contains(&attr("version", env!("CARGO_PKG_VERSION"))));
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.treasury, "owner0000");
+    assert_eq!(config.coefficient_up, Decimal::from_ratio(2u128, 1u128));
+    assert_eq!(config.coefficient_claims, 100);
+    assert_eq!(config.current_balance, Uint128::new(9000));
+
+    let msg = QueryMsg::MerkleRoot { stage: 1 };
+    let root: MerkleRootResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(root.merkle_root, "aa".repeat(32));
+    let msg = QueryMsg::IsClaimed {
+        stage: 1,
+        address: "bostrom1claimed".to_string(),
+        source_chain: None,
+    };
+    let res: IsClaimedResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert!(res.is_claimed);
+    // v1 claims become claim records of the target
+    let msg = QueryMsg::HasClaimed {
+        address: "bostrom1claimed".to_string(),
+    };
+    let res: HasClaimedResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert!(res.has_claimed);
+    let res: CurrentCoefficientResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::CurrentCoefficient {}).unwrap())
+            .unwrap();
+    assert_eq!(res.claims_count, 1);
+
+    // upgraded state is only bumped
+    let msg = MigrateMsg::UpgradeV1 {
+        treasury: None,
+        coefficient_claims: None,
+    };
+    let err = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::CannotMigrateVersion { .. }));
+    let _res = migrate(deps.as_mut(), mock_env(), MigrateMsg::Bump {}).unwrap();
+
+    set_contract_version(&mut deps.storage, "crates.io:other", "0.1.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::Bump {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CannotMigrate {
+            previous_contract: "crates.io:other".to_string()
+        }
+    );
 }
@@ -0,0 +1,188 @@
+//! Budgets of the compiled contract, so regressions of the claim verification show up
+//! in CI. The size is checked by `cargo test` whenever the wasm was built by `cargo wasm`
+//! or the rust-optimizer, and skipped otherwise unless `WASM_PATH` is set.
+//!
+//! The claim gas is metered in cosmwasm-vm with the `vm-budgets` feature. CI compiles
+//! this check but does not run it, so it stays ignored: cosmwasm-vm 1.0.0-beta3 runs MVP
+//! wasm only, and the std of current toolchains rejects how cosmwasm-std 1.0.0-beta3
+//! frees memory. Run it with `cargo test -- --ignored` on a rust-optimizer build.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+#[cfg(feature = "vm-budgets")]
+use cosmwasm_std::{Coin, Decimal, Empty, Response, Uint128};
+#[cfg(feature = "vm-budgets")]
+use cosmwasm_vm::testing::{
+    execute, instantiate, mock_env, mock_info, mock_instance_with_options, MockInstanceOptions,
+};
+
+#[cfg(feature = "vm-budgets")]
+use crate::msg::{ClaimerType, ExecuteMsg, InstantiateMsg};
+#[cfg(feature = "vm-budgets")]
+use crate::testing::merkle_tree;
+#[cfg(feature = "vm-budgets")]
+use crate::tests::{claim_msg, eth_address, eth_proof, signing_key, ETH_PRIVATE_KEY, NATIVE_TOKEN};
+
+// bytes of the wasm, overridden by WASM_SIZE_CEILING
+const WASM_SIZE_CEILING: u64 = 2_500_000;
+// gas a claim can take, about a millisecond, overridden by CLAIM_GAS_BUDGET
+#[cfg(feature = "vm-budgets")]
+const CLAIM_GAS_BUDGET: u64 = 1_000_000_000_000;
+// gas limit of the instance, enough for any budget
+#[cfg(feature = "vm-budgets")]
+const GAS_LIMIT: u64 = 10_000_000_000_000;
+
+/// Returns the budget set by the env variable or the default.
+fn budget(name: &str, default: u64) -> u64 {
+    env::var(name)
+        .map(|budget| budget.parse().expect("budget must be a number"))
+        .unwrap_or(default)
+}
+
+/// Returns the path of the wasm set by WASM_PATH, else of the rust-optimizer artifact
+/// or the release build.
+fn wasm_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("WASM_PATH") {
+        return Some(PathBuf::from(path));
+    }
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..");
+    [
+        "artifacts/cw_cyber_airdrop.wasm",
+        "target/wasm32-unknown-unknown/release/cw_cyber_airdrop.wasm",
+    ]
+    .iter()
+    .map(|path| root.join(path))
+    .find(|path| path.exists())
+}
+
+/// Reads the wasm, failing if it was not built.
+#[cfg(feature = "vm-budgets")]
+fn wasm() -> (PathBuf, Vec<u8>) {
+    let path = wasm_path().expect("no contract wasm, run `cargo wasm` first");
+    let wasm = fs::read(&path).unwrap();
+    (path, wasm)
+}
+
+#[test]
+fn wasm_size() {
+    let path = match wasm_path() {
+        Some(path) => path,
+        None => {
+            eprintln!("no contract wasm, skipping the size check, run `cargo wasm` first");
+            return;
+        }
+    };
+    let wasm = fs::read(&path).unwrap();
+    let size_ceiling = budget("WASM_SIZE_CEILING", WASM_SIZE_CEILING);
+    assert!(
+        wasm.len() as u64 <= size_ceiling,
+        "{} is {} bytes, over the ceiling of {}",
+        path.display(),
+        wasm.len(),
+        size_ceiling
+    );
+}
+
+#[test]
+#[ignore]
+#[cfg(feature = "vm-budgets")]
+fn claim_gas() {
+    let (_, wasm) = wasm();
+    let contract_balance = [Coin {
+        denom: NATIVE_TOKEN.to_string(),
+        amount: Uint128::new(100000),
+    }];
+    let mut deps = mock_instance_with_options(
+        &wasm,
+        MockInstanceOptions {
+            contract_balance: Some(&contract_balance),
+            gas_limit: GAS_LIMIT,
+            ..Default::default()
+        },
+    );
+    let key = signing_key(ETH_PRIVATE_KEY);
+    let claimer = eth_address(&key);
+    let (root, proofs) = merkle_tree(&[
+        (claimer.as_str(), Uint128::new(1000)),
+        ("osmo1random", Uint128::new(4000)),
+    ]);
+
+    let msg = InstantiateMsg {
+        owner: Some("owner0000".to_string()),
+        allowed_native: NATIVE_TOKEN.to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::new(100000),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("addr0000", &contract_balance);
+    let _res: Response<Empty> = instantiate(&mut deps, mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: root,
+        start: None,
+        expiration: None,
+        total_amount: Uint128::new(1_000_000),
+        leaf_format: None,
+        hash_algo: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    let _res: Response<Empty> = execute(&mut deps, mock_env(), info, msg).unwrap();
+
+    let claim_msg = claim_msg(ClaimerType::Ethereum, &claimer, "bostrom1target");
+    let msg = ExecuteMsg::Claim {
+        stage: 1,
+        signature: eth_proof(&key, &claim_msg),
+        claim_msg,
+        claim_amount: Uint128::new(1000),
+        extra_amounts: vec![],
+        leaf_index: None,
+        proof: proofs[0].clone(),
+    };
+    let gas_before = deps.get_gas_left();
+    let info = mock_info("bostrom1target", &[]);
+    let _res: Response<Empty> = execute(&mut deps, mock_env(), info, msg).unwrap();
+    let gas_used = gas_before - deps.get_gas_left();
+    let gas_budget = budget("CLAIM_GAS_BUDGET", CLAIM_GAS_BUDGET);
+    assert!(
+        gas_used <= gas_budget,
+        "claim used {} gas, over the budget of {}",
+        gas_used,
+        gas_budget
+    );
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+#[allow(dead_code)]
+#[path = "../examples/schema.rs"]
+mod schema;
+
+/// Reads the JSON files of a schema dir by file name.
+fn read_schemas(dir: &Path) -> BTreeMap<String, String> {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some(OsStr::new("json")))
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn schema_coverage() {
+    // the committed schemas are the ones examples/schema.rs generates
+    let out_dir =
+        std::env::temp_dir().join(format!("cw-cyber-airdrop-schema-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+    schema::export_schemas(&out_dir);
+    let generated = read_schemas(&out_dir);
+    fs::remove_dir_all(&out_dir).unwrap();
+    let committed = read_schemas(&Path::new(env!("CARGO_MANIFEST_DIR")).join("schema"));
+    for (name, schema) in &generated {
+        assert!(
+            committed.get(name) == Some(schema),
+            "schema/{} is not the generated one, run the schema example",
+            name
+        );
+    }
+    for name in committed.keys() {
+        assert!(
+            generated.contains_key(name),
+            "schema/{} is not generated",
+            name
+        );
+    }
+
+    // every public msg and response type is exported by examples/schema.rs
+    let sources = [
+        include_str!("../src/msg.rs"),
+        include_str!("../src/hooks.rs"),
+        include_str!("../src/ibc.rs"),
+    ];
+    let types = sources
+        .iter()
+        .flat_map(|source| source.lines())
+        .filter_map(|line| {
+            let name = line
+                .strip_prefix("pub struct ")
+                .or_else(|| line.strip_prefix("pub enum "))?;
+            let name = name.split(|c: char| !c.is_alphanumeric()).next()?;
+            let public = ["Msg", "Response", "Ack"]
+                .iter()
+                .any(|suffix| name.ends_with(suffix));
+            public.then(|| name.to_string())
+        })
+        .collect::<Vec<_>>();
+    assert!(types.contains(&"MigrateMsg".to_string()));
+
+    for name in types {
+        let mut file = String::new();
+        for (i, c) in name.chars().enumerate() {
+            if c.is_uppercase() && i > 0 {
+                file.push('_');
+            }
+            file.push(c.to_ascii_lowercase());
+        }
+        let file = format!("{}.json", file);
+        assert!(
+            generated.contains_key(&file),
+            "{} is missing from the schema",
+            name
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use cosmwasm_std::StdError;
+use cyber_gift_proofs::ProofError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Proof(#[from] ProofError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Invalid input")]
+    InvalidInput {},
+
+    #[error("Nickname {nickname} is taken")]
+    NicknameTaken { nickname: String },
+
+    #[error("No passport with nickname {nickname}")]
+    PassportNotFound { nickname: String },
+
+    #[error("Address {address} is already linked to a passport")]
+    AlreadyLinked { address: String },
+
+    #[error("Address {address} is not linked to the passport")]
+    NotLinked { address: String },
+
+    #[error("Passport links at most {max_addresses} addresses")]
+    TooManyAddresses { max_addresses: u32 },
+
+    #[error("Invalid link msg, {msg}")]
+    InvalidLinkMsg { msg: String },
+}
@@ -0,0 +1,367 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    attr, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use cyber_gift_proofs::{verify_cosmos, verify_eth, ClaimMsg, ClaimerType};
+
+use crate::error::ContractError;
+use crate::msg::{
+    AddressesByPassportResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, LinkProof,
+    LinkedAddressResponse, PassportAddressesResponse, PassportResponse, QueryMsg,
+};
+use crate::state::{
+    address_key, Config, LinkedAddress, Passport, ADDRESS_PASSPORTS, CONFIG, LINKED_ADDRESSES,
+    PASSPORTS,
+};
+
+// Version info, for migration info
+const CONTRACT_NAME: &str = "crates.io:cyber-passport";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Passports link 8 addresses by default
+pub const DEFAULT_MAX_ADDRESSES: u32 = 8;
+// Nicknames are stored as keys, so their length is capped
+pub const MAX_NICKNAME_BYTES: usize = 32;
+
+// Pagination info for address queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let owner = msg
+        .owner
+        .map_or(Ok(info.sender), |o| deps.api.addr_validate(&o))?;
+    let max_addresses = msg.max_addresses.unwrap_or(DEFAULT_MAX_ADDRESSES);
+    if max_addresses == 0 {
+        return Err(ContractError::InvalidInput {});
+    }
+    let config = Config {
+        owner: Some(owner),
+        max_addresses,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            new_owner,
+            max_addresses,
+        } => execute_update_config(deps, env, info, new_owner, max_addresses),
+        ExecuteMsg::CreatePassport { nickname } => {
+            execute_create_passport(deps, env, info, nickname)
+        }
+        ExecuteMsg::LinkAddress { link_msg, proof } => {
+            execute_link_address(deps, env, info, link_msg, proof)
+        }
+        ExecuteMsg::UnlinkAddress { nickname, address } => {
+            execute_unlink_address(deps, env, info, nickname, address)
+        }
+        ExecuteMsg::VerifyAddress { address, owner } => {
+            execute_verify_address(deps, env, info, address, owner)
+        }
+    }
+}
+
+pub fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_owner: Option<String>,
+    max_addresses: Option<u32>,
+) -> Result<Response, ContractError> {
+    // authorize owner
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let owner = cfg.owner.clone().ok_or(ContractError::Unauthorized {})?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(new_owner) = new_owner {
+        cfg.owner = Some(deps.api.addr_validate(&new_owner)?);
+    }
+    if let Some(max_addresses) = max_addresses {
+        if max_addresses == 0 {
+            return Err(ContractError::InvalidInput {});
+        }
+        cfg.max_addresses = max_addresses;
+    }
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+pub fn execute_create_passport(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    nickname: String,
+) -> Result<Response, ContractError> {
+    let valid = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_';
+    if nickname.is_empty() || nickname.len() > MAX_NICKNAME_BYTES || !nickname.chars().all(valid) {
+        return Err(ContractError::InvalidInput {});
+    }
+    if PASSPORTS.has(deps.storage, nickname.clone()) {
+        return Err(ContractError::NicknameTaken { nickname });
+    }
+    let passport = Passport {
+        owner: info.sender.clone(),
+        created_at: env.block.height,
+        address_count: 0,
+    };
+    PASSPORTS.save(deps.storage, nickname.clone(), &passport)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "create_passport"),
+        attr("nickname", nickname),
+        attr("owner", info.sender),
+    ]))
+}
+
+/// Links the signer of the link msg to the passport of the sender, the link msg must
+/// be signed for this contract and chain with the sender as target_addr.
+pub fn execute_link_address(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    link_msg: ClaimMsg,
+    proof: LinkProof,
+) -> Result<Response, ContractError> {
+    let nickname = link_msg.nickname.clone();
+    let mut passport = load_passport(deps.as_ref(), &nickname)?;
+    if passport.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let invalid = |msg: &str| ContractError::InvalidLinkMsg {
+        msg: msg.to_string(),
+    };
+    if link_msg.target_addr != info.sender.as_str() {
+        return Err(invalid("target_addr must be the passport owner"));
+    }
+    if link_msg.contract_addr != env.contract.address.as_str() {
+        return Err(invalid("link msg is signed for another contract"));
+    }
+    if link_msg.chain_id != env.block.chain_id {
+        return Err(invalid("link msg is signed for another chain"));
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    if passport.address_count >= cfg.max_addresses {
+        return Err(ContractError::TooManyAddresses {
+            max_addresses: cfg.max_addresses,
+        });
+    }
+
+    let address = address_key(&link_msg.gift_claiming_address);
+    if ADDRESS_PASSPORTS.has(deps.storage, address.clone()) {
+        return Err(ContractError::AlreadyLinked { address });
+    }
+    match (&link_msg.gift_claiming_address_type, &proof) {
+        (
+            ClaimerType::Ethereum,
+            LinkProof::EthSig {
+                signature,
+                message_encoding,
+            },
+        ) => verify_eth(deps.api, &link_msg, signature, *message_encoding, None)?,
+        (
+            ClaimerType::Cosmos,
+            LinkProof::CosmosSig {
+                pub_key,
+                signature,
+                pub_key_type,
+            },
+        ) => verify_cosmos(
+            deps.api,
+            &link_msg,
+            pub_key,
+            pub_key_type.as_deref(),
+            signature,
+            &[],
+        )?,
+        _ => return Err(invalid("proof does not match the address type")),
+    };
+
+    let linked = LinkedAddress {
+        address_type: link_msg.gift_claiming_address_type,
+        proof,
+        linked_at: env.block.height,
+    };
+    LINKED_ADDRESSES.save(deps.storage, (nickname.clone(), address.clone()), &linked)?;
+    ADDRESS_PASSPORTS.save(deps.storage, address.clone(), &nickname)?;
+    passport.address_count += 1;
+    PASSPORTS.save(deps.storage, nickname.clone(), &passport)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "link_address"),
+        attr("nickname", nickname),
+        attr("address", address),
+    ]))
+}
+
+pub fn execute_unlink_address(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    nickname: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    let mut passport = load_passport(deps.as_ref(), &nickname)?;
+    if passport.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = address_key(&address);
+    let key = (nickname.clone(), address.clone());
+    if !LINKED_ADDRESSES.has(deps.storage, key.clone()) {
+        return Err(ContractError::NotLinked { address });
+    }
+    LINKED_ADDRESSES.remove(deps.storage, key);
+    ADDRESS_PASSPORTS.remove(deps.storage, address.clone());
+    passport.address_count -= 1;
+    PASSPORTS.save(deps.storage, nickname.clone(), &passport)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "unlink_address"),
+        attr("nickname", nickname),
+        attr("address", address),
+    ]))
+}
+
+/// Fails unless the address is linked in a passport of the owner, so that the gift
+/// contract verifies the linkage again within the claim.
+pub fn execute_verify_address(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    address: String,
+    owner: String,
+) -> Result<Response, ContractError> {
+    let linked_owner = match ADDRESS_PASSPORTS.may_load(deps.storage, address_key(&address))? {
+        Some(nickname) => Some(load_passport(deps.as_ref(), &nickname)?.owner),
+        None => None,
+    };
+    if linked_owner.is_none_or(|linked_owner| linked_owner != owner) {
+        return Err(ContractError::NotLinked { address });
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "verify_address"),
+        attr("address", address),
+    ]))
+}
+
+fn load_passport(deps: Deps, nickname: &str) -> Result<Passport, ContractError> {
+    PASSPORTS
+        .may_load(deps.storage, nickname.to_string())?
+        .ok_or_else(|| ContractError::PassportNotFound {
+            nickname: nickname.to_string(),
+        })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PassportByAddress { address } => {
+            to_binary(&query_passport_by_address(deps, address)?)
+        }
+        QueryMsg::PassportByNickname { nickname } => {
+            to_binary(&query_passport_by_nickname(deps, nickname)?)
+        }
+        QueryMsg::AddressesByPassport {
+            nickname,
+            start_after,
+            limit,
+        } => to_binary(&query_addresses_by_passport(
+            deps,
+            nickname,
+            start_after,
+            limit,
+        )?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: cfg.owner.map(|o| o.to_string()),
+        max_addresses: cfg.max_addresses,
+    })
+}
+
+pub fn query_passport_by_address(deps: Deps, address: String) -> StdResult<PassportResponse> {
+    let nickname = ADDRESS_PASSPORTS.load(deps.storage, address_key(&address))?;
+    let passport = PASSPORTS.load(deps.storage, nickname.clone())?;
+    Ok(PassportResponse {
+        owner: passport.owner.to_string(),
+        nickname,
+    })
+}
+
+/// Returns all the linked addresses of the passport, they are capped by max_addresses.
+pub fn query_passport_by_nickname(
+    deps: Deps,
+    nickname: String,
+) -> StdResult<PassportAddressesResponse> {
+    let passport = PASSPORTS.load(deps.storage, nickname.clone())?;
+    let addresses = LINKED_ADDRESSES
+        .prefix_de(nickname.clone())
+        .keys_de(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    Ok(PassportAddressesResponse {
+        owner: passport.owner.to_string(),
+        nickname,
+        addresses,
+        created_at: Some(passport.created_at),
+    })
+}
+
+pub fn query_addresses_by_passport(
+    deps: Deps,
+    nickname: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AddressesByPassportResponse> {
+    if !PASSPORTS.has(deps.storage, nickname.clone()) {
+        return Err(StdError::not_found("passport"));
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let addresses = LINKED_ADDRESSES
+        .prefix_de(nickname.clone())
+        .range_de(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (address, linked) = item?;
+            Ok(LinkedAddressResponse {
+                address,
+                address_type: linked.address_type,
+                proof: linked.proof,
+                linked_at: linked.linked_at,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AddressesByPassportResponse {
+        nickname,
+        addresses,
+    })
+}
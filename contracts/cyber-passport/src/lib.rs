@@ -0,0 +1,8 @@
+mod error;
+pub mod execute;
+pub mod msg;
+pub mod state;
+#[cfg(test)]
+mod tests;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,118 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Binary;
+use cyber_gift_proofs::{ClaimMsg, ClaimerType, MessageEncoding};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// Owner if none set to info.sender.
+    pub owner: Option<String>,
+    /// MaxAddresses is the number of addresses a passport links at most, 8 if none set.
+    pub max_addresses: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum ExecuteMsg {
+    UpdateConfig {
+        /// NewOwner if non sent, owner is not changed.
+        new_owner: Option<String>,
+        /// MaxAddresses if non sent, max_addresses is not changed. Passports linking
+        /// more addresses keep them but can not link new ones.
+        max_addresses: Option<u32>,
+    },
+    /// CreatePassport mints the passport with the nickname to the sender.
+    CreatePassport { nickname: String },
+    /// LinkAddress links the gift_claiming_address of the link msg to the passport with
+    /// its nickname. The link msg is a claim msg signed for this contract and chain with
+    /// the sender, the passport owner, as target_addr.
+    LinkAddress {
+        link_msg: ClaimMsg,
+        proof: LinkProof,
+    },
+    /// UnlinkAddress removes the address from the passport of the sender.
+    UnlinkAddress { nickname: String, address: String },
+    /// VerifyAddress fails unless the address is linked in a passport of the owner.
+    VerifyAddress { address: String, owner: String },
+}
+
+/// LinkProof is the signature of the link msg by the key of the linked address, it is
+/// verified like the signature of a gift claim.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkProof {
+    /// EthSig is the r, s and v of an Ethereum signature.
+    EthSig {
+        signature: Binary,
+        /// MessageEncoding is how the link msg JSON was passed to `personal_sign`.
+        #[serde(default)]
+        message_encoding: MessageEncoding,
+    },
+    /// CosmosSig is an ADR-36 or Ledger signature with the compressed secp256k1 public
+    /// key of the signer.
+    CosmosSig {
+        pub_key: Binary,
+        signature: Binary,
+        #[serde(default)]
+        pub_key_type: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    /// PassportByAddress returns the passport the address is linked to.
+    PassportByAddress {
+        address: String,
+    },
+    /// PassportByNickname returns the owner and the linked addresses of the passport.
+    PassportByNickname {
+        nickname: String,
+    },
+    /// AddressesByPassport returns the linked addresses of the passport with their
+    /// proofs, ordered by address.
+    AddressesByPassport {
+        nickname: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: Option<String>,
+    pub max_addresses: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PassportResponse {
+    pub owner: String,
+    pub nickname: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PassportAddressesResponse {
+    pub owner: String,
+    pub nickname: String,
+    pub addresses: Vec<String>,
+    /// CreatedAt is the block the passport was minted at.
+    pub created_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LinkedAddressResponse {
+    pub address: String,
+    pub address_type: ClaimerType,
+    pub proof: LinkProof,
+    /// LinkedAt is the block the address was linked at.
+    pub linked_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressesByPassportResponse {
+    pub nickname: String,
+    pub addresses: Vec<LinkedAddressResponse>,
+}
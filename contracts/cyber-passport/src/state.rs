@@ -0,0 +1,65 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Addr;
+use cw_storage_plus::{Item, Map};
+use cyber_gift_proofs::ClaimerType;
+
+use crate::msg::LinkProof;
+
+pub mod layout {
+    // Items are stored under their key
+    pub const CONFIG_KEY: &str = "config";
+
+    // Map entries are stored under the length prefixed namespace and key parts
+    pub const PASSPORTS_PREFIX: &str = "passports";
+    pub const LINKED_ADDRESSES_PREFIX: &str = "linked_addresses";
+    pub const ADDRESS_PASSPORTS_PREFIX: &str = "address_passports";
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// Owner If None set, contract is frozen.
+    pub owner: Option<Addr>,
+    pub max_addresses: u32,
+}
+
+pub const CONFIG: Item<Config> = Item::new(layout::CONFIG_KEY);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Passport {
+    pub owner: Addr,
+    pub created_at: u64,
+    /// AddressCount is the number of linked addresses, capped by max_addresses.
+    pub address_count: u32,
+}
+
+/// Passports are keyed by nickname.
+pub const PASSPORTS: Map<String, Passport> = Map::new(layout::PASSPORTS_PREFIX);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LinkedAddress {
+    pub address_type: ClaimerType,
+    pub proof: LinkProof,
+    pub linked_at: u64,
+}
+
+/// LinkedAddresses are keyed by nickname and address, so the addresses of a passport
+/// are paginated in address order.
+pub const LINKED_ADDRESSES: Map<(String, String), LinkedAddress> =
+    Map::new(layout::LINKED_ADDRESSES_PREFIX);
+
+/// AddressPassports are the nicknames of the passports addresses are linked to, an
+/// address is linked to one passport at most.
+pub const ADDRESS_PASSPORTS: Map<String, String> = Map::new(layout::ADDRESS_PASSPORTS_PREFIX);
+
+/// Returns the key of a linked address, 0x addresses are lowercased so that an
+/// Ethereum address is linked once whatever its checksum. Contracts comparing
+/// addresses to the linked ones compare their keys.
+pub fn address_key(address: &str) -> String {
+    if address.starts_with("0x") {
+        address.to_lowercase()
+    } else {
+        address.to_string()
+    }
+}
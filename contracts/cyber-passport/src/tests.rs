@@ -0,0 +1,267 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{from_binary, Binary, Decimal, MemoryStorage, OwnedDeps};
+use cyber_gift_proofs::{
+    cosmos_address_raw, ethereum_address_raw, sign_bytes, ClaimMsg, ClaimerType, MessageEncoding,
+    ProofError,
+};
+use k256::ecdsa::signature::DigestSigner;
+use k256::ecdsa::{recoverable, Signature as Secp256k1Signature, SigningKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+use crate::execute::{execute, instantiate, query};
+use crate::msg::{
+    AddressesByPassportResponse, ExecuteMsg, InstantiateMsg, LinkProof, PassportAddressesResponse,
+    PassportResponse, QueryMsg,
+};
+use crate::ContractError;
+
+const OWNER: &str = "bostrom1owner";
+const NICKNAME: &str = "alice";
+
+type Deps = OwnedDeps<MemoryStorage, MockApi, MockQuerier>;
+
+fn setup(max_addresses: u32) -> Deps {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        owner: None,
+        max_addresses: Some(max_addresses),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    let msg = ExecuteMsg::CreatePassport {
+        nickname: NICKNAME.to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    deps
+}
+
+fn signing_key(seed: u8) -> SigningKey {
+    SigningKey::from_bytes(&[seed; 32]).unwrap()
+}
+
+/// Returns the link msg of the key address to the passport of OWNER.
+fn link_msg(claimer_type: ClaimerType, address: String) -> ClaimMsg {
+    let env = mock_env();
+    ClaimMsg {
+        nickname: NICKNAME.to_string(),
+        avatar_cid: String::new(),
+        gift_claiming_address_type: claimer_type,
+        gift_claiming_address: address,
+        target_addr: OWNER.to_string(),
+        recipient: None,
+        referrer: None,
+        payout_channel: None,
+        delegate_to: None,
+        source_chain: None,
+        memo: None,
+        relayer_reward: Decimal::zero(),
+        contract_addr: env.contract.address.to_string(),
+        chain_id: env.block.chain_id,
+        signature_type: Default::default(),
+        address_derivation: Default::default(),
+    }
+}
+
+fn eth_link(key: &SigningKey) -> ExecuteMsg {
+    let pubkey = key.verifying_key().to_encoded_point(false);
+    let address = hex::encode(ethereum_address_raw(pubkey.as_bytes()).unwrap());
+    let link_msg = link_msg(ClaimerType::Ethereum, format!("0x{}", address));
+    let bytes = sign_bytes(&link_msg).unwrap();
+    let signature: recoverable::Signature = key.sign_digest(Keccak256::new().chain(&bytes));
+    let mut signature = signature.as_ref().to_vec();
+    signature[64] += 27;
+    ExecuteMsg::LinkAddress {
+        link_msg,
+        proof: LinkProof::EthSig {
+            signature: Binary::from(signature),
+            message_encoding: MessageEncoding::Utf8,
+        },
+    }
+}
+
+fn cosmos_link(key: &SigningKey) -> ExecuteMsg {
+    use bech32::{ToBase32, Variant};
+    let pub_key = key.verifying_key().to_bytes();
+    let address = cosmos_address_raw(&pub_key).unwrap();
+    let address = bech32::encode("cosmos", address.to_base32(), Variant::Bech32).unwrap();
+    let link_msg = link_msg(ClaimerType::Cosmos, address);
+    let bytes = sign_bytes(&link_msg).unwrap();
+    let signature: Secp256k1Signature = key.sign_digest(Sha256::new().chain(&bytes));
+    ExecuteMsg::LinkAddress {
+        link_msg,
+        proof: LinkProof::CosmosSig {
+            pub_key: Binary::from(pub_key.as_slice()),
+            signature: Binary::from(signature.as_ref()),
+            pub_key_type: None,
+        },
+    }
+}
+
+fn linked_address(msg: &ExecuteMsg) -> String {
+    match msg {
+        ExecuteMsg::LinkAddress { link_msg, .. } => link_msg.gift_claiming_address.clone(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn link_addresses() {
+    let mut deps = setup(3);
+    let eth = eth_link(&signing_key(1));
+    let cosmos = cosmos_link(&signing_key(2));
+    for msg in [eth.clone(), cosmos.clone()] {
+        // only the passport owner links addresses
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("other", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    }
+
+    // an address is linked once
+    let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), eth).unwrap_err();
+    assert!(matches!(err, ContractError::AlreadyLinked { .. }));
+
+    // the signature covers the link msg
+    let mut changed = eth_link(&signing_key(3));
+    if let ExecuteMsg::LinkAddress { link_msg, .. } = &mut changed {
+        link_msg.avatar_cid = "changed".to_string();
+    }
+    let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), changed).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Proof(ProofError::AddressMismatch { .. })
+    ));
+
+    // links are capped by max_addresses
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        eth_link(&signing_key(3)),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        eth_link(&signing_key(4)),
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::TooManyAddresses { max_addresses: 3 }, err);
+
+    let passport: PassportResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PassportByAddress {
+                address: linked_address(&cosmos),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(OWNER, passport.owner);
+    assert_eq!(NICKNAME, passport.nickname);
+
+    // addresses are paginated in address order
+    let passport: PassportAddressesResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PassportByNickname {
+                nickname: NICKNAME.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(3, passport.addresses.len());
+    let mut start_after = None;
+    let mut paginated = vec![];
+    loop {
+        let msg = QueryMsg::AddressesByPassport {
+            nickname: NICKNAME.to_string(),
+            start_after,
+            limit: Some(2),
+        };
+        let page: AddressesByPassportResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        match page.addresses.last() {
+            Some(last) => start_after = Some(last.address.clone()),
+            None => break,
+        }
+        paginated.extend(
+            page.addresses
+                .into_iter()
+                .map(|a| (a.address, a.address_type)),
+        );
+    }
+    let addresses: Vec<_> = paginated.iter().map(|(a, _)| a.clone()).collect();
+    assert_eq!(passport.addresses, addresses);
+    assert!(paginated.contains(&(linked_address(&cosmos), ClaimerType::Cosmos)));
+}
+
+#[test]
+fn unlink_and_verify_address() {
+    let mut deps = setup(1);
+    let link = eth_link(&signing_key(1));
+    let address = linked_address(&link);
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), link).unwrap();
+
+    let verify = |owner: &str| ExecuteMsg::VerifyAddress {
+        address: address.clone(),
+        owner: owner.to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gift", &[]),
+        verify(OWNER),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gift", &[]),
+        verify("other"),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NotLinked { .. }));
+
+    let unlink = ExecuteMsg::UnlinkAddress {
+        nickname: NICKNAME.to_string(),
+        address: address.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("other", &[]),
+        unlink.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err);
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), unlink).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gift", &[]),
+        verify(OWNER),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NotLinked { .. }));
+
+    // the unlinked address frees its slot
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        eth_link(&signing_key(2)),
+    )
+    .unwrap();
+}
@@ -0,0 +1,40 @@
+use cosmwasm_std::{Api, RecoverPubkeyError, VerificationError};
+
+/// ProofApi are the secp256k1 operations claim verification needs, it is implemented
+/// for every cosmwasm Api so that contracts pass `deps.api` and off-chain tools a
+/// `MockApi`.
+pub trait ProofApi {
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Result<Vec<u8>, RecoverPubkeyError>;
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError>;
+}
+
+impl<A: Api + ?Sized> ProofApi for A {
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Result<Vec<u8>, RecoverPubkeyError> {
+        Api::secp256k1_recover_pubkey(self, message_hash, signature, recovery_param)
+    }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        Api::secp256k1_verify(self, message_hash, signature, public_key)
+    }
+}
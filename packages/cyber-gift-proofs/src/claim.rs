@@ -0,0 +1,315 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_vec, Decimal, Env, StdResult};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimMsg {
+    pub nickname: String,
+    pub avatar_cid: String,
+    pub gift_claiming_address_type: ClaimerType,
+    pub gift_claiming_address: String,
+    pub target_addr: String,
+    /// Recipient if set receives the payout and the vesting position instead of target_addr.
+    #[serde(default)]
+    pub recipient: Option<String>,
+    /// Referrer is the target address of an earlier claim, credited a bonus from the
+    /// referral pool.
+    #[serde(default)]
+    pub referrer: Option<String>,
+    /// PayoutChannel if set is the IBC channel the liquid payout is transferred over,
+    /// the recipient is an address on the other side of the channel.
+    #[serde(default)]
+    pub payout_channel: Option<String>,
+    /// DelegateTo if set is the validator the liquid payout is delegated to instead of
+    /// sent, the contract holds the delegation on behalf of the recipient.
+    #[serde(default)]
+    pub delegate_to: Option<String>,
+    /// SourceChain if set is the snapshot chain of the entry, e.g. `osmosis`, so that an
+    /// address in several snapshots has separately claimable entries.
+    #[serde(default)]
+    pub source_chain: Option<String>,
+    /// Memo if set is a short message of the claimer stored with the claim, at most
+    /// 256 bytes long.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// RelayerReward is the optional share of the gift paid to the sender of the claim
+    /// if it is relayed on behalf of the claimer. None set means no reward.
+    #[serde(default)]
+    pub relayer_reward: Decimal,
+    /// ContractAddr and ChainId bind the signature to this deployment, so it can not be
+    /// replayed on another contract or chain.
+    pub contract_addr: String,
+    pub chain_id: String,
+    /// SignatureType is the way gift_claiming_address signed this message.
+    #[serde(default)]
+    pub signature_type: SignatureType,
+    /// AddressDerivation is the way a Cosmos gift_claiming_address is derived from its key.
+    #[serde(default)]
+    pub address_derivation: AddressDerivation,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressDerivation {
+    /// ripemd160(sha256(pubkey)) of secp256k1 keys, sign docs are hashed with sha256.
+    #[default]
+    Cosmos,
+    /// Last 20 bytes of keccak256(pubkey) of ethsecp256k1 keys, as on Injective and
+    /// Evmos, sign docs are hashed with keccak256.
+    Ethereum,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimerType {
+    Ethereum,
+    Cosmos,
+    Solana,
+    Bitcoin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureType {
+    /// Ethereum `personal_sign` of the claim msg JSON, or ADR-36 for Cosmos claims.
+    #[default]
+    PersonalSign,
+    /// Ethereum EIP-712 `signTypedData_v4` of the claim msg structure.
+    Eip712,
+    /// Ledger amino sign doc with the claim msg JSON as memo, for Cosmos claims
+    /// signed with Ledger devices.
+    Ledger,
+    /// Passport claims are not signed, gift_claiming_address must be linked in the
+    /// passport of target_addr and the claim must be sent by target_addr.
+    Passport,
+    /// ContractWallet claims are not signed, they must be approved by the owner
+    /// and sent by target_addr.
+    ContractWallet,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageEncoding {
+    /// The claim msg JSON is signed as is.
+    #[default]
+    Utf8,
+    /// The 0x prefixed hex of the claim msg JSON is signed, as by wallets signing the
+    /// hex representation of messages.
+    Hex,
+}
+
+impl ClaimMsg {
+    /// Returns the address the gift is paid to.
+    pub fn recipient(&self) -> &str {
+        self.recipient.as_deref().unwrap_or(&self.target_addr)
+    }
+
+    /// Returns the snapshot entry of the claim, see snapshot_key.
+    pub fn snapshot_key(&self) -> String {
+        snapshot_key(self.source_chain.as_deref(), &self.gift_claiming_address)
+    }
+
+    /// Returns the source of the claim aggregates, the source chain of the entry or the
+    /// claimer type if none set.
+    pub fn source(&self) -> String {
+        self.source_chain
+            .clone()
+            .unwrap_or_else(|| self.gift_claiming_address_type.name().to_string())
+    }
+
+    /// Builds the unsigned passport claim msg of an address linked in the passport with
+    /// the nickname, made for this deployment.
+    pub fn passport(
+        nickname: &str,
+        gift_claiming_address_type: ClaimerType,
+        gift_claiming_address: String,
+        target_addr: String,
+        env: &Env,
+    ) -> Self {
+        ClaimMsg {
+            nickname: nickname.to_string(),
+            avatar_cid: String::new(),
+            gift_claiming_address_type,
+            gift_claiming_address,
+            target_addr,
+            recipient: None,
+            referrer: None,
+            payout_channel: None,
+            delegate_to: None,
+            source_chain: None,
+            memo: None,
+            relayer_reward: Decimal::zero(),
+            contract_addr: env.contract.address.to_string(),
+            chain_id: env.block.chain_id.clone(),
+            signature_type: SignatureType::Passport,
+            address_derivation: AddressDerivation::default(),
+        }
+    }
+
+    /// Returns the canonical JSON of the claim msg signed by claimers, with sorted keys,
+    /// no whitespace and the optional fields left out if none set.
+    pub fn to_canonical_json(&self) -> StdResult<Vec<u8>> {
+        to_vec(&CanonicalClaimMsg {
+            address_derivation: &self.address_derivation,
+            avatar_cid: &self.avatar_cid,
+            chain_id: &self.chain_id,
+            contract_addr: &self.contract_addr,
+            delegate_to: self.delegate_to.as_deref(),
+            gift_claiming_address: &self.gift_claiming_address,
+            gift_claiming_address_type: &self.gift_claiming_address_type,
+            memo: self.memo.as_deref(),
+            nickname: &self.nickname,
+            payout_channel: self.payout_channel.as_deref(),
+            recipient: self.recipient.as_deref(),
+            referrer: self.referrer.as_deref(),
+            relayer_reward: &self.relayer_reward,
+            signature_type: &self.signature_type,
+            source_chain: self.source_chain.as_deref(),
+            target_addr: &self.target_addr,
+        })
+    }
+}
+
+/// Returns the address of a snapshot entry, `{source_chain}:{address}` for entries of a
+/// source chain. It is the claimer of the merkle leaf and keys the claims of the entry.
+pub fn snapshot_key(source_chain: Option<&str>, address: &str) -> String {
+    match source_chain {
+        Some(chain) => format!("{}:{}", chain, address),
+        None => address.to_string(),
+    }
+}
+
+/// CanonicalClaimMsg fields are declared in alphabetical order so that the serialized
+/// claim msg has sorted keys.
+#[derive(Serialize)]
+struct CanonicalClaimMsg<'a> {
+    address_derivation: &'a AddressDerivation,
+    avatar_cid: &'a str,
+    chain_id: &'a str,
+    contract_addr: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegate_to: Option<&'a str>,
+    gift_claiming_address: &'a str,
+    gift_claiming_address_type: &'a ClaimerType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memo: Option<&'a str>,
+    nickname: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payout_channel: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipient: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    referrer: Option<&'a str>,
+    #[serde(skip_serializing_if = "is_zero")]
+    relayer_reward: &'a Decimal,
+    signature_type: &'a SignatureType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_chain: Option<&'a str>,
+    target_addr: &'a str,
+}
+
+fn is_zero(decimal: &&Decimal) -> bool {
+    decimal.is_zero()
+}
+
+impl ClaimerType {
+    /// Returns the snake case name of the claimer type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClaimerType::Ethereum => "ethereum",
+            ClaimerType::Cosmos => "cosmos",
+            ClaimerType::Solana => "solana",
+            ClaimerType::Bitcoin => "bitcoin",
+        }
+    }
+}
+
+impl SignatureType {
+    /// Unsigned claims do not cover the relay reward, so they can not be relayed.
+    pub fn is_signed(&self) -> bool {
+        !matches!(
+            self,
+            SignatureType::Passport | SignatureType::ContractWallet
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockApi;
+    use cosmwasm_std::{from_slice, Binary};
+
+    use crate::cosmos::verify_cosmos;
+    use crate::eth::verify_eth;
+    use crate::testing::{
+        claim_msg, cosmos_address, cosmos_sign, eth_address, eth_sign, signing_key,
+        COSMOS_PRIVATE_KEY, ETH_PRIVATE_KEY,
+    };
+
+    #[test]
+    fn canonical_claim_msg() {
+        // keys are sorted and none set optional fields and a zero relayer reward are left out,
+        // whatever the client order
+        let key = signing_key(ETH_PRIVATE_KEY);
+        let eth_claim_msg = claim_msg(ClaimerType::Ethereum, &eth_address(&key), "bostrom1target");
+        let eth_json = r#"{"address_derivation":"cosmos","avatar_cid":"QmRX8qYgeZoYM3M5zzQaWEpVFdpin6FvVXvp6RPQK3oufV","chain_id":"cosmos-testnet-14002","contract_addr":"cosmos2contract","gift_claiming_address":"0x2c7536e3605d9c16a7a3d7b1898e529396a65c23","gift_claiming_address_type":"ethereum","nickname":"alice","signature_type":"personal_sign","target_addr":"bostrom1target"}"#;
+        assert_eq!(
+            eth_json.as_bytes(),
+            eth_claim_msg.to_canonical_json().unwrap()
+        );
+        let client_json = r#"{
+            "target_addr": "bostrom1target",
+            "gift_claiming_address": "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23",
+            "gift_claiming_address_type": "ethereum",
+            "nickname": "alice",
+            "avatar_cid": "QmRX8qYgeZoYM3M5zzQaWEpVFdpin6FvVXvp6RPQK3oufV",
+            "recipient": null,
+            "chain_id": "cosmos-testnet-14002",
+            "contract_addr": "cosmos2contract"
+        }"#;
+        let client_claim_msg: ClaimMsg = from_slice(client_json.as_bytes()).unwrap();
+        assert_eq!(
+            eth_json.as_bytes(),
+            client_claim_msg.to_canonical_json().unwrap()
+        );
+
+        // golden personal_sign signature of the canonical JSON
+        let signature = Binary::from(hex::decode("6e5fb140aa1bbbe334c8d92fa974aa6e719201c9114265a6bb9c840f307b46442759e92ec4fc2de5fcee67dbb072da7645292de9864ffd2224cb862447b5f7991c").unwrap());
+        assert_eq!(signature, eth_sign(&key, &eth_claim_msg));
+        let api = MockApi::default();
+        assert!(verify_eth(
+            &api,
+            &client_claim_msg,
+            &signature,
+            MessageEncoding::Utf8,
+            None
+        )
+        .unwrap());
+
+        // golden ADR-36 signature of the canonical JSON with a recipient
+        let key = signing_key(COSMOS_PRIVATE_KEY);
+        let mut cosmos_claim_msg = claim_msg(
+            ClaimerType::Cosmos,
+            &cosmos_address(&key, "cosmos"),
+            "bostrom1target",
+        );
+        cosmos_claim_msg.recipient = Some("bostrom1recipient".to_string());
+        let cosmos_json = r#"{"address_derivation":"cosmos","avatar_cid":"QmRX8qYgeZoYM3M5zzQaWEpVFdpin6FvVXvp6RPQK3oufV","chain_id":"cosmos-testnet-14002","contract_addr":"cosmos2contract","gift_claiming_address":"cosmos1vurawzkallyz275w03nglkctsrr78e4h446kkq","gift_claiming_address_type":"cosmos","nickname":"alice","recipient":"bostrom1recipient","signature_type":"personal_sign","target_addr":"bostrom1target"}"#;
+        assert_eq!(
+            cosmos_json.as_bytes(),
+            cosmos_claim_msg.to_canonical_json().unwrap()
+        );
+        let pub_key = Binary::from_base64("A75ZvnhH5lPRBy80tPV8uWqcoAb2jZwYzzzZhAz+iQPe").unwrap();
+        let signature = Binary::from_base64(
+            "Q+Zlr9Xj4G375p+ueyyxWiZTfSFuyDcv3FN5vSxxu8MKiEX6xdxEiV7/Yy80csO9/L1iw7Xu1Gr8iuGZKe/BYg==",
+        )
+        .unwrap();
+        assert_eq!(
+            (pub_key.clone(), signature.clone()),
+            cosmos_sign(&key, &cosmos_claim_msg)
+        );
+        assert!(verify_cosmos(&api, &cosmos_claim_msg, &pub_key, None, &signature, &[]).unwrap());
+    }
+}
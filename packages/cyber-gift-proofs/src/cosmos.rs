@@ -0,0 +1,349 @@
+use bech32::FromBase32;
+use cosmwasm_std::{to_vec, Binary, Coin, StdError, StdResult};
+use ripemd160::Ripemd160;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use std::convert::TryInto;
+
+use crate::api::ProofApi;
+use crate::claim::{AddressDerivation, ClaimMsg, SignatureType};
+use crate::error::ProofError;
+use crate::eth::ethereum_address_raw;
+
+/// Verifies an ADR-36 signature (Keplr `signArbitrary`) of the claim message
+/// made by the key behind the bech32 `gift_claiming_address`. The amino
+/// pub_key_type, when given, must match the address_derivation of the claim.
+pub fn verify_cosmos<A: ProofApi + ?Sized>(
+    api: &A,
+    claim_msg: &ClaimMsg,
+    pub_key: &Binary,
+    pub_key_type: Option<&str>,
+    signature: &Binary,
+    prefixes: &[String],
+) -> Result<bool, ProofError> {
+    let sign_bytes = serialize_sign_doc(&cosmos_sign_doc(claim_msg)?)?;
+    if let Some(key_type) = pub_key_type {
+        let supported = match claim_msg.address_derivation {
+            AddressDerivation::Cosmos => key_type == SECP256K1_PUB_KEY_TYPE,
+            AddressDerivation::Ethereum => key_type.ends_with(ETH_SECP256K1_PUB_KEY_TYPE_SUFFIX),
+        };
+        if !supported {
+            return Err(ProofError::SignatureMismatch {
+                msg: format!("public key type {} is not supported", key_type),
+            });
+        }
+    }
+
+    // Hashing
+    let hash: [u8; 32] = match claim_msg.address_derivation {
+        AddressDerivation::Cosmos => Sha256::digest(&sign_bytes).into(),
+        AddressDerivation::Ethereum => Keccak256::digest(&sign_bytes).into(),
+    };
+
+    // Prefix is not part of the key hash, so only the whitelist limits the chains
+    let (prefix, claimed_address) = decode_bech32_address(&claim_msg.gift_claiming_address)?;
+    if !prefixes.is_empty() && !prefixes.contains(&prefix) {
+        return Err(ProofError::InvalidAddress {
+            msg: format!("bech32 prefix {} is not accepted", prefix),
+        });
+    }
+    let calculated_address = match claim_msg.address_derivation {
+        AddressDerivation::Cosmos => cosmos_address_raw(pub_key)?,
+        AddressDerivation::Ethereum => {
+            let pub_key = recover_uncompressed_pubkey(api, &hash, signature, pub_key)?;
+            ethereum_address_raw(&pub_key)?
+        }
+    };
+    if calculated_address != claimed_address {
+        return Err(ProofError::AddressMismatch {
+            address: claim_msg.gift_claiming_address.clone(),
+            msg: "signer address is not calculated addr".to_string(),
+        });
+    }
+
+    // Verification
+    let verified = api
+        .secp256k1_verify(hash.as_ref(), signature.as_slice(), pub_key.as_slice())
+        .map_err(|err| ProofError::SignatureMismatch {
+            msg: err.to_string(),
+        })?;
+    if !verified {
+        return Err(ProofError::SignatureMismatch {
+            msg: "signature verification failed".to_string(),
+        });
+    }
+    Ok(true)
+}
+
+/// Compresses an uncompressed secp256k1 public key
+pub fn compress_pubkey(pubkey: &[u8]) -> StdResult<Vec<u8>> {
+    if pubkey.len() != 65 || pubkey[0] != 0x04 {
+        return Err(StdError::generic_err("Public key must be 65 bytes long"));
+    }
+    let prefix = if pubkey[64].is_multiple_of(2) {
+        0x02
+    } else {
+        0x03
+    };
+    Ok([&[prefix], &pubkey[1..33]].concat())
+}
+
+/// Recovers the uncompressed form of a compressed public key from its signature
+fn recover_uncompressed_pubkey<A: ProofApi + ?Sized>(
+    api: &A,
+    hash: &[u8],
+    signature: &[u8],
+    pub_key: &[u8],
+) -> Result<Vec<u8>, ProofError> {
+    for recovery in 0..2 {
+        if let Ok(recovered) = api.secp256k1_recover_pubkey(hash, signature, recovery) {
+            if compress_pubkey(&recovered)? == pub_key {
+                return Ok(recovered);
+            }
+        }
+    }
+    Err(ProofError::SignatureMismatch {
+        msg: "public key is not the signer".to_string(),
+    })
+}
+
+/// Returns a raw 20 byte Cosmos address, ripemd160(sha256(pubkey)), of a
+/// compressed secp256k1 public key
+pub fn cosmos_address_raw(pubkey: &[u8]) -> StdResult<[u8; 20]> {
+    if pubkey.len() != 33 {
+        return Err(StdError::generic_err("Public key must be 33 bytes long"));
+    }
+    if pubkey[0] != 0x02 && pubkey[0] != 0x03 {
+        return Err(StdError::generic_err(
+            "Public key must start with 0x02 or 0x03",
+        ));
+    }
+
+    let hash = Ripemd160::digest(&Sha256::digest(pubkey));
+    Ok(hash.as_slice().try_into().unwrap())
+}
+
+/// Splits a bech32 address into its prefix and raw 20 byte address
+fn decode_bech32_address(address: &str) -> Result<(String, [u8; 20]), ProofError> {
+    let invalid = |msg: String| ProofError::InvalidAddress { msg };
+    let (prefix, data, _variant) = bech32::decode(address)
+        .map_err(|err| invalid(format!("invalid bech32 address: {}", err)))?;
+    let raw = Vec::<u8>::from_base32(&data)
+        .map_err(|err| invalid(format!("invalid bech32 address: {}", err)))?;
+    let raw = raw
+        .as_slice()
+        .try_into()
+        .map_err(|_| invalid("bech32 address must be 20 bytes long".to_string()))?;
+    Ok((prefix, raw))
+}
+
+/// Builds the sign doc of the claim msg for the signature type of a Cosmos claim.
+pub fn cosmos_sign_doc(claim_msg: &ClaimMsg) -> Result<SignDoc, ProofError> {
+    match claim_msg.signature_type {
+        SignatureType::PersonalSign => Ok(adr36_sign_doc(
+            &claim_msg.gift_claiming_address,
+            claim_msg.to_canonical_json()?,
+        )),
+        SignatureType::Ledger => Ok(ledger_sign_doc(claim_msg.to_canonical_json()?)?),
+        _ => Err(ProofError::SignatureMismatch {
+            msg: "signature type is not supported for cosmos addresses".to_string(),
+        }),
+    }
+}
+
+/// Builds the ADR-36 amino sign doc for arbitrary data. Fields are declared in
+/// alphabetical order so that the serialized doc has sorted keys.
+pub fn adr36_sign_doc(signer: &str, data: Vec<u8>) -> SignDoc {
+    SignDoc {
+        account_number: "0".to_string(),
+        chain_id: "".to_string(),
+        fee: Fee {
+            amount: vec![],
+            gas: "0".to_string(),
+        },
+        memo: "".to_string(),
+        msgs: vec![Msg {
+            msg_type: "sign/MsgSignData".to_string(),
+            value: MsgSignData {
+                data: Binary::from(data),
+                signer: signer.to_string(),
+            },
+        }],
+        sequence: "0".to_string(),
+    }
+}
+
+/// Builds the amino sign doc signed by Ledger devices, the claim message is
+/// carried in the memo of a doc without messages.
+pub fn ledger_sign_doc(data: Vec<u8>) -> StdResult<SignDoc> {
+    let memo = String::from_utf8(data).map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(SignDoc {
+        account_number: "0".to_string(),
+        chain_id: "".to_string(),
+        fee: Fee {
+            amount: vec![],
+            gas: "0".to_string(),
+        },
+        memo,
+        msgs: vec![],
+        sequence: "0".to_string(),
+    })
+}
+
+/// Serializes the sign doc like Keplr does, the sorted JSON with `&`, `<` and `>`
+/// escaped as unicode sequences.
+pub fn serialize_sign_doc(sign_doc: &SignDoc) -> StdResult<Vec<u8>> {
+    let json = String::from_utf8(to_vec(sign_doc)?)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(json
+        .replace('&', "\\u0026")
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .into_bytes())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SignDoc {
+    pub account_number: String,
+    pub chain_id: String,
+    pub fee: Fee,
+    pub memo: String,
+    pub msgs: Vec<Msg>,
+    pub sequence: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Fee {
+    pub amount: Vec<Coin>,
+    pub gas: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Msg {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    pub value: MsgSignData,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MsgSignData {
+    pub data: Binary,
+    pub signer: String,
+}
+
+pub const SECP256K1_PUB_KEY_TYPE: &str = "tendermint/PubKeySecp256k1";
+/// Amino types of ethsecp256k1 keys, e.g. `ethermint/PubKeyEthSecp256k1`, end with the suffix
+pub const ETH_SECP256K1_PUB_KEY_TYPE_SUFFIX: &str = "/PubKeyEthSecp256k1";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bech32::{ToBase32, Variant};
+    use cosmwasm_std::testing::MockApi;
+    use k256::ecdsa::signature::DigestSigner;
+    use k256::ecdsa::Signature as Secp256k1Signature;
+    use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+    use crate::claim::ClaimerType;
+    use crate::testing::{
+        claim_msg, cosmos_address, cosmos_sign, keplr_sign_doc, ledger_sign, signing_key,
+        COSMOS_PRIVATE_KEY, ETH_PRIVATE_KEY,
+    };
+
+    #[test]
+    fn verify_prefixes() {
+        let api = MockApi::default();
+        let key = signing_key(COSMOS_PRIVATE_KEY);
+        for prefix in ["cosmos", "osmo", "terra", "juno", "bostrom"] {
+            let claimer = cosmos_address(&key, prefix);
+            let claim_msg = claim_msg(ClaimerType::Cosmos, &claimer, "bostrom1target");
+            let (pub_key, signature) = cosmos_sign(&key, &claim_msg);
+            assert!(verify_cosmos(&api, &claim_msg, &pub_key, None, &signature, &[]).unwrap());
+        }
+
+        // the whitelist limits the prefixes
+        let claim_msg = claim_msg(
+            ClaimerType::Cosmos,
+            &cosmos_address(&key, "osmo"),
+            "bostrom1target",
+        );
+        let (pub_key, signature) = cosmos_sign(&key, &claim_msg);
+        let prefixes = ["cosmos".to_string()];
+        let err =
+            verify_cosmos(&api, &claim_msg, &pub_key, None, &signature, &prefixes).unwrap_err();
+        assert!(matches!(err, ProofError::InvalidAddress { .. }));
+    }
+
+    #[test]
+    fn verify_ledger() {
+        let api = MockApi::default();
+        let key = signing_key(COSMOS_PRIVATE_KEY);
+        let claimer = cosmos_address(&key, "cosmos");
+        let mut claim_msg = claim_msg(ClaimerType::Cosmos, &claimer, "bostrom1target");
+        claim_msg.signature_type = SignatureType::Ledger;
+        let (pub_key, signature) = ledger_sign(&key, &claim_msg);
+        assert!(verify_cosmos(&api, &claim_msg, &pub_key, None, &signature, &[]).unwrap());
+
+        // ledger signature is not a keplr signature and vice versa
+        claim_msg.signature_type = SignatureType::PersonalSign;
+        let err = verify_cosmos(&api, &claim_msg, &pub_key, None, &signature, &[]).unwrap_err();
+        assert!(matches!(err, ProofError::SignatureMismatch { .. }));
+        let (pub_key, signature) = cosmos_sign(&key, &claim_msg);
+        claim_msg.signature_type = SignatureType::Ledger;
+        let err = verify_cosmos(&api, &claim_msg, &pub_key, None, &signature, &[]).unwrap_err();
+        assert!(matches!(err, ProofError::SignatureMismatch { .. }));
+    }
+
+    #[test]
+    fn verify_eth_derivation() {
+        let api = MockApi::default();
+        let key = signing_key(COSMOS_PRIVATE_KEY);
+        let uncompressed = key.verifying_key().to_encoded_point(false);
+        let hash = Keccak256::digest(&uncompressed.as_bytes()[1..]);
+        let claimer = bech32::encode("inj", (&hash[12..]).to_base32(), Variant::Bech32).unwrap();
+        let mut claim_msg = claim_msg(ClaimerType::Cosmos, &claimer, "bostrom1target");
+        claim_msg.address_derivation = AddressDerivation::Ethereum;
+
+        let signature: Secp256k1Signature =
+            key.sign_digest(Keccak256::new().chain(keplr_sign_doc(&claim_msg)));
+        let signature = Binary::from(signature.as_ref());
+        let pub_key = Binary::from(key.verifying_key().to_bytes().as_slice());
+        let key_type = Some("injective/PubKeyEthSecp256k1");
+        assert!(verify_cosmos(&api, &claim_msg, &pub_key, key_type, &signature, &[]).unwrap());
+
+        // address derivation is covered by the signature
+        claim_msg.address_derivation = AddressDerivation::Cosmos;
+        let err = verify_cosmos(&api, &claim_msg, &pub_key, key_type, &signature, &[]).unwrap_err();
+        assert!(matches!(err, ProofError::SignatureMismatch { .. }));
+    }
+
+    #[test]
+    fn verify_amino() {
+        let api = MockApi::default();
+        let key = signing_key(COSMOS_PRIVATE_KEY);
+        let claimer = cosmos_address(&key, "cosmos");
+        let claim_msg = claim_msg(ClaimerType::Cosmos, &claimer, "bostrom1target");
+        let (pub_key, signature) = cosmos_sign(&key, &claim_msg);
+
+        // Keplr signArbitrary response is accepted with its amino key type
+        let verify = |key_type: &str, pub_key: &Binary| {
+            verify_cosmos(&api, &claim_msg, pub_key, Some(key_type), &signature, &[])
+        };
+        assert!(verify(SECP256K1_PUB_KEY_TYPE, &pub_key).unwrap());
+        let err = verify("tendermint/PubKeyEd25519", &pub_key).unwrap_err();
+        assert!(matches!(err, ProofError::SignatureMismatch { .. }));
+
+        // public key must hash to the claimed address
+        let other_key = signing_key(ETH_PRIVATE_KEY);
+        let other_pub_key = Binary::from(other_key.verifying_key().to_bytes().as_slice());
+        let err = verify(SECP256K1_PUB_KEY_TYPE, &other_pub_key).unwrap_err();
+        assert!(matches!(err, ProofError::AddressMismatch { .. }));
+
+        // sign doc is serialized with html characters escaped like Keplr does
+        let sign_doc = adr36_sign_doc("<cosmos&>", vec![]);
+        let serialized = String::from_utf8(serialize_sign_doc(&sign_doc).unwrap()).unwrap();
+        assert!(serialized.contains(r#""signer":"\u003ccosmos\u0026\u003e""#));
+    }
+}
@@ -0,0 +1,24 @@
+use cosmwasm_std::{RecoverPubkeyError, StdError};
+use hex::FromHexError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ProofError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Hex(#[from] FromHexError),
+
+    #[error("{0}")]
+    Pubkey(#[from] RecoverPubkeyError),
+
+    #[error("Signature mismatch, {msg}")]
+    SignatureMismatch { msg: String },
+
+    #[error("Address mismatch {address}, {msg}")]
+    AddressMismatch { address: String, msg: String },
+
+    #[error("Invalid address, {msg}")]
+    InvalidAddress { msg: String },
+}
@@ -0,0 +1,469 @@
+use cosmwasm_std::{Binary, StdError, StdResult};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use sha3::{Digest, Keccak256};
+use std::convert::TryInto;
+
+use crate::api::ProofApi;
+use crate::claim::{ClaimMsg, MessageEncoding, SignatureType};
+use crate::error::ProofError;
+
+/// Verifies the Ethereum signature (r || s || v) of the claim msg is made by the key of
+/// the 0x `gift_claiming_address`, v may take more than one byte with EIP-155.
+pub fn verify_eth<A: ProofApi + ?Sized>(
+    api: &A,
+    claim_msg: &ClaimMsg,
+    signature: &Binary,
+    message_encoding: MessageEncoding,
+    chain_id: Option<u64>,
+) -> Result<bool, ProofError> {
+    let hash: [u8; 32] = Keccak256::digest(&eth_sign_bytes(claim_msg, message_encoding)?).into();
+    // Decompose signature, v may take more than one byte with EIP-155
+    if signature.len() <= 64 || signature.len() > 72 {
+        return Err(ProofError::SignatureMismatch {
+            msg: "Signature must be r, s and v".to_string(),
+        });
+    }
+    let (rs, v) = signature.split_at(64);
+    let v = v.iter().fold(0u64, |v, byte| v << 8 | *byte as u64);
+    let recovery = get_recovery_param(v, chain_id)?;
+
+    // Verification
+    let calculated_pubkey = api.secp256k1_recover_pubkey(&hash, rs, recovery)?;
+    let calculated_address = ethereum_address_raw(&calculated_pubkey)?;
+    if decode_ethereum_address(&claim_msg.gift_claiming_address)? != calculated_address {
+        return Err(ProofError::AddressMismatch {
+            address: claim_msg.gift_claiming_address.clone(),
+            msg: "signer address is not calculated addr".to_string(),
+        });
+    }
+    let verified = api
+        .secp256k1_verify(&hash, rs, &calculated_pubkey)
+        .map_err(|err| ProofError::SignatureMismatch {
+            msg: err.to_string(),
+        })?;
+    if !verified {
+        return Err(ProofError::SignatureMismatch {
+            msg: "signature verification failed".to_string(),
+        });
+    }
+    Ok(true)
+}
+
+/// Returns the raw address recovered from an Ethereum signature of the claim msg, none
+/// if no key is recovered.
+pub fn recover_eth_address<A: ProofApi + ?Sized>(
+    api: &A,
+    claim_msg: &ClaimMsg,
+    signature: &[u8],
+    message_encoding: MessageEncoding,
+    chain_id: Option<u64>,
+) -> Option<[u8; 20]> {
+    if signature.len() <= 64 || signature.len() > 72 {
+        return None;
+    }
+    let sign_bytes = eth_sign_bytes(claim_msg, message_encoding).ok()?;
+    let hash: [u8; 32] = Keccak256::digest(&sign_bytes).into();
+    let (rs, v) = signature.split_at(64);
+    let v = v.iter().fold(0u64, |v, byte| v << 8 | *byte as u64);
+    let recovery = get_recovery_param(v, chain_id).ok()?;
+    let pubkey = api.secp256k1_recover_pubkey(&hash, rs, recovery).ok()?;
+    ethereum_address_raw(&pubkey).ok()
+}
+
+/// Returns the bytes signed by an Ethereum wallet for the signature type of the claim msg.
+pub fn eth_sign_bytes(
+    claim_msg: &ClaimMsg,
+    message_encoding: MessageEncoding,
+) -> Result<Vec<u8>, ProofError> {
+    match claim_msg.signature_type {
+        SignatureType::PersonalSign => Ok(personal_sign_bytes(claim_msg, message_encoding)?),
+        SignatureType::Eip712 => Ok(eip712_sign_bytes(claim_msg)),
+        SignatureType::Passport => Err(ProofError::SignatureMismatch {
+            msg: "passport claims are not signed".to_string(),
+        }),
+        SignatureType::Ledger => Err(ProofError::SignatureMismatch {
+            msg: "signature type is not supported for ethereum addresses".to_string(),
+        }),
+        SignatureType::ContractWallet => Err(ProofError::SignatureMismatch {
+            msg: "contract wallet claims are not signed".to_string(),
+        }),
+    }
+}
+
+/// Returns the bytes signed by `personal_sign` of the claim msg JSON, or of its hex
+/// encoding.
+fn personal_sign_bytes(
+    claim_msg: &ClaimMsg,
+    message_encoding: MessageEncoding,
+) -> StdResult<Vec<u8>> {
+    let msg = match message_encoding {
+        MessageEncoding::Utf8 => claim_msg.to_canonical_json()?,
+        MessageEncoding::Hex => {
+            format!("0x{}", hex::encode(claim_msg.to_canonical_json()?)).into_bytes()
+        }
+    };
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", msg.len());
+    Ok([prefix.as_bytes(), &msg].concat())
+}
+
+pub const EIP712_DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,string chainId,string verifyingContract)";
+pub const EIP712_DOMAIN_NAME: &str = "Cyber Gift";
+pub const EIP712_DOMAIN_VERSION: &str = "1";
+pub const EIP712_CLAIM_MSG_TYPE: &str = "ClaimMsg(string nickname,string avatar_cid,string gift_claiming_address_type,string gift_claiming_address,string target_addr,string recipient,string referrer,string payout_channel,string delegate_to,string source_chain,string memo,string relayer_reward,string contract_addr,string chain_id)";
+
+/// Returns the bytes signed by `signTypedData_v4` of the claim msg, all fields are typed as strings.
+/// The domain is bound to the chain-id and the gift contract of the claim msg, both are
+/// Cosmos values so chainId and verifyingContract are typed as strings too.
+fn eip712_sign_bytes(claim_msg: &ClaimMsg) -> Vec<u8> {
+    let keccak = |data: &[u8]| -> [u8; 32] { Keccak256::digest(data).into() };
+    // optional fields are hashed as empty strings if none set
+    let keccak_optional =
+        |value: &Option<String>| keccak(value.as_deref().unwrap_or_default().as_bytes());
+
+    let domain_separator = keccak(
+        &[
+            keccak(EIP712_DOMAIN_TYPE.as_bytes()),
+            keccak(EIP712_DOMAIN_NAME.as_bytes()),
+            keccak(EIP712_DOMAIN_VERSION.as_bytes()),
+            keccak(claim_msg.chain_id.as_bytes()),
+            keccak(claim_msg.contract_addr.as_bytes()),
+        ]
+        .concat(),
+    );
+    let claimer_type = claim_msg.gift_claiming_address_type.name();
+    let claim_msg_hash = keccak(
+        &[
+            keccak(EIP712_CLAIM_MSG_TYPE.as_bytes()),
+            keccak(claim_msg.nickname.as_bytes()),
+            keccak(claim_msg.avatar_cid.as_bytes()),
+            keccak(claimer_type.as_bytes()),
+            keccak(claim_msg.gift_claiming_address.as_bytes()),
+            keccak(claim_msg.target_addr.as_bytes()),
+            keccak_optional(&claim_msg.recipient),
+            keccak_optional(&claim_msg.referrer),
+            keccak_optional(&claim_msg.payout_channel),
+            keccak_optional(&claim_msg.delegate_to),
+            keccak_optional(&claim_msg.source_chain),
+            keccak_optional(&claim_msg.memo),
+            keccak(claim_msg.relayer_reward.to_string().as_bytes()),
+            keccak(claim_msg.contract_addr.as_bytes()),
+            keccak(claim_msg.chain_id.as_bytes()),
+        ]
+        .concat(),
+    );
+
+    [&b"\x19\x01"[..], &domain_separator, &claim_msg_hash].concat()
+}
+
+fn get_recovery_param(v: u64, chain_id: Option<u64>) -> StdResult<u8> {
+    match (v, chain_id) {
+        (27, _) => Ok(0),
+        (28, _) => Ok(1),
+        (v, Some(chain_id)) if v >= 35 => {
+            // v = 35 + chain_id * 2 + parity
+            match chain_id
+                .checked_mul(2)
+                .and_then(|id| (v - 35).checked_sub(id))
+            {
+                Some(0) => Ok(0),
+                Some(1) => Ok(1),
+                _ => Err(StdError::generic_err(format!(
+                    "Value of v is not replay protected for chain id {}",
+                    chain_id
+                ))),
+            }
+        }
+        _ => Err(StdError::generic_err("Values of v other than 27 and 28 not supported. Replay protection (EIP-155) cannot be used here."))
+    }
+}
+
+/// Returns a raw 20 byte Ethereum address of a secp256k1 public key, compressed
+/// keys are decompressed first
+pub fn ethereum_address_raw(pubkey: &[u8]) -> StdResult<[u8; 20]> {
+    let uncompressed;
+    let pubkey = match pubkey.len() {
+        33 => {
+            uncompressed = decompress_pubkey(pubkey)?;
+            &uncompressed[..]
+        }
+        _ => pubkey,
+    };
+    let (tag, data) = match pubkey.split_first() {
+        Some(pair) => pair,
+        None => return Err(StdError::generic_err("Public key must not be empty")),
+    };
+    if *tag != 0x04 {
+        return Err(StdError::generic_err("Public key must start with 0x04"));
+    }
+    if data.len() != 64 {
+        return Err(StdError::generic_err(
+            "Public key must be 33 or 65 bytes long",
+        ));
+    }
+
+    let hash = Keccak256::digest(data);
+    Ok(hash[hash.len() - 20..].try_into().unwrap())
+}
+
+/// Decodes a 0x prefixed hex Ethereum address into its raw 20 bytes
+fn decode_ethereum_address(address: &str) -> Result<[u8; 20], ProofError> {
+    let hex_address = address
+        .strip_prefix("0x")
+        .ok_or_else(|| StdError::generic_err("Ethereum address must start with 0x"))?;
+    let mut address_buf = [0u8; 20];
+    hex::decode_to_slice(hex_address.to_lowercase(), &mut address_buf)?;
+    Ok(address_buf)
+}
+
+/// Returns the 65 byte uncompressed form of a 33 byte compressed public key
+fn decompress_pubkey(pubkey: &[u8]) -> StdResult<Vec<u8>> {
+    let pubkey = k256::PublicKey::from_sec1_bytes(pubkey)
+        .map_err(|_| StdError::generic_err("Public key is not a valid secp256k1 point"))?;
+    Ok(pubkey.to_encoded_point(false).as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockApi;
+    use cosmwasm_std::Decimal;
+    use k256::ecdsa::signature::DigestSigner;
+    use k256::ecdsa::{recoverable, SigningKey};
+
+    use crate::claim::ClaimerType;
+    use crate::testing::{claim_msg, eth_address, eth_sign, signing_key, ETH_PRIVATE_KEY};
+
+    fn signed_claim(key: &SigningKey) -> (ClaimMsg, Binary) {
+        let pubkey = key.verifying_key().to_encoded_point(false);
+        let claim_msg = ClaimMsg {
+            nickname: "bob".to_string(),
+            avatar_cid: String::new(),
+            gift_claiming_address_type: ClaimerType::Ethereum,
+            gift_claiming_address: format!(
+                "0x{}",
+                hex::encode(ethereum_address_raw(pubkey.as_bytes()).unwrap())
+            ),
+            target_addr: "bostrom1target".to_string(),
+            recipient: None,
+            referrer: None,
+            payout_channel: None,
+            delegate_to: None,
+            source_chain: None,
+            memo: None,
+            relayer_reward: Decimal::zero(),
+            contract_addr: "contract".to_string(),
+            chain_id: "bostrom".to_string(),
+            signature_type: SignatureType::PersonalSign,
+            address_derivation: Default::default(),
+        };
+        let bytes = eth_sign_bytes(&claim_msg, MessageEncoding::Utf8).unwrap();
+        let signature: recoverable::Signature = key.sign_digest(Keccak256::new().chain(&bytes));
+        let mut signature = signature.as_ref().to_vec();
+        signature[64] += 27;
+        (claim_msg, Binary::from(signature))
+    }
+
+    #[test]
+    fn verify_personal_sign() {
+        let api = MockApi::default();
+        let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let (claim_msg, signature) = signed_claim(&key);
+        assert!(verify_eth(&api, &claim_msg, &signature, MessageEncoding::Utf8, None).unwrap());
+        assert_eq!(
+            recover_eth_address(&api, &claim_msg, &signature, MessageEncoding::Utf8, None)
+                .map(hex::encode),
+            claim_msg
+                .gift_claiming_address
+                .strip_prefix("0x")
+                .map(String::from)
+        );
+
+        // the signature does not cover a changed claim msg
+        let mut changed = claim_msg;
+        changed.target_addr = "bostrom1other".to_string();
+        let err = verify_eth(&api, &changed, &signature, MessageEncoding::Utf8, None).unwrap_err();
+        assert!(matches!(err, ProofError::AddressMismatch { .. }));
+    }
+
+    /// Signs the claim message like `signTypedData_v4` does, returning r || s || v
+    fn eth_sign_typed_data(key: &SigningKey, claim_msg: &ClaimMsg) -> Binary {
+        let keccak = |data: &[u8]| Keccak256::digest(data).to_vec();
+        let domain_separator = keccak(
+            &[
+                keccak(
+                    b"EIP712Domain(string name,string version,string chainId,string verifyingContract)",
+                ),
+                keccak(b"Cyber Gift"),
+                keccak(b"1"),
+                keccak(claim_msg.chain_id.as_bytes()),
+                keccak(claim_msg.contract_addr.as_bytes()),
+            ]
+            .concat(),
+        );
+        let claim_msg_hash = keccak(
+            &[
+                keccak(b"ClaimMsg(string nickname,string avatar_cid,string gift_claiming_address_type,string gift_claiming_address,string target_addr,string recipient,string referrer,string payout_channel,string delegate_to,string source_chain,string memo,string relayer_reward,string contract_addr,string chain_id)"),
+                keccak(claim_msg.nickname.as_bytes()),
+                keccak(claim_msg.avatar_cid.as_bytes()),
+                keccak(b"ethereum"),
+                keccak(claim_msg.gift_claiming_address.as_bytes()),
+                keccak(claim_msg.target_addr.as_bytes()),
+                keccak(claim_msg.recipient.as_deref().unwrap_or_default().as_bytes()),
+                keccak(claim_msg.referrer.as_deref().unwrap_or_default().as_bytes()),
+                keccak(claim_msg.payout_channel.as_deref().unwrap_or_default().as_bytes()),
+                keccak(claim_msg.delegate_to.as_deref().unwrap_or_default().as_bytes()),
+                keccak(claim_msg.source_chain.as_deref().unwrap_or_default().as_bytes()),
+                keccak(claim_msg.memo.as_deref().unwrap_or_default().as_bytes()),
+                keccak(claim_msg.relayer_reward.to_string().as_bytes()),
+                keccak(claim_msg.contract_addr.as_bytes()),
+                keccak(claim_msg.chain_id.as_bytes()),
+            ]
+            .concat(),
+        );
+        let digest = Keccak256::new()
+            .chain(b"\x19\x01")
+            .chain(domain_separator)
+            .chain(claim_msg_hash);
+        let signature: recoverable::Signature = key.sign_digest(digest);
+        let mut signature = signature.as_ref().to_vec();
+        signature[64] += 27;
+        Binary::from(signature)
+    }
+
+    #[test]
+    fn verify_typed_data() {
+        let api = MockApi::default();
+        let key = signing_key(ETH_PRIVATE_KEY);
+        let mut claim_msg = claim_msg(ClaimerType::Ethereum, &eth_address(&key), "bostrom1target");
+        claim_msg.signature_type = SignatureType::Eip712;
+        let signature = eth_sign_typed_data(&key, &claim_msg);
+        assert!(verify_eth(&api, &claim_msg, &signature, MessageEncoding::Utf8, None).unwrap());
+
+        // the domain is bound to the chain-id of the claim
+        let mut other_chain = claim_msg.clone();
+        other_chain.chain_id = "other-chain".to_string();
+        let err = verify_eth(
+            &api,
+            &claim_msg,
+            &eth_sign_typed_data(&key, &other_chain),
+            MessageEncoding::Utf8,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ProofError::AddressMismatch { .. }));
+
+        // typed data signature is not a personal_sign signature
+        claim_msg.signature_type = SignatureType::PersonalSign;
+        let err =
+            verify_eth(&api, &claim_msg, &signature, MessageEncoding::Utf8, None).unwrap_err();
+        assert_eq!(
+            err,
+            ProofError::AddressMismatch {
+                address: claim_msg.gift_claiming_address.clone(),
+                msg: "signer address is not calculated addr".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn verify_hex_message() {
+        let api = MockApi::default();
+        let key = signing_key(ETH_PRIVATE_KEY);
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &eth_address(&key), "bostrom1target");
+        let msg = format!("0x{}", hex::encode(claim_msg.to_canonical_json().unwrap()));
+        let digest = Keccak256::new()
+            .chain(format!("\x19Ethereum Signed Message:\n{}", msg.len()))
+            .chain(&msg);
+        let signature: recoverable::Signature = key.sign_digest(digest);
+        let mut signature = signature.as_ref().to_vec();
+        signature[64] += 27;
+        let signature = Binary::from(signature);
+        assert!(verify_eth(&api, &claim_msg, &signature, MessageEncoding::Hex, None).unwrap());
+
+        // the hex is not the signed JSON
+        let err =
+            verify_eth(&api, &claim_msg, &signature, MessageEncoding::Utf8, None).unwrap_err();
+        assert_eq!(
+            err,
+            ProofError::AddressMismatch {
+                address: claim_msg.gift_claiming_address.clone(),
+                msg: "signer address is not calculated addr".to_string()
+            }
+        );
+    }
+
+    /// Re-encodes the v of a signature as 35 + chain_id * 2 + parity, big endian without leading zeroes
+    fn eip155_signature(signature: &Binary, chain_id: u64) -> Binary {
+        let v = 35 + chain_id * 2 + (signature[64] - 27) as u64;
+        let v = v.to_be_bytes();
+        let leading_zeroes = v.iter().take_while(|byte| **byte == 0).count();
+        Binary::from([&signature[..64], &v[leading_zeroes..]].concat())
+    }
+
+    #[test]
+    fn verify_replay_protected() {
+        let api = MockApi::default();
+        let key = signing_key(ETH_PRIVATE_KEY);
+        let claim_msg = claim_msg(ClaimerType::Ethereum, &eth_address(&key), "bostrom1target");
+        let signature = eth_sign(&key, &claim_msg);
+
+        for chain_id in [1, 137, 1_000_000] {
+            let eip155_signature = eip155_signature(&signature, chain_id);
+            let verify = |chain_id| {
+                verify_eth(
+                    &api,
+                    &claim_msg,
+                    &eip155_signature,
+                    MessageEncoding::Utf8,
+                    chain_id,
+                )
+            };
+            assert!(verify(Some(chain_id)).unwrap());
+
+            // chain id is required to accept the signature
+            assert!(matches!(verify(None).unwrap_err(), ProofError::Std(_)));
+            assert!(matches!(
+                verify(Some(chain_id + 1)).unwrap_err(),
+                ProofError::Std(_)
+            ));
+        }
+
+        // legacy v values are still accepted
+        assert!(verify_eth(&api, &claim_msg, &signature, MessageEncoding::Utf8, Some(1)).unwrap());
+    }
+
+    #[test]
+    fn ethereum_address_encodings() {
+        // generator point, the public key of private key 1
+        let compressed =
+            hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        let uncompressed = hex::decode(
+            "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+             483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        )
+        .unwrap();
+        let address = hex::decode("7e5f4552091a69125d5dfcb7b8c2659029395bdf").unwrap();
+        assert_eq!(ethereum_address_raw(&compressed).unwrap().to_vec(), address);
+        assert_eq!(
+            ethereum_address_raw(&uncompressed).unwrap().to_vec(),
+            address
+        );
+
+        // both encodings of a signing key derive its address
+        let key = signing_key(ETH_PRIVATE_KEY);
+        let address = eth_address(&key);
+        for compress in [true, false] {
+            let pub_key = key.verifying_key().to_encoded_point(compress);
+            let raw = ethereum_address_raw(pub_key.as_bytes()).unwrap();
+            assert_eq!(format!("0x{}", hex::encode(raw)), address);
+        }
+
+        // other tags and lengths are rejected
+        let mut invalid = compressed.clone();
+        invalid[0] = 0x05;
+        assert!(ethereum_address_raw(&invalid).is_err());
+        assert!(ethereum_address_raw(&compressed[..32]).is_err());
+    }
+}
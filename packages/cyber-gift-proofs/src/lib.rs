@@ -0,0 +1,35 @@
+mod api;
+mod claim;
+mod cosmos;
+mod error;
+mod eth;
+#[cfg(test)]
+mod testing;
+
+pub use crate::api::ProofApi;
+pub use crate::claim::{
+    snapshot_key, AddressDerivation, ClaimMsg, ClaimerType, MessageEncoding, SignatureType,
+};
+pub use crate::cosmos::{
+    adr36_sign_doc, compress_pubkey, cosmos_address_raw, cosmos_sign_doc, ledger_sign_doc,
+    serialize_sign_doc, verify_cosmos, Fee, Msg, MsgSignData, SignDoc,
+    ETH_SECP256K1_PUB_KEY_TYPE_SUFFIX, SECP256K1_PUB_KEY_TYPE,
+};
+pub use crate::error::ProofError;
+pub use crate::eth::{
+    eth_sign_bytes, ethereum_address_raw, recover_eth_address, verify_eth, EIP712_CLAIM_MSG_TYPE,
+    EIP712_DOMAIN_NAME, EIP712_DOMAIN_TYPE, EIP712_DOMAIN_VERSION,
+};
+
+/// Returns the bytes signed by the gift_claiming_address for the claim msg, they are
+/// hashed with keccak256 for Ethereum addresses and Cosmos keys of the ethereum address
+/// derivation, with sha256 otherwise.
+pub fn sign_bytes(claim_msg: &ClaimMsg) -> Result<Vec<u8>, ProofError> {
+    match claim_msg.gift_claiming_address_type {
+        ClaimerType::Ethereum => eth_sign_bytes(claim_msg, MessageEncoding::Utf8),
+        ClaimerType::Cosmos => Ok(serialize_sign_doc(&cosmos_sign_doc(claim_msg)?)?),
+        _ => Err(ProofError::SignatureMismatch {
+            msg: "sign bytes are built for ethereum and cosmos addresses only".to_string(),
+        }),
+    }
+}
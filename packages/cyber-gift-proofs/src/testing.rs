@@ -0,0 +1,99 @@
+//! Keys and wallet signatures of claim msgs for the tests.
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{to_vec, Binary, Decimal};
+use k256::ecdsa::signature::DigestSigner;
+use k256::ecdsa::{recoverable, Signature as Secp256k1Signature, SigningKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+use crate::claim::{AddressDerivation, ClaimMsg, ClaimerType, SignatureType};
+
+pub const ETH_PRIVATE_KEY: &str =
+    "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+pub const COSMOS_PRIVATE_KEY: &str =
+    "6c6b0e0f4f4a3b8e9b71a5e26a1dfe3b8a3e0d5a4c1f17e9a7c3b2a1f0e9d8c7";
+
+pub fn signing_key(private_key: &str) -> SigningKey {
+    SigningKey::from_bytes(&hex::decode(private_key).unwrap()).unwrap()
+}
+
+pub fn eth_address(key: &SigningKey) -> String {
+    let pubkey = key.verifying_key().to_encoded_point(false);
+    let hash = Keccak256::digest(&pubkey.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+pub fn cosmos_address(key: &SigningKey, prefix: &str) -> String {
+    let hash = Ripemd160::digest(&Sha256::digest(&key.verifying_key().to_bytes()));
+    bech32::encode(prefix, hash.to_base32(), Variant::Bech32).unwrap()
+}
+
+/// Returns the claim msg of the claimer made for the mock contract and chain.
+pub fn claim_msg(claimer_type: ClaimerType, claimer: &str, target: &str) -> ClaimMsg {
+    ClaimMsg {
+        nickname: "alice".to_string(),
+        avatar_cid: "QmRX8qYgeZoYM3M5zzQaWEpVFdpin6FvVXvp6RPQK3oufV".to_string(),
+        gift_claiming_address_type: claimer_type,
+        gift_claiming_address: claimer.to_string(),
+        target_addr: target.to_string(),
+        recipient: None,
+        referrer: None,
+        payout_channel: None,
+        delegate_to: None,
+        source_chain: None,
+        memo: None,
+        relayer_reward: Decimal::zero(),
+        contract_addr: "cosmos2contract".to_string(),
+        chain_id: "cosmos-testnet-14002".to_string(),
+        signature_type: SignatureType::PersonalSign,
+        address_derivation: AddressDerivation::Cosmos,
+    }
+}
+
+/// Signs the claim message like `personal_sign` does, returning r || s || v
+pub fn eth_sign(key: &SigningKey, claim_msg: &ClaimMsg) -> Binary {
+    let msg = claim_msg.to_canonical_json().unwrap();
+    let digest = Keccak256::new()
+        .chain(format!("\x19Ethereum Signed Message:\n{}", msg.len()))
+        .chain(&msg);
+    let signature: recoverable::Signature = key.sign_digest(digest);
+    let mut signature = signature.as_ref().to_vec();
+    signature[64] += 27;
+    Binary::from(signature)
+}
+
+/// Builds the ADR-36 sign doc of the claim message like Keplr `signArbitrary` does
+pub fn keplr_sign_doc(claim_msg: &ClaimMsg) -> String {
+    format!(
+        "{{\"account_number\":\"0\",\"chain_id\":\"\",\"fee\":{{\"amount\":[],\"gas\":\"0\"}},\"memo\":\"\",\"msgs\":[{{\"type\":\"sign/MsgSignData\",\"value\":{{\"data\":\"{}\",\"signer\":\"{}\"}}}}],\"sequence\":\"0\"}}",
+        Binary::from(claim_msg.to_canonical_json().unwrap()),
+        claim_msg.gift_claiming_address
+    )
+}
+
+/// Signs the claim message like Keplr `signArbitrary` does, returning the public key
+/// and the signature
+pub fn cosmos_sign(key: &SigningKey, claim_msg: &ClaimMsg) -> (Binary, Binary) {
+    let signature: Secp256k1Signature =
+        key.sign_digest(Sha256::new().chain(keplr_sign_doc(claim_msg)));
+    (
+        Binary::from(key.verifying_key().to_bytes().as_slice()),
+        Binary::from(signature.as_ref()),
+    )
+}
+
+/// Signs the claim message like a Ledger device signs an amino doc with it as memo
+pub fn ledger_sign(key: &SigningKey, claim_msg: &ClaimMsg) -> (Binary, Binary) {
+    let memo = String::from_utf8(claim_msg.to_canonical_json().unwrap()).unwrap();
+    let sign_doc = format!(
+        "{{\"account_number\":\"0\",\"chain_id\":\"\",\"fee\":{{\"amount\":[],\"gas\":\"0\"}},\"memo\":{},\"msgs\":[],\"sequence\":\"0\"}}",
+        String::from_utf8(to_vec(&memo).unwrap()).unwrap()
+    );
+    let signature: Secp256k1Signature = key.sign_digest(Sha256::new().chain(sign_doc));
+    (
+        Binary::from(key.verifying_key().to_bytes().as_slice()),
+        Binary::from(signature.as_ref()),
+    )
+}
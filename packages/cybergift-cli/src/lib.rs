@@ -0,0 +1,343 @@
+//! Builds claim payloads signed with Ethereum or Cosmos keys and verifies them with
+//! the contract code, so payloads are checked against the same serialization the
+//! contract verifies.
+use anyhow::{anyhow, bail, Result};
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, Addr, Binary, Decimal, Uint128};
+use cw_cyber_airdrop::execute::{execute, instantiate, query};
+use cw_cyber_airdrop::msg::{
+    ClaimMsg, ClaimerType, ExecuteMsg, InstantiateMsg, IsEligibleResponse, MessageEncoding, Proof,
+    ProofNode, QueryMsg, SignatureType,
+};
+use cw_cyber_airdrop::state::LeafFormat;
+use cyber_gift_proofs::{cosmos_address_raw, ethereum_address_raw, sign_bytes};
+use cybergift_merkle::{Entry, MerkleTree};
+use k256::ecdsa::signature::DigestSigner;
+use k256::ecdsa::{recoverable, Signature as Secp256k1Signature, SigningKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+/// ClaimOptions are the claim msg fields and the stage of a payload.
+#[derive(Clone, Debug)]
+pub struct ClaimOptions {
+    pub stage: u8,
+    pub leaf_format: LeafFormat,
+    pub claimer_type: ClaimerType,
+    pub signature_type: SignatureType,
+    /// Prefix is the bech32 prefix of the Cosmos claimer address.
+    pub prefix: String,
+    pub nickname: String,
+    pub avatar_cid: String,
+    pub target_addr: String,
+    pub recipient: Option<String>,
+    pub referrer: Option<String>,
+    pub payout_channel: Option<String>,
+    pub delegate_to: Option<String>,
+    pub source_chain: Option<String>,
+    pub memo: Option<String>,
+    pub relayer_reward: Decimal,
+    pub contract_addr: String,
+    pub chain_id: String,
+}
+
+/// Returns the claimer address of the key for the claimer type.
+pub fn claimer_address(
+    key: &SigningKey,
+    claimer_type: &ClaimerType,
+    prefix: &str,
+) -> Result<String> {
+    match claimer_type {
+        ClaimerType::Ethereum => {
+            let pubkey = key.verifying_key().to_encoded_point(false);
+            let address = ethereum_address_raw(pubkey.as_bytes())?;
+            Ok(format!("0x{}", hex::encode(address)))
+        }
+        ClaimerType::Cosmos => {
+            let address = cosmos_address_raw(&key.verifying_key().to_bytes())?;
+            Ok(bech32::encode(
+                prefix,
+                address.to_base32(),
+                Variant::Bech32,
+            )?)
+        }
+        _ => bail!("only ethereum and cosmos keys are supported"),
+    }
+}
+
+/// Signs the claim msg like the wallets of the claimer type do.
+pub fn sign_claim(key: &SigningKey, claim_msg: &ClaimMsg) -> Result<Proof> {
+    let bytes = sign_bytes(claim_msg)?;
+    match claim_msg.gift_claiming_address_type {
+        // r || s || v
+        ClaimerType::Ethereum => {
+            let signature: recoverable::Signature = key.sign_digest(Keccak256::new().chain(&bytes));
+            let mut signature = signature.as_ref().to_vec();
+            signature[64] += 27;
+            Ok(Proof::EthSig {
+                signature: Binary::from(signature),
+                message_encoding: MessageEncoding::Utf8,
+            })
+        }
+        ClaimerType::Cosmos => {
+            let signature: Secp256k1Signature = key.sign_digest(Sha256::new().chain(&bytes));
+            Ok(Proof::CosmosSig {
+                pub_key: Binary::from(key.verifying_key().to_bytes().as_slice()),
+                signature: Binary::from(signature.as_ref()),
+                pub_key_type: None,
+            })
+        }
+        _ => bail!("only ethereum and cosmos claims are signed"),
+    }
+}
+
+/// Builds the signed claim of the key entry in the snapshot.
+pub fn claim_payload(
+    entries: &[Entry],
+    key: &SigningKey,
+    opts: &ClaimOptions,
+) -> Result<ExecuteMsg> {
+    let claimer = claimer_address(key, &opts.claimer_type, &opts.prefix)?;
+    let index = entries
+        .iter()
+        .position(|entry| entry.address == claimer)
+        .ok_or_else(|| anyhow!("{} is not in the snapshot", claimer))?;
+    let tree = MerkleTree::new(merkle_leaf_format(&opts.leaf_format)?, entries)?;
+
+    let claim_msg = ClaimMsg {
+        nickname: opts.nickname.clone(),
+        avatar_cid: opts.avatar_cid.clone(),
+        gift_claiming_address_type: opts.claimer_type.clone(),
+        gift_claiming_address: claimer,
+        target_addr: opts.target_addr.clone(),
+        recipient: opts.recipient.clone(),
+        referrer: opts.referrer.clone(),
+        payout_channel: opts.payout_channel.clone(),
+        delegate_to: opts.delegate_to.clone(),
+        source_chain: opts.source_chain.clone(),
+        memo: opts.memo.clone(),
+        relayer_reward: opts.relayer_reward,
+        contract_addr: opts.contract_addr.clone(),
+        chain_id: opts.chain_id.clone(),
+        signature_type: opts.signature_type.clone(),
+        address_derivation: Default::default(),
+    };
+    let signature = sign_claim(key, &claim_msg)?;
+    let proof = tree
+        .proof(index)?
+        .iter()
+        .map(|node| ProofNode(Binary::from(node.as_ref())))
+        .collect();
+    Ok(ExecuteMsg::Claim {
+        stage: opts.stage,
+        claim_msg,
+        signature,
+        claim_amount: entries[index].amount,
+        extra_amounts: entries[index].extra_amounts.clone(),
+        leaf_index: match opts.leaf_format {
+            LeafFormat::Indexed => Some(index as u32),
+            _ => None,
+        },
+        proof,
+    })
+}
+
+/// Verifies the claim with the contract against the root of the snapshot, registered
+/// as the stage of the claim in a mock deployment at the claim msg contract_addr.
+pub fn verify_payload(
+    entries: &[Entry],
+    leaf_format: &LeafFormat,
+    msg: ExecuteMsg,
+) -> Result<IsEligibleResponse> {
+    let (stage, claim_msg, signature, claim_amount, extra_amounts, leaf_index, proof) = match msg {
+        ExecuteMsg::Claim {
+            stage,
+            claim_msg,
+            signature,
+            claim_amount,
+            extra_amounts,
+            leaf_index,
+            proof,
+        } => (
+            stage,
+            claim_msg,
+            signature,
+            claim_amount,
+            extra_amounts,
+            leaf_index,
+            proof,
+        ),
+        _ => bail!("payload is not a claim"),
+    };
+    let tree = MerkleTree::new(merkle_leaf_format(leaf_format)?, entries)?;
+
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    env.contract.address = Addr::unchecked(&claim_msg.contract_addr);
+    env.block.chain_id = claim_msg.chain_id.clone();
+    let msg = InstantiateMsg {
+        owner: None,
+        allowed_native: "boot".to_string(),
+        cw20_token: None,
+        initial_balance: Uint128::zero(),
+        extra_balances: vec![],
+        coefficient_up: Decimal::one(),
+        coefficient_down: Decimal::one(),
+        coefficient_claims: None,
+        eth_chain_id: None,
+        treasury: None,
+        guardian: None,
+        passport: None,
+        target_prefix: None,
+        liquid_share: None,
+        release_blocks: None,
+        activation_threshold: None,
+        referral_share: None,
+        referral_balance: None,
+        max_claims_per_block: None,
+        claim_minimum: None,
+        claim_maximum: None,
+        fee_recipient: None,
+        fee_bps: None,
+        ibc_channels: vec![],
+        ibc_timeout_seconds: None,
+        ibc_controller: None,
+        ibc_connection_id: None,
+        unbonding_seconds: None,
+        root_timelock_seconds: None,
+        root_guardians: None,
+        root_guardian_threshold: None,
+        linked_claim_policy: None,
+        campaign_end: None,
+        verified_claims: None,
+        receipt_nft: None,
+        milestone_tiers: None,
+        cosmos_prefixes: None,
+        history_interval: None,
+        lottery_ticket_size: None,
+        verbose_errors: None,
+        first_stage: None,
+    };
+    let info = mock_info("owner", &[]);
+    instantiate(deps.as_mut(), env.clone(), info.clone(), msg)?;
+    for _ in 0..stage {
+        let msg = ExecuteMsg::RegisterMerkleRoot {
+            merkle_root: tree.root_hex(),
+            start: None,
+            expiration: None,
+            total_amount: Uint128::new(1_000_000),
+            leaf_format: Some(leaf_format.clone()),
+            hash_algo: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg)?;
+    }
+
+    let msg = QueryMsg::IsEligible {
+        stage,
+        claim_msg,
+        signature,
+        claim_amount,
+        extra_amounts,
+        leaf_index,
+        proof,
+    };
+    Ok(from_binary(&query(deps.as_ref(), env, msg)?)?)
+}
+
+fn merkle_leaf_format(leaf_format: &LeafFormat) -> Result<cybergift_merkle::LeafFormat> {
+    Ok(serde_json::from_value(serde_json::to_value(leaf_format)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ETH_PRIVATE_KEY: &str =
+        "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn options(claimer_type: ClaimerType, signature_type: SignatureType) -> ClaimOptions {
+        ClaimOptions {
+            stage: 1,
+            leaf_format: LeafFormat::Concat,
+            claimer_type,
+            signature_type,
+            prefix: "cosmos".to_string(),
+            nickname: "alice".to_string(),
+            avatar_cid: "QmRX8qYgeZoYM3M5zzQaWEpVFdpin6FvVXvp6RPQK3oufV".to_string(),
+            target_addr: "bostrom1target".to_string(),
+            recipient: None,
+            referrer: None,
+            payout_channel: None,
+            delegate_to: None,
+            source_chain: None,
+            memo: None,
+            relayer_reward: Decimal::zero(),
+            contract_addr: "bostrom1gift".to_string(),
+            chain_id: "bostrom".to_string(),
+        }
+    }
+
+    #[test]
+    fn claim_payloads_verify() {
+        let key = SigningKey::from_bytes(&hex::decode(ETH_PRIVATE_KEY).unwrap()).unwrap();
+        let entries = cybergift_merkle::parse_csv(&format!(
+            "{},1000\n{},2000\nbostrom1other,3000\n",
+            claimer_address(&key, &ClaimerType::Ethereum, "").unwrap(),
+            claimer_address(&key, &ClaimerType::Cosmos, "cosmos").unwrap(),
+        ))
+        .unwrap();
+
+        let cases = [
+            options(ClaimerType::Ethereum, SignatureType::PersonalSign),
+            options(ClaimerType::Ethereum, SignatureType::Eip712),
+            options(ClaimerType::Cosmos, SignatureType::PersonalSign),
+            options(ClaimerType::Cosmos, SignatureType::Ledger),
+            ClaimOptions {
+                stage: 2,
+                leaf_format: LeafFormat::Indexed,
+                ..options(ClaimerType::Cosmos, SignatureType::PersonalSign)
+            },
+        ];
+        for opts in cases {
+            let msg = claim_payload(&entries, &key, &opts).unwrap();
+            let res = verify_payload(&entries, &opts.leaf_format, msg.clone()).unwrap();
+            assert!(res.valid_proof && res.valid_signature, "{:?}", opts);
+
+            // payloads are bound to the snapshot and the claim msg
+            let res = verify_payload(&entries[..2], &opts.leaf_format, msg.clone()).unwrap();
+            assert!(!res.valid_proof);
+            let msg = match msg {
+                ExecuteMsg::Claim {
+                    stage,
+                    mut claim_msg,
+                    signature,
+                    claim_amount,
+                    extra_amounts,
+                    leaf_index,
+                    proof,
+                } => {
+                    claim_msg.target_addr = "bostrom1other".to_string();
+                    ExecuteMsg::Claim {
+                        stage,
+                        claim_msg,
+                        signature,
+                        claim_amount,
+                        extra_amounts,
+                        leaf_index,
+                        proof,
+                    }
+                }
+                _ => unreachable!(),
+            };
+            let res = verify_payload(&entries, &opts.leaf_format, msg).unwrap();
+            assert!(!res.valid_signature);
+        }
+
+        let opts = ClaimOptions {
+            prefix: "osmo".to_string(),
+            ..options(ClaimerType::Cosmos, SignatureType::PersonalSign)
+        };
+        assert!(claim_payload(&entries, &key, &opts).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+
+use anyhow::{anyhow, bail, Result};
+use cosmwasm_std::Decimal;
+use cw_cyber_airdrop::msg::ExecuteMsg;
+use cw_cyber_airdrop::state::LeafFormat;
+use cybergift_cli::{claim_payload, verify_payload, ClaimOptions};
+use cybergift_merkle::Entry;
+use k256::ecdsa::SigningKey;
+use serde::de::DeserializeOwned;
+use std::str::FromStr;
+
+const USAGE: &str = "Usage:
+  cybergift claim --snapshot <file> --key <hex private key> --target <address>
+      --contract <address> --chain-id <chain-id> [--stage 1] [--format concat]
+      [--claimer-type ethereum|cosmos] [--signature-type personal_sign|eip712|ledger]
+      [--prefix cosmos] [--nickname <nickname>] [--avatar-cid <cid>]
+      [--recipient <address>] [--referrer <address>] [--relayer-reward 0]
+      [--payout-channel <channel-id>]
+      [--delegate-to <valoper>]
+      [--source-chain <chain>] [--memo <memo>]
+  cybergift verify --snapshot <file> [--format concat] [--payload <file>|-]
+
+claim prints the claim execute msg JSON, ready to submit with
+`cyber tx wasm execute <contract> <json>`. verify checks a claim execute msg with
+the contract code against the snapshot root and prints the is eligible response.
+Snapshots with the .json extension are parsed as JSON, others as CSV.";
+
+fn main() {
+    if let Err(err) = run(std::env::args().skip(1).collect()) {
+        eprintln!("error: {}\n\n{}", err, USAGE);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<()> {
+    let (command, flags) = args
+        .split_first()
+        .ok_or_else(|| anyhow!("missing command"))?;
+    if command != "claim" && command != "verify" {
+        bail!("unknown command {}", command);
+    }
+    let flags = parse_flags(flags)?;
+    let entries = read_snapshot(required(&flags, "snapshot")?)?;
+    let leaf_format: LeafFormat = parse_enum(flags.get("format").map_or("concat", String::as_str))?;
+
+    match command.as_str() {
+        "claim" => {
+            let key = SigningKey::from_bytes(&hex::decode(
+                required(&flags, "key")?.trim_start_matches("0x"),
+            )?)?;
+            let opts = ClaimOptions {
+                stage: flags.get("stage").map_or(Ok(1), |s| s.parse())?,
+                leaf_format,
+                claimer_type: parse_enum(
+                    flags.get("claimer-type").map_or("ethereum", String::as_str),
+                )?,
+                signature_type: parse_enum(
+                    flags
+                        .get("signature-type")
+                        .map_or("personal_sign", String::as_str),
+                )?,
+                prefix: flags
+                    .get("prefix")
+                    .cloned()
+                    .unwrap_or_else(|| "cosmos".to_string()),
+                nickname: flags.get("nickname").cloned().unwrap_or_default(),
+                avatar_cid: flags.get("avatar-cid").cloned().unwrap_or_default(),
+                target_addr: required(&flags, "target")?.to_string(),
+                recipient: flags.get("recipient").cloned(),
+                referrer: flags.get("referrer").cloned(),
+                payout_channel: flags.get("payout-channel").cloned(),
+                delegate_to: flags.get("delegate-to").cloned(),
+                source_chain: flags.get("source-chain").cloned(),
+                memo: flags.get("memo").cloned(),
+                relayer_reward: flags
+                    .get("relayer-reward")
+                    .map_or(Ok(Decimal::zero()), |r| Decimal::from_str(r))?,
+                contract_addr: required(&flags, "contract")?.to_string(),
+                chain_id: required(&flags, "chain-id")?.to_string(),
+            };
+            let msg = claim_payload(&entries, &key, &opts)?;
+            println!("{}", serde_json::to_string(&msg)?);
+        }
+        "verify" => {
+            let payload = match flags.get("payload").map(String::as_str) {
+                None | Some("-") => {
+                    let mut payload = String::new();
+                    std::io::stdin().read_to_string(&mut payload)?;
+                    payload
+                }
+                Some(file) => fs::read_to_string(file)?,
+            };
+            let msg: ExecuteMsg = serde_json::from_str(&payload)?;
+            let res = verify_payload(&entries, &leaf_format, msg)?;
+            println!("{}", serde_json::to_string(&res)?);
+            if !res.valid_proof || !res.valid_signature {
+                bail!("claim is not valid");
+            }
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Parses `--name value` pairs.
+fn parse_flags(args: &[String]) -> Result<HashMap<String, String>> {
+    let mut flags = HashMap::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let name = arg
+            .strip_prefix("--")
+            .ok_or_else(|| anyhow!("unexpected argument {}", arg))?;
+        let value = args
+            .next()
+            .ok_or_else(|| anyhow!("missing value of --{}", name))?;
+        flags.insert(name.to_string(), value.clone());
+    }
+    Ok(flags)
+}
+
+fn required<'a>(flags: &'a HashMap<String, String>, name: &str) -> Result<&'a str> {
+    flags
+        .get(name)
+        .map(String::as_str)
+        .ok_or_else(|| anyhow!("missing --{}", name))
+}
+
+/// Parses a snake case enum value, like it is serialized in the contract messages.
+fn parse_enum<T: DeserializeOwned>(value: &str) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| anyhow!("invalid value {}", value))
+}
+
+fn read_snapshot(file: &str) -> Result<Vec<Entry>> {
+    let data = fs::read_to_string(file)?;
+    if file.ends_with(".json") {
+        Ok(cybergift_merkle::parse_json(&data)?)
+    } else {
+        Ok(cybergift_merkle::parse_csv(&data)?)
+    }
+}
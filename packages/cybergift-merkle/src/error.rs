@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MerkleError {
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid snapshot line {line}: {msg}")]
+    InvalidLine { line: usize, msg: String },
+
+    #[error("Gift of {address} has extra denoms but no denom")]
+    NoDenom { address: String },
+
+    #[error("Snapshot has no entries")]
+    Empty {},
+
+    #[error("No entry at index {index}")]
+    NoEntry { index: usize },
+
+    #[error("No level at depth {depth}")]
+    NoLevel { depth: usize },
+}
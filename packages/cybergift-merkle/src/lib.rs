@@ -0,0 +1,7 @@
+mod error;
+mod snapshot;
+mod tree;
+
+pub use crate::error::MerkleError;
+pub use crate::snapshot::{parse_csv, parse_json, Entry};
+pub use crate::tree::{hash_leaf, leaf, verify_proof, LeafFormat, MerkleTree};
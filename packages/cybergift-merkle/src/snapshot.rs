@@ -0,0 +1,156 @@
+use cosmwasm_std::{Coin, Uint128};
+use serde::{Deserialize, Serialize};
+
+use crate::error::MerkleError;
+
+/// Entry is the gift of an address in a snapshot.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub address: String,
+    pub amount: Uint128,
+    /// Denom of amount, gifts with extra denoms need it as their leaf encodes all coins.
+    #[serde(default)]
+    pub denom: Option<String>,
+    /// ExtraAmounts are the extra denoms gifted along with amount.
+    #[serde(default)]
+    pub extra_amounts: Vec<Coin>,
+}
+
+/// Parses a JSON array snapshot.
+pub fn parse_json(data: &str) -> Result<Vec<Entry>, MerkleError> {
+    let entries: Vec<Entry> = serde_json::from_str(data)?;
+    if entries.is_empty() {
+        return Err(MerkleError::Empty {});
+    }
+    if let Some(entry) = entries
+        .iter()
+        .find(|e| e.denom.is_none() && !e.extra_amounts.is_empty())
+    {
+        return Err(MerkleError::NoDenom {
+            address: entry.address.clone(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Parses a CSV snapshot of `address,amount[,coin...]` rows, empty lines and a header
+/// row are skipped. The amount of rows with extra coins is a coin too.
+pub fn parse_csv(data: &str) -> Result<Vec<Entry>, MerkleError> {
+    let mut entries = vec![];
+    for (index, row) in data.lines().enumerate() {
+        let line = index + 1;
+        let row = row.trim();
+        if row.is_empty() {
+            continue;
+        }
+        let mut columns = row.split(',').map(str::trim);
+        let address = columns.next().unwrap_or_default();
+        let amount = columns.next().unwrap_or_default();
+        let amount = match amount.parse::<u128>() {
+            Ok(amount) => Some(Coin {
+                denom: String::new(),
+                amount: Uint128::new(amount),
+            }),
+            Err(_) => parse_coin(amount),
+        };
+        if index == 0 && amount.is_none() {
+            continue;
+        }
+        if address.is_empty() {
+            return Err(invalid_line(line, "address is empty"));
+        }
+        let amount = amount.ok_or_else(|| invalid_line(line, "invalid amount"))?;
+        let extra_amounts = columns
+            .map(|coin| parse_coin(coin).ok_or_else(|| invalid_line(line, "invalid coin")))
+            .collect::<Result<Vec<Coin>, _>>()?;
+        let denom = Some(amount.denom).filter(|denom| !denom.is_empty());
+        if denom.is_none() && !extra_amounts.is_empty() {
+            return Err(invalid_line(line, "amount of extra coins needs a denom"));
+        }
+        entries.push(Entry {
+            address: address.to_string(),
+            amount: amount.amount,
+            denom,
+            extra_amounts,
+        });
+    }
+    if entries.is_empty() {
+        return Err(MerkleError::Empty {});
+    }
+    Ok(entries)
+}
+
+fn invalid_line(line: usize, msg: &str) -> MerkleError {
+    MerkleError::InvalidLine {
+        line,
+        msg: msg.to_string(),
+    }
+}
+
+/// Parses a coin like it is displayed, e.g. `500hydrogen`.
+fn parse_coin(coin: &str) -> Option<Coin> {
+    let split = coin
+        .find(|c: char| !c.is_ascii_digit())
+        .filter(|i| *i > 0)?;
+    let (amount, denom) = coin.split_at(split);
+    Some(Coin {
+        denom: denom.to_string(),
+        amount: Uint128::new(amount.parse().ok()?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coin;
+
+    #[test]
+    fn csv_snapshot() {
+        let data =
+            "address,amount\nbostrom1alice,1000\n\nbostrom1bob,2000boot,500hydrogen,20volt\n";
+        let entries = parse_csv(data).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    address: "bostrom1alice".to_string(),
+                    amount: Uint128::new(1000),
+                    denom: None,
+                    extra_amounts: vec![],
+                },
+                Entry {
+                    address: "bostrom1bob".to_string(),
+                    amount: Uint128::new(2000),
+                    denom: Some("boot".to_string()),
+                    extra_amounts: vec![coin(500, "hydrogen"), coin(20, "volt")],
+                },
+            ]
+        );
+
+        let err = parse_csv("bostrom1alice,1000\nbostrom1bob,many").unwrap_err();
+        assert!(matches!(err, MerkleError::InvalidLine { line: 2, .. }));
+        let err = parse_csv("bostrom1alice,1000boot,hydrogen").unwrap_err();
+        assert!(matches!(err, MerkleError::InvalidLine { line: 1, .. }));
+        // all coins of a gift with extra denoms have a denom
+        let err = parse_csv("bostrom1alice,1000\nbostrom1bob,2000,500hydrogen").unwrap_err();
+        assert!(matches!(err, MerkleError::InvalidLine { line: 2, .. }));
+        assert!(matches!(
+            parse_csv("address,amount\n").unwrap_err(),
+            MerkleError::Empty {}
+        ));
+    }
+
+    #[test]
+    fn json_snapshot() {
+        let data = r#"[
+            {"address": "bostrom1alice", "amount": "1000"},
+            {"address": "bostrom1bob", "amount": "2000", "denom": "boot", "extra_amounts": [{"denom": "hydrogen", "amount": "500"}]}
+        ]"#;
+        let entries = parse_json(data).unwrap();
+        assert_eq!(entries[0].amount, Uint128::new(1000));
+        assert_eq!(entries[1].extra_amounts, vec![coin(500, "hydrogen")]);
+        assert!(parse_json("[]").is_err());
+        let err = parse_json(&data.replace(r#""denom": "boot", "#, "")).unwrap_err();
+        assert!(matches!(err, MerkleError::NoDenom { .. }));
+    }
+}
@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+use crate::error::MerkleError;
+use crate::snapshot::Entry;
+
+/// LeafFormat is the encoding of the merkle leaves and nodes of a stage, it mirrors
+/// the string leaf formats of the contract.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeafFormat {
+    /// sha256 of `{address}{amount}`.
+    #[default]
+    Concat,
+    /// sha256 of `{address}:{amount}`.
+    Cw20MerkleAirdrop,
+    /// keccak256 of `{address}{amount}`, nodes are hashed with keccak256 too.
+    Keccak256,
+    /// sha256 of `{index}:{address}{amount}`.
+    Indexed,
+}
+
+/// Returns the leaf of the entry at the index, gifts with extra denoms encode all their
+/// coins, e.g. `{address}1000boot,500hydrogen`.
+pub fn leaf(format: LeafFormat, index: usize, entry: &Entry) -> String {
+    let mut leaf = match format {
+        LeafFormat::Cw20MerkleAirdrop => format!("{}:{}", entry.address, entry.amount),
+        LeafFormat::Indexed => format!("{}:{}{}", index, entry.address, entry.amount),
+        _ => format!("{}{}", entry.address, entry.amount),
+    };
+    if !entry.extra_amounts.is_empty() {
+        leaf.push_str(entry.denom.as_deref().unwrap_or_default());
+    }
+    for coin in &entry.extra_amounts {
+        leaf.push_str(&format!(",{}", coin));
+    }
+    leaf
+}
+
+/// Hashes the data with the hash function of the leaf format.
+pub fn hash_leaf(format: LeafFormat, data: &[u8]) -> [u8; 32] {
+    match format {
+        LeafFormat::Keccak256 => Keccak256::digest(data).into(),
+        _ => Sha256::digest(data).into(),
+    }
+}
+
+/// MerkleTree is a sorted pair merkle tree, odd nodes are promoted to the next level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerkleTree {
+    root: [u8; 32],
+    proofs: Vec<Vec<[u8; 32]>>,
+    /// Levels are the nodes of the tree from the leaf hashes up to the root.
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// Builds the tree of the snapshot entries in the leaf format.
+    pub fn new(format: LeafFormat, entries: &[Entry]) -> Result<Self, MerkleError> {
+        let leaves: Vec<String> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| leaf(format, index, entry))
+            .collect();
+        let hashes = leaves
+            .iter()
+            .map(|leaf| hash_leaf(format, leaf.as_bytes()))
+            .collect();
+        Self::from_hashes(hashes, |data| hash_leaf(format, data))
+    }
+
+    /// Builds the tree of the leaves with the hash function.
+    pub fn from_leaves(
+        leaves: &[String],
+        hash: fn(&[u8]) -> [u8; 32],
+    ) -> Result<Self, MerkleError> {
+        let hashes = leaves.iter().map(|leaf| hash(leaf.as_bytes())).collect();
+        Self::from_hashes(hashes, hash)
+    }
+
+    fn from_hashes(
+        mut level: Vec<[u8; 32]>,
+        hash: impl Fn(&[u8]) -> [u8; 32],
+    ) -> Result<Self, MerkleError> {
+        if level.is_empty() {
+            return Err(MerkleError::Empty {});
+        }
+        let mut positions: Vec<usize> = (0..level.len()).collect();
+        let mut proofs = vec![vec![]; level.len()];
+        let mut levels = vec![];
+        while level.len() > 1 {
+            for (leaf, position) in positions.iter_mut().enumerate() {
+                if let Some(sibling) = level.get(*position ^ 1) {
+                    proofs[leaf].push(*sibling);
+                }
+                *position /= 2;
+            }
+            levels.push(level.clone());
+            level = level
+                .chunks(2)
+                .map(|pair| {
+                    let mut pair = pair.to_vec();
+                    pair.sort_unstable();
+                    match pair.as_slice() {
+                        [a, b] => hash(&[*a, *b].concat()),
+                        _ => pair[0],
+                    }
+                })
+                .collect();
+        }
+        levels.push(level.clone());
+        Ok(MerkleTree {
+            root: level[0],
+            proofs,
+            levels,
+        })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// Returns the hex encoded root, as registered in the contract.
+    pub fn root_hex(&self) -> String {
+        hex::encode(self.root)
+    }
+
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    pub fn proof(&self, index: usize) -> Result<&[[u8; 32]], MerkleError> {
+        self.proofs
+            .get(index)
+            .map(Vec::as_slice)
+            .ok_or(MerkleError::NoEntry { index })
+    }
+
+    /// Returns the nodes of the level at the depth below the root, as registered with
+    /// RegisterProofLevel.
+    pub fn level(&self, depth: usize) -> Result<&[[u8; 32]], MerkleError> {
+        self.levels
+            .len()
+            .checked_sub(depth + 1)
+            .map(|height| self.levels[height].as_slice())
+            .ok_or(MerkleError::NoLevel { depth })
+    }
+
+    /// Returns the compact proof of the entry at the index, the nodes of its proof below
+    /// the level at the depth.
+    pub fn compact_proof(&self, index: usize, depth: usize) -> Result<&[[u8; 32]], MerkleError> {
+        let proof = self.proof(index)?;
+        let height = self.level(depth).map(|_| self.levels.len() - depth - 1)?;
+        // levels without a sibling of the node added no proof node
+        let len = (0..height)
+            .filter(|&level| (index >> level) ^ 1 < self.levels[level].len())
+            .count();
+        Ok(&proof[..len])
+    }
+
+    /// Returns the hex encoded proof of the entry at the index, as accepted by the contract.
+    pub fn proof_hex(&self, index: usize) -> Result<Vec<String>, MerkleError> {
+        Ok(self.proof(index)?.iter().map(hex::encode).collect())
+    }
+}
+
+/// Verifies the proof of the leaf against the root like the contract does.
+pub fn verify_proof(format: LeafFormat, root: [u8; 32], leaf: &str, proof: &[[u8; 32]]) -> bool {
+    let hash = proof
+        .iter()
+        .fold(hash_leaf(format, leaf.as_bytes()), |hash, node| {
+            let mut hashes = [hash, *node];
+            hashes.sort_unstable();
+            hash_leaf(format, &hashes.concat())
+        });
+    hash == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::parse_csv;
+
+    const SNAPSHOT: &str =
+        "bostrom1alice,1000\nbostrom1bob,2000boot,500hydrogen\nbostrom1carol,3000\n";
+
+    #[test]
+    fn leaves() {
+        let entries = parse_csv(SNAPSHOT).unwrap();
+        assert_eq!(
+            "bostrom1alice1000",
+            leaf(LeafFormat::Concat, 0, &entries[0])
+        );
+        assert_eq!(
+            "bostrom1bob:2000boot,500hydrogen",
+            leaf(LeafFormat::Cw20MerkleAirdrop, 1, &entries[1])
+        );
+        assert_eq!(
+            "2:bostrom1carol3000",
+            leaf(LeafFormat::Indexed, 2, &entries[2])
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let entries = parse_csv(SNAPSHOT).unwrap();
+        for format in [
+            LeafFormat::Concat,
+            LeafFormat::Cw20MerkleAirdrop,
+            LeafFormat::Keccak256,
+            LeafFormat::Indexed,
+        ] {
+            let tree = MerkleTree::new(format, &entries).unwrap();
+            assert_eq!(3, tree.len());
+            for (index, entry) in entries.iter().enumerate() {
+                let proof = tree.proof(index).unwrap();
+                assert!(verify_proof(
+                    format,
+                    tree.root(),
+                    &leaf(format, index, entry),
+                    proof
+                ));
+                assert!(!verify_proof(format, tree.root(), &entry.address, proof));
+            }
+        }
+
+        // indexed leaves are bound to their index
+        let tree = MerkleTree::new(LeafFormat::Indexed, &entries).unwrap();
+        let proof = tree.proof(0).unwrap();
+        assert!(!verify_proof(
+            LeafFormat::Indexed,
+            tree.root(),
+            &leaf(LeafFormat::Indexed, 1, &entries[0]),
+            proof
+        ));
+
+        // single entry root is its leaf hash
+        let tree = MerkleTree::new(LeafFormat::Concat, &entries[..1]).unwrap();
+        assert_eq!(
+            tree.root(),
+            hash_leaf(LeafFormat::Concat, b"bostrom1alice1000")
+        );
+        assert!(tree.proof(0).unwrap().is_empty());
+        assert!(tree.proof(1).is_err());
+        assert!(MerkleTree::new(LeafFormat::Concat, &[]).is_err());
+    }
+
+    #[test]
+    fn compact_proofs() {
+        let entries = parse_csv(&format!(
+            "{}bostrom1dave,4000\nbostrom1erin,5000\n",
+            SNAPSHOT
+        ))
+        .unwrap();
+        let tree = MerkleTree::new(LeafFormat::Concat, &entries).unwrap();
+        assert_eq!(&[tree.root()], tree.level(0).unwrap());
+        assert_eq!(5, tree.level(3).unwrap().len());
+        assert!(tree.level(4).is_err());
+
+        for depth in 0..4 {
+            let level = tree.level(depth).unwrap();
+            for (index, entry) in entries.iter().enumerate() {
+                let leaf = leaf(LeafFormat::Concat, index, entry);
+                let proof = tree.compact_proof(index, depth).unwrap();
+                assert!(level.iter().any(|node| verify_proof(
+                    LeafFormat::Concat,
+                    *node,
+                    &leaf,
+                    proof
+                )));
+            }
+        }
+        // the promoted leaf has no sibling below the root level
+        assert_eq!(tree.proof(4).unwrap(), tree.compact_proof(4, 0).unwrap());
+        assert!(tree.compact_proof(4, 1).unwrap().is_empty());
+    }
+}